@@ -18,8 +18,19 @@ qollage is the HQS python package to draw qoqo quantum circuits.
     :toctree: generated/
 
     draw_circuit
+    draw_qasm
+    draw_quantum_program
+    draw_device
+    draw_circuit_on_device
+    draw_circuit_timeline
     save_circuit
     circuit_to_typst_str
+    circuit_to_quantikz_str
+    circuit_to_text
+    print_circuit
+    circuit_operation_columns
+    RenderOptions
+    Renderer
 
 """
 
@@ -18,21 +18,293 @@ qollage is the HQS python package to draw qoqo quantum circuits.
     :toctree: generated/
 
     draw_circuit
+    draw_qasm
+    draw_quantum_program
+    draw_device
+    draw_circuit_on_device
+    draw_circuit_timeline
     save_circuit
     circuit_to_typst_str
+    circuit_to_quantikz_str
+    circuit_to_text
+    print_circuit
+    RenderOptions
+    Renderer
 
 """
 
-from qoqo import Circuit  # type: ignore
-from typing import Optional
+from qoqo import Circuit, QuantumProgram  # type: ignore
+from typing import Any, Dict, List, Optional, Union
+
+class RenderOptions:
+    """
+    Options of the circuit rendering.
+
+    The options can be passed to every drawing function of qollage, the arguments given directly
+    to the function take precedence over the options.
+
+    ## Args:
+         * pixel_per_point (float, optional): The pixels per point ration of the png images. 3.0 if None.
+         * render_pragmas (str, optional): How to render Pragmas operations. "all" if None.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit, "state",
+             "qubit" or "inferred". "state" if None.
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * fallback_unsupported (bool): Whether to draw the operations not supported by qollage as a generic box
+             labelled with their name instead of raising an error. A UserWarning names each operation drawn this way.
+         * theme (Optional(Union[str, dict])): The colours of the gates. Either a built-in theme, "default", "light",
+             "dark", "high_contrast" or "colour_blind", or a dict mapping gate families ("rotation", "clifford",
+             "entangler", "measurement", "noise", "bosonic") to a dict with the typst "fill" and "stroke" of their gates.
+         * background (Optional(str)): The background of the page, "white" if None, "transparent" or a typst colour,
+             e.g. 'rgb("#1e1e1e")'. The text and the wires are drawn in white on dark colours.
+         * page_margin (Optional(float)): The margin around the circuit in points. 5.0 if None.
+         * qubit_labels (Optional(Dict[int, str])): The names of the qubits by index, e.g. {0: "ancilla"}. They
+             replace q[n] at the beginning of the lines, also on the wrapped lines.
+         * boson_labels (Optional(Dict[int, str])): The names of the bosonic modes by index, e.g. {0: "cavity A"}.
+             They replace b[n] at the beginning of the lines.
+         * register_labels (Optional(Dict[str, str])): The names displayed for the classical registers, by
+             register name.
+         * qubit_states (Optional(Dict[int, str])): The initial states of the qubits by index, as typst math drawn
+             as |state>, e.g. {1: "+", 2: "psi"}. The other qubits start in |0>.
+         * boson_states (Optional(Dict[int, str])): The initial states of the bosonic modes by index.
+         * qubit_order (Optional(Union[str, List[int]])): The order of the qubit wires from top to bottom,
+             "ascending" if None, "reversed" to draw the highest qubit at the top or the list of the qubits drawn
+             first, followed by the others in ascending order. The controls and multi-qubit boxes follow the wires.
+         * qubit_subset (Optional(List[int])): The qubits to draw. The operations acting only on other qubits are
+             not drawn, the lines to the other qubits are cut at the boundary.
+         * hide_idle_qubits (bool): Whether to hide the wires of the qubits without any operation.
+         * layering (Optional(str)): How the operations are assigned to the columns, "greedy" if None to draw every
+             operation next to the previous ones on its wires, "strict" to draw them one after the other, "asap" or
+             "alap" to pack them in moments as soon or as late as possible, or "respect_barriers" to pack them as soon
+             as possible without moving them across a PragmaStopParallelBlock.
+         * column_numbers (bool): Whether to number the columns above the diagram.
+         * depth_footer (bool): Whether to add a footer with the depth of the diagram and the number of operations
+             drawn on each qubit.
+
+    ## Raises:
+         * TypeError: The theme is neither a str nor a dict, or the qubit order neither a str nor a list.
+         * ValueError: One of the arguments is not in a suitable format.
+    """
+
+    def __init__(
+        self,
+        pixel_per_point: Optional[float] = None,
+        render_pragmas: Optional[str] = None,
+        initialization_mode: Optional[str] = None,
+        max_circuit_length: Optional[int] = None,
+        rounding_accuracy: Optional[int] = None,
+        cache_dir: Optional[str] = None,
+        network_policy: Optional[str] = None,
+        fallback_unsupported: bool = False,
+        theme: Optional[Union[str, dict]] = None,
+        background: Optional[str] = None,
+        page_margin: Optional[float] = None,
+        qubit_labels: Optional[Dict[int, str]] = None,
+        boson_labels: Optional[Dict[int, str]] = None,
+        register_labels: Optional[Dict[str, str]] = None,
+        qubit_states: Optional[Dict[int, str]] = None,
+        boson_states: Optional[Dict[int, str]] = None,
+        qubit_order: Optional[Union[str, List[int]]] = None,
+        qubit_subset: Optional[List[int]] = None,
+        hide_idle_qubits: bool = False,
+        layering: Optional[str] = None,
+        column_numbers: bool = False,
+        depth_footer: bool = False,
+    ) -> None: ...
+    def to_json(self) -> str:
+        """
+        Returns the json representation of the options, e.g. to store them in a config file.
+        The custom gate renderers are not serialized.
+
+        ## Returns:
+             * str: The serialized options.
+        """
+    @staticmethod
+    def from_json(input: str) -> RenderOptions:
+        """
+        Creates the options from their json representation. Missing fields take their default value.
+
+        ## Args:
+             * input (str): The serialized options.
+
+        ## Returns:
+             * RenderOptions: The deserialized options.
+
+        ## Raises:
+             * ValueError: Input cannot be deserialized to RenderOptions.
+        """
+    def register_label(self, hqslang: str, label: str) -> None:
+        """
+        Draws the operation as a box with a text label across its qubits.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+             * label (str): The text of the box.
+        """
+    def register_box(self, hqslang: str, content: str) -> None:
+        """
+        Draws the operation as a box with a typst math content on each of its qubits.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+             * content (str): The typst math content of the boxes.
+        """
+    def register_multi_qubit_gate(self, hqslang: str, content: str) -> None:
+        """
+        Draws the operation as a multi-qubit gate with a typst math content.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+             * content (str): The typst math content of the gate.
+        """
+    def register_typst(self, hqslang: str, items: List[str]) -> None:
+        """
+        Draws the operation with raw quill code.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+             * items (List[str]): The quill code placed on each qubit of the operation, in increasing qubit order.
+        """
+    def unregister(self, hqslang: str) -> None:
+        """
+        Removes the custom renderer of the operation.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+        """
+    def mark_unsupported(self, hqslang: str) -> None:
+        """
+        Disables the built-in drawing of the operation, which is then handled as an unsupported operation.
+
+        ## Args:
+             * hqslang (str): The hqslang name of the operation.
+        """
+    def set_fallback(self, enabled: bool) -> None:
+        """
+        Sets whether the operations not supported by qollage are drawn as a generic box labelled with their name.
+
+        ## Args:
+             * enabled (bool): Whether to draw the unsupported operations instead of raising an error.
+        """
+    def set_gate_style(
+        self, family: str, fill: Optional[str] = None, stroke: Optional[str] = None
+    ) -> None:
+        """
+        Sets the colours of the gates of a family, overriding the theme.
+
+        ## Args:
+             * family (str): The gate family, "rotation", "clifford", "entangler", "measurement", "noise" or "bosonic".
+             * fill (Optional(str)): The typst fill colour of the gates, e.g. "blue".
+             * stroke (Optional(str)): The typst stroke of the single-qubit gates, e.g. "1pt + red".
+
+        ## Raises:
+             * ValueError: Unknown gate family.
+        """
+
+class Renderer:
+    """
+    Renderer of many circuits with the same options.
+
+    The typst library, the fonts and the loaded packages are kept across calls, so rendering many
+    circuits, e.g. from a parameter sweep, is much faster than calling `save_circuit` for each of them.
+    `save_many` and `render_many` render the circuits in parallel.
+
+    ## Args:
+         * pixel_per_point (float, optional): The pixels per point ration of the png images. 3.0 if None.
+         * render_pragmas (str, optional): How to render Pragmas operations. "all" if None.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit, "state",
+             "qubit" or "inferred". "state" if None.
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * ValueError: One of the arguments is not in a suitable format.
+    """
+
+    def __init__(
+        self,
+        pixel_per_point: Optional[float] = None,
+        render_pragmas: Optional[str] = None,
+        initialization_mode: Optional[str] = None,
+        max_circuit_length: Optional[int] = None,
+        rounding_accuracy: Optional[int] = None,
+        cache_dir: Optional[str] = None,
+        network_policy: Optional[str] = None,
+        options: Optional[Union[RenderOptions, dict]] = None,
+    ) -> None: ...
+    def options(self) -> RenderOptions:
+        """
+        Returns the rendering options of the renderer.
+
+        ## Returns:
+             * RenderOptions: The rendering options.
+        """
+    def draw(self, circuit: Circuit) -> None:
+        """
+        Displays the qoqo circuit as an image output.
+
+        ## Args:
+             * circuit (Circuit): The qoqo circuit to draw.
+
+        ## Raises:
+             * TypeError: Circuit conversion error.
+             * ValueError: Operation not supported.
+        """
+    def save(self, circuit: Circuit, path: Optional[str] = None) -> None:
+        """
+        Saves the qoqo circuit as a png, svg or pdf image, depending on the extension of `path`.
+
+        ## Args:
+             * circuit (Circuit): The qoqo circuit to save.
+             * path (Optional(str)): The path of the image, `circuit.png` if None.
+
+        ## Raises:
+             * TypeError: Circuit conversion error.
+             * ValueError: Operation not supported.
+        """
+    def save_many(self, circuits: List[Circuit], paths: List[str]) -> None:
+        """
+        Saves the qoqo circuits as png, svg or pdf images, rendering them in parallel.
+
+        ## Args:
+             * circuits (List[Circuit]): The qoqo circuits to save.
+             * paths (List[str]): The path of the image of each circuit.
+
+        ## Raises:
+             * TypeError: Circuit conversion error.
+             * ValueError: Operation not supported | The numbers of circuits and paths differ.
+        """
+    def render_many(self, circuits: List[Circuit]) -> List[bytes]:
+        """
+        Renders the qoqo circuits as png images in parallel.
+
+        ## Args:
+             * circuits (List[Circuit]): The qoqo circuits to render.
+
+        ## Returns:
+             * List[bytes]: The png image of each circuit.
+
+        ## Raises:
+             * TypeError: Circuit conversion error.
+             * ValueError: Operation not supported.
+        """
 
 def draw_circuit(
     circuit: Circuit,
-    pixel_per_point: float = 3.0,
-    render_pragmas: str = "all",
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
     initialization_mode: Optional[str] = None,
     max_circuit_length: Optional[int] = None,
     rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
 ) -> None:
     """
     Displays the qoqo circuit as an image output
@@ -48,24 +320,225 @@ def draw_circuit(
          * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
              - "state" for "|0>". Used if None. \n
              - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
          * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
              The default setting `None` does not create a new line.
          * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
              If None, the default rounding accuracy of roqollage (3) is used.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages:
+             "default" for `$QOLLAGE_CACHE_DIR` or `.qollage/cache`, "user" for the user cache directory,
+             any other value is used as the path of the cache directory.
+         * network_policy (Optional(str)): Whether packages can be downloaded: "allow", "deny"
+             or the base URL of a package registry mirror.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
 
     ## Raises:
          * TypeError: Circuit conversion error.
          * ValueError: Operation not supported | Memory limit exceeded if pixel_per_point is too large.
     """
 
+def draw_qasm(
+    qasm: str,
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Converts an OpenQASM 2 or 3 program to a circuit and displays it as an image output
+
+    The gates of the standard libraries (`qelib1.inc` and `stdgates.inc`) are mapped to the
+    corresponding qoqo operations and the gates defined in the program are expanded.
+
+    ## Args:
+         * qasm (str): The QASM program to draw.
+         * pixel_per_point (float, optional): The pixels per point ration of the image.
+            The higher the value, the bigger the image will be but the longer it will take to render.
+         * render_pragmas (str, optional): How to render Pragmas operations:\n
+             - `"all"` to render every pragmas.\n
+             - `"none"` to not render any pragmas.\n
+             - `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
+             - "state" for "|0>". Used if None. \n
+             - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+             The default setting `None` does not create a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
+             If None, the default rounding accuracy of roqollage (3) is used.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * ValueError: Invalid or unsupported QASM program | Operation not supported.
+    """
+
+def draw_quantum_program(
+    quantum_program: QuantumProgram,
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Displays the qoqo QuantumProgram as an image output
+
+    The constant circuit and each measurement circuit are drawn as labelled panels, below the
+    input parameter names of the program. For PauliZProduct measurements, each panel lists the
+    Pauli products measured by its circuit. For PauliZProduct and CheatedPauliZProduct
+    measurements, the table of the Pauli products and the formulas of the expectation values are
+    drawn next to the circuits.
+
+    ## Args:
+         * quantum_program (QuantumProgram): The qoqo QuantumProgram to draw.
+         * pixel_per_point (float, optional): The pixels per point ration of the image.
+            The higher the value, the bigger the image will be but the longer it will take to render.
+         * render_pragmas (str, optional): How to render Pragmas operations. "all" if None.
+         * initialization_mode (str, optional): What to display at the beginning of the circuits. "state" if None.
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * TypeError: QuantumProgram conversion error.
+         * ValueError: Operation not supported.
+    """
+
+def draw_device(
+    device: Any,
+    pixel_per_point: Optional[float] = None,
+    rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Displays the two-qubit connectivity graph of a qoqo device as an image output
+
+    The qubits are drawn on a lattice when the connectivity allows it, e.g. for a
+    SquareLatticeDevice, and on a circle otherwise. The qubits are coloured by their total
+    decoherence rate and the edges are annotated with the gate times of the two-qubit gates.
+
+    ## Args:
+         * device (Device): The qoqo device to draw, e.g. an AllToAllDevice, a SquareLatticeDevice
+             or a GenericDevice.
+         * pixel_per_point (float, optional): The pixels per point ration of the image.
+            The higher the value, the bigger the image will be but the longer it will take to render.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * TypeError: Device conversion error.
+         * ValueError: Error during the rendering.
+    """
+
+def draw_circuit_on_device(
+    circuit: Circuit,
+    device: Any,
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Displays the qoqo circuit as an image output, with the operations that cannot run as is on a
+    device outlined in red
+
+    The operations acting on qubits outside of the device, the gates that are not in the gate set
+    of the device and the two-qubit gates acting on qubits that are not connected in the device
+    are outlined, a legend below the circuit lists them.
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to draw.
+         * device (Device): The qoqo device the circuit should run on, e.g. an AllToAllDevice, a
+             SquareLatticeDevice or a GenericDevice.
+         * pixel_per_point (float, optional): The pixels per point ration of the image.
+            The higher the value, the bigger the image will be but the longer it will take to render.
+         * render_pragmas (str, optional): How to render Pragmas operations. "all" if None.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit, "state",
+             "qubit" or "inferred". "state" if None.
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * TypeError: Circuit or device conversion error.
+         * ValueError: Operation not supported.
+    """
+
+def draw_circuit_timeline(
+    circuit: Circuit,
+    device: Any,
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
+    rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Displays the qoqo circuit as a timeline image output, the width of the operations being
+    proportional to their duration
+
+    The durations are the gate times of the device and the times of the PragmaSleep and
+    PragmaStopParallelBlock operations, the other operations are drawn as instantaneous markers.
+    The idle periods of the qubits are shaded and a time axis is drawn below the circuit.
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to draw.
+         * device (Device): The qoqo device giving the gate times, e.g. an AllToAllDevice, a
+             SquareLatticeDevice or a GenericDevice.
+         * pixel_per_point (float, optional): The pixels per point ration of the image.
+            The higher the value, the bigger the image will be but the longer it will take to render.
+         * render_pragmas (str, optional): How to render Pragmas operations. "all" if None.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats. 3 if None.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+         * network_policy (Optional(str)): Whether packages can be downloaded.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * TypeError: Circuit or device conversion error.
+         * ValueError: Error during the rendering.
+    """
+
 def save_circuit(
     circuit: Circuit,
     path: Optional[str] = None,
-    pixel_per_point: float = 3.0,
-    render_pragmas: str = "all",
+    pixel_per_point: Optional[float] = None,
+    render_pragmas: Optional[str] = None,
     initialization_mode: Optional[str] = None,
     max_circuit_length: Optional[int] = None,
     rounding_accuracy: Optional[int] = None,
+    cache_dir: Optional[str] = None,
+    network_policy: Optional[str] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
 ) -> None:
     """
     Saves the qoqo circuit as a png image
@@ -82,10 +555,19 @@ def save_circuit(
          * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
              - "state" for "|0>". Used if None. \n
              - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
          * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
              The default setting `None` does not create a new line.
          * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
              If None, the default rounding accuracy of roqollage (3) is used.
+         * cache_dir (Optional(str)): Where to cache the downloaded typst packages:
+             "default" for `$QOLLAGE_CACHE_DIR` or `.qollage/cache`, "user" for the user cache directory,
+             any other value is used as the path of the cache directory.
+         * network_policy (Optional(str)): Whether packages can be downloaded: "allow", "deny"
+             or the base URL of a package registry mirror.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
 
     ## Raises:
          * TypeError: Circuit conversion error
@@ -94,10 +576,11 @@ def save_circuit(
 
 def circuit_to_typst_str(
     circuit: Circuit,
-    render_pragmas: str = "all",
+    render_pragmas: Optional[str] = None,
     initialization_mode: Optional[str] = None,
     max_circuit_length: Optional[int] = None,
     rounding_accuracy: Optional[int] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
 ) -> str:
     """
     Returns the circuit's representation in Typst.
@@ -113,10 +596,14 @@ def circuit_to_typst_str(
          * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
              - "state" for "|0>". Used if None. \n
              - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
          * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
              The default setting `None` does not create a new line.
          * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
              If None, the default rounding accuracy of roqollage (3) is used.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
 
     ## Returns:
          * str: The circuit's representation in Typst.
@@ -125,3 +612,136 @@ def circuit_to_typst_str(
          * TypeError: Circuit conversion error
          * ValueError: Operation not supported. | Memory limit exceeded if pixel_per_point is too large. | Couldn't create the corresponding file.
     """
+
+def circuit_to_quantikz_str(
+    circuit: Circuit,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> str:
+    """
+    Returns the circuit's representation as a LaTeX quantikz environment.
+    The output uses the `quantikz2` TikZ library (`\\usetikzlibrary{quantikz2}`).
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to convert.
+         * render_pragmas (str, optional): How to render Pragmas operations:\n
+             - "all" to render every pragmas.\n
+             - "none" to not render any pragmas.\n
+             - "PragmaOperation1, PragmaOperation2" to render only some pragmas.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
+             - "state" for "|0>". Used if None. \n
+             - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+             The default setting `None` does not create a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
+             If None, the default rounding accuracy of roqollage (3) is used.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Returns:
+         * str: The circuit's representation in LaTeX quantikz.
+
+    ## Raises:
+         * TypeError: Circuit conversion error
+         * ValueError: Operation not supported.
+    """
+
+def circuit_to_text(
+    circuit: Circuit,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> str:
+    """
+    Returns the circuit's representation as a text diagram drawn with box-drawing characters,
+    e.g. to display it in a terminal or a log.
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to draw.
+         * render_pragmas (str, optional): How to render Pragmas operations:\n
+             - "all" to render every pragmas.\n
+             - "none" to not render any pragmas.\n
+             - "PragmaOperation1, PragmaOperation2" to render only some pragmas.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
+             - "state" for "|0>". Used if None. \n
+             - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+             The default setting `None` does not create a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
+             If None, the default rounding accuracy of roqollage (3) is used.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Returns:
+         * str: The text diagram of the circuit.
+
+    ## Raises:
+         * TypeError: Circuit conversion error
+         * ValueError: Operation not supported.
+    """
+
+def print_circuit(
+    circuit: Circuit,
+    render_pragmas: Optional[str] = None,
+    initialization_mode: Optional[str] = None,
+    max_circuit_length: Optional[int] = None,
+    rounding_accuracy: Optional[int] = None,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> None:
+    """
+    Prints the circuit's representation as a text diagram drawn with box-drawing characters.
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to draw.
+         * render_pragmas (str, optional): How to render Pragmas operations:\n
+             - "all" to render every pragmas.\n
+             - "none" to not render any pragmas.\n
+             - "PragmaOperation1, PragmaOperation2" to render only some pragmas.
+         * initialization_mode (str, optional): What to display at the beginning of the circuit:\n
+             - "state" for "|0>". Used if None. \n
+             - "qubit" for "q[n]".\n
+             - "inferred" for the states prepared by the PragmaSetStateVector, PragmaSetDensityMatrix and InputBit
+               operations at the beginning of the circuit.\n
+         * max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+             The default setting `None` does not create a new line.
+         * rounding_accuracy (Optional(int)): The number of decimals displayed for floats.
+             If None, the default rounding accuracy of roqollage (3) is used.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments. The other arguments take precedence over the options.
+
+    ## Raises:
+         * TypeError: Circuit conversion error
+         * ValueError: Operation not supported.
+    """
+
+def circuit_operation_columns(
+    circuit: Circuit,
+    options: Optional[Union[RenderOptions, dict]] = None,
+) -> Dict[int, int]:
+    """
+    Returns the column of the diagram in which each operation of the circuit is drawn.
+
+    The operations are indexed in the order of the circuit iteration, the definitions first. The operations
+    that are not drawn, e.g. the definitions or the pragmas that are not rendered, have no column.
+
+    ## Args:
+         * circuit (Circuit): The qoqo circuit to draw.
+         * options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+             RenderOptions arguments.
+
+    ## Returns:
+         * Dict[int, int]: The column of each drawn operation, by index of the operation.
+
+    ## Raises:
+         * TypeError: Circuit conversion error
+         * ValueError: Operation not supported.
+    """
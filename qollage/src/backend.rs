@@ -10,197 +10,1596 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{io::Cursor, path::PathBuf, str::FromStr};
+use std::{collections::HashMap, ffi::CString, io::Cursor, path::PathBuf, str::FromStr, sync::Arc};
 
+use image::DynamicImage;
 use pyo3::{
-    exceptions::{PyTypeError, PyValueError},
+    exceptions::{PyTypeError, PyUserWarning, PyValueError},
     prelude::*,
+    types::PyDict,
 };
 use qoqo::convert_into_circuit;
-use roqollage::{circuit_into_typst_str, circuit_to_image, InitializationMode, RenderPragmas};
+use roqollage::{
+    circuit_into_layout, circuit_into_quantikz_str_with_warnings,
+    circuit_into_timeline_typst_str_with_warnings, circuit_into_typst_str_with_warnings,
+    circuit_on_device_into_typst_str_with_warnings, device_to_image, layout_to_text,
+    qasm_to_circuit, quantum_program_into_typst_str_with_warnings, render_typst_str, Background,
+    BoxRenderer, CacheDirectory, GateFamily, GateRenderer, GateStyle, GenericRenderer,
+    InitializationMode, LabelRenderer, LayeringStrategy, MultiQubitGateRenderer, NetworkPolicy,
+    QubitOrder, RenderOptions, RenderPragmas, Renderer, Theme, TypstBackendConfig, TypstRenderer,
+    WireLabels,
+};
+use roqoqo::{devices::GenericDevice, Circuit, QuantumProgram, RoqoqoBackendError};
+
+/// Builds the TypstBackend configuration from the python arguments.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to update.
+/// * `cache_dir` - The cache directory setting, `None` to keep the current one.
+/// * `network_policy` - The network policy setting, `None` to keep the current one.
+///
+/// ## Returns
+///
+/// * `Ok(TypstBackendConfig)` - The configuration of the TypstBackend.
+/// * `Err(PyValueError)` - One of the settings is not in a suitable format.
+fn backend_config(
+    mut config: TypstBackendConfig,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+) -> PyResult<TypstBackendConfig> {
+    if let Some(cache_dir) = cache_dir {
+        config =
+            config.cache_directory(CacheDirectory::from_str(&cache_dir).map_err(|x| {
+                PyValueError::new_err(format!("Cache directory not accepted: {x:?}"))
+            })?);
+    }
+    if let Some(network_policy) = network_policy {
+        config =
+            config.network_policy(NetworkPolicy::from_str(&network_policy).map_err(|x| {
+                PyValueError::new_err(format!("Network policy not accepted: {x:?}"))
+            })?);
+    }
+    Ok(config)
+}
+
+/// Overrides the rendering options with the python arguments that are set.
+///
+/// # Arguments
+///
+/// * `options` - The rendering options to update.
+/// * `pixel_per_point` - The pixels per point ratio of the png images.
+/// * `render_pragmas` - How to render Pragmas operations.
+/// * `initialization_mode` - What to display at the beginning of the circuit.
+/// * `max_circuit_length` - The maximum number of gates per qubit before going to a new line.
+/// * `rounding_accuracy` - The number of digits to round to when displaying floats.
+/// * `cache_dir` - The cache directory setting.
+/// * `network_policy` - The network policy setting.
+///
+/// ## Returns
+///
+/// * `Ok(RenderOptions)` - The updated rendering options.
+/// * `Err(PyValueError)` - One of the settings is not in a suitable format.
+#[allow(clippy::too_many_arguments)]
+fn override_options(
+    mut options: RenderOptions,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+) -> PyResult<RenderOptions> {
+    if let Some(pixel_per_point) = pixel_per_point {
+        options.pixels_per_point = pixel_per_point;
+    }
+    if let Some(render_pragmas) = render_pragmas {
+        options.render_pragmas = RenderPragmas::from_str(render_pragmas).map_err(|x| {
+            PyValueError::new_err(format!(
+                "Error: render_pragmas is not in a suitable format: {x:?}"
+            ))
+        })?;
+    }
+    if let Some(initialization_mode) = initialization_mode {
+        options.initialization_mode =
+            InitializationMode::from_str(&initialization_mode).map_err(|x| {
+                PyValueError::new_err(format!("Initialization mode not accepted: {x:?}"))
+            })?;
+    }
+    if max_circuit_length.is_some() {
+        options.max_length = max_circuit_length;
+    }
+    if let Some(rounding_accuracy) = rounding_accuracy {
+        options.rounding_accuracy = rounding_accuracy;
+    }
+    options.backend_config = backend_config(options.backend_config, cache_dir, network_policy)?;
+    Ok(options)
+}
+
+/// Extracts a theme from the name of a built-in theme or from a dict of gate styles per family.
+///
+/// # Arguments
+///
+/// * `theme` - The python theme, e.g. `"dark"` or `{"clifford": {"fill": "yellow"}}`.
+///
+/// ## Returns
+///
+/// * `Ok(Theme)` - The theme, the families missing from the dict keep quill's defaults.
+/// * `Err(PyValueError)` - Unknown theme, gate family or style key.
+/// * `Err(PyTypeError)` - The python object is neither a str nor a dict.
+fn extract_theme(theme: &Bound<PyAny>) -> PyResult<Theme> {
+    if let Ok(name) = theme.extract::<String>() {
+        return Theme::from_str(&name)
+            .map_err(|x| PyValueError::new_err(format!("Theme not accepted: {x:?}")));
+    }
+    let Ok(styles) = theme.cast::<PyDict>() else {
+        return Err(PyTypeError::new_err(
+            "theme must be the name of a theme or a dict of gate styles per gate family",
+        ));
+    };
+    let mut output = Theme::default();
+    for (family, style) in styles.iter() {
+        let family = GateFamily::from_str(&family.extract::<String>()?)
+            .map_err(|x| PyValueError::new_err(format!("Gate family not accepted: {x:?}")))?;
+        let mut gate_style = GateStyle::new();
+        for (key, value) in style.extract::<HashMap<String, String>>()? {
+            gate_style = match key.as_str() {
+                "fill" => gate_style.fill(value),
+                "stroke" => gate_style.stroke(value),
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Gate style not accepted: {key}, use `fill` or `stroke`"
+                    )))
+                }
+            };
+        }
+        output = output.family_style(family, gate_style);
+    }
+    Ok(output)
+}
+
+/// Extracts a qubit order from its name or from the list of the qubits drawn first.
+///
+/// # Arguments
+///
+/// * `qubit_order` - The python qubit order, e.g. `"reversed"` or `[2, 0, 1]`.
+///
+/// ## Returns
+///
+/// * `Ok(QubitOrder)` - The qubit order.
+/// * `Err(PyValueError)` - Unknown qubit order.
+/// * `Err(PyTypeError)` - The python object is neither a str nor a list of int.
+fn extract_qubit_order(qubit_order: &Bound<PyAny>) -> PyResult<QubitOrder> {
+    if let Ok(name) = qubit_order.extract::<String>() {
+        return QubitOrder::from_str(&name)
+            .map_err(|x| PyValueError::new_err(format!("Qubit order not accepted: {x:?}")));
+    }
+    qubit_order
+        .extract::<Vec<usize>>()
+        .map(QubitOrder::Custom)
+        .map_err(|_| {
+            PyTypeError::new_err(
+                "qubit_order must be the name of an order or a list of the qubits drawn first",
+            )
+        })
+}
+
+/// Extracts the rendering options from a RenderOptions instance or a dict of its arguments.
+///
+/// # Arguments
+///
+/// * `options` - The python rendering options, the default ones if not set.
+///
+/// ## Returns
+///
+/// * `Ok(RenderOptions)` - The rendering options.
+/// * `Err(PyTypeError)` - The python object is neither a RenderOptions nor a dict.
+fn extract_options(options: Option<&Bound<PyAny>>) -> PyResult<RenderOptions> {
+    let Some(options) = options else {
+        return Ok(RenderOptions::default());
+    };
+    if let Ok(wrapper) = options.extract::<RenderOptionsWrapper>() {
+        return Ok(wrapper.internal);
+    }
+    if let Ok(kwargs) = options.cast::<PyDict>() {
+        let wrapper = options
+            .py()
+            .get_type::<RenderOptionsWrapper>()
+            .call((), Some(kwargs))?
+            .extract::<RenderOptionsWrapper>()?;
+        return Ok(wrapper.internal);
+    }
+    Err(PyTypeError::new_err(
+        "options must be a RenderOptions or a dict of RenderOptions arguments",
+    ))
+}
+
+/// Converts the circuit to a typst string, emitting the conversion warnings as python warnings.
+///
+/// # Arguments
+///
+/// * `py` - The python interpreter.
+/// * `circuit` - The circuit to convert.
+/// * `options` - The rendering options.
+///
+/// ## Returns
+///
+/// * `Ok(String)` - The typst representation of the circuit.
+/// * `Err(PyValueError)` - Operation not supported.
+fn typst_str_with_warnings(
+    py: Python,
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> PyResult<String> {
+    let (typst_str, warnings) = circuit_into_typst_str_with_warnings(circuit, options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    emit_warnings(py, warnings)?;
+    Ok(typst_str)
+}
+
+/// Emits the warnings of the circuit drawing as python UserWarnings.
+///
+/// # Arguments
+///
+/// * `py` - The python interpreter.
+/// * `warnings` - The warnings to emit.
+///
+/// ## Returns
+///
+/// * `Ok(())` - The warnings were emitted.
+/// * `Err(PyErr)` - A warning was turned into an error by the python warning filters.
+fn emit_warnings(py: Python, warnings: Vec<String>) -> PyResult<()> {
+    for warning in warnings {
+        let message = CString::new(warning)
+            .map_err(|x| PyValueError::new_err(format!("Invalid warning message: {x:?}")))?;
+        PyErr::warn(py, &py.get_type::<PyUserWarning>(), &message, 1)?;
+    }
+    Ok(())
+}
+
+/// Converts a circuit to its text diagram, emitting the warnings as python UserWarnings.
+///
+/// # Arguments
+///
+/// * `py` - The python interpreter.
+/// * `circuit` - The circuit to convert.
+/// * `options` - The rendering options.
+///
+/// ## Returns
+///
+/// * `Ok(String)` - The text diagram of the circuit.
+/// * `Err(PyValueError)` - Operation not supported.
+fn text_with_warnings(py: Python, circuit: &Circuit, options: &RenderOptions) -> PyResult<String> {
+    let layout = circuit_into_layout(circuit, options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    emit_warnings(py, layout.warnings.clone())?;
+    Ok(layout_to_text(&layout, options))
+}
+
+/// Renders a circuit and displays it as an image output.
+///
+/// # Arguments
+///
+/// * `py` - The python interpreter.
+/// * `circuit` - The circuit to display.
+/// * `options` - The rendering options.
+///
+/// ## Returns
+///
+/// * `Ok(())` - The circuit was displayed.
+/// * `Err(PyValueError)` - Operation not supported.
+fn display_circuit(py: Python, circuit: &Circuit, options: &RenderOptions) -> PyResult<()> {
+    let typst_str = typst_str_with_warnings(py, circuit, options)?;
+    let image = render_typst_str(typst_str, options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    display_image(&image)
+}
+
+/// Encodes an image as png.
+///
+/// # Arguments
+///
+/// * `image` - The image to encode.
+///
+/// ## Returns
+///
+/// * `Ok(Vec<u8>)` - The bytes of the png image.
+/// * `Err(RoqoqoBackendError)` - The image cannot be encoded.
+fn png_bytes(image: &DynamicImage) -> Result<Vec<u8>, RoqoqoBackendError> {
+    let mut buffer = Cursor::new(Vec::new());
+    image
+        .write_to(&mut buffer, image::ImageFormat::Png)
+        .map_err(|x| RoqoqoBackendError::GenericError {
+            msg: format!("Error during the generation of the Png file: {x:?}"),
+        })?;
+    Ok(buffer.into_inner())
+}
+
+/// Displays an image as an image output.
+///
+/// # Arguments
+///
+/// * `image` - The image to display.
+///
+/// ## Returns
+///
+/// * `Ok(())` - The image was displayed.
+/// * `Err(PyValueError)` - The image cannot be encoded.
+fn display_image(image: &DynamicImage) -> PyResult<()> {
+    let buffer = png_bytes(image).map_err(|x| PyValueError::new_err(format!("{x:?}")))?;
+
+    Python::attach(|py| {
+        let pil = PyModule::import(py, "PIL.Image").unwrap();
+        let io = PyModule::import(py, "io").unwrap();
+        let display = PyModule::import(py, "IPython.display").unwrap();
+        let builtins = PyModule::import(py, "builtins").unwrap();
+
+        let bytes_image_data = builtins.call_method1("bytes", (buffer,)).unwrap();
+        let bytes_io = io.call_method1("BytesIO", (bytes_image_data,)).unwrap();
+        let image = pil.call_method1("open", (bytes_io,)).unwrap();
+
+        display.call_method1("display", (image,)).unwrap();
+    });
+    Ok(())
+}
+
+/// Returns the path where a circuit is saved, appending `.png` if the format is not known.
+///
+/// # Arguments
+///
+/// * `path` - The path given by the user, a directory or a file.
+fn output_path(path: Option<PathBuf>) -> String {
+    match path {
+        Some(path) => {
+            if path.is_dir() && path.exists() {
+                format!("{}/circuit.png", path.to_str().unwrap_or("."))
+            } else {
+                let s = path.to_str().unwrap_or("circuit").to_owned();
+                if s.ends_with(".png") || s.ends_with(".svg") || s.ends_with(".pdf") {
+                    s
+                } else {
+                    format!("{s}.png")
+                }
+            }
+        }
+        None => "circuit.png".to_owned(),
+    }
+}
+
+/// Renders a typst string and saves it as a png, svg or pdf image depending on the extension.
+///
+/// # Arguments
+///
+/// * `renderer` - The renderer compiling the typst string.
+/// * `typst_str` - The typst string to render.
+/// * `path` - The path of the image.
+///
+/// ## Returns
+///
+/// * `Ok(())` - The image was saved.
+/// * `Err(RoqoqoBackendError)` - Error during the compilation or the saving of the image.
+fn save_typst_str(
+    renderer: &Renderer,
+    typst_str: String,
+    path: &str,
+) -> Result<(), RoqoqoBackendError> {
+    let saving_error = |x: std::io::Error| RoqoqoBackendError::GenericError {
+        msg: format!("Error during image saving: {x:?}"),
+    };
+    if path.ends_with(".svg") {
+        return std::fs::write(path, renderer.render_typst_str_svg(typst_str)?)
+            .map_err(saving_error);
+    }
+    if path.ends_with(".pdf") {
+        return std::fs::write(path, renderer.render_typst_str_pdf(typst_str)?)
+            .map_err(saving_error);
+    }
+    std::fs::write(path, png_bytes(&renderer.render_typst_str(typst_str)?)?).map_err(saving_error)
+}
+
+/// Options of the circuit rendering.
+///
+/// The options can be passed to every drawing function of qollage, the arguments given directly
+/// to the function take precedence over the options.
+///
+/// Args:
+///     pixel_per_point (Optional(float)): The pixels per point ratio of the png images, 3.0 by default.
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats, 3 by default.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     fallback_unsupported (bool): Whether to draw the operations not supported by qollage as a
+///         generic box labelled with their name instead of raising an error. A UserWarning names
+///         each operation drawn this way.
+///     theme (Optional(Union[str, dict])): The colours of the gates. Either a built-in theme,
+///         `"default"`, `"light"`, `"dark"`, `"high_contrast"` or `"colour_blind"`, or a dict
+///         mapping gate families (`"rotation"`, `"clifford"`, `"entangler"`, `"measurement"`,
+///         `"noise"`, `"bosonic"`) to a dict with the typst `"fill"` and `"stroke"` of their gates.
+///     background (Optional(str)): The background of the page, `"white"` by default,
+///         `"transparent"` or a typst colour, e.g. `'rgb("#1e1e1e")'`. The text and the wires are
+///         drawn in white on dark colours, e.g. for dark-mode notebooks.
+///     page_margin (Optional(float)): The margin around the circuit in points, 5.0 by default.
+///     qubit_labels (Optional(Dict[int, str])): The names of the qubits by index, e.g. `{0: "ancilla"}`.
+///         They replace `q[n]` at the beginning of the lines, also on the wrapped lines.
+///     boson_labels (Optional(Dict[int, str])): The names of the bosonic modes by index,
+///         e.g. `{0: "cavity A"}`. They replace `b[n]` at the beginning of the lines.
+///     register_labels (Optional(Dict[str, str])): The names displayed for the classical
+///         registers, by register name.
+///     qubit_states (Optional(Dict[int, str])): The initial states of the qubits by index, as
+///         typst math drawn as `|state>`, e.g. `{1: "+", 2: "psi"}`. The other qubits start in `|0>`.
+///     boson_states (Optional(Dict[int, str])): The initial states of the bosonic modes by index.
+///     qubit_order (Optional(Union[str, List[int]])): The order of the qubit wires from top to
+///         bottom, `"ascending"` by default, `"reversed"` to draw the highest qubit at the top or
+///         the list of the qubits drawn first, followed by the others in ascending order. The
+///         controls and the multi-qubit boxes follow the wires of their qubits.
+///     qubit_subset (Optional(List[int])): The qubits to draw. The operations acting only on other
+///         qubits are not drawn, the lines to the other qubits are cut at the boundary.
+///     hide_idle_qubits (bool): Whether to hide the wires of the qubits without any operation.
+///     layering (Optional(str)): How the operations are assigned to the columns, `"greedy"` by
+///         default to draw every operation next to the previous ones on its wires, `"strict"` to
+///         draw them one after the other, `"asap"` or `"alap"` to pack them in moments as soon or
+///         as late as possible, or `"respect_barriers"` to pack them as soon as possible without
+///         moving them across a PragmaStopParallelBlock.
+///     column_numbers (bool): Whether to number the columns above the diagram.
+///     depth_footer (bool): Whether to add a footer with the depth of the diagram and the number
+///         of operations drawn on each qubit.
+///
+/// Raises:
+///     TypeError: The theme is neither a str nor a dict, or the qubit order neither a str nor a list
+///     ValueError: One of the arguments is not in a suitable format
+#[pyclass(name = "RenderOptions", module = "qollage", from_py_object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptionsWrapper {
+    /// The internal roqollage RenderOptions.
+    pub internal: RenderOptions,
+}
+
+#[pymethods]
+impl RenderOptionsWrapper {
+    /// Creates new rendering options.
+    #[new]
+    #[pyo3(signature = (pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, fallback_unsupported=false, theme=None, background=None, page_margin=None, qubit_labels=None, boson_labels=None, register_labels=None, qubit_states=None, boson_states=None, qubit_order=None, qubit_subset=None, hide_idle_qubits=false, layering=None, column_numbers=false, depth_footer=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        pixel_per_point: Option<f32>,
+        render_pragmas: Option<&str>,
+        initialization_mode: Option<String>,
+        max_circuit_length: Option<usize>,
+        rounding_accuracy: Option<usize>,
+        cache_dir: Option<String>,
+        network_policy: Option<String>,
+        fallback_unsupported: bool,
+        theme: Option<&Bound<PyAny>>,
+        background: Option<String>,
+        page_margin: Option<f32>,
+        qubit_labels: Option<HashMap<usize, String>>,
+        boson_labels: Option<HashMap<usize, String>>,
+        register_labels: Option<HashMap<String, String>>,
+        qubit_states: Option<HashMap<usize, String>>,
+        boson_states: Option<HashMap<usize, String>>,
+        qubit_order: Option<&Bound<PyAny>>,
+        qubit_subset: Option<Vec<usize>>,
+        hide_idle_qubits: bool,
+        layering: Option<String>,
+        column_numbers: bool,
+        depth_footer: bool,
+    ) -> PyResult<Self> {
+        let mut internal = override_options(
+            RenderOptions::default(),
+            pixel_per_point,
+            render_pragmas,
+            initialization_mode,
+            max_circuit_length,
+            rounding_accuracy,
+            cache_dir,
+            network_policy,
+        )?
+        .fallback_unsupported(fallback_unsupported);
+        if let Some(theme) = theme {
+            internal.theme = extract_theme(theme)?;
+        }
+        if let Some(background) = background {
+            internal.background = Background::from_str(&background)
+                .map_err(|x| PyValueError::new_err(format!("Background not accepted: {x:?}")))?;
+        }
+        if let Some(page_margin) = page_margin {
+            internal.page_margin = page_margin;
+        }
+        internal.wire_labels = WireLabels {
+            qubits: qubit_labels.unwrap_or_default(),
+            bosons: boson_labels.unwrap_or_default(),
+            registers: register_labels.unwrap_or_default(),
+            qubit_states: qubit_states.unwrap_or_default(),
+            boson_states: boson_states.unwrap_or_default(),
+        };
+        if let Some(qubit_order) = qubit_order {
+            internal.qubit_order = extract_qubit_order(qubit_order)?;
+        }
+        internal.qubit_subset = qubit_subset;
+        internal.hide_idle_qubits = hide_idle_qubits;
+        if let Some(layering) = layering {
+            internal.layering = LayeringStrategy::from_str(&layering).map_err(|x| {
+                PyValueError::new_err(format!("Layering strategy not accepted: {x:?}"))
+            })?;
+        }
+        internal.column_numbers = column_numbers;
+        internal.depth_footer = depth_footer;
+        Ok(Self { internal })
+    }
+
+    /// Returns the json representation of the options, e.g. to store them in a config file.
+    ///
+    /// The custom gate renderers are not serialized.
+    ///
+    /// Returns:
+    ///     str: The serialized options.
+    ///
+    /// Raises:
+    ///     ValueError: Cannot serialize the options to json
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.internal)
+            .map_err(|x| PyValueError::new_err(format!("Cannot serialize RenderOptions: {x:?}")))
+    }
+
+    /// Creates the options from their json representation.
+    ///
+    /// Missing fields take their default value.
+    ///
+    /// Args:
+    ///     input (str): The serialized options.
+    ///
+    /// Returns:
+    ///     RenderOptions: The deserialized options.
+    ///
+    /// Raises:
+    ///     ValueError: Input cannot be deserialized to RenderOptions
+    #[staticmethod]
+    pub fn from_json(input: &str) -> PyResult<Self> {
+        Ok(Self {
+            internal: serde_json::from_str(input).map_err(|x| {
+                PyValueError::new_err(format!("Cannot deserialize RenderOptions: {x:?}"))
+            })?,
+        })
+    }
+
+    /// Draws the operation as a box with a text label across its qubits.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    ///     label (str): The text of the box.
+    pub fn register_label(&mut self, hqslang: &str, label: &str) {
+        self.internal
+            .gate_renderers
+            .register(hqslang, LabelRenderer::new(label));
+    }
+
+    /// Draws the operation as a box with a typst math content on each of its qubits.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    ///     content (str): The typst math content of the boxes.
+    pub fn register_box(&mut self, hqslang: &str, content: &str) {
+        self.internal
+            .gate_renderers
+            .register(hqslang, BoxRenderer::new(content));
+    }
+
+    /// Draws the operation as a multi-qubit gate with a typst math content.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    ///     content (str): The typst math content of the gate.
+    pub fn register_multi_qubit_gate(&mut self, hqslang: &str, content: &str) {
+        self.internal
+            .gate_renderers
+            .register(hqslang, MultiQubitGateRenderer::new(content));
+    }
 
-/// Saves the qoqo circuit as a png image
+    /// Draws the operation with raw quill code.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    ///     items (list[str]): The quill code placed on each qubit of the operation, in
+    ///         increasing qubit order, e.g. `["ctrl(1)", "gate($X$)"]`.
+    pub fn register_typst(&mut self, hqslang: &str, items: Vec<String>) {
+        self.internal
+            .gate_renderers
+            .register(hqslang, TypstRenderer::new(items));
+    }
+
+    /// Removes the custom renderer of the operation.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    pub fn unregister(&mut self, hqslang: &str) {
+        self.internal.gate_renderers.unregister(hqslang);
+    }
+
+    /// Disables the built-in drawing of the operation.
+    ///
+    /// Without a custom renderer, the operation is handled as an operation not supported by
+    /// qollage: it is drawn by the fallback with a warning, or raises an error.
+    ///
+    /// Args:
+    ///     hqslang (str): The hqslang name of the operation.
+    pub fn mark_unsupported(&mut self, hqslang: &str) {
+        self.internal.gate_renderers.mark_unsupported(hqslang);
+    }
+
+    /// Sets whether the operations not supported by qollage are drawn as a generic box
+    /// labelled with their name instead of raising an error.
+    ///
+    /// Args:
+    ///     enabled (bool): Whether to draw the unsupported operations.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.internal
+            .gate_renderers
+            .set_fallback(enabled.then(|| Arc::new(GenericRenderer) as Arc<dyn GateRenderer>));
+    }
+
+    /// Sets the colours of the gates of a family, overriding the theme.
+    ///
+    /// Args:
+    ///     family (str): The gate family, `"rotation"`, `"clifford"`, `"entangler"`,
+    ///         `"measurement"`, `"noise"` or `"bosonic"`.
+    ///     fill (Optional(str)): The typst fill colour of the gates, e.g. `"blue"`.
+    ///     stroke (Optional(str)): The typst stroke of the single-qubit gates, e.g. `"1pt + red"`.
+    ///
+    /// Raises:
+    ///     ValueError: Unknown gate family
+    #[pyo3(signature = (family, fill=None, stroke=None))]
+    pub fn set_gate_style(
+        &mut self,
+        family: &str,
+        fill: Option<String>,
+        stroke: Option<String>,
+    ) -> PyResult<()> {
+        let family = GateFamily::from_str(family)
+            .map_err(|x| PyValueError::new_err(format!("Gate family not accepted: {x:?}")))?;
+        self.internal.theme = self
+            .internal
+            .theme
+            .clone()
+            .family_style(family, GateStyle { fill, stroke });
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.internal)
+    }
+}
+
+/// Renderer of many circuits with the same options.
+///
+/// The typst library, the fonts and the loaded packages are kept across calls, so rendering many
+/// circuits, e.g. from a parameter sweep, is much faster than calling `save_circuit` for each of
+/// them. `save_many` and `render_many` render the circuits in parallel.
+///
+/// Args:
+///     pixel_per_point (Optional(float)): The pixels per point ratio of the png images.
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     ValueError: One of the arguments is not in a suitable format
+#[pyclass(name = "Renderer", module = "qollage", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct RendererWrapper {
+    /// The internal roqollage Renderer.
+    pub internal: Renderer,
+}
+
+impl RendererWrapper {
+    /// Converts the python circuits and their typst strings, emitting the conversion warnings.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuits` - The python circuits.
+    ///
+    /// ## Returns
+    ///
+    /// * `Ok(Vec<String>)` - The typst string of each circuit.
+    /// * `Err(PyErr)` - A circuit cannot be converted.
+    fn typst_strs(&self, circuits: &[Bound<PyAny>]) -> PyResult<Vec<String>> {
+        circuits
+            .iter()
+            .map(|circuit| {
+                let py = circuit.py();
+                let circuit = convert_into_circuit(circuit).map_err(|x| {
+                    PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+                })?;
+                typst_str_with_warnings(py, &circuit, self.internal.options())
+            })
+            .collect()
+    }
+}
+
+#[pymethods]
+impl RendererWrapper {
+    /// Creates a new renderer.
+    #[new]
+    #[pyo3(signature = (pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        pixel_per_point: Option<f32>,
+        render_pragmas: Option<&str>,
+        initialization_mode: Option<String>,
+        max_circuit_length: Option<usize>,
+        rounding_accuracy: Option<usize>,
+        cache_dir: Option<String>,
+        network_policy: Option<String>,
+        options: Option<&Bound<PyAny>>,
+    ) -> PyResult<Self> {
+        let options = override_options(
+            extract_options(options)?,
+            pixel_per_point,
+            render_pragmas,
+            initialization_mode,
+            max_circuit_length,
+            rounding_accuracy,
+            cache_dir,
+            network_policy,
+        )?;
+        Ok(Self {
+            internal: Renderer::new(options)
+                .map_err(|x| PyValueError::new_err(format!("Cannot create Renderer: {x:?}")))?,
+        })
+    }
+
+    /// Returns the rendering options of the renderer.
+    ///
+    /// Returns:
+    ///     RenderOptions: The rendering options.
+    pub fn options(&self) -> RenderOptionsWrapper {
+        RenderOptionsWrapper {
+            internal: self.internal.options().clone(),
+        }
+    }
+
+    /// Displays the qoqo circuit as an image output.
+    ///
+    /// Args:
+    ///     circuit (Circuit): The qoqo circuit to draw.
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not supported
+    pub fn draw(&self, circuit: &Bound<PyAny>) -> PyResult<()> {
+        let typst_str = self.typst_strs(std::slice::from_ref(circuit))?.remove(0);
+        let image = self
+            .internal
+            .render_typst_str(typst_str)
+            .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+        display_image(&image)
+    }
+
+    /// Saves the qoqo circuit as a png, svg or pdf image, depending on the extension of `path`.
+    ///
+    /// Args:
+    ///     circuit (Circuit): The qoqo circuit to save.
+    ///     path (Optional(str)): The path of the image, `circuit.png` if not set.
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not supported
+    #[pyo3(signature = (circuit, path=None))]
+    pub fn save(&self, circuit: &Bound<PyAny>, path: Option<PathBuf>) -> PyResult<()> {
+        let typst_str = self.typst_strs(std::slice::from_ref(circuit))?.remove(0);
+        save_typst_str(&self.internal, typst_str, &output_path(path))
+            .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))
+    }
+
+    /// Saves the qoqo circuits as png, svg or pdf images, rendering them in parallel.
+    ///
+    /// Args:
+    ///     circuits (list[Circuit]): The qoqo circuits to save.
+    ///     paths (list[str]): The path of the image of each circuit.
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not supported | The numbers of circuits and paths differ
+    pub fn save_many(
+        &self,
+        py: Python,
+        circuits: Vec<Bound<PyAny>>,
+        paths: Vec<PathBuf>,
+    ) -> PyResult<()> {
+        if circuits.len() != paths.len() {
+            return Err(PyValueError::new_err(format!(
+                "Got {} circuits but {} paths.",
+                circuits.len(),
+                paths.len()
+            )));
+        }
+        let inputs: Vec<(String, String)> = self
+            .typst_strs(&circuits)?
+            .into_iter()
+            .zip(paths.into_iter().map(|path| output_path(Some(path))))
+            .collect();
+        py.detach(|| {
+            self.internal
+                .render_many_with(&inputs, |renderer, (typst_str, path)| {
+                    save_typst_str(renderer, typst_str.clone(), path)
+                })
+                .into_iter()
+                .collect::<Result<Vec<()>, RoqoqoBackendError>>()
+        })
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+        Ok(())
+    }
+
+    /// Renders the qoqo circuits as png images in parallel.
+    ///
+    /// Args:
+    ///     circuits (list[Circuit]): The qoqo circuits to render.
+    ///
+    /// Returns:
+    ///     list[bytes]: The png image of each circuit.
+    ///
+    /// Raises:
+    ///     TypeError: Circuit conversion error
+    ///     ValueError: Operation not supported
+    pub fn render_many(&self, py: Python, circuits: Vec<Bound<PyAny>>) -> PyResult<Vec<Vec<u8>>> {
+        let typst_strs = self.typst_strs(&circuits)?;
+        py.detach(|| {
+            self.internal
+                .render_many_with(&typst_strs, |renderer, typst_str| {
+                    png_bytes(&renderer.render_typst_str(typst_str.clone())?)
+                })
+                .into_iter()
+                .collect::<Result<Vec<Vec<u8>>, RoqoqoBackendError>>()
+        })
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Renderer({:?})", self.internal.options())
+    }
+}
+
+/// Saves the qoqo circuit as a png, svg or pdf image
+///
+/// The format is chosen from the extension of `path`: a path ending with `.svg` is saved as an
+/// svg image, a path ending with `.pdf` is saved as a pdf document and any other path is saved
+/// as a png image.
 ///
 /// Args:
 ///     circuit (Circuit): The qoqo circuit to be saved
 ///     path (String): The path to where the image should be saved
-///     pixel_per_point (f32): The pixels per point ration of the image.  
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.  
 ///        The higher the value, the bigger the image will be but the longer it will take to render  
-///      render_pragmas (bool): How to render Pragmas operations:  
+///        Ignored for svg and pdf images.
+///      render_pragmas (Optional(str)): How to render Pragmas operations:  
 ///        `"all"` to render every pragmas.
 ///        `"none"` to not render any pragmas.
 ///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.  
 ///     initialization_mode (String): What to display at the begginning of the circuit. "state" for "|0>" and  
-///         "qubit" for "q[n]" State will be used if the parameter is not set.
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
 ///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
 ///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages:
+///         `"default"` for `$QOLLAGE_CACHE_DIR` or `.qollage/cache`,
+///         `"user"` for the user cache directory (e.g. `~/.cache/qollage`),
+///         any other value is used as the path of the cache directory.
+///     network_policy (Optional(str)): Whether packages can be downloaded:
+///         `"allow"` to download from the typst registry, `"deny"` to only use cached packages,
+///         or the base URL of a package registry mirror.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
 ///
 /// Raises:
 ///     TypeError: Circuit conversion error
 ///     ValueError: Operation not supported
 #[pyfunction]
-#[pyo3(signature = (circuit, path=None, pixel_per_point=3.0, render_pragmas="all", initialization_mode=None, max_circuit_length=None, rounding_accuracy=None))]
+#[pyo3(signature = (circuit, path=None, pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
 pub fn save_circuit(
     circuit: &Bound<PyAny>,
     path: Option<PathBuf>,
-    pixel_per_point: f32,
-    render_pragmas: &str,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
     initialization_mode: Option<String>,
     max_circuit_length: Option<usize>,
     rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
 ) -> PyResult<()> {
+    let py = circuit.py();
     let circuit = convert_into_circuit(circuit).map_err(|x| {
         PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
     })?;
-    let initialization_mode = initialization_mode
-        .map(|mode: String| InitializationMode::from_str(mode.as_str()))
-        .transpose()
-        .map_err(|x| PyValueError::new_err(format!("Initialization mode not accepted: {x:?}")))?;
-    let image = circuit_to_image(
-        &circuit,
-        Some(pixel_per_point),
-        RenderPragmas::from_str(render_pragmas).map_err(|x| {
-            PyValueError::new_err(format!(
-                "Error: render_pragmas is not in a suitable format: {x:?}"
-            ))
-        })?,
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
         initialization_mode,
         max_circuit_length,
         rounding_accuracy,
-    )
-    .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+        cache_dir,
+        network_policy,
+    )?;
 
-    let path = match path {
-        Some(path) => {
-            if path.is_dir() && path.exists() {
-                format!("{}/circuit.png", path.to_str().unwrap_or("."))
-            } else {
-                let s = path.to_str().unwrap_or("circuit").to_owned();
-                if s.ends_with(".png") {
-                    s
-                } else {
-                    format!("{s}.png")
-                }
-            }
-        }
-        None => "circuit.png".to_owned(),
-    };
-    image
-        .save(path)
-        .map_err(|x| PyValueError::new_err(format!("Error during image saving: {x:?}")))?;
-    Ok(())
+    let typst_str = typst_str_with_warnings(py, &circuit, &options)?;
+    let renderer = Renderer::new(options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    save_typst_str(&renderer, typst_str, &output_path(path))
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))
 }
 
 /// Displays the qoqo circuit as an image output
 ///
 /// Args:
 ///     circuit (Circuit): The qoqo circuit to draw
-///     pixel_per_point (Option<f32>): The pixels per point ration of the image.  
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.  
 ///        The higher the value, the bigger the image will be but the longer it will take to render  
-///     render_pragmas (bool): How to render Pragmas operations:  
+///     render_pragmas (Optional(str)): How to render Pragmas operations:  
 ///        `"all"` to render every pragmas.
 ///        `"none"` to not render any pragmas.
 ///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.  
 ///     initialization_mode (String): What to display at the begginning of the circuit. "state" for "|0>" and  
-///         "qubit" for "q[n]" State will be used if the parameter is not set.
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
 ///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
 ///         The default setting `None` does not create a new line.
-///    rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages:
+///         `"default"` for `$QOLLAGE_CACHE_DIR` or `.qollage/cache`,
+///         `"user"` for the user cache directory (e.g. `~/.cache/qollage`),
+///         any other value is used as the path of the cache directory.
+///     network_policy (Optional(str)): Whether packages can be downloaded:
+///         `"allow"` to download from the typst registry, `"deny"` to only use cached packages,
+///         or the base URL of a package registry mirror.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
 ///
 /// Raises:
 ///     TypeError: Circuit conversion error
 ///     ValueError: Operation not supported
 #[pyfunction]
-#[pyo3(signature = (circuit, pixel_per_point=3.0, render_pragmas="All", initialization_mode=None, max_circuit_length=None, rounding_accuracy=None))]
+#[pyo3(signature = (circuit, pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
 pub fn draw_circuit(
     circuit: &Bound<PyAny>,
-    pixel_per_point: f32,
-    render_pragmas: &str,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
     initialization_mode: Option<String>,
     max_circuit_length: Option<usize>,
     rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
 ) -> PyResult<()> {
+    let py = circuit.py();
     let circuit = convert_into_circuit(circuit).map_err(|x| {
         PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
     })?;
-    let initialization_mode = initialization_mode
-        .map(|mode: String| InitializationMode::from_str(mode.as_str()))
-        .transpose()
-        .map_err(|x| PyValueError::new_err(format!("Initialization mode not accepted: {x:?}")))?;
-    let image = circuit_to_image(
-        &circuit,
-        Some(pixel_per_point),
-        RenderPragmas::from_str(render_pragmas).unwrap(),
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
         initialization_mode,
         max_circuit_length,
         rounding_accuracy,
-    )
-    .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
-    let mut buffer = Cursor::new(Vec::new());
-    image
-        .write_to(&mut buffer, image::ImageFormat::Png)
-        .map_err(|x| {
-            PyValueError::new_err(format!(
-                "Error during the generation of the Png file: {x:?}"
-            ))
-        })?;
-
-    Python::attach(|py| {
-        let pil = PyModule::import(py, "PIL.Image").unwrap();
-        let io = PyModule::import(py, "io").unwrap();
-        let display = PyModule::import(py, "IPython.display").unwrap();
-        let builtins = PyModule::import(py, "builtins").unwrap();
-
-        let bytes_image_data = builtins
-            .call_method1("bytes", (buffer.clone().into_inner(),))
-            .unwrap();
-        let bytes_io = io.call_method1("BytesIO", (bytes_image_data,)).unwrap();
-        let image = pil.call_method1("open", (bytes_io,)).unwrap();
-
-        display.call_method1("display", (image,)).unwrap();
-    });
-    Ok(())
+        cache_dir,
+        network_policy,
+    )?;
+    display_circuit(py, &circuit, &options)
 }
 
 /// Displays the qoqo circuit as an image output
 ///
 /// Args:
 ///     circuit (Circuit): The qoqo circuit to draw
-///     render_pragmas (bool): How to render Pragmas operations:  
+///     render_pragmas (Optional(str)): How to render Pragmas operations:  
 ///        `"all"` to render every pragmas.
 ///        `"none"` to not render any pragmas.
 ///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.  
 ///     initialization_mode (String): What to display at the begginning of the circuit. "state" for "|0>" and  
-///         "qubit" for "q[n]" State will be used if the parameter is not set.
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
 ///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
 ///         The default setting `None` does not create a new line.
 ///    rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
 ///
 /// Raises:
 ///     TypeError: Circuit conversion error
 ///     ValueError: Operation not supported
 #[pyfunction]
-#[pyo3(signature = (circuit, render_pragmas="All", initialization_mode=None, max_circuit_length=None, rounding_accuracy=None))]
+#[pyo3(signature = (circuit, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, options=None))]
 pub fn circuit_to_typst_str(
     circuit: &Bound<PyAny>,
-    render_pragmas: &str,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<String> {
+    let py = circuit.py();
+    let circuit = convert_into_circuit(circuit).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+    })?;
+    let options = override_options(
+        extract_options(options)?,
+        None,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        None,
+        None,
+    )?;
+    typst_str_with_warnings(py, &circuit, &options)
+}
+
+/// Returns the circuit's representation as a LaTeX quantikz environment.
+/// The output uses the `quantikz2` TikZ library (`\usetikzlibrary{quantikz2}`).
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to convert.
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Returns:
+///     str: The circuit's representation in LaTeX quantikz.
+///
+/// Raises:
+///     TypeError: Circuit conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (circuit, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, options=None))]
+pub fn circuit_to_quantikz_str(
+    circuit: &Bound<PyAny>,
+    render_pragmas: Option<&str>,
     initialization_mode: Option<String>,
     max_circuit_length: Option<usize>,
     rounding_accuracy: Option<usize>,
+    options: Option<&Bound<PyAny>>,
 ) -> PyResult<String> {
+    let py = circuit.py();
+    let circuit = convert_into_circuit(circuit).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+    })?;
+    let options = override_options(
+        extract_options(options)?,
+        None,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        None,
+        None,
+    )?;
+    let (quantikz_str, warnings) = circuit_into_quantikz_str_with_warnings(&circuit, &options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    emit_warnings(py, warnings)?;
+    Ok(quantikz_str)
+}
+
+/// Returns the circuit's representation as a text diagram drawn with box-drawing characters,
+/// e.g. to display it in a terminal or a log.
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to draw.
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Returns:
+///     str: The text diagram of the circuit.
+///
+/// Raises:
+///     TypeError: Circuit conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (circuit, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, options=None))]
+pub fn circuit_to_text(
+    circuit: &Bound<PyAny>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<String> {
+    let py = circuit.py();
+    let circuit = convert_into_circuit(circuit).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+    })?;
+    let options = override_options(
+        extract_options(options)?,
+        None,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        None,
+        None,
+    )?;
+    text_with_warnings(py, &circuit, &options)
+}
+
+/// Prints the circuit's representation as a text diagram drawn with box-drawing characters.
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to draw.
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     TypeError: Circuit conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (circuit, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, options=None))]
+pub fn print_circuit(
+    circuit: &Bound<PyAny>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let py = circuit.py();
+    let text = circuit_to_text(
+        circuit,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        options,
+    )?;
+    // Printed through python so that the output is redirected like any other print.
+    PyModule::import(py, "builtins")?.call_method1("print", (text,))?;
+    Ok(())
+}
+
+/// Returns the column of the diagram in which each operation of the circuit is drawn.
+///
+/// The operations are indexed in the order of the circuit iteration, the definitions first. The
+/// operations that are not drawn, e.g. the definitions or the pragmas that are not rendered, have
+/// no column.
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to draw.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments.
+///
+/// Returns:
+///     Dict[int, int]: The column of each drawn operation, by index of the operation.
+///
+/// Raises:
+///     TypeError: Circuit conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (circuit, options=None))]
+pub fn circuit_operation_columns(
+    circuit: &Bound<PyAny>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<HashMap<usize, usize>> {
+    let circuit = convert_into_circuit(circuit).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+    })?;
+    let layout = circuit_into_layout(&circuit, &extract_options(options)?)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    Ok(layout.operation_columns)
+}
+
+/// Converts an OpenQASM 2 or 3 program to a circuit and displays it as an image output, in the
+/// same style as the qoqo circuits.
+///
+/// The gates of the standard libraries (`qelib1.inc` and `stdgates.inc`) are mapped to the
+/// corresponding qoqo operations and the gates defined in the program are expanded.
+///
+/// Args:
+///     qasm (str): The QASM program to draw.
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.
+///        The higher the value, the bigger the image will be but the longer it will take to render
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     ValueError: Invalid or unsupported QASM program | Operation not supported
+#[pyfunction]
+#[pyo3(signature = (qasm, pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_qasm(
+    py: Python,
+    qasm: &str,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let circuit = qasm_to_circuit(qasm)
+        .map_err(|x| PyValueError::new_err(format!("Cannot convert QASM to Circuit: {x:?}")))?;
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        cache_dir,
+        network_policy,
+    )?;
+    display_circuit(py, &circuit, &options)
+}
+
+/// Converts a qoqo QuantumProgram to a roqoqo QuantumProgram through its json representation.
+///
+/// # Arguments
+///
+/// * `quantum_program` - The python QuantumProgram.
+///
+/// ## Returns
+///
+/// * `Ok(QuantumProgram)` - The roqoqo QuantumProgram.
+/// * `Err(PyTypeError)` - The python object is not a QuantumProgram.
+fn convert_into_quantum_program(quantum_program: &Bound<PyAny>) -> PyResult<QuantumProgram> {
+    let json = quantum_program
+        .call_method0("to_json")
+        .and_then(|json| json.extract::<String>())
+        .map_err(|x| {
+            PyTypeError::new_err(format!(
+                "Cannot convert python object to QuantumProgram: {x:?}"
+            ))
+        })?;
+    serde_json::from_str(&json).map_err(|x| {
+        PyTypeError::new_err(format!(
+            "Cannot convert python object to QuantumProgram: {x:?}"
+        ))
+    })
+}
+
+/// Displays the qoqo QuantumProgram as an image output
+///
+/// The constant circuit and each measurement circuit are drawn as labelled panels, below the
+/// input parameter names of the program. For PauliZProduct measurements, each panel lists the
+/// Pauli products measured by its circuit. For PauliZProduct and CheatedPauliZProduct
+/// measurements, the table of the Pauli products and the formulas of the expectation values are
+/// drawn next to the circuits.
+///
+/// Args:
+///     quantum_program (QuantumProgram): The qoqo QuantumProgram to draw
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.
+///        The higher the value, the bigger the image will be but the longer it will take to render
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     TypeError: QuantumProgram conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (quantum_program, pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_quantum_program(
+    quantum_program: &Bound<PyAny>,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let py = quantum_program.py();
+    let quantum_program = convert_into_quantum_program(quantum_program)?;
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
+        initialization_mode,
+        max_circuit_length,
+        rounding_accuracy,
+        cache_dir,
+        network_policy,
+    )?;
+    let (typst_str, warnings) =
+        quantum_program_into_typst_str_with_warnings(&quantum_program, &options).map_err(|x| {
+            PyValueError::new_err(format!("Error during QuantumProgram drawing: {x:?}"))
+        })?;
+    emit_warnings(py, warnings)?;
+    let image = render_typst_str(typst_str, &options).map_err(|x| {
+        PyValueError::new_err(format!("Error during QuantumProgram drawing: {x:?}"))
+    })?;
+    display_image(&image)
+}
+
+/// Converts a qoqo device to a roqoqo GenericDevice through the json representation of its
+/// generic device.
+///
+/// # Arguments
+///
+/// * `device` - The python device, e.g. an AllToAllDevice, a SquareLatticeDevice or a GenericDevice.
+///
+/// ## Returns
+///
+/// * `Ok(GenericDevice)` - The roqoqo GenericDevice with the same qubits, gate times and
+///   decoherence rates.
+/// * `Err(PyTypeError)` - The python object is not a device.
+fn convert_into_device(device: &Bound<PyAny>) -> PyResult<GenericDevice> {
+    let generic_device = if device.hasattr("generic_device")? {
+        device.call_method0("generic_device")?
+    } else {
+        device.clone()
+    };
+    let json = generic_device
+        .call_method0("to_json")
+        .and_then(|json| json.extract::<String>())
+        .map_err(|x| {
+            PyTypeError::new_err(format!("Cannot convert python object to device: {x:?}"))
+        })?;
+    serde_json::from_str(&json)
+        .map_err(|x| PyTypeError::new_err(format!("Cannot convert python object to device: {x:?}")))
+}
+
+/// Displays the two-qubit connectivity graph of a qoqo device as an image output
+///
+/// The qubits are drawn on a lattice when the connectivity allows it, e.g. for a
+/// SquareLatticeDevice, and on a circle otherwise. The qubits are coloured by their total
+/// decoherence rate and the edges are annotated with the gate times of the two-qubit gates.
+///
+/// Args:
+///     device (Device): The qoqo device to draw, e.g. an AllToAllDevice, a SquareLatticeDevice
+///         or a GenericDevice.
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.
+///        The higher the value, the bigger the image will be but the longer it will take to render
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     TypeError: Device conversion error
+///     ValueError: Error during the rendering
+#[pyfunction]
+#[pyo3(signature = (device, pixel_per_point=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+pub fn draw_device(
+    device: &Bound<PyAny>,
+    pixel_per_point: Option<f32>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let device = convert_into_device(device)?;
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        None,
+        None,
+        None,
+        rounding_accuracy,
+        cache_dir,
+        network_policy,
+    )?;
+    let image = device_to_image(&device, &options)
+        .map_err(|x| PyValueError::new_err(format!("Error during device drawing: {x:?}")))?;
+    display_image(&image)
+}
+
+/// Displays the qoqo circuit as an image output, with the operations that cannot run as is on a
+/// device outlined in red
+///
+/// The operations acting on qubits outside of the device, the gates that are not in the gate set
+/// of the device and the two-qubit gates acting on qubits that are not connected in the device
+/// are outlined, a legend below the circuit lists them.
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to draw
+///     device (Device): The qoqo device the circuit should run on, e.g. an AllToAllDevice, a
+///         SquareLatticeDevice or a GenericDevice.
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.
+///        The higher the value, the bigger the image will be but the longer it will take to render
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     initialization_mode (Optional(str)): What to display at the begginning of the circuit. "state" for "|0>" and
+///         "qubit" for "q[n]", "inferred" for the states prepared by the PragmaSetStateVector,
+///         PragmaSetDensityMatrix and InputBit operations at the beginning of the circuit.
+///         State will be used if the parameter is not set.
+///     max_circuit_length (Optional(int)): The maximum number of gates per qubit before going to a new line.
+///         The default setting `None` does not create a new line.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     TypeError: Circuit or device conversion error
+///     ValueError: Operation not supported
+#[pyfunction]
+#[pyo3(signature = (circuit, device, pixel_per_point=None, render_pragmas=None, initialization_mode=None, max_circuit_length=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_circuit_on_device(
+    circuit: &Bound<PyAny>,
+    device: &Bound<PyAny>,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
+    initialization_mode: Option<String>,
+    max_circuit_length: Option<usize>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let py = circuit.py();
     let circuit = convert_into_circuit(circuit).map_err(|x| {
         PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
     })?;
-    let initialization_mode = initialization_mode
-        .map(|mode: String| InitializationMode::from_str(mode.as_str()))
-        .transpose()
-        .map_err(|x| PyValueError::new_err(format!("Initialization mode not accepted: {x:?}")))?;
-    circuit_into_typst_str(
-        &circuit,
-        RenderPragmas::from_str(render_pragmas).unwrap(),
+    let device = convert_into_device(device)?;
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
         initialization_mode,
         max_circuit_length,
         rounding_accuracy,
-    )
-    .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))
+        cache_dir,
+        network_policy,
+    )?;
+    let (typst_str, warnings) =
+        circuit_on_device_into_typst_str_with_warnings(&circuit, &device, &options)
+            .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    emit_warnings(py, warnings)?;
+    let image = render_typst_str(typst_str, &options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    display_image(&image)
+}
+
+/// Displays the qoqo circuit as a timeline image output, the width of the operations being
+/// proportional to their duration
+///
+/// The durations are the gate times of the device and the times of the PragmaSleep and
+/// PragmaStopParallelBlock operations, the other operations are drawn as instantaneous markers.
+/// The idle periods of the qubits are shaded and a time axis is drawn below the circuit.
+///
+/// Args:
+///     circuit (Circuit): The qoqo circuit to draw
+///     device (Device): The qoqo device giving the gate times, e.g. an AllToAllDevice, a
+///         SquareLatticeDevice or a GenericDevice.
+///     pixel_per_point (Optional(float)): The pixels per point ration of the image.
+///        The higher the value, the bigger the image will be but the longer it will take to render
+///     render_pragmas (Optional(str)): How to render Pragmas operations:
+///        `"all"` to render every pragmas.
+///        `"none"` to not render any pragmas.
+///        `"PragmaOperation1, PragmaOperation2"` to render only some pragmas.
+///     rounding_accuracy (Optional(int)): The number of digits to round to when displaying floats.
+///     cache_dir (Optional(str)): Where to cache the downloaded typst packages.
+///     network_policy (Optional(str)): Whether packages can be downloaded.
+///     options (Optional(Union[RenderOptions, dict])): The rendering options, or a dict of the
+///         RenderOptions arguments. The other arguments take precedence over the options.
+///
+/// Raises:
+///     TypeError: Circuit or device conversion error
+///     ValueError: Error during the rendering
+#[pyfunction]
+#[pyo3(signature = (circuit, device, pixel_per_point=None, render_pragmas=None, rounding_accuracy=None, cache_dir=None, network_policy=None, options=None))]
+#[allow(clippy::too_many_arguments)]
+pub fn draw_circuit_timeline(
+    circuit: &Bound<PyAny>,
+    device: &Bound<PyAny>,
+    pixel_per_point: Option<f32>,
+    render_pragmas: Option<&str>,
+    rounding_accuracy: Option<usize>,
+    cache_dir: Option<String>,
+    network_policy: Option<String>,
+    options: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let py = circuit.py();
+    let circuit = convert_into_circuit(circuit).map_err(|x| {
+        PyTypeError::new_err(format!("Cannot convert python object to Circuit: {x:?}"))
+    })?;
+    let device = convert_into_device(device)?;
+    let options = override_options(
+        extract_options(options)?,
+        pixel_per_point,
+        render_pragmas,
+        None,
+        None,
+        rounding_accuracy,
+        cache_dir,
+        network_policy,
+    )?;
+    let (typst_str, warnings) =
+        circuit_into_timeline_typst_str_with_warnings(&circuit, &device, &options);
+    emit_warnings(py, warnings)?;
+    let image = render_typst_str(typst_str, &options)
+        .map_err(|x| PyValueError::new_err(format!("Error during Circuit drawing: {x:?}")))?;
+    display_image(&image)
 }
@@ -31,7 +31,18 @@ pub use backend::*;
 #[pymodule]
 fn qollage(_py: Python, module: &Bound<PyModule>) -> PyResult<()> {
     module.add_function(wrap_pyfunction!(draw_circuit, module)?)?;
+    module.add_function(wrap_pyfunction!(draw_qasm, module)?)?;
+    module.add_function(wrap_pyfunction!(draw_quantum_program, module)?)?;
+    module.add_function(wrap_pyfunction!(draw_device, module)?)?;
+    module.add_function(wrap_pyfunction!(draw_circuit_on_device, module)?)?;
+    module.add_function(wrap_pyfunction!(draw_circuit_timeline, module)?)?;
     module.add_function(wrap_pyfunction!(save_circuit, module)?)?;
     module.add_function(wrap_pyfunction!(circuit_to_typst_str, module)?)?;
+    module.add_function(wrap_pyfunction!(circuit_to_quantikz_str, module)?)?;
+    module.add_function(wrap_pyfunction!(circuit_to_text, module)?)?;
+    module.add_function(wrap_pyfunction!(print_circuit, module)?)?;
+    module.add_function(wrap_pyfunction!(circuit_operation_columns, module)?)?;
+    module.add_class::<RenderOptionsWrapper>()?;
+    module.add_class::<RendererWrapper>()?;
     Ok(())
 }
@@ -12,10 +12,17 @@
 //
 //! Testing the qollage Backend
 
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
-use pyo3::{types::PyAnyMethods, Bound, Py, Python};
-use qollage::{circuit_to_typst_str, draw_circuit, save_circuit};
+use pyo3::{
+    types::{PyAnyMethods, PyDict, PyDictMethods},
+    Bound, IntoPyObject, Py, Python,
+};
+use qollage::{
+    circuit_operation_columns, circuit_to_quantikz_str, circuit_to_text, circuit_to_typst_str,
+    draw_circuit, draw_circuit_on_device, draw_circuit_timeline, draw_device, draw_qasm,
+    draw_quantum_program, print_circuit, save_circuit, RenderOptionsWrapper, RendererWrapper,
+};
 use qoqo::{operations::convert_operation_to_pyobject, CircuitWrapper};
 use qoqo_calculator::CalculatorFloat;
 use qoqo_calculator_pyo3::CalculatorFloatWrapper;
@@ -61,8 +68,11 @@ fn test_file() {
         assert!(save_circuit(
             &circuitpy,
             None,
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
             None,
             None,
             None
@@ -71,8 +81,11 @@ fn test_file() {
         assert!(save_circuit(
             &circuitpy,
             Some("..".into()),
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
             None,
             None,
             None
@@ -81,8 +94,11 @@ fn test_file() {
         assert!(save_circuit(
             &circuitpy,
             Some("qollage/circuit.png".into()),
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
             None,
             None,
             None
@@ -91,8 +107,11 @@ fn test_file() {
         assert!(save_circuit(
             &circuitpy,
             Some("../roqollage/circuit".into()),
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
             None,
             None,
             None
@@ -101,10 +120,39 @@ fn test_file() {
         assert!(save_circuit(
             &circuitpy,
             None,
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
             Some("Qubit".to_owned()),
             None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_ok());
+        assert!(save_circuit(
+            &circuitpy,
+            Some("circuit.svg".into()),
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_ok());
+        assert!(save_circuit(
+            &circuitpy,
+            Some("circuit.pdf".into()),
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
+            None,
+            None,
             None
         )
         .is_ok());
@@ -114,6 +162,8 @@ fn test_file() {
         "../circuit.png",
         "qollage/circuit.png",
         "../roqollage/circuit.png",
+        "circuit.svg",
+        "circuit.pdf",
     ];
     for path_str in paths {
         let read_in_path = Path::new(path_str);
@@ -159,8 +209,11 @@ fn test_file_error() {
         assert!(save_circuit(
             calc.bind(py),
             None,
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
             None,
             None,
             None
@@ -169,14 +222,42 @@ fn test_file_error() {
         assert!(save_circuit(
             &circuitpy,
             None,
-            1.5,
-            "PragmaOverrotation",
+            Some(1.5),
+            Some("PragmaOverrotation"),
             Some("not_a_mode".to_owned()),
             None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+        assert!(save_circuit(
+            &circuitpy,
+            None,
+            Some(1.5),
+            Some("all"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+        assert!(save_circuit(
+            &circuitpy,
+            None,
+            Some(1.5),
+            Some("all"),
+            None,
+            None,
+            None,
+            None,
+            Some("not_a_policy".to_owned()),
             None
         )
         .is_err());
-        assert!(save_circuit(&circuitpy, None, 1.5, "all", None, None, None).is_err());
     });
 }
 
@@ -206,10 +287,16 @@ fn test_str() {
     Python::attach(|py| {
         let circuitpy = circuitpy_from_circuitru(py, circuit);
 
-        assert!(circuit_to_typst_str(&circuitpy, "", None, None, None).is_ok());
-        assert!(
-            circuit_to_typst_str(&circuitpy, "all", Some("Qubit".to_owned()), None, None).is_ok()
-        );
+        assert!(circuit_to_typst_str(&circuitpy, Some(""), None, None, None, None).is_ok());
+        assert!(circuit_to_typst_str(
+            &circuitpy,
+            Some("all"),
+            Some("Qubit".to_owned()),
+            None,
+            None,
+            None
+        )
+        .is_ok());
     });
 }
 
@@ -247,18 +334,25 @@ fn test_str_error() {
         )
         .unwrap();
 
-        assert!(
-            circuit_to_typst_str(calc.bind(py), "PragmaOverrotation", None, None, None).is_err()
-        );
+        assert!(circuit_to_typst_str(
+            calc.bind(py),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
         assert!(circuit_to_typst_str(
             &circuitpy,
-            "PragmaOverrotation",
+            Some("PragmaOverrotation"),
             Some("not_a_mode".to_owned()),
             None,
+            None,
             None
         )
         .is_err());
-        assert!(circuit_to_typst_str(&circuitpy, "", None, None, None).is_err());
+        assert!(circuit_to_typst_str(&circuitpy, Some(""), None, None, None, None).is_err());
     });
 }
 
@@ -288,13 +382,27 @@ fn test_draw() {
     Python::attach(|py| {
         let circuitpy = circuitpy_from_circuitru(py, circuit);
 
-        assert!(draw_circuit(&circuitpy, 0.5, "none", None, None, None).is_ok());
         assert!(draw_circuit(
             &circuitpy,
-            0.5,
-            "PragmaOverrotation",
+            Some(0.5),
+            Some("none"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_ok());
+        assert!(draw_circuit(
+            &circuitpy,
+            Some(0.5),
+            Some("PragmaOverrotation"),
             Some("State".to_owned()),
             None,
+            None,
+            None,
+            None,
             None
         )
         .is_ok());
@@ -340,16 +448,733 @@ fn test_draw_error() {
         )
         .unwrap();
 
-        assert!(draw_circuit(calc.bind(py), 0.5, "PragmaOverrotation", None, None, None).is_err());
+        assert!(draw_circuit(
+            calc.bind(py),
+            Some(0.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
         assert!(draw_circuit(
             &circuitpy,
-            0.5,
-            "PragmaOverrotation",
+            Some(0.5),
+            Some("PragmaOverrotation"),
             Some("not_a_mode".to_owned()),
             None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+        assert!(draw_circuit(
+            &circuitpy,
+            Some(0.5),
+            Some("PragmaOverrotation"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+    });
+}
+
+#[test]
+fn test_draw_qasm_error() {
+    Python::initialize();
+    Python::attach(|py| {
+        assert!(draw_qasm(
+            py,
+            "OPENQASM 2.0;\nqreg q[2];\nfoo q[0];",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+        assert!(draw_qasm(
+            py,
+            "OPENQASM 2.0;\nqreg q[2];\nh q[0];",
+            None,
+            None,
+            Some("not_a_mode".to_owned()),
+            None,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_err());
+    });
+}
+
+#[test]
+fn test_draw_quantum_program_error() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let calc = Py::new(
+            py,
+            CalculatorFloatWrapper {
+                internal: CalculatorFloat::from("0.0"),
+            },
+        )
+        .unwrap();
+        for not_a_program in [circuitpy.as_any(), calc.bind(py).as_any()] {
+            assert!(draw_quantum_program(
+                not_a_program,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err());
+        }
+    });
+}
+
+#[test]
+fn test_draw_device_error() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let calc = Py::new(
+            py,
+            CalculatorFloatWrapper {
+                internal: CalculatorFloat::from("0.0"),
+            },
+        )
+        .unwrap();
+        for not_a_device in [circuitpy.as_any(), calc.bind(py).as_any()] {
+            assert!(draw_device(not_a_device, None, None, None, None, None).is_err());
+            assert!(draw_circuit_on_device(
+                circuitpy.as_any(),
+                not_a_device,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err());
+            assert!(draw_circuit_timeline(
+                circuitpy.as_any(),
+                not_a_device,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None
+            )
+            .is_err());
+        }
+    });
+}
+
+#[test]
+fn test_render_options() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PragmaBoostNoise::new(CalculatorFloat::from("12.7")));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let options = Py::new(
+            py,
+            RenderOptionsWrapper {
+                internal: roqollage::RenderOptions::new()
+                    .render_pragmas(roqollage::RenderPragmas::None)
+                    .initialization_mode(roqollage::InitializationMode::Qubit),
+            },
+        )
+        .unwrap();
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options.bind(py).as_any()),
+        )
+        .unwrap();
+        assert!(typst_str.contains("q[1]"));
+        assert!(!typst_str.contains("BoostNoise"));
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("initialization_mode", "qubit").unwrap();
+        kwargs.set_item("render_pragmas", "none").unwrap();
+        assert_eq!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap(),
+            typst_str
+        );
+
+        let overridden = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            Some("state".to_owned()),
+            None,
+            None,
+            Some(kwargs.as_any()),
+        )
+        .unwrap();
+        assert!(!overridden.contains("q[1]"));
+
+        let json = options.borrow(py).to_json().unwrap();
+        assert_eq!(
+            RenderOptionsWrapper::from_json(&json).unwrap(),
+            *options.borrow(py)
+        );
+
+        kwargs
+            .set_item("initialization_mode", "not_a_mode")
+            .unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+        let calc = Py::new(
+            py,
+            CalculatorFloatWrapper {
+                internal: CalculatorFloat::from("0.0"),
+            },
+        )
+        .unwrap();
+        assert!(circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(calc.bind(py).as_any())
+        )
+        .is_err());
+    });
+}
+
+#[test]
+fn test_renderer() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("initialization_mode", "qubit").unwrap();
+        let renderer = py
+            .get_type::<RendererWrapper>()
+            .call((), Some(&kwargs))
+            .unwrap()
+            .extract::<RendererWrapper>()
+            .unwrap();
+        assert_eq!(
+            renderer.options().internal.initialization_mode,
+            roqollage::InitializationMode::Qubit
+        );
+
+        let directory = std::env::temp_dir().join("qollage_renderer_test");
+        fs::create_dir_all(&directory).unwrap();
+        let paths = vec![directory.join("first.png"), directory.join("second.svg")];
+        renderer
+            .save_many(
+                py,
+                vec![circuitpy.as_any().clone(), circuitpy.as_any().clone()],
+                paths.clone(),
+            )
+            .unwrap();
+        assert!(paths.iter().all(|path| path.exists()));
+        assert!(renderer
+            .save_many(py, vec![circuitpy.as_any().clone()], paths)
+            .is_err());
+
+        let images = renderer
+            .render_many(py, vec![circuitpy.as_any().clone(); 3])
+            .unwrap();
+        assert_eq!(images.len(), 3);
+        assert!(images.iter().all(|image| image.starts_with(b"\x89PNG")));
+
+        kwargs
+            .set_item("initialization_mode", "not_a_mode")
+            .unwrap();
+        assert!(py
+            .get_type::<RendererWrapper>()
+            .call((), Some(&kwargs))
+            .is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    });
+}
+
+#[test]
+fn test_render_options_theme() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(RotateX::new(0, CalculatorFloat::from(0.5)));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("theme", "light").unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains(r##"gate($ H $, fill: rgb("#e2f0d9"))"##));
+
+        let styles = PyDict::new(py);
+        let clifford = PyDict::new(py);
+        clifford.set_item("fill", "yellow").unwrap();
+        styles.set_item("clifford", &clifford).unwrap();
+        kwargs.set_item("theme", &styles).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("gate($ H $, fill: yellow)"));
+        assert!(typst_str.contains(r#"$ "Rx"(0.5) $"#));
+
+        let mut options = RenderOptionsWrapper {
+            internal: roqollage::RenderOptions::new(),
+        };
+        options
+            .set_gate_style("rotation", Some("blue".to_owned()), None)
+            .unwrap();
+        assert!(options.set_gate_style("other", None, None).is_err());
+        let options_py = Py::new(py, options).unwrap();
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options_py.bind(py).as_any()),
+        )
+        .unwrap();
+        assert!(typst_str.contains(r#"gate($ "Rx"(0.5) $, fill: blue)"#));
+
+        for theme in [
+            "neon".into_pyobject(py).unwrap().into_any(),
+            3_i64.into_pyobject(py).unwrap().into_any(),
+        ] {
+            kwargs.set_item("theme", theme).unwrap();
+            assert!(circuit_to_typst_str(
+                &circuitpy,
+                None,
+                None,
+                None,
+                None,
+                Some(kwargs.as_any())
+            )
+            .is_err());
+        }
+        clifford.set_item("colour", "red").unwrap();
+        kwargs.set_item("theme", &styles).unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+    });
+}
+
+#[test]
+fn test_render_options_background() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("background", "transparent").unwrap();
+        kwargs.set_item("page_margin", 10.0).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("margin: 10pt, fill: none)"));
+
+        kwargs.set_item("background", "black").unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("#set text(fill: white)"));
+        assert!(typst_str.contains("wire: .7pt + white, color: white, fill: black,"));
+
+        kwargs.set_item("page_margin", "wide").unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+    });
+}
+
+#[test]
+fn test_render_options_labels() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 0));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let qubit_labels = PyDict::new(py);
+        qubit_labels.set_item(0, "ancilla").unwrap();
+        let register_labels = PyDict::new(py);
+        register_labels.set_item("ro", "readout").unwrap();
+        let qubit_states = PyDict::new(py);
+        qubit_states.set_item(1, "+").unwrap();
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("qubit_labels", qubit_labels).unwrap();
+        kwargs.set_item("register_labels", register_labels).unwrap();
+        kwargs.set_item("qubit_states", qubit_states).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("lstick($\"ancilla\": |0>$, label: \"Qubits\")"));
+        assert!(typst_str.contains("lstick($|+>$)"));
+        assert!(typst_str.contains("\"readout : \""));
+
+        kwargs.set_item("qubit_labels", "ancilla").unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+    });
+}
+
+#[test]
+fn test_render_options_qubit_arrangement() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 40));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("initialization_mode", "qubit").unwrap();
+        kwargs.set_item("hide_idle_qubits", true).unwrap();
+        kwargs.set_item("qubit_order", "reversed").unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("lstick($q[40]$, label: \"Qubits\")"));
+        assert!(typst_str.contains("lstick($q[0]$)"));
+        assert!(!typst_str.contains("q[1]"));
+
+        kwargs.set_item("qubit_order", vec![40, 0]).unwrap();
+        kwargs.set_item("qubit_subset", vec![0]).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("lstick($q[0]$, label: \"Qubits\")"));
+        assert!(!typst_str.contains("lstick($q[40]$"));
+        assert!(typst_str.contains(r#"gate($ "CNOT" $, label: "with q[40]")"#));
+
+        kwargs.set_item("qubit_order", 1.5).unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+        kwargs.set_item("qubit_order", "sideways").unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+    });
+}
+
+#[test]
+fn test_render_options_layering() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(PauliX::new(1));
+    circuit.add_operation(CNOT::new(0, 1));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        let mut typst_strs = Vec::new();
+        for layering in ["greedy", "strict", "asap", "alap", "respect_barriers"] {
+            kwargs.set_item("layering", layering).unwrap();
+            typst_strs.push(
+                circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                    .unwrap(),
+            );
+        }
+        assert_eq!(typst_strs[0], typst_strs[2]);
+        assert_ne!(typst_strs[0], typst_strs[1]);
+
+        kwargs.set_item("layering", "sideways").unwrap();
+        assert!(
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .is_err()
+        );
+    });
+}
+
+#[test]
+fn test_render_options_column_numbers() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("initialization_mode", "qubit").unwrap();
+        kwargs.set_item("column_numbers", true).unwrap();
+        kwargs.set_item("depth_footer", true).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains("setwire(0), lstick([])"));
+        assert!(typst_str.contains("Depth: 2"));
+        let text =
+            circuit_to_text(&circuitpy, None, None, None, None, Some(kwargs.as_any())).unwrap();
+        assert!(text.ends_with("Gates: q[0]: 2, q[1]: 1"));
+
+        let columns = circuit_operation_columns(&circuitpy, None).unwrap();
+        assert_eq!(columns, HashMap::from([(1, 0), (2, 1)]));
+        kwargs.set_item("layering", "strict").unwrap();
+        let columns = circuit_operation_columns(&circuitpy, Some(kwargs.as_any())).unwrap();
+        assert_eq!(columns, HashMap::from([(1, 0), (2, 1)]));
+    });
+}
+
+#[test]
+fn test_inferred_initialization_mode() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(InputBit::new("ro".to_owned(), 0, true));
+    circuit.add_operation(PragmaSetStateVector::new(ndarray::array![
+        0.0.into(),
+        1.0.into()
+    ]));
+    circuit.add_operation(PauliX::new(0));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            Some("inferred".to_owned()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(typst_str.contains("lstick($|1>$, label: \"Qubits\")"));
+        assert!(typst_str.contains("\"ro = 10 : \""));
+        assert!(!typst_str.contains("SetStatevector"));
+    });
+}
+
+#[test]
+fn test_render_options_renderers() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(GPi2::new(1, CalculatorFloat::from(0.5)));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let mut options = RenderOptionsWrapper {
+            internal: roqollage::RenderOptions::new(),
+        };
+        options.register_label("Hadamard", "Had");
+        options.register_label("GPi2", "GPi2");
+        options.set_fallback(true);
+        let options_py = Py::new(py, options.clone()).unwrap();
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options_py.bind(py).as_any()),
+        )
+        .unwrap();
+        assert!(typst_str.contains(r#"$ "Had" $"#));
+        assert!(typst_str.contains(r#"$ "GPi2" $"#));
+
+        options.set_fallback(false);
+        let options_py = Py::new(py, options).unwrap();
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options_py.bind(py).as_any()),
+        )
+        .unwrap();
+        assert!(typst_str.contains(r#"$ "GPi2" $"#));
+    });
+}
+
+#[test]
+fn test_fallback_unsupported() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(GPi2::new(1, CalculatorFloat::from(0.5)));
+    circuit.add_operation(CNOT::new(0, 1));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("fallback_unsupported", true).unwrap();
+        let typst_str =
+            circuit_to_typst_str(&circuitpy, None, None, None, None, Some(kwargs.as_any()))
+                .unwrap();
+        assert!(typst_str.contains(r#"$ "GPi2"(0.5) $"#));
+
+        let mut options = RenderOptionsWrapper {
+            internal: roqollage::RenderOptions::new(),
+        };
+        options.mark_unsupported("CNOT");
+        let options_py = Py::new(py, options.clone()).unwrap();
+        assert!(circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options_py.bind(py).as_any())
+        )
+        .is_err());
+
+        options.set_fallback(true);
+        let options_py = Py::new(py, options).unwrap();
+        let typst_str = circuit_to_typst_str(
+            &circuitpy,
+            None,
+            None,
+            None,
+            None,
+            Some(options_py.bind(py).as_any()),
+        )
+        .unwrap();
+        assert!(typst_str.contains(r#"mqgate($ "CNOT" $"#));
+    });
+}
+
+#[test]
+fn test_text() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PragmaGlobalPhase::new(CalculatorFloat::from(0.5)));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let text = circuit_to_text(&circuitpy, None, None, None, None, None).unwrap();
+        assert!(text.contains("┤H├"));
+        assert!(text.contains("GlobalPhase"));
+        let text = circuit_to_text(
+            &circuitpy,
+            Some("none"),
+            Some("qubit".to_owned()),
+            Some(1),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(text.starts_with("q[0]"));
+        assert!(!text.contains("GlobalPhase"));
+        assert_eq!(
+            text.lines().filter(|line| line.starts_with("q[0]")).count(),
+            2
+        );
+        assert!(print_circuit(&circuitpy, None, None, None, None, None).is_ok());
+
+        assert!(circuit_to_text(
+            &circuitpy,
+            Some("all"),
+            Some("error".to_owned()),
+            None,
+            None,
+            None
+        )
+        .is_err());
+        let wrong = PyDict::new(py);
+        assert!(circuit_to_text(&wrong, None, None, None, None, None).is_err());
+    });
+}
+
+#[test]
+fn test_quantikz_str() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PragmaGlobalPhase::new(CalculatorFloat::from(0.5)));
+
+    Python::initialize();
+    Python::attach(|py| {
+        let circuitpy = circuitpy_from_circuitru(py, circuit);
+
+        let quantikz_str =
+            circuit_to_quantikz_str(&circuitpy, None, None, None, None, None).unwrap();
+        assert!(quantikz_str.starts_with("\\begin{quantikz}"));
+        assert!(quantikz_str.contains("\\gate{H}"));
+        assert!(quantikz_str.contains("GlobalPhase"));
+        let quantikz_str = circuit_to_quantikz_str(
+            &circuitpy,
+            Some("none"),
+            Some("qubit".to_owned()),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(quantikz_str.contains("\\lstick{$q[0]$}"));
+        assert!(!quantikz_str.contains("GlobalPhase"));
+
+        assert!(circuit_to_quantikz_str(
+            &circuitpy,
+            Some("all"),
+            Some("error".to_owned()),
+            None,
+            None,
             None
         )
         .is_err());
-        assert!(draw_circuit(&circuitpy, 0.5, "PragmaOverrotation", None, None, None).is_err());
+        let wrong = PyDict::new(py);
+        assert!(circuit_to_quantikz_str(&wrong, None, None, None, None, None).is_err());
     });
 }
@@ -0,0 +1,372 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use roqoqo::{
+    operations::{InvolveQubits, InvolvedQubits, Operate, Operation, Substitute},
+    Circuit, RoqoqoBackendError, RoqoqoError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{add_gate, CircuitLayout, LayoutItem, RenderOptions, WireRef};
+
+/// Order in which the qubit wires are drawn, from top to bottom.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QubitOrder {
+    /// Qubit 0 at the top.
+    #[default]
+    Ascending,
+    /// The highest qubit at the top, qubit 0 at the bottom.
+    Reversed,
+    /// The given qubits first, then the other qubits in ascending order.
+    Custom(Vec<usize>),
+}
+
+impl FromStr for QubitOrder {
+    type Err = RoqoqoBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ascending" | "default" | "" => Ok(QubitOrder::Ascending),
+            "reversed" | "descending" => Ok(QubitOrder::Reversed),
+            qubits => qubits
+                .split(',')
+                .map(|qubit| qubit.trim().parse::<usize>())
+                .collect::<Result<Vec<usize>, _>>()
+                .map(QubitOrder::Custom)
+                .map_err(|_| {
+                    RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                        msg: format!(
+                            r#"Invalid qubit order: {s}, use `ascending`, `reversed` or a comma-separated list of qubits."#
+                        ),
+                    })
+                }),
+        }
+    }
+}
+
+/// Arrangement of the qubit wires of a circuit diagram.
+///
+/// The circuit is remapped so that the drawn qubits are the first wires, in their drawing order,
+/// and the hidden qubits the following wires, removed from the layout once it is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QubitArrangement {
+    /// The qubit drawn on each wire, from top to bottom.
+    qubits: Vec<usize>,
+    /// The wire of each qubit of the circuit, the hidden qubits after the drawn ones.
+    mapping: HashMap<usize, usize>,
+}
+
+impl QubitArrangement {
+    /// Creates the arrangement of the qubit wires of a circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to draw.
+    /// * `options` - The qubit order, the qubit subset and whether to hide the idle qubits.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<QubitArrangement>` - The arrangement, `None` when every qubit is drawn in
+    ///   ascending order.
+    pub(crate) fn new(circuit: &Circuit, options: &RenderOptions) -> Option<Self> {
+        if options.qubit_order == QubitOrder::Ascending
+            && options.qubit_subset.is_none()
+            && !options.hide_idle_qubits
+        {
+            return None;
+        }
+        let mut used = BTreeSet::new();
+        for operation in circuit.iter() {
+            if let InvolvedQubits::Set(qubits) = operation.involved_qubits() {
+                used.extend(qubits);
+            }
+        }
+        let mut drawn: Vec<usize> = match &options.qubit_subset {
+            Some(subset) => subset
+                .iter()
+                .copied()
+                .collect::<BTreeSet<usize>>()
+                .into_iter()
+                .collect(),
+            None => (0..used.last().map_or(0, |&max| max + 1)).collect(),
+        };
+        if options.hide_idle_qubits {
+            drawn.retain(|qubit| used.contains(qubit));
+        }
+        let qubits = match &options.qubit_order {
+            QubitOrder::Ascending => drawn,
+            QubitOrder::Reversed => drawn.into_iter().rev().collect(),
+            QubitOrder::Custom(order) => {
+                let mut qubits: Vec<usize> = Vec::new();
+                for qubit in order {
+                    if drawn.contains(qubit) && !qubits.contains(qubit) {
+                        qubits.push(*qubit);
+                    }
+                }
+                drawn.retain(|qubit| !qubits.contains(qubit));
+                qubits.extend(drawn);
+                qubits
+            }
+        };
+        let n_qubits = used
+            .iter()
+            .chain(qubits.iter())
+            .max()
+            .map_or(0, |&max| max + 1);
+        let mut mapping: HashMap<usize, usize> = qubits
+            .iter()
+            .enumerate()
+            .map(|(wire, &qubit)| (qubit, wire))
+            .collect();
+        let hidden: Vec<usize> = (0..n_qubits)
+            .filter(|qubit| !mapping.contains_key(qubit))
+            .collect();
+        for (wire, qubit) in hidden.into_iter().enumerate() {
+            mapping.insert(qubit, qubits.len() + wire);
+        }
+        Some(Self { qubits, mapping })
+    }
+
+    /// Returns the circuit with the qubits remapped to their wires.
+    ///
+    /// The operations acting only on hidden qubits are removed, the operations acting on all the
+    /// qubits, e.g. the state preparations, are kept as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to draw.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((Circuit, Vec<usize>))` - The remapped circuit and the index of each of its
+    ///   operations in the original circuit.
+    /// * `Err(RoqoqoBackendError)` - An operation cannot be remapped.
+    pub(crate) fn arrange(
+        &self,
+        circuit: &Circuit,
+    ) -> Result<(Circuit, Vec<usize>), RoqoqoBackendError> {
+        let mut arranged = Circuit::new();
+        let mut indices = Vec::new();
+        for (index, operation) in circuit.iter().enumerate() {
+            match operation.involved_qubits() {
+                InvolvedQubits::Set(qubits) => {
+                    if qubits
+                        .iter()
+                        .any(|qubit| self.mapping[qubit] < self.qubits.len())
+                    {
+                        arranged.add_operation(
+                            operation
+                                .remap_qubits(&self.mapping)
+                                .map_err(RoqoqoBackendError::RoqoqoError)?,
+                        );
+                        indices.push(index);
+                    }
+                }
+                _ => {
+                    arranged.add_operation(operation.clone());
+                    indices.push(index);
+                }
+            }
+        }
+        Ok((arranged, indices))
+    }
+
+    /// Returns the items drawing an operation of the arranged circuit cut at the boundary of the
+    /// drawn qubits.
+    ///
+    /// An operation acting on drawn and hidden qubits is drawn as a box on each of its drawn
+    /// wires, labelled with the hidden qubits, instead of lines leading out of the diagram. The
+    /// box shows the content of the gate, e.g. its parameters, or the name of the operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation of the arranged circuit.
+    /// * `options` - The options of the representation, e.g. the gate renderers.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Option<Vec<(usize, LayoutItem)>>)` - The box on each drawn wire of the operation,
+    ///   `None` when the operation only acts on drawn qubits.
+    /// * `Err(RoqoqoBackendError)` - The operation is drawn with raw Typst items spanning wires
+    ///   that the arrangement moves apart, their wire offsets cannot be remapped.
+    pub(crate) fn cut(
+        &self,
+        operation: &Operation,
+        options: &RenderOptions,
+    ) -> Result<Option<Vec<(usize, LayoutItem)>>, RoqoqoBackendError> {
+        let wires: BTreeSet<usize> = match operation.involved_qubits() {
+            InvolvedQubits::Set(wires) => wires.into_iter().collect(),
+            _ => return Ok(None),
+        };
+        let (drawn, hidden): (Vec<usize>, Vec<usize>) =
+            wires.iter().partition(|&&wire| wire < self.qubits.len());
+        if hidden.is_empty() && wires.len() < 2 {
+            return Ok(None);
+        }
+        let mut drawing = CircuitLayout::new();
+        let rendered = add_gate(&mut drawing, operation, options).is_ok();
+        let items: Vec<&LayoutItem> = drawing.qubits.iter().flatten().collect();
+        if hidden.is_empty() {
+            if items
+                .iter()
+                .any(|item| matches!(item, LayoutItem::Typst(_)))
+                && !self.keeps_offsets(&wires)
+            {
+                return Err(RoqoqoBackendError::GenericError {
+                    msg: format!(
+                        "The raw Typst items of {} cannot be drawn with the qubit order and \
+                         subset of the RenderOptions, their wire offsets would change.",
+                        operation.hqslang()
+                    ),
+                });
+            }
+            return Ok(None);
+        }
+        let content = rendered
+            .then(|| cut_content(operation, &items))
+            .flatten()
+            .unwrap_or_else(|| format!("\"{}\"", operation.hqslang()));
+        let mut hidden_qubits: Vec<usize> = self
+            .mapping
+            .iter()
+            .filter(|(_, wire)| hidden.contains(wire))
+            .map(|(&qubit, _)| qubit)
+            .collect();
+        hidden_qubits.sort_unstable();
+        let label = format!(
+            "with {}",
+            hidden_qubits
+                .iter()
+                .map(|qubit| format!("q[{qubit}]"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        Ok(Some(
+            drawn
+                .into_iter()
+                .map(|wire| {
+                    (
+                        wire,
+                        LayoutItem::Gate {
+                            content: content.clone(),
+                            label: Some(label.clone()),
+                            fill: None,
+                            stroke: None,
+                        },
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns whether the wires of an operation keep the distances between its qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `wires` - The wires of the operation in the arranged circuit.
+    fn keeps_offsets(&self, wires: &BTreeSet<usize>) -> bool {
+        let mut offsets = wires.iter().map(|&wire| {
+            self.mapping
+                .iter()
+                .find(|(_, &mapped)| mapped == wire)
+                .map(|(&qubit, _)| qubit as i64 - wire as i64)
+        });
+        let first = offsets.next().flatten();
+        offsets.all(|offset| offset == first)
+    }
+
+    /// Removes the wires of the hidden qubits from a layout and cuts the lines leading to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout of the arranged circuit.
+    pub(crate) fn restrict(&self, layout: &mut CircuitLayout) {
+        let n_wires = self.qubits.len();
+        layout.qubits.resize_with(n_wires, Vec::new);
+        let hidden =
+            |wire: &Option<WireRef>| matches!(wire, Some(WireRef::Qubit(row)) if *row >= n_wires);
+        for (row, items) in layout.qubits.iter_mut().enumerate() {
+            for item in items.iter_mut() {
+                match item {
+                    LayoutItem::MultiGate {
+                        n_wires: Some(n_covered),
+                        inputs,
+                        ..
+                    } if row + *n_covered > n_wires => {
+                        *n_covered = n_wires - row;
+                        inputs.retain(|input| *input < n_wires - row);
+                    }
+                    LayoutItem::GateGroup {
+                        n_wires: n_covered, ..
+                    } => *n_covered = (*n_covered).min(n_wires - row),
+                    _ => (),
+                }
+            }
+        }
+        for item in layout
+            .qubits
+            .iter_mut()
+            .chain(layout.bosons.iter_mut())
+            .chain(layout.classical.iter_mut())
+            .flatten()
+        {
+            match item {
+                LayoutItem::Control { target, .. }
+                | LayoutItem::MultiGate { target, .. }
+                | LayoutItem::Meter { target, .. }
+                    if hidden(target) =>
+                {
+                    *target = None
+                }
+                LayoutItem::Swap { partner, .. } if hidden(partner) => *partner = None,
+                _ => (),
+            }
+        }
+        layout.qubit_indices = self.qubits.clone();
+    }
+}
+
+/// Returns the content of the box drawing an operation cut at the boundary of the drawn qubits.
+///
+/// The content of the gate box is kept. The controlled operations are named after the operation,
+/// followed by the parameters of their target box, e.g. `"ControlledPhaseShift"(0.5)`.
+///
+/// # Arguments
+///
+/// * `operation` - The operation of the arranged circuit.
+/// * `items` - The items drawing the operation on its own.
+///
+/// # Returns
+///
+/// * `Option<String>` - The content of the box, `None` when the operation is not drawn as a box.
+fn cut_content(operation: &Operation, items: &[&LayoutItem]) -> Option<String> {
+    let content = items.iter().find_map(|item| match item {
+        LayoutItem::Gate { content, .. } | LayoutItem::MultiGate { content, .. } => Some(content),
+        _ => None,
+    })?;
+    if !items
+        .iter()
+        .any(|item| matches!(item, LayoutItem::Control { .. }))
+    {
+        return Some(content.clone());
+    }
+    let parameters = match content.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').map_or("", |(_, rest)| rest),
+        None => content.find('(').map_or("", |start| &content[start..]),
+    };
+    Some(format!("\"{}\"{}", operation.hqslang(), parameters))
+}
@@ -10,21 +10,31 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, io::Cursor, path::PathBuf, str::FromStr, sync::RwLock};
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+};
 
 use image::DynamicImage;
 use roqoqo::{Circuit, RoqoqoBackendError, RoqoqoError};
+use serde::{Deserialize, Serialize};
 use typst::{
-    diag::{FileError, FileResult, PackageError},
+    diag::{FileError, FileResult, PackageError, SourceDiagnostic},
     foundations::{Bytes, Datetime},
-    layout::PagedDocument,
-    syntax::{FileId, Source},
+    layout::{Page, PagedDocument},
+    syntax::{package::PackageSpec, FileId, Source, VirtualPath},
     text::{Font, FontBook},
     utils::LazyHash,
     Library,
 };
 
-use crate::{add_gate, effective_len, flatten_multiple_vec};
+use crate::{
+    circuit_into_layout, effective_len, escape_latex_text, CircuitLayout, GlobalStart, LayoutItem,
+    RenderOptions, WireRef, WireRows,
+};
 
 /// Typst Backend
 ///
@@ -34,32 +44,161 @@ use crate::{add_gate, effective_len, flatten_multiple_vec};
 /// It has to implement the typst::World trait.
 #[derive(Debug)]
 pub struct TypstBackend {
+    /// Library, fonts and dependency files, possibly shared with other backends.
+    resources: Arc<TypstResources>,
+    /// Typst source file to be compiled.
+    source: Source,
+    /// Current time.
+    time: time::OffsetDateTime,
+}
+
+/// The parts of the TypstBackend that do not depend on the compiled source.
+///
+/// Building them parses the font and the standard library, and the package files are loaded
+/// on first use, so they are shared by the backends of a [crate::Renderer].
+#[derive(Debug)]
+pub(crate) struct TypstResources {
     /// Typst standard library used by the backend.
     library: LazyHash<Library>,
     /// Metadata about a collection of fonts.
     book: LazyHash<FontBook>,
-    /// Typst source file to be compiled.
-    source: Source,
     /// Typst dependency files used during compilation.
     files: RwLock<HashMap<FileId, Bytes>>,
     /// Collection of fonts.
     fonts: Vec<Font>,
-    /// Current time.
-    time: time::OffsetDateTime,
     /// Path to the cache directory containing the font files and dependencies.
     dependencies: PathBuf,
+    /// Whether and from where missing dependencies can be downloaded.
+    network_policy: NetworkPolicy,
+    /// Lock held while a missing package is downloaded, so that it is fetched only once.
+    downloads: Mutex<()>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// What to display at the left of the circuit.
 pub enum InitializationMode {
     /// States |0>.
     State,
     /// Qubits q[n].
     Qubit,
+    /// States prepared by the `PragmaSetStateVector`, `PragmaSetDensityMatrix` and `InputBit`
+    /// operations at the beginning of the circuit, |0> for the other qubits.
+    Inferred,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Background of the rendered page.
+pub enum Background {
+    /// White page, typst's default.
+    #[default]
+    White,
+    /// No page fill, the png images have a transparent background.
+    Transparent,
+    /// Page filled with a typst colour, e.g. `rgb("#1e1e1e")`. The text and the wires are drawn
+    /// in white on dark colours.
+    Fill(String),
+}
+
+impl Background {
+    /// Returns whether the background is a dark colour, on which the text and the wires are
+    /// drawn in white.
+    ///
+    /// The named typst colours, `luma(..)`, `rgb(..)` with integer or percentage components and
+    /// hexadecimal colours are recognised, the other colours are considered light.
+    pub fn is_dark(&self) -> bool {
+        match self {
+            Background::Fill(colour) => {
+                colour_lightness(colour).is_some_and(|lightness| lightness < 0.5)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the perceived lightness of a typst colour, between 0 and 1.
+///
+/// ## Arguments
+///
+/// * `colour` - The typst colour expression.
+///
+/// ## Returns
+///
+/// * `Some(f64)` - The lightness of the colour.
+/// * `None` - The colour expression is not recognised.
+fn colour_lightness(colour: &str) -> Option<f64> {
+    let colour = colour.trim();
+    let lightness = |red: f64, green: f64, blue: f64| 0.299 * red + 0.587 * green + 0.114 * blue;
+    let component = |value: &str| -> Option<f64> {
+        let value = value.trim();
+        match value.strip_suffix('%') {
+            Some(percentage) => percentage.trim().parse::<f64>().ok().map(|p| p / 100.0),
+            None => value.parse::<f64>().ok().map(|v| v / 255.0),
+        }
+    };
+    let hexadecimal = |hex: &str| -> Option<f64> {
+        let hex = hex.trim_start_matches('#');
+        if !hex.is_ascii() {
+            return None;
+        }
+        let digits: Vec<u8> = match hex.len() {
+            3 | 4 => hex
+                .chars()
+                .take(3)
+                .map(|digit| u8::from_str_radix(&digit.to_string(), 16).map(|d| d * 17))
+                .collect::<Result<_, _>>()
+                .ok()?,
+            6 | 8 => (0..3)
+                .map(|index| u8::from_str_radix(&hex[2 * index..2 * index + 2], 16))
+                .collect::<Result<_, _>>()
+                .ok()?,
+            _ => return None,
+        };
+        Some(lightness(
+            digits[0] as f64 / 255.0,
+            digits[1] as f64 / 255.0,
+            digits[2] as f64 / 255.0,
+        ))
+    };
+    if colour.starts_with('#') {
+        return hexadecimal(colour);
+    }
+    if let Some(arguments) = colour
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let arguments = arguments.trim();
+        if let Some(hex) = arguments
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            return hexadecimal(hex);
+        }
+        let components: Vec<f64> = arguments
+            .split(',')
+            .take(3)
+            .map(component)
+            .collect::<Option<_>>()?;
+        return (components.len() == 3)
+            .then(|| lightness(components[0], components[1], components[2]));
+    }
+    if let Some(argument) = colour
+        .strip_prefix("luma(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return component(argument.split(',').next()?);
+    }
+    match colour {
+        "black" => Some(0.0),
+        "navy" | "maroon" | "purple" | "eastern" | "teal" | "green" | "olive" | "blue" | "red" => {
+            Some(0.3)
+        }
+        "gray" | "fuchsia" | "orange" => Some(0.6),
+        "silver" | "aqua" | "lime" | "yellow" | "white" => Some(0.9),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Choose how to render Pragmas operations.
 pub enum RenderPragmas {
     /// Render no Pragmas operations.
@@ -70,15 +209,177 @@ pub enum RenderPragmas {
     Partial(Vec<String>),
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Where the typst packages are cached.
+pub enum CacheDirectory {
+    /// `$QOLLAGE_CACHE_DIR` if set, `.qollage/cache` in the current directory otherwise.
+    #[default]
+    Default,
+    /// The `qollage` directory in the user cache directory (`$XDG_CACHE_HOME`, `~/.cache` or
+    /// `%LOCALAPPDATA%`).
+    User,
+    /// The given directory.
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Whether and from where the backend can download the typst packages missing from the cache.
+pub enum NetworkPolicy {
+    /// Download the packages from the typst package registry.
+    #[default]
+    Allow,
+    /// Never download the packages, missing packages result in an error.
+    Deny,
+    /// Download the packages from the registry at the given base URL, for instance a local mirror.
+    Registry(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+/// Configuration of the TypstBackend.
+pub struct TypstBackendConfig {
+    /// Where the typst packages are cached.
+    cache_directory: CacheDirectory,
+    /// Whether and from where missing typst packages can be downloaded.
+    network_policy: NetworkPolicy,
+}
+
 const FIRA_MATH_FONT: &[u8] = include_bytes!("../assets/FiraMath.otf");
 
+/// Returns the page settings of the typst documents.
+///
+/// On dark backgrounds the text and the lines are drawn in white.
+///
+/// ## Arguments
+///
+/// * `options` - The background and the margin of the page.
+///
+/// ## Returns
+///
+/// * `String` - The typst set rules of the document.
+pub(crate) fn typst_header(options: &RenderOptions) -> String {
+    let fill = match &options.background {
+        Background::White => String::new(),
+        Background::Transparent => ", fill: none".to_owned(),
+        Background::Fill(colour) => format!(", fill: {colour}"),
+    };
+    let mut header = format!(
+        "#set page(width: auto, height: auto, margin: {}pt{fill})\n#show math.equation: set text(font: \"Fira Math\")\n",
+        options.page_margin
+    );
+    if options.background.is_dark() {
+        header.push_str("#set text(fill: white)\n#set line(stroke: white)\n");
+    }
+    header
+}
+
+/// Base URL of the typst package registry.
+const TYPST_PACKAGE_REGISTRY: &str = "https://packages.typst.org";
+
+/// Environment variable overriding the default cache directory.
+const CACHE_DIRECTORY_ENV: &str = "QOLLAGE_CACHE_DIR";
+
+impl TypstBackendConfig {
+    /// Creates a new TypstBackendConfig with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets where the typst packages are cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_directory` - The cache directory to use.
+    pub fn cache_directory(mut self, cache_directory: CacheDirectory) -> Self {
+        self.cache_directory = cache_directory;
+        self
+    }
+
+    /// Sets whether and from where missing typst packages can be downloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_policy` - The network policy to use.
+    pub fn network_policy(mut self, network_policy: NetworkPolicy) -> Self {
+        self.network_policy = network_policy;
+        self
+    }
+
+    /// Returns the path of the cache directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` - The path of the cache directory.
+    /// * `Err(RoqoqoBackendError)` - The user cache directory could not be determined.
+    pub fn cache_path(&self) -> Result<PathBuf, RoqoqoBackendError> {
+        match &self.cache_directory {
+            CacheDirectory::Default => Ok(std::env::var_os(CACHE_DIRECTORY_ENV)
+                .filter(|path| !path.is_empty())
+                .map_or_else(|| PathBuf::from(".qollage/cache"), PathBuf::from)),
+            CacheDirectory::User => std::env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+                .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+                .map(|path| path.join("qollage"))
+                .ok_or_else(|| {
+                    RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                        msg: "Couldn't find the user cache directory".to_owned(),
+                    })
+                }),
+            CacheDirectory::Path(path) => Ok(path.clone()),
+        }
+    }
+}
+
 impl TypstBackend {
-    /// Creates a new TypstBackend.
+    /// Creates a new TypstBackend with the default configuration.
     ///
     /// # Arguments
     ///
     /// * `typst_str` - The typst source file.
     pub fn new(typst_str: String) -> Result<Self, RoqoqoBackendError> {
+        Self::with_config(typst_str, &TypstBackendConfig::default())
+    }
+
+    /// Creates a new TypstBackend.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The typst source file.
+    /// * `config` - The configuration of the cache directory and of the network access.
+    pub fn with_config(
+        typst_str: String,
+        config: &TypstBackendConfig,
+    ) -> Result<Self, RoqoqoBackendError> {
+        Ok(Self::with_resources(
+            typst_str,
+            Arc::new(TypstResources::new(config)?),
+        ))
+    }
+
+    /// Creates a new TypstBackend using already built resources.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The typst source file.
+    /// * `resources` - The library, fonts and dependency files of the backend.
+    pub(crate) fn with_resources(typst_str: String, resources: Arc<TypstResources>) -> Self {
+        Self {
+            resources,
+            source: Source::detached(typst_str),
+            time: time::OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl TypstResources {
+    /// Creates the resources of a TypstBackend.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the cache directory and of the network access.
+    pub(crate) fn new(config: &TypstBackendConfig) -> Result<Self, RoqoqoBackendError> {
         let bytes = FIRA_MATH_FONT.to_vec();
         let buffer = Bytes::new(bytes);
         let fonts = Font::new(buffer.clone(), 0).map_or_else(std::vec::Vec::new, |font| vec![font]);
@@ -86,15 +387,11 @@ impl TypstBackend {
         Ok(Self {
             library: LazyHash::new(library),
             book: LazyHash::new(FontBook::from_fonts(&fonts)),
-            source: Source::detached(typst_str.clone()),
             files: RwLock::new(HashMap::new()),
             fonts,
-            time: time::OffsetDateTime::now_utc(),
-            dependencies: PathBuf::from_str(".qollage/cache").map_err(|_| {
-                RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
-                    msg: "Couldn't access `.qollage/cache` directory".to_owned(),
-                })
-            })?,
+            dependencies: config.cache_path()?,
+            network_policy: config.network_policy.clone(),
+            downloads: Mutex::new(()),
         })
     }
 
@@ -113,34 +410,21 @@ impl TypstBackend {
             return Ok(bytes.clone());
         }
         if let Some(package) = id.package() {
+            if let Some(archive) = vendored_package(package) {
+                self.load_vendored_package(package, archive)?;
+                return self
+                    .files
+                    .read()
+                    .expect("Backend couldn't access the files.")
+                    .get(&id)
+                    .cloned()
+                    .ok_or_else(|| FileError::NotFound(id.vpath().as_rootless_path().into()));
+            }
             let package_subdir =
                 format!("{}/{}/{}", package.namespace, package.name, package.version);
             let package_path = self.dependencies.join(package_subdir);
             if !package_path.exists() {
-                let url = format!(
-                    "https://packages.typst.org/{}/{}-{}.tar.gz",
-                    package.namespace, package.name, package.version,
-                );
-                let response = ureq::get(&url)
-                    .call()
-                    .map_err(|_| FileError::AccessDenied)?;
-                let data = response
-                    .into_body()
-                    .read_to_vec()
-                    .map_err(|error| FileError::from_io(error.into_io(), &package_path))?;
-                let decompressed_data = zune_inflate::DeflateDecoder::new(&data)
-                    .decode_gzip()
-                    .map_err(|error| {
-                        FileError::Package(PackageError::MalformedArchive(Some(
-                            format!("Error during decompression:{error}.").into(),
-                        )))
-                    })?;
-                let mut archive = tar::Archive::new(decompressed_data.as_slice());
-                archive.unpack(&package_path).map_err(|error| {
-                    FileError::Package(PackageError::MalformedArchive(Some(
-                        format!("Error during unpacking:{error}.").into(),
-                    )))
-                })?;
+                self.download_package(package, &package_path)?;
             }
             if let Some(file_path) = id.vpath().resolve(&package_path) {
                 let contents = std::fs::read(&file_path)
@@ -155,17 +439,169 @@ impl TypstBackend {
         }
         Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
     }
+
+    /// Downloads a package missing from the cache and unpacks it into the cache directory.
+    ///
+    /// The archive is unpacked into a temporary directory which is then renamed into place,
+    /// so that concurrent renders never read a partially unpacked package.
+    ///
+    /// # Arguments
+    ///
+    /// * `package` - The specification of the missing package.
+    /// * `package_path` - The directory of the package in the cache.
+    fn download_package(
+        &self,
+        package: &PackageSpec,
+        package_path: &Path,
+    ) -> Result<(), FileError> {
+        let _guard = self
+            .downloads
+            .lock()
+            .expect("Backend couldn't access the package downloads.");
+        if package_path.exists() {
+            return Ok(());
+        }
+        let registry = match &self.network_policy {
+            NetworkPolicy::Allow => TYPST_PACKAGE_REGISTRY,
+            NetworkPolicy::Registry(url) => url.trim_end_matches('/'),
+            NetworkPolicy::Deny => {
+                return Err(FileError::Package(PackageError::NetworkFailed(Some(
+                    format!("Package {package} is not in the cache and network access is denied.")
+                        .into(),
+                ))))
+            }
+        };
+        let url = format!(
+            "{}/{}/{}-{}.tar.gz",
+            registry, package.namespace, package.name, package.version,
+        );
+        let response = ureq::get(&url)
+            .call()
+            .map_err(|_| FileError::AccessDenied)?;
+        let data = response
+            .into_body()
+            .read_to_vec()
+            .map_err(|error| FileError::from_io(error.into_io(), package_path))?;
+        let decompressed_data = decompress_package(&data)?;
+        let mut archive = tar::Archive::new(decompressed_data.as_slice());
+        let unpack_path = package_path.with_file_name(format!(
+            "{}.partial-{}",
+            package.version,
+            std::process::id()
+        ));
+        archive.unpack(&unpack_path).map_err(|error| {
+            let _ = std::fs::remove_dir_all(&unpack_path);
+            FileError::Package(PackageError::MalformedArchive(Some(
+                format!("Error during unpacking:{error}.").into(),
+            )))
+        })?;
+        if let Err(error) = std::fs::rename(&unpack_path, package_path) {
+            let _ = std::fs::remove_dir_all(&unpack_path);
+            // Another process may have unpacked the same package in the meantime.
+            if !package_path.exists() {
+                return Err(FileError::from_io(error, package_path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads all the files of a package bundled with the crate into memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `package` - The specification of the bundled package.
+    /// * `archive` - The gzipped tarball of the bundled package.
+    fn load_vendored_package(
+        &self,
+        package: &PackageSpec,
+        archive: &[u8],
+    ) -> Result<(), FileError> {
+        let malformed_archive = |error: std::io::Error| {
+            FileError::Package(PackageError::MalformedArchive(Some(
+                format!("Error during unpacking:{error}.").into(),
+            )))
+        };
+        let decompressed_data = decompress_package(archive)?;
+        let mut archive = tar::Archive::new(decompressed_data.as_slice());
+        let mut files = self
+            .files
+            .write()
+            .expect("Backend couldn't access the files.");
+        for entry in archive.entries().map_err(malformed_archive)? {
+            let mut entry = entry.map_err(malformed_archive)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path().map_err(malformed_archive)?.into_owned();
+            let mut contents = Vec::new();
+            entry
+                .read_to_end(&mut contents)
+                .map_err(malformed_archive)?;
+            files.insert(
+                FileId::new(Some(package.clone()), VirtualPath::new(path)),
+                Bytes::new(contents),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Typst packages bundled with the crate.
+///
+/// Each entry contains the namespace, name and version of the package and its gzipped tarball,
+/// as downloaded from the typst package registry.
+#[cfg(feature = "vendored_quill")]
+const VENDORED_PACKAGES: &[(&str, &str, &str, &[u8])] = &[(
+    "preview",
+    "quill",
+    "0.7.1",
+    include_bytes!("../assets/quill-0.7.1.tar.gz"),
+)];
+
+/// Typst packages bundled with the crate.
+#[cfg(not(feature = "vendored_quill"))]
+const VENDORED_PACKAGES: &[(&str, &str, &str, &[u8])] = &[];
+
+/// Returns the gzipped tarball of a package bundled with the crate, if any.
+///
+/// # Arguments
+///
+/// * `package` - The specification of the package.
+fn vendored_package(package: &PackageSpec) -> Option<&'static [u8]> {
+    VENDORED_PACKAGES
+        .iter()
+        .find(|(namespace, name, version, _)| {
+            package.namespace.as_str() == *namespace
+                && package.name.as_str() == *name
+                && package.version.to_string() == *version
+        })
+        .map(|(_, _, _, archive)| *archive)
+}
+
+/// Decompresses the gzipped tarball of a typst package.
+///
+/// # Arguments
+///
+/// * `data` - The gzipped tarball.
+fn decompress_package(data: &[u8]) -> Result<Vec<u8>, FileError> {
+    zune_inflate::DeflateDecoder::new(data)
+        .decode_gzip()
+        .map_err(|error| {
+            FileError::Package(PackageError::MalformedArchive(Some(
+                format!("Error during decompression:{error}.").into(),
+            )))
+        })
 }
 
 impl typst::World for TypstBackend {
     /// The standard library.
     fn library(&self) -> &LazyHash<Library> {
-        &self.library
+        &self.resources.library
     }
 
     /// Metadata about all known fonts.
     fn book(&self) -> &LazyHash<FontBook> {
-        &self.book
+        &self.resources.book
     }
 
     /// Access the main source file.
@@ -187,12 +623,12 @@ impl typst::World for TypstBackend {
 
     /// Try to access the specified file.
     fn file(&self, id: FileId) -> FileResult<Bytes> {
-        self.load_file(id)
+        self.resources.load_file(id)
     }
 
     /// Try to access the font with the given index in the font book.
     fn font(&self, index: usize) -> Option<Font> {
-        self.fonts.get(index).cloned()
+        self.resources.fonts.get(index).cloned()
     }
 
     /// Get the current date.
@@ -216,54 +652,55 @@ impl FromStr for InitializationMode {
         match s.to_lowercase().as_str() {
             "state" => Ok(InitializationMode::State),
             "qubit" => Ok(InitializationMode::Qubit),
+            "inferred" => Ok(InitializationMode::Inferred),
             _ => Err(RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
-                msg: format!(r#"Invalid initialization mode: {s}, use `state` or `qubit`."#),
+                msg: format!(
+                    r#"Invalid initialization mode: {s}, use `state`, `qubit` or `inferred`."#
+                ),
             })),
         }
     }
 }
 
-/// Replaces `replace_by_classical_len_{n}` by n_qubits + n_bosons + n.
-/// Needs to be done after going through all the circuit to know n_qubits and n_bosons.
-///
-/// # Arguments
-///
-/// * `bosonic_gate` - The bosonic gate in typst representation.
-/// * `n_qubits` - The number of qubits.
-/// * `n_bosons` - The number of bosons.
-fn replace_classical_index(
-    classical_gate: &String,
-    n_qubits: usize,
-    n_bosons: usize,
-    n_classical: usize,
-) -> String {
-    let mut output = classical_gate.to_owned();
-    for index in 0..n_classical + 1 {
-        let pattern = format!("replace_by_classical_len_{index}");
-        if output.contains(&pattern) {
-            output = output.replace(&pattern, &(index + n_qubits + n_bosons).to_string());
+impl FromStr for CacheDirectory {
+    type Err = RoqoqoBackendError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" | "" => Ok(CacheDirectory::Default),
+            "user" => Ok(CacheDirectory::User),
+            _ => Ok(CacheDirectory::Path(PathBuf::from(s))),
         }
     }
-    output
 }
 
-/// Replaces `replace_by_n_qubits_plus_{n}` by n_qubits + n.
-/// Needs to be done after going through all the circuit to know n_qubits.
-///
-/// # Arguments
-///
-/// * `bosonic_gate` - The bosonic gate in typst representation.
-/// * `n_qubits` - The number of qubits.
-/// * `n_bosons` - The number of bosons.
-fn replace_boson_index(bosonic_gate: &String, n_qubits: usize, n_bosons: usize) -> String {
-    let mut output = bosonic_gate.to_owned();
-    for boson in 0..n_bosons + 1 {
-        let pattern = format!("replace_by_n_qubits_plus_{boson}");
-        if output.contains(&pattern) {
-            output = output.replace(&pattern, &(boson + n_qubits).to_string());
+impl FromStr for NetworkPolicy {
+    type Err = RoqoqoBackendError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "allow" | "" => Ok(NetworkPolicy::Allow),
+            "deny" => Ok(NetworkPolicy::Deny),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(NetworkPolicy::Registry(s.to_owned()))
+            }
+            _ => Err(RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                msg: format!(
+                    r#"Invalid network policy: {s}, use `allow`, `deny` or a registry URL."#
+                ),
+            })),
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = RoqoqoBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "white" | "default" | "" => Ok(Background::White),
+            "transparent" | "none" => Ok(Background::Transparent),
+            _ => Ok(Background::Fill(s.trim().to_owned())),
         }
     }
-    output
 }
 
 impl FromStr for RenderPragmas {
@@ -283,12 +720,115 @@ impl FromStr for RenderPragmas {
     }
 }
 
+impl RenderPragmas {
+    /// Returns whether an operation is drawn, the operations that are not pragmas always are.
+    ///
+    /// ## Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    pub fn renders(&self, hqslang: &str) -> bool {
+        match self {
+            _ if !hqslang.starts_with("Pragma") => true,
+            RenderPragmas::All => true,
+            RenderPragmas::None => false,
+            RenderPragmas::Partial(pragmas) => pragmas.iter().any(|pragma| pragma == hqslang),
+        }
+    }
+}
+
+/// Formats the diagnostics emitted by the typst compiler.
+///
+/// ## Arguments
+///
+/// * `diagnostics` - The diagnostics to format.
+///
+/// ## Returns
+///
+/// * `String` - One line per diagnostic.
+fn format_diagnostics(diagnostics: &[SourceDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diag| {
+            format!(
+                "File: {:?}, Range: {:?}, Severity: {:?}, Message: {}, Hints: [{}]",
+                diag.span.id(),
+                diag.span.range(),
+                diag.severity,
+                diag.message,
+                diag.hints
+                    .iter()
+                    .map(|h| h.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Compiles the given typst string into a paged document.
+///
+/// ## Arguments
+///
+/// * `typst_str` - The string to give to the typst compiler.
+/// * `backend_config` - The configuration of the TypstBackend.
+///
+/// ## Returns
+///
+/// * `Ok(PagedDocument)` - The document compiled from the typst string.
+/// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
+fn compile_typst_str(
+    typst_str: String,
+    backend_config: &TypstBackendConfig,
+) -> Result<PagedDocument, RoqoqoBackendError> {
+    compile_with_resources(typst_str, Arc::new(TypstResources::new(backend_config)?))
+}
+
+/// Compiles the given typst string into a paged document, using already built resources.
+///
+/// ## Arguments
+///
+/// * `typst_str` - The string to give to the typst compiler.
+/// * `resources` - The library, fonts and dependency files of the TypstBackend.
+///
+/// ## Returns
+///
+/// * `Ok(PagedDocument)` - The document compiled from the typst string.
+/// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
+pub(crate) fn compile_with_resources(
+    typst_str: String,
+    resources: Arc<TypstResources>,
+) -> Result<PagedDocument, RoqoqoBackendError> {
+    let typst_backend = TypstBackend::with_resources(typst_str, resources);
+    typst::compile(&typst_backend)
+        .output
+        .map_err(|err| RoqoqoBackendError::GenericError {
+            msg: format!(
+                "Error during the Typst compilation: {}",
+                format_diagnostics(&err)
+            ),
+        })
+}
+
+/// Returns the first page of a compiled typst document.
+///
+/// ## Arguments
+///
+/// * `doc` - The compiled typst document.
+pub(crate) fn first_page(doc: &PagedDocument) -> Result<&Page, RoqoqoBackendError> {
+    doc.pages
+        .first()
+        .ok_or_else(|| RoqoqoBackendError::GenericError {
+            msg: "Typst document has no pages.".to_owned(),
+        })
+}
+
 /// Uses the Typst compiler to generate an image from the given typst string.
 ///
 /// ## Arguments
 ///
 /// * `typst_string` - The string to give to the typst compiler.
-/// * `pixels_per_point` - The pixel per point ratio.
+/// * `options` - The pixel per point ratio and the configuration of the TypstBackend.
 ///
 /// ## Returns
 ///
@@ -296,48 +836,41 @@ impl FromStr for RenderPragmas {
 /// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
 pub fn render_typst_str(
     typst_str: String,
-    pixels_per_point: Option<f32>,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    let doc = compile_typst_str(typst_str, &options.backend_config)?;
+    document_to_image(&doc, options.pixels_per_point)
+}
+
+/// Renders the first page of a compiled typst document to an image.
+///
+/// ## Arguments
+///
+/// * `doc` - The compiled typst document.
+/// * `pixels_per_point` - The pixel per point ratio of the image.
+///
+/// ## Returns
+///
+/// * `Ok(DynamicImage)` - The image of the first page.
+/// * `Err(RoqoqoBackendError)` - The document has no pages or the image cannot be encoded.
+pub(crate) fn document_to_image(
+    doc: &PagedDocument,
+    pixels_per_point: f32,
 ) -> Result<DynamicImage, RoqoqoBackendError> {
-    let typst_backend = TypstBackend::new(typst_str)?;
-    let doc: PagedDocument =
-        typst::compile(&typst_backend)
-            .output
-            .map_err(|err| RoqoqoBackendError::GenericError {
-                msg: format!(
-                    "Error during the Typst compilation: {}",
-                    err.iter()
-                        .map(|diag| {
-                            format!(
-                                "File: {:?}, Range: {:?}, Severity: {:?}, Message: {}, Hints: [{}]",
-                                diag.span.id(),
-                                diag.span.range(),
-                                diag.severity,
-                                diag.message,
-                                diag.hints
-                                    .iter()
-                                    .map(|h| h.as_str())
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                ),
-            })?;
     let mut writer = Cursor::new(Vec::new());
-    let pixmap = typst_render::render(
-        &doc.pages
-            .first()
-            .ok_or("error")
-            .map_err(|_| RoqoqoBackendError::GenericError {
-                msg: "Typst document has no pages.".to_owned(),
-            })?
-            .clone(),
-        pixels_per_point.unwrap_or(3.0),
-    );
+    let pixmap = typst_render::render(first_page(doc)?, pixels_per_point);
+    // tiny-skia stores premultiplied colours, which only match the png colours on opaque pages.
+    let pixels: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        })
+        .collect();
     image::write_buffer_with_format(
         &mut writer,
-        bytemuck::cast_slice(pixmap.pixels()),
+        &pixels,
         pixmap.width(),
         pixmap.height(),
         image::ColorType::Rgba8,
@@ -354,20 +887,72 @@ pub fn render_typst_str(
     Ok(image)
 }
 
-fn effective_split(vec: &mut Vec<String>, split_index: usize) -> (Vec<String>, Vec<String>, usize) {
+/// Uses the Typst compiler to generate an SVG image from the given typst string.
+///
+/// ## Arguments
+///
+/// * `typst_string` - The string to give to the typst compiler.
+/// * `options` - The configuration of the TypstBackend.
+///
+/// ## Returns
+///
+/// * `Ok(String)` - The SVG image generated from the typst string.
+/// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
+pub fn render_typst_str_svg(
+    typst_str: String,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    let doc = compile_typst_str(typst_str, &options.backend_config)?;
+    Ok(typst_svg::svg(first_page(&doc)?))
+}
+
+/// Uses the Typst compiler to generate a PDF document from the given typst string.
+///
+/// ## Arguments
+///
+/// * `typst_string` - The string to give to the typst compiler.
+/// * `options` - The configuration of the TypstBackend.
+///
+/// ## Returns
+///
+/// * `Ok(Vec<u8>)` - The bytes of the PDF document generated from the typst string.
+/// * `Err(RoqoqoBackendError)` - Error during the Typst compilation or the PDF export.
+pub fn render_typst_str_pdf(
+    typst_str: String,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, RoqoqoBackendError> {
+    let doc = compile_typst_str(typst_str, &options.backend_config)?;
+    document_to_pdf(&doc)
+}
+
+/// Exports a compiled typst document to PDF.
+///
+/// ## Arguments
+///
+/// * `doc` - The compiled typst document.
+///
+/// ## Returns
+///
+/// * `Ok(Vec<u8>)` - The bytes of the PDF document.
+/// * `Err(RoqoqoBackendError)` - Error during the PDF export.
+pub(crate) fn document_to_pdf(doc: &PagedDocument) -> Result<Vec<u8>, RoqoqoBackendError> {
+    typst_pdf::pdf(doc, &typst_pdf::PdfOptions::default()).map_err(|err| {
+        RoqoqoBackendError::GenericError {
+            msg: format!("Error during the PDF export: {}", format_diagnostics(&err)),
+        }
+    })
+}
+
+fn effective_split(
+    vec: &mut Vec<LayoutItem>,
+    split_index: usize,
+) -> (Vec<LayoutItem>, Vec<LayoutItem>, usize) {
     let mut first = vec![];
     let mut group_len = 0;
     while !vec.is_empty() && effective_len(first.as_slice()) < split_index.max(group_len) {
         let op = vec.remove(0);
-        if op.contains("gategroup") {
-            group_len = op
-                .split(',')
-                .nth(1)
-                .unwrap_or("0")
-                .trim()
-                .parse::<usize>()
-                .unwrap_or_default()
-                + first.len();
+        if let LayoutItem::GateGroup { n_columns, .. } = op {
+            group_len = n_columns + first.len();
         }
         first.push(op);
     }
@@ -375,13 +960,13 @@ fn effective_split(vec: &mut Vec<String>, split_index: usize) -> (Vec<String>, V
 }
 
 fn split_gates(
-    gates_vec: &mut [Vec<String>],
+    gates_vec: &mut [Vec<LayoutItem>],
     max_len: usize,
     new_len_map: &HashMap<i64, usize>,
-) -> Option<Vec<Vec<Vec<String>>>> {
+) -> Option<Vec<Vec<Vec<LayoutItem>>>> {
     if !gates_vec.is_empty() && gates_vec[0].len() > max_len {
-        let mut chunks: Vec<Vec<Vec<String>>> = vec![];
-        let mut inner_chunks: Vec<Vec<String>> = vec![];
+        let mut chunks: Vec<Vec<Vec<LayoutItem>>> = vec![];
+        let mut inner_chunks: Vec<Vec<LayoutItem>> = vec![];
         for _ in 0..gates_vec.len() {
             inner_chunks.push(vec![]);
         }
@@ -427,7 +1012,7 @@ fn split_gates(
 }
 
 fn split_in_chunk_preprocess(
-    gates_vec: &[Vec<String>],
+    gates_vec: &[Vec<LayoutItem>],
     max_len: usize,
     new_len_map: &mut HashMap<i64, usize>,
 ) -> bool {
@@ -486,78 +1071,131 @@ fn split_in_chunk_preprocess(
     true
 }
 
+/// Serializes the items of a wire to their quill representation.
+///
+/// # Arguments
+///
+/// * `items` - The items of the wire.
+/// * `wire` - The wire the items are placed on.
+/// * `rows` - The rows of the wires in the diagram.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The quill representation of each item.
+fn items_to_typst(items: &[LayoutItem], wire: WireRef, rows: &WireRows) -> Vec<String> {
+    items.iter().map(|item| item.to_typst(wire, rows)).collect()
+}
+
 /// Converts a qoqo circuit to a typst string.
 ///
 ///  ## Arguments
 ///
 /// * `circuit` - The circuit to convert.
-/// * `render_pragmas` - Whether to render Pragma Operations or not.
-/// * `initialization_mode` - The initialization mode of the circuit representation.
-/// * `max_length` - The maximum length of a circuit line. If the circuit line is longer than this
-///   value, it will be split into multiple lines.
-/// * `rounding_accuracy` - The number of digits to round to when displaying floats.
+/// * `options` - The pragmas to render, the initialization mode, the maximum length of a circuit
+///   line and the rounding accuracy of the circuit representation.
 ///
 /// ## Returns
 ///
 /// * `String` - The string representation of the circuit in Typst.
 pub fn circuit_into_typst_str(
     circuit: &Circuit,
-    render_pragmas: RenderPragmas,
-    initialization_mode: Option<InitializationMode>,
-    max_length: Option<usize>,
-    rounding_accuracy: Option<usize>,
+    options: &RenderOptions,
 ) -> Result<String, RoqoqoBackendError> {
-    let mut typst_str = r#"#set page(width: auto, height: auto, margin: 5pt)
-#show math.equation: set text(font: "Fira Math")
-#{ 
+    circuit_into_typst_str_with_warnings(circuit, options).map(|(typst_str, _)| typst_str)
+}
+
+/// Converts a qoqo circuit to a typst string, with the warnings emitted during the conversion.
+///
+///  ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The pragmas to render, the initialization mode, the maximum length of a circuit
+///   line and the rounding accuracy of the circuit representation.
+///
+/// ## Returns
+///
+/// * `(String, Vec<String>)` - The string representation of the circuit in Typst and the
+///   warnings, e.g. the operations drawn by the fallback renderer.
+pub fn circuit_into_typst_str_with_warnings(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<(String, Vec<String>), RoqoqoBackendError> {
+    let (block, warnings) = circuit_into_typst_block(circuit, options)?;
+    Ok((format!("{}{block}", typst_header(options)), warnings))
+}
+
+/// Converts a qoqo circuit to the typst code block drawing it with quill, without the page
+/// settings of the document.
+///
+/// ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The options of the circuit representation.
+///
+/// ## Returns
+///
+/// * `(String, Vec<String>)` - The typst code block of the circuit and the warnings.
+pub(crate) fn circuit_into_typst_block(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<(String, Vec<String>), RoqoqoBackendError> {
+    Ok(layout_into_typst_block(
+        circuit_into_layout(circuit, options)?,
+        options,
+    ))
+}
+
+/// Serializes the layout of a circuit to the typst code block drawing it with quill.
+///
+/// ## Arguments
+///
+/// * `layout` - The layout of the circuit, with all the wires of the same length.
+/// * `options` - The initialization mode and the maximum length of a circuit line.
+///
+/// ## Returns
+///
+/// * `(String, Vec<String>)` - The typst code block of the circuit and the warnings of the layout.
+pub(crate) fn layout_into_typst_block(
+    layout: CircuitLayout,
+    options: &RenderOptions,
+) -> (String, Vec<String>) {
+    let mut typst_str = r#"#{ 
     import "@preview/quill:0.7.1": *
     quantum-circuit(
 "#
     .to_owned();
-    let mut circuit_gates: Vec<Vec<String>> = Vec::new();
-    let mut bosonic_gates: Vec<Vec<String>> = Vec::new();
-    let mut classical_gates: Vec<Vec<String>> = Vec::new();
-    let mut circuit_lock: Vec<(usize, usize)> = Vec::new();
-    let mut bosonic_lock: Vec<(usize, usize)> = Vec::new();
-    let mut classical_lock: Vec<(usize, usize)> = Vec::new();
-    for operation in circuit.iter() {
-        add_gate(
-            &mut circuit_gates,
-            &mut bosonic_gates,
-            &mut classical_gates,
-            &mut circuit_lock,
-            &mut bosonic_lock,
-            &mut classical_lock,
-            operation,
-            &render_pragmas,
-            rounding_accuracy.unwrap_or(3),
-        )?;
-    }
-    let n_qubits = circuit_gates.len();
-    let n_bosons = bosonic_gates.len();
-    let n_classical = classical_gates.len();
-    flatten_multiple_vec(
-        &mut circuit_gates,
-        &mut bosonic_gates,
-        (0..n_qubits).collect::<Vec<usize>>().as_slice(),
-        (0..n_bosons).collect::<Vec<usize>>().as_slice(),
-    );
-    flatten_multiple_vec(
-        &mut circuit_gates,
-        &mut classical_gates,
-        (0..n_qubits).collect::<Vec<usize>>().as_slice(),
-        (0..n_classical).collect::<Vec<usize>>().as_slice(),
-    );
-    flatten_multiple_vec(
-        &mut bosonic_gates,
-        &mut classical_gates,
-        (0..n_bosons).collect::<Vec<usize>>().as_slice(),
-        (0..n_classical).collect::<Vec<usize>>().as_slice(),
-    );
+    if let Background::Fill(colour) = &options.background {
+        if options.background.is_dark() {
+            typst_str.push_str(&format!(
+                "       wire: .7pt + white, color: white, fill: {colour},\n"
+            ));
+        }
+    }
+    let rows = layout.wire_rows();
+    let qubit_indices: Vec<usize> = (0..layout.qubits.len())
+        .map(|row| layout.qubit_index(row))
+        .collect();
+    let global_starts: Vec<Option<GlobalStart>> = (0..layout.qubits.len())
+        .map(|row| layout.global_start(row))
+        .collect();
+    let footer = options.depth_footer.then(|| {
+        typst_footer(
+            layout.depth(),
+            &layout.qubit_gate_counts,
+            &qubit_indices,
+            options,
+        )
+    });
+    let warnings = layout.warnings;
+    let prepared_state = layout.prepared_state;
+    let mut circuit_gates = layout.qubits;
+    let mut bosonic_gates = layout.bosons;
+    let mut classical_gates = layout.classical;
+    options.wire_labels.rename_registers(&mut classical_gates);
     let mut additional_circuit_gates = None;
     let mut additional_bosonic_gates = None;
     let mut additional_classical_gates = None;
-    if let Some(max_circuit_length) = max_length {
+    if let Some(max_circuit_length) = options.max_length {
         let mut new_len_map: HashMap<i64, usize> = HashMap::new();
         while !split_in_chunk_preprocess(&circuit_gates, max_circuit_length, &mut new_len_map) {}
         additional_circuit_gates =
@@ -567,31 +1205,47 @@ pub fn circuit_into_typst_str(
         additional_classical_gates =
             split_gates(&mut classical_gates, max_circuit_length, &new_len_map);
     }
+    let mut first_column = 0;
+    if options.column_numbers {
+        let n_columns = line_columns(&[&circuit_gates, &bosonic_gates, &classical_gates]);
+        typst_str.push_str(&column_numbers_row(first_column, n_columns));
+        first_column += n_columns;
+    }
     let mut is_first = true;
     for (qubit_index, gates) in circuit_gates.iter().enumerate() {
         typst_str.push_str(&format!(
-            "       lstick(${}${}), {} 1, {}[\\ ],\n",
-            match initialization_mode {
-                Some(InitializationMode::Qubit) => format!("q[{qubit_index}]"),
-                Some(InitializationMode::State) | None => "|0>".to_owned(),
+            "       lstick({}{}), {} 1, {}[\\ ],\n",
+            match global_starts[qubit_index] {
+                Some(GlobalStart::First {
+                    n_rows,
+                    mixed: true,
+                }) => format!("$rho$, n: {n_rows}"),
+                Some(GlobalStart::First {
+                    n_rows,
+                    mixed: false,
+                }) => {
+                    format!("$|psi>$, n: {n_rows}")
+                }
+                Some(GlobalStart::Spanned { .. }) => "[]".to_owned(),
+                Some(GlobalStart::Alone { mixed: true }) => "$rho$".to_owned(),
+                Some(GlobalStart::Alone { mixed: false }) => "$|psi>$".to_owned(),
+                None => format!(
+                    "${}$",
+                    options.wire_labels.typst_start(
+                        WireRef::Qubit(qubit_indices[qubit_index]),
+                        options.initialization_mode,
+                        prepared_state.as_ref()
+                    )
+                ),
             },
             if is_first {
                 ", label: \"Qubits\""
             } else {
                 Default::default()
             },
-            gates
-                .iter()
-                .map(|gate| {
-                    if gate.contains("replace_by_n_qubits_") {
-                        replace_boson_index(gate, n_qubits, n_bosons)
-                    } else if gate.contains("replace_by_classical_len_") {
-                        replace_classical_index(gate, n_qubits, n_bosons, n_classical)
-                    } else {
-                        gate.to_owned()
-                    }
-                })
-                .chain(vec!["".to_owned()].into_iter())
+            items_to_typst(gates, WireRef::Qubit(qubit_index), &rows)
+                .into_iter()
+                .chain(vec!["".to_owned()])
                 .collect::<Vec<String>>()
                 .join(", "),
             if additional_circuit_gates.is_some() {
@@ -606,16 +1260,17 @@ pub fn circuit_into_typst_str(
     for (n_boson, gates) in bosonic_gates.iter().enumerate() {
         typst_str.push_str(&format!(
             "       lstick(${}${}), {}, 1, {}[\\ ],\n",
-            match initialization_mode {
-                Some(InitializationMode::Qubit) => format!("q[{n_boson}]"),
-                Some(InitializationMode::State) | None => "|0>".to_owned(),
-            },
+            options.wire_labels.typst_start(
+                WireRef::Boson(n_boson),
+                options.initialization_mode,
+                None
+            ),
             if is_first {
                 ", label: \"Bosons\""
             } else {
                 Default::default()
             },
-            gates.join(", "),
+            items_to_typst(gates, WireRef::Boson(n_boson), &rows).join(", "),
             if additional_bosonic_gates.is_some() {
                 "rstick($···$),"
             } else {
@@ -624,10 +1279,13 @@ pub fn circuit_into_typst_str(
         ));
         is_first = false;
     }
-    for gates in classical_gates.iter() {
-        typst_str.push_str(&format!("       {}, 1, [\\ ],\n", gates.join(", ")));
+    for (index, gates) in classical_gates.iter().enumerate() {
+        typst_str.push_str(&format!(
+            "       {}, 1, [\\ ],\n",
+            items_to_typst(gates, WireRef::Classical(index), &rows).join(", ")
+        ));
     }
-    if max_length.is_some()
+    if options.max_length.is_some()
         && (additional_circuit_gates.is_some()
             || additional_bosonic_gates.is_some()
             || additional_classical_gates.is_some())
@@ -647,25 +1305,32 @@ pub fn circuit_into_typst_str(
                     )
             });
         for chunk_number in 0..number_of_chunks {
+            if options.column_numbers {
+                let chunk = |gates: &Option<Vec<Vec<Vec<LayoutItem>>>>| {
+                    gates
+                        .as_ref()
+                        .and_then(|chunks| chunks.get(chunk_number))
+                        .cloned()
+                        .unwrap_or_default()
+                };
+                let n_columns = line_columns(&[
+                    &chunk(&additional_circuit_gates),
+                    &chunk(&additional_bosonic_gates),
+                    &chunk(&additional_classical_gates),
+                ]);
+                typst_str.push_str(&column_numbers_row(first_column, n_columns));
+                first_column += n_columns;
+            }
             if let Some(ref add_circuit_gates) = additional_circuit_gates {
                 let current_chunk = &add_circuit_gates[chunk_number];
                 for (qubit_index, gates) in current_chunk.iter().enumerate() {
                     typst_str.push_str(&format!(
-                        "lstick($···q[{}]$), {}, 1, {}[\\ ],\n",
-                        qubit_index,
-                        gates
-                            .iter()
-                            .map(|gate| {
-                                if gate.contains("replace_by_n_qubits_") {
-                                    replace_boson_index(gate, n_qubits, n_bosons)
-                                } else if gate.contains("replace_by_classical_len_") {
-                                    replace_classical_index(gate, n_qubits, n_bosons, n_classical)
-                                } else {
-                                    gate.to_owned()
-                                }
-                            })
-                            .collect::<Vec<String>>()
-                            .join(", "),
+                        "lstick($···{}$), {}, 1, {}[\\ ],\n",
+                        options
+                            .wire_labels
+                            .name(WireRef::Qubit(qubit_indices[qubit_index]))
+                            .to_typst(),
+                        items_to_typst(gates, WireRef::Qubit(qubit_index), &rows).join(", "),
                         if chunk_number != number_of_chunks - 1 {
                             "rstick($···$),"
                         } else {
@@ -678,9 +1343,12 @@ pub fn circuit_into_typst_str(
                 let current_chunk = &add_bosonic_gates[chunk_number];
                 for (qubit_index, gates) in current_chunk.iter().enumerate() {
                     typst_str.push_str(&format!(
-                        "lstick($···b[{}]$), {}, 1, {}[\\ ],\n",
-                        qubit_index,
-                        gates.join(", "),
+                        "lstick($···{}$), {}, 1, {}[\\ ],\n",
+                        options
+                            .wire_labels
+                            .name(WireRef::Boson(qubit_index))
+                            .to_typst(),
+                        items_to_typst(gates, WireRef::Boson(qubit_index), &rows).join(", "),
                         if chunk_number != number_of_chunks - 1 {
                             "rstick($···$),"
                         } else {
@@ -692,8 +1360,11 @@ pub fn circuit_into_typst_str(
             if let Some(ref add_classical_gates) = additional_classical_gates {
                 let current_chunk = &add_classical_gates[chunk_number];
                 for (index, gates) in current_chunk.clone().iter_mut().enumerate() {
-                    gates.insert(0, classical_gates[index][1].clone());
-                    typst_str.push_str(&format!("{}, 1, [\\ ],\n", gates.join(", "),));
+                    gates.insert(0, LayoutItem::ClassicalWire);
+                    typst_str.push_str(&format!(
+                        "{}, 1, [\\ ],\n",
+                        items_to_typst(gates, WireRef::Classical(index), &rows).join(", ")
+                    ));
                 }
             }
         }
@@ -703,7 +1374,329 @@ pub fn circuit_into_typst_str(
         .map(str::to_owned)
         .unwrap_or(typst_str);
     typst_str.push_str(")\n}\n");
-    Ok(typst_str)
+    if let Some(footer) = footer {
+        typst_str.push_str(&footer);
+    }
+    (typst_str, warnings)
+}
+
+/// Returns the number of columns of a line of the diagram.
+///
+/// # Arguments
+///
+/// * `wires` - The qubit, bosonic and classical wires of the line.
+fn line_columns(wires: &[&[Vec<LayoutItem>]]) -> usize {
+    wires
+        .iter()
+        .flat_map(|wires| wires.iter())
+        .map(|items| effective_len(items))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the quill row numbering the columns of a line of the diagram, without wire.
+///
+/// # Arguments
+///
+/// * `first_column` - The number of the first column of the line.
+/// * `n_columns` - The number of columns of the line.
+///
+/// # Returns
+///
+/// * `String` - The row of the column numbers, empty for a line without columns.
+fn column_numbers_row(first_column: usize, n_columns: usize) -> String {
+    if n_columns == 0 {
+        return String::new();
+    }
+    format!(
+        "       setwire(0), lstick([]), {}, [\\ ],\n",
+        (first_column..first_column + n_columns)
+            .map(|column| format!("gate(text(size: 0.7em)[{column}], box: false)"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Returns the typst footer with the depth of the diagram and the gate count of each qubit.
+///
+/// # Arguments
+///
+/// * `depth` - The depth of the diagram.
+/// * `qubit_gate_counts` - The number of operations drawn on each qubit wire.
+/// * `qubit_indices` - The qubit drawn on each qubit wire.
+/// * `options` - The labels of the wires.
+///
+/// # Returns
+///
+/// * `String` - The typst markup of the footer.
+fn typst_footer(
+    depth: usize,
+    qubit_gate_counts: &[usize],
+    qubit_indices: &[usize],
+    options: &RenderOptions,
+) -> String {
+    let mut footer = format!("#text(size: 0.8em)[Depth: {depth}");
+    if !qubit_gate_counts.is_empty() {
+        footer.push_str(&format!(
+            " \\\n Gates: {}",
+            qubit_gate_counts
+                .iter()
+                .zip(qubit_indices)
+                .map(|(count, &qubit)| format!(
+                    "${}$: {count}",
+                    options.wire_labels.name(WireRef::Qubit(qubit)).to_typst()
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    footer.push_str("]\n");
+    footer
+}
+
+/// Serializes the items of a wire to their quantikz cells.
+///
+/// The slices are drawn right of the previous cell and the gate groups start in the next cell,
+/// the register label becomes the label of the wire.
+///
+/// # Arguments
+///
+/// * `items` - The items of the wire.
+/// * `wire` - The wire the items are placed on.
+/// * `rows` - The rows of the wires in the diagram.
+/// * `max_length` - The maximum number of columns of a circuit line, the gate groups are cut at
+///   the end of their line.
+///
+/// # Returns
+///
+/// * `(Option<String>, String, Vec<String>)` - The register label, the content of the label cell
+///   and the content of each column.
+fn items_to_quantikz(
+    items: &[LayoutItem],
+    wire: WireRef,
+    rows: &WireRows,
+    max_length: Option<usize>,
+) -> (Option<String>, String, Vec<String>) {
+    let mut register = None;
+    let mut start = String::new();
+    let mut cells: Vec<String> = Vec::new();
+    let mut pending = String::new();
+    for item in items {
+        match item {
+            LayoutItem::Slice { .. } => {
+                let cell = cells.last_mut().unwrap_or(&mut start);
+                cell.push(' ');
+                cell.push_str(&item.to_quantikz(wire, rows));
+            }
+            LayoutItem::GateGroup {
+                n_wires,
+                n_columns,
+                label,
+                stroke,
+            } => {
+                let n_columns = max_length.map_or(*n_columns, |max_length| {
+                    (*n_columns).min(max_length - cells.len() % max_length)
+                });
+                let group = LayoutItem::GateGroup {
+                    n_wires: *n_wires,
+                    n_columns,
+                    label: label.clone(),
+                    stroke: stroke.clone(),
+                };
+                pending.push_str(&group.to_quantikz(wire, rows));
+                pending.push(' ');
+            }
+            LayoutItem::RegisterLabel(name) => register = Some(name.clone()),
+            LayoutItem::ClassicalWire => (),
+            _ => {
+                let mut cell = std::mem::take(&mut pending);
+                cell.push_str(&item.to_quantikz(wire, rows));
+                cells.push(cell);
+            }
+        }
+    }
+    (register, start, cells)
+}
+
+/// Returns the LaTeX label of a global prepared state.
+///
+/// # Arguments
+///
+/// * `mixed` - Whether the state is a density matrix.
+fn global_state_latex(mixed: bool) -> &'static str {
+    if mixed {
+        "$\\rho$"
+    } else {
+        "$\\ket{\\psi}$"
+    }
+}
+
+/// Converts a qoqo circuit to a LaTeX quantikz environment.
+///
+/// The output uses the `quantikz2` TikZ library, e.g. `\usetikzlibrary{quantikz2}`. When the
+/// circuit is split into several lines, one environment is written per line.
+///
+///  ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The pragmas to render, the initialization mode, the maximum length of a circuit
+///   line and the rounding accuracy of the circuit representation.
+///
+/// ## Returns
+///
+/// * `String` - The quantikz representation of the circuit.
+pub fn circuit_into_quantikz_str(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    circuit_into_quantikz_str_with_warnings(circuit, options).map(|(quantikz_str, _)| quantikz_str)
+}
+
+/// Converts a qoqo circuit to a LaTeX quantikz environment, with the warnings emitted during the
+/// conversion.
+///
+///  ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The pragmas to render, the initialization mode, the maximum length of a circuit
+///   line and the rounding accuracy of the circuit representation.
+///
+/// ## Returns
+///
+/// * `(String, Vec<String>)` - The quantikz representation of the circuit and the warnings, e.g.
+///   the operations drawn by the fallback renderer.
+pub fn circuit_into_quantikz_str_with_warnings(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<(String, Vec<String>), RoqoqoBackendError> {
+    let layout = circuit_into_layout(circuit, options)?;
+    let rows = layout.wire_rows();
+    let mut warnings = layout.warnings.clone();
+    let wires: Vec<(WireRef, &Vec<LayoutItem>)> = layout
+        .qubits
+        .iter()
+        .enumerate()
+        .map(|(index, items)| (WireRef::Qubit(index), items))
+        .chain(
+            layout
+                .bosons
+                .iter()
+                .enumerate()
+                .map(|(index, items)| (WireRef::Boson(index), items)),
+        )
+        .chain(
+            layout
+                .classical
+                .iter()
+                .enumerate()
+                .map(|(index, items)| (WireRef::Classical(index), items)),
+        )
+        .collect();
+    if wires.iter().any(|(_, items)| {
+        items
+            .iter()
+            .any(|item| matches!(item, LayoutItem::Typst(_)))
+    }) {
+        warnings.push(
+            "Raw Typst items have no quantikz equivalent and were left out of the diagram."
+                .to_owned(),
+        );
+    }
+    let max_length = options.max_length.map(|max_length| max_length.max(1));
+    let wires: Vec<(WireRef, Option<String>, String, Vec<String>)> = wires
+        .into_iter()
+        .map(|(wire, items)| {
+            let (register, start, cells) = items_to_quantikz(items, wire, &rows, max_length);
+            (wire, register, start, cells)
+        })
+        .collect();
+    let n_columns = wires
+        .iter()
+        .map(|(.., cells)| cells.len())
+        .max()
+        .unwrap_or(0);
+    let chunk_len = max_length.unwrap_or(n_columns).max(1);
+    let n_chunks = n_columns.div_ceil(chunk_len).max(1);
+    let mut environments = Vec::new();
+    for chunk in 0..n_chunks {
+        let mut lines = Vec::new();
+        for (wire, register, start, cells) in wires.iter() {
+            let (span, label) = match wire {
+                _ if chunk > 0 => {
+                    let name = match wire {
+                        WireRef::Qubit(row) => Some(
+                            options
+                                .wire_labels
+                                .name(WireRef::Qubit(layout.qubit_index(*row)))
+                                .to_latex(),
+                        ),
+                        WireRef::Boson(_) => Some(options.wire_labels.name(*wire).to_latex()),
+                        WireRef::Classical(_) => register.as_deref().map(|name| {
+                            format!(
+                                "\\text{{{}}}",
+                                escape_latex_text(options.wire_labels.register_name(name))
+                            )
+                        }),
+                    };
+                    (
+                        String::new(),
+                        name.map_or("$\\cdots$".to_owned(), |name| format!("$\\cdots {name}$")),
+                    )
+                }
+                WireRef::Classical(_) => (
+                    String::new(),
+                    register
+                        .as_deref()
+                        .map(|name| escape_latex_text(options.wire_labels.register_name(name)))
+                        .unwrap_or_default(),
+                ),
+                WireRef::Qubit(row) => match layout.global_start(*row) {
+                    Some(GlobalStart::First { n_rows, mixed }) => {
+                        (format!("[{n_rows}]"), global_state_latex(mixed).to_owned())
+                    }
+                    Some(GlobalStart::Spanned { .. }) => (String::new(), String::new()),
+                    Some(GlobalStart::Alone { mixed }) => {
+                        (String::new(), global_state_latex(mixed).to_owned())
+                    }
+                    None => (
+                        String::new(),
+                        options.wire_labels.latex_start(
+                            WireRef::Qubit(layout.qubit_index(*row)),
+                            options.initialization_mode,
+                            layout.prepared_state.as_ref(),
+                        ),
+                    ),
+                },
+                WireRef::Boson(_) => (
+                    String::new(),
+                    options
+                        .wire_labels
+                        .latex_start(*wire, options.initialization_mode, None),
+                ),
+            };
+            let mut line = format!("\\lstick{span}{{{label}}}");
+            if matches!(wire, WireRef::Classical(_)) {
+                line.push_str(" \\setwiretype{c}");
+            }
+            if chunk == 0 {
+                line.push_str(start);
+            }
+            for column in chunk * chunk_len..((chunk + 1) * chunk_len).min(n_columns) {
+                line.push_str(" & ");
+                line.push_str(cells.get(column).map_or("", String::as_str));
+            }
+            line.push_str(" &");
+            if chunk + 1 < n_chunks {
+                line.push_str(" \\rstick{$\\cdots$}");
+            }
+            lines.push(line);
+        }
+        environments.push(format!(
+            "\\begin{{quantikz}}\n{}\n\\end{{quantikz}}\n",
+            lines.join(" \\\\\n")
+        ));
+    }
+    Ok((environments.join("\n"), warnings))
 }
 
 /// Converts a qoqo circuit to an image.
@@ -711,30 +1704,51 @@ pub fn circuit_into_typst_str(
 ///  ## Arguments
 ///
 /// * `circuit` - The circuit to convert.
-/// * `pixels_per_point` - The pixel per point ratio.
-/// * `render_pragmas` - Whether to render Pragma Operations or not.
-/// * `initialization_mode` - The initialization mode of the circuit representation.
-/// * `max_length` - The maximum length of a circuit line. If the circuit line
-///   is longer than this value, it will be split into multiple lines.
-/// * `rounding_accuracy` - The number of digits to round to when displaying floats.
+/// * `options` - The options of the circuit representation and of the rendering.
 ///
 /// ## Returns
 ///
 /// * DynamicImage: The image reprensenting the circuit.
 pub fn circuit_to_image(
     circuit: &Circuit,
-    pixels_per_point: Option<f32>,
-    render_pragmas: RenderPragmas,
-    initialization_mode: Option<InitializationMode>,
-    max_length: Option<usize>,
-    rounding_accuracy: Option<usize>,
+    options: &RenderOptions,
 ) -> Result<DynamicImage, RoqoqoBackendError> {
-    let typst_str = circuit_into_typst_str(
-        circuit,
-        render_pragmas,
-        initialization_mode,
-        max_length,
-        rounding_accuracy,
-    )?;
-    render_typst_str(typst_str, pixels_per_point)
+    let typst_str = circuit_into_typst_str(circuit, options)?;
+    render_typst_str(typst_str, options)
+}
+
+/// Converts a qoqo circuit to an SVG image.
+///
+///  ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The options of the circuit representation and of the rendering.
+///
+/// ## Returns
+///
+/// * String: The SVG image reprensenting the circuit.
+pub fn circuit_to_svg(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    let typst_str = circuit_into_typst_str(circuit, options)?;
+    render_typst_str_svg(typst_str, options)
+}
+
+/// Converts a qoqo circuit to a PDF document.
+///
+///  ## Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The options of the circuit representation and of the rendering.
+///
+/// ## Returns
+///
+/// * Vec<u8>: The bytes of the PDF document reprensenting the circuit.
+pub fn circuit_to_pdf(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, RoqoqoBackendError> {
+    let typst_str = circuit_into_typst_str(circuit, options)?;
+    render_typst_str_pdf(typst_str, options)
 }
@@ -0,0 +1,199 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use image::DynamicImage;
+use rayon::prelude::*;
+use roqoqo::{Circuit, RoqoqoBackendError};
+use typst::layout::PagedDocument;
+
+use crate::{
+    circuit_into_typst_str, compile_with_resources, document_to_image, document_to_pdf, first_page,
+    RenderOptions, TypstResources,
+};
+
+/// Renderer of circuits reusing the typst standard library, the fonts and the loaded package
+/// files across calls.
+///
+/// The free functions such as [crate::circuit_to_image] build all of them for every circuit,
+/// the Renderer builds them once so that rendering many circuits, e.g. from a parameter sweep,
+/// only pays for the compilation of each circuit.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    /// The options of the circuit representation and of the rendering.
+    options: RenderOptions,
+    /// The library, fonts and dependency files shared by the compilations.
+    resources: Arc<TypstResources>,
+}
+
+impl Renderer {
+    /// Creates a new Renderer.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options of the circuit representation and of the rendering.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Renderer)` - The new Renderer.
+    /// * `Err(RoqoqoBackendError)` - The cache directory of the backend could not be determined.
+    pub fn new(options: RenderOptions) -> Result<Self, RoqoqoBackendError> {
+        let resources = Arc::new(TypstResources::new(&options.backend_config)?);
+        Ok(Self { options, resources })
+    }
+
+    /// Returns the options of the circuit representation and of the rendering.
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    /// Compiles a typst string with the shared resources.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The string to give to the typst compiler.
+    fn compile(&self, typst_str: String) -> Result<PagedDocument, RoqoqoBackendError> {
+        compile_with_resources(typst_str, self.resources.clone())
+    }
+
+    /// Uses the Typst compiler to generate an image from the given typst string.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The string to give to the typst compiler.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DynamicImage)` - The image generated from the typst string.
+    /// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
+    pub fn render_typst_str(&self, typst_str: String) -> Result<DynamicImage, RoqoqoBackendError> {
+        document_to_image(&self.compile(typst_str)?, self.options.pixels_per_point)
+    }
+
+    /// Uses the Typst compiler to generate an SVG image from the given typst string.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The string to give to the typst compiler.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The SVG image generated from the typst string.
+    /// * `Err(RoqoqoBackendError)` - Error during the Typst compilation.
+    pub fn render_typst_str_svg(&self, typst_str: String) -> Result<String, RoqoqoBackendError> {
+        Ok(typst_svg::svg(first_page(&self.compile(typst_str)?)?))
+    }
+
+    /// Uses the Typst compiler to generate a PDF document from the given typst string.
+    ///
+    /// # Arguments
+    ///
+    /// * `typst_str` - The string to give to the typst compiler.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The bytes of the PDF document generated from the typst string.
+    /// * `Err(RoqoqoBackendError)` - Error during the Typst compilation or the PDF export.
+    pub fn render_typst_str_pdf(&self, typst_str: String) -> Result<Vec<u8>, RoqoqoBackendError> {
+        document_to_pdf(&self.compile(typst_str)?)
+    }
+
+    /// Converts a qoqo circuit to an image.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to convert.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DynamicImage)` - The image representing the circuit.
+    /// * `Err(RoqoqoBackendError)` - The circuit cannot be converted or compiled.
+    pub fn circuit_to_image(&self, circuit: &Circuit) -> Result<DynamicImage, RoqoqoBackendError> {
+        self.render_typst_str(circuit_into_typst_str(circuit, &self.options)?)
+    }
+
+    /// Converts a qoqo circuit to an SVG image.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to convert.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The SVG image representing the circuit.
+    /// * `Err(RoqoqoBackendError)` - The circuit cannot be converted or compiled.
+    pub fn circuit_to_svg(&self, circuit: &Circuit) -> Result<String, RoqoqoBackendError> {
+        self.render_typst_str_svg(circuit_into_typst_str(circuit, &self.options)?)
+    }
+
+    /// Converts a qoqo circuit to a PDF document.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to convert.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<u8>)` - The bytes of the PDF document representing the circuit.
+    /// * `Err(RoqoqoBackendError)` - The circuit cannot be converted, compiled or exported.
+    pub fn circuit_to_pdf(&self, circuit: &Circuit) -> Result<Vec<u8>, RoqoqoBackendError> {
+        self.render_typst_str_pdf(circuit_into_typst_str(circuit, &self.options)?)
+    }
+
+    /// Applies a rendering function to many inputs in parallel.
+    ///
+    /// The first input is rendered alone so that the typst packages are downloaded and loaded
+    /// only once, the other ones are rendered in parallel with rayon.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The inputs to render, e.g. circuits or typst strings.
+    /// * `render` - The rendering function, e.g. [Renderer::circuit_to_svg].
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Result<T, RoqoqoBackendError>>` - The result of each input, in the order of the inputs.
+    pub fn render_many_with<I, T, F>(
+        &self,
+        inputs: &[I],
+        render: F,
+    ) -> Vec<Result<T, RoqoqoBackendError>>
+    where
+        I: Sync,
+        T: Send,
+        F: Fn(&Self, &I) -> Result<T, RoqoqoBackendError> + Sync,
+    {
+        let Some((first, others)) = inputs.split_first() else {
+            return Vec::new();
+        };
+        let mut results = vec![render(self, first)];
+        results.par_extend(others.par_iter().map(|input| render(self, input)));
+        results
+    }
+
+    /// Converts many qoqo circuits to images in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuits` - The circuits to convert.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Result<DynamicImage, RoqoqoBackendError>>` - The image of each circuit, in the order of the circuits.
+    pub fn render_many(
+        &self,
+        circuits: &[Circuit],
+    ) -> Vec<Result<DynamicImage, RoqoqoBackendError>> {
+        self.render_many_with(circuits, Self::circuit_to_image)
+    }
+}
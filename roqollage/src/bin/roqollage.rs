@@ -0,0 +1,608 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command-line interface drawing the roqoqo circuits and QuantumPrograms serialized as JSON or
+//! bincode.
+
+use std::{
+    fs,
+    io::{Cursor, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+};
+
+use roqollage::{
+    circuit_into_layout, circuit_into_typst_str_with_warnings, layout_to_text,
+    quantum_program_into_typst_str_with_warnings, render_typst_str, render_typst_str_pdf,
+    render_typst_str_svg, Background, CacheDirectory, InitializationMode, LayeringStrategy,
+    NetworkPolicy, QubitOrder, RenderOptions, RenderPragmas, Theme,
+};
+use roqoqo::{measurements::Measure, Circuit, QuantumProgram};
+
+const USAGE: &str = "\
+Draws a roqoqo Circuit or QuantumProgram serialized as JSON or bincode.
+
+Usage: roqollage [OPTIONS] [INPUT]
+
+Arguments:
+  [INPUT]  The serialized circuit or QuantumProgram, read from stdin if missing or `-`
+
+Options:
+  -o, --output <PATH>              The output file, written to stdout if missing
+  -f, --format <FORMAT>            png, svg, pdf, typst or text, guessed from the extension of the
+                                   output file, text if there is no output file
+      --input-format <FORMAT>      json or bincode, both are tried if missing
+      --circuit <INDEX>            Draws only this measurement circuit of a QuantumProgram, after
+                                   its constant circuit, instead of all its circuits as panels.
+                                   Required by the text format for programs of several circuits
+      --options <PATH>             A JSON file of RenderOptions, the other options take precedence
+      --pixels-per-point <VALUE>   The pixel per point ratio of the png images [default: 3]
+      --render-pragmas <PRAGMAS>   all, none or a comma-separated list of pragmas [default: all]
+      --initialization-mode <MODE> state for |0>, qubit for q[n] or inferred for the states
+                                   prepared at the beginning of the circuit [default: state]
+      --max-length <LENGTH>        The maximum number of gates per line
+      --rounding-accuracy <DIGITS> The number of digits displayed for floats [default: 3]
+      --cache-dir <DIR>            default, user or the path of the typst package cache
+      --network-policy <POLICY>    allow, deny or the URL of a typst package registry
+      --fallback-unsupported       Draws the unsupported operations as boxes labelled with their name
+      --theme <THEME>              default, light, dark, high_contrast or colour_blind gate colours
+      --background <COLOUR>        white, transparent or a typst colour of the page [default: white]
+      --page-margin <POINTS>       The margin around the circuit [default: 5]
+      --qubit-label <INDEX=NAME>   The name of a qubit, e.g. `0=ancilla`, can be repeated
+      --boson-label <INDEX=NAME>   The name of a bosonic mode, can be repeated
+      --register-label <REG=NAME>  The name displayed for a classical register, can be repeated
+      --qubit-state <INDEX=STATE>  The initial state of a qubit as typst math, e.g. `1=psi`
+      --boson-state <INDEX=STATE>  The initial state of a bosonic mode as typst math
+      --qubit-order <ORDER>        ascending, reversed or a comma-separated list of the qubits
+                                   drawn first, from top to bottom [default: ascending]
+      --qubit-subset <QUBITS>      A comma-separated list of the qubits to draw
+      --hide-idle-qubits           Hides the wires of the qubits without any operation
+      --layering <STRATEGY>        greedy, strict, asap, alap or respect_barriers assignment of the
+                                   operations to the columns [default: greedy]
+      --column-numbers             Numbers the columns above the diagram
+      --depth-footer               Adds the depth and the gate count of each qubit below the diagram
+  -h, --help                       Prints this help
+  -V, --version                    Prints the version
+";
+
+/// The output formats of the command-line interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Png,
+    Svg,
+    Pdf,
+    Typst,
+    Text,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(OutputFormat::Png),
+            "svg" => Ok(OutputFormat::Svg),
+            "pdf" => Ok(OutputFormat::Pdf),
+            "typst" | "typ" => Ok(OutputFormat::Typst),
+            "text" | "txt" => Ok(OutputFormat::Text),
+            _ => Err(format!(
+                "Invalid output format: {s}, use `png`, `svg`, `pdf`, `typst` or `text`."
+            )),
+        }
+    }
+}
+
+/// The serialization formats of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Json,
+    Bincode,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(InputFormat::Json),
+            "bincode" => Ok(InputFormat::Bincode),
+            _ => Err(format!(
+                "Invalid input format: {s}, use `json` or `bincode`."
+            )),
+        }
+    }
+}
+
+/// The parsed command-line arguments.
+#[derive(Debug, Default)]
+struct Arguments {
+    /// The input file, stdin if not set.
+    input: Option<PathBuf>,
+    /// The output file, stdout if not set.
+    output: Option<PathBuf>,
+    /// The output format, guessed from the output file if not set.
+    format: Option<OutputFormat>,
+    /// The input format, all the formats are tried if not set.
+    input_format: Option<InputFormat>,
+    /// The index of the measurement circuit of a QuantumProgram, all the circuits if not set.
+    circuit_index: Option<usize>,
+    /// The rendering options.
+    options: RenderOptions,
+    /// Whether to print the help.
+    help: bool,
+    /// Whether to print the version.
+    version: bool,
+}
+
+/// Parses a value of a command-line option.
+///
+/// # Arguments
+///
+/// * `name` - The name of the option.
+/// * `value` - The value of the option.
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value `{value}` for `{name}`."))
+}
+
+/// Splits the value of a command-line option of the form `KEY=VALUE`.
+///
+/// # Arguments
+///
+/// * `name` - The name of the option.
+/// * `value` - The value of the option.
+fn split_assignment<'a>(name: &str, value: &'a str) -> Result<(&'a str, &'a str), String> {
+    value
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid value `{value}` for `{name}`, expected `KEY=VALUE`."))
+}
+
+/// Parses the command-line arguments.
+///
+/// The rendering options given on the command line take precedence over the `--options` file.
+///
+/// # Arguments
+///
+/// * `arguments` - The command-line arguments, without the name of the program.
+///
+/// # Returns
+///
+/// * `Ok(Arguments)` - The parsed arguments.
+/// * `Err(String)` - An argument is unknown or has an invalid value.
+fn parse_arguments(arguments: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut parsed = Arguments::default();
+    let mut overrides: Vec<(String, String)> = Vec::new();
+    let mut arguments = arguments;
+    while let Some(argument) = arguments.next() {
+        let (name, inline_value) = match argument.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_owned(), Some(value.to_owned()))
+            }
+            _ => (argument.clone(), None),
+        };
+        match name.as_str() {
+            "-h" | "--help" => parsed.help = true,
+            "-V" | "--version" => parsed.version = true,
+            "--fallback-unsupported"
+            | "--hide-idle-qubits"
+            | "--column-numbers"
+            | "--depth-footer" => overrides.push((name, String::new())),
+            "-" => parsed.input = None,
+            "-o"
+            | "--output"
+            | "-f"
+            | "--format"
+            | "--input-format"
+            | "--circuit"
+            | "--options"
+            | "--pixels-per-point"
+            | "--render-pragmas"
+            | "--initialization-mode"
+            | "--max-length"
+            | "--rounding-accuracy"
+            | "--cache-dir"
+            | "--network-policy"
+            | "--theme"
+            | "--background"
+            | "--page-margin"
+            | "--qubit-label"
+            | "--boson-label"
+            | "--register-label"
+            | "--qubit-state"
+            | "--boson-state"
+            | "--qubit-order"
+            | "--qubit-subset"
+            | "--layering" => {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => arguments
+                        .next()
+                        .ok_or_else(|| format!("Missing value for `{name}`."))?,
+                };
+                match name.as_str() {
+                    "-o" | "--output" => parsed.output = Some(PathBuf::from(value)),
+                    "-f" | "--format" => parsed.format = Some(value.parse()?),
+                    "--input-format" => parsed.input_format = Some(value.parse()?),
+                    "--circuit" => parsed.circuit_index = Some(parse_value(&name, &value)?),
+                    "--options" => {
+                        let json = fs::read_to_string(&value)
+                            .map_err(|err| format!("Cannot read `{value}`: {err}"))?;
+                        parsed.options = serde_json::from_str(&json)
+                            .map_err(|err| format!("Invalid options in `{value}`: {err}"))?;
+                    }
+                    _ => overrides.push((name, value)),
+                }
+            }
+            _ if name.starts_with('-') => return Err(format!("Unknown option `{name}`.")),
+            _ if parsed.input.is_some() => {
+                return Err(format!("Unexpected argument `{argument}`."))
+            }
+            _ => parsed.input = Some(PathBuf::from(argument)),
+        }
+    }
+    for (name, value) in overrides {
+        let options = &mut parsed.options;
+        match name.as_str() {
+            "--pixels-per-point" => options.pixels_per_point = parse_value(&name, &value)?,
+            "--render-pragmas" => {
+                options.render_pragmas = RenderPragmas::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--initialization-mode" => {
+                options.initialization_mode = InitializationMode::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--max-length" => options.max_length = Some(parse_value(&name, &value)?),
+            "--rounding-accuracy" => options.rounding_accuracy = parse_value(&name, &value)?,
+            "--fallback-unsupported" => options.fallback_unsupported = true,
+            "--hide-idle-qubits" => options.hide_idle_qubits = true,
+            "--column-numbers" => options.column_numbers = true,
+            "--depth-footer" => options.depth_footer = true,
+            "--qubit-order" => {
+                options.qubit_order = QubitOrder::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--layering" => {
+                options.layering = LayeringStrategy::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--qubit-subset" => {
+                options.qubit_subset = Some(
+                    value
+                        .split(',')
+                        .map(|qubit| parse_value(&name, qubit.trim()))
+                        .collect::<Result<Vec<usize>, String>>()?,
+                )
+            }
+            "--background" => {
+                options.background = Background::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--page-margin" => options.page_margin = parse_value(&name, &value)?,
+            "--theme" => {
+                options.theme = Theme::from_str(&value)
+                    .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?
+            }
+            "--register-label" => {
+                let (register, label) = split_assignment(&name, &value)?;
+                options.wire_labels = options.wire_labels.clone().register(register, label);
+            }
+            "--qubit-label" | "--boson-label" | "--qubit-state" | "--boson-state" => {
+                let (index, label) = split_assignment(&name, &value)?;
+                let index = parse_value(&name, index)?;
+                let labels = options.wire_labels.clone();
+                options.wire_labels = match name.as_str() {
+                    "--qubit-label" => labels.qubit(index, label),
+                    "--boson-label" => labels.boson(index, label),
+                    "--qubit-state" => labels.qubit_state(index, label),
+                    _ => labels.boson_state(index, label),
+                };
+            }
+            "--cache-dir" => {
+                options.backend_config = options.backend_config.clone().cache_directory(
+                    CacheDirectory::from_str(&value)
+                        .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?,
+                )
+            }
+            _ => {
+                options.backend_config = options.backend_config.clone().network_policy(
+                    NetworkPolicy::from_str(&value)
+                        .map_err(|err| format!("Invalid value for `{name}`: {err:?}"))?,
+                )
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Deserializes a value serialized with bincode, as done by `to_bincode` in qoqo.
+///
+/// # Arguments
+///
+/// * `bytes` - The serialized value, that must be read completely.
+fn from_bincode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    match bincode::serde::decode_from_slice(bytes, bincode::config::legacy()) {
+        Ok((value, read)) if read == bytes.len() => Some(value),
+        _ => None,
+    }
+}
+
+/// The deserialized input of the command-line interface.
+#[derive(Debug)]
+enum Input {
+    Circuit(Circuit),
+    Program(QuantumProgram),
+}
+
+/// Deserializes a circuit or a QuantumProgram.
+///
+/// # Arguments
+///
+/// * `bytes` - The serialized circuit or QuantumProgram.
+/// * `input_format` - The serialization format, all the formats are tried if not set.
+///
+/// # Returns
+///
+/// * `Ok(Input)` - The circuit or QuantumProgram to draw.
+/// * `Err(String)` - The input is neither a circuit nor a QuantumProgram.
+fn read_input(bytes: &[u8], input_format: Option<InputFormat>) -> Result<Input, String> {
+    let try_json = !matches!(input_format, Some(InputFormat::Bincode));
+    let try_bincode = !matches!(input_format, Some(InputFormat::Json));
+    let mut json_errors = Vec::new();
+    if try_json {
+        match serde_json::from_slice::<Circuit>(bytes) {
+            Ok(circuit) => return Ok(Input::Circuit(circuit)),
+            Err(err) => json_errors.push(format!("as a Circuit: {err}")),
+        }
+    }
+    if let Some(circuit) = try_bincode
+        .then(|| from_bincode::<Circuit>(bytes))
+        .flatten()
+    {
+        return Ok(Input::Circuit(circuit));
+    }
+    if try_json {
+        match serde_json::from_slice::<QuantumProgram>(bytes) {
+            Ok(program) => return Ok(Input::Program(program)),
+            Err(err) => json_errors.push(format!("as a QuantumProgram: {err}")),
+        }
+    }
+    if let Some(program) = try_bincode
+        .then(|| from_bincode::<QuantumProgram>(bytes))
+        .flatten()
+    {
+        return Ok(Input::Program(program));
+    }
+    let mut message =
+        "The input is neither a serialized Circuit nor a serialized QuantumProgram.".to_owned();
+    if !json_errors.is_empty() {
+        message.push_str(&format!(
+            " JSON deserialization failed {}.",
+            json_errors.join(", ")
+        ));
+    }
+    Err(message)
+}
+
+/// Returns a measurement circuit of a QuantumProgram preceded by its constant circuit.
+///
+/// # Arguments
+///
+/// * `program` - The QuantumProgram.
+/// * `circuit_index` - The measurement circuit, the only circuit of the program if not set.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit to draw.
+/// * `Err(String)` - The circuit does not exist, or is not set and the program has several circuits.
+fn program_circuit(
+    program: &QuantumProgram,
+    circuit_index: Option<usize>,
+) -> Result<Circuit, String> {
+    let (constant_circuit, circuits): (Option<Circuit>, Vec<Circuit>) = match program {
+        QuantumProgram::PauliZProduct { measurement, .. } => (
+            measurement.constant_circuit().clone(),
+            measurement.circuits().cloned().collect(),
+        ),
+        QuantumProgram::CheatedPauliZProduct { measurement, .. } => (
+            measurement.constant_circuit().clone(),
+            measurement.circuits().cloned().collect(),
+        ),
+        QuantumProgram::Cheated { measurement, .. } => (
+            measurement.constant_circuit().clone(),
+            measurement.circuits().cloned().collect(),
+        ),
+        QuantumProgram::ClassicalRegister { measurement, .. } => (
+            measurement.constant_circuit().clone(),
+            measurement.circuits().cloned().collect(),
+        ),
+        _ => return Err("The QuantumProgram variant is not supported.".to_owned()),
+    };
+    let circuit_index = match circuit_index {
+        Some(circuit_index) => circuit_index,
+        None if circuits.len() <= 1 => 0,
+        None => {
+            return Err(format!(
+                "The QuantumProgram has {} circuits and the text format draws a single circuit, select one with `--circuit <INDEX>`.",
+                circuits.len()
+            ))
+        }
+    };
+    let measurement_circuit = circuits.get(circuit_index).ok_or_else(|| {
+        format!(
+            "The QuantumProgram has {} circuits, cannot draw circuit {circuit_index}.",
+            circuits.len()
+        )
+    })?;
+    let mut circuit = constant_circuit.unwrap_or_else(Circuit::new);
+    for operation in measurement_circuit.iter() {
+        circuit.add_operation(operation.clone());
+    }
+    Ok(circuit)
+}
+
+/// Draws the input in the requested format.
+///
+/// A QuantumProgram is drawn as the panels of its circuits, unless a single measurement circuit is
+/// selected. The text format draws a single circuit.
+///
+/// # Arguments
+///
+/// * `input` - The circuit or QuantumProgram to draw.
+/// * `circuit_index` - The measurement circuit of a QuantumProgram to draw, if set.
+/// * `format` - The output format.
+/// * `options` - The rendering options.
+///
+/// # Returns
+///
+/// * `Ok((Vec<u8>, Vec<String>))` - The drawing and the warnings emitted while drawing.
+/// * `Err(String)` - The input cannot be drawn.
+fn draw(
+    input: &Input,
+    circuit_index: Option<usize>,
+    format: OutputFormat,
+    options: &RenderOptions,
+) -> Result<(Vec<u8>, Vec<String>), String> {
+    let circuit = match input {
+        Input::Program(program) if circuit_index.is_none() && format != OutputFormat::Text => {
+            let (typst_str, warnings) =
+                quantum_program_into_typst_str_with_warnings(program, options)
+                    .map_err(|err| format!("{err:?}"))?;
+            return Ok((render(typst_str, format, options)?, warnings));
+        }
+        Input::Program(program) => program_circuit(program, circuit_index)?,
+        Input::Circuit(circuit) => circuit.clone(),
+    };
+    if format == OutputFormat::Text {
+        let layout = circuit_into_layout(&circuit, options).map_err(|err| format!("{err:?}"))?;
+        return Ok((
+            layout_to_text(&layout, options).into_bytes(),
+            layout.warnings,
+        ));
+    }
+    let (typst_str, warnings) = circuit_into_typst_str_with_warnings(&circuit, options)
+        .map_err(|err| format!("{err:?}"))?;
+    Ok((render(typst_str, format, options)?, warnings))
+}
+
+/// Renders a typst document in the requested format.
+///
+/// # Arguments
+///
+/// * `typst_str` - The typst document.
+/// * `format` - The output format, the typst document itself for the typst and text formats.
+/// * `options` - The rendering options.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The rendered document.
+/// * `Err(String)` - The document cannot be rendered.
+fn render(
+    typst_str: String,
+    format: OutputFormat,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, String> {
+    Ok(match format {
+        OutputFormat::Typst | OutputFormat::Text => typst_str.into_bytes(),
+        OutputFormat::Svg => render_typst_str_svg(typst_str, options)
+            .map_err(|err| format!("{err:?}"))?
+            .into_bytes(),
+        OutputFormat::Pdf => {
+            render_typst_str_pdf(typst_str, options).map_err(|err| format!("{err:?}"))?
+        }
+        OutputFormat::Png => {
+            let image = render_typst_str(typst_str, options).map_err(|err| format!("{err:?}"))?;
+            let mut writer = Cursor::new(Vec::new());
+            image
+                .write_to(&mut writer, image::ImageFormat::Png)
+                .map_err(|err| err.to_string())?;
+            writer.into_inner()
+        }
+    })
+}
+
+/// Runs the command-line interface.
+///
+/// # Arguments
+///
+/// * `arguments` - The parsed command-line arguments.
+fn run(arguments: Arguments) -> Result<(), String> {
+    let format = match (arguments.format, &arguments.output) {
+        (Some(format), _) => format,
+        (None, Some(output)) => output
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .ok_or_else(|| {
+                format!(
+                    "Cannot guess the format of `{}`, use `--format`.",
+                    output.display()
+                )
+            })?
+            .parse()?,
+        (None, None) => OutputFormat::Text,
+    };
+    let bytes = match &arguments.input {
+        Some(input) => {
+            fs::read(input).map_err(|err| format!("Cannot read `{}`: {err}", input.display()))?
+        }
+        None => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut bytes)
+                .map_err(|err| format!("Cannot read stdin: {err}"))?;
+            bytes
+        }
+    };
+    let input = read_input(&bytes, arguments.input_format)?;
+    let (mut drawing, warnings) =
+        draw(&input, arguments.circuit_index, format, &arguments.options)?;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    match &arguments.output {
+        Some(output) => fs::write(output, drawing)
+            .map_err(|err| format!("Cannot write `{}`: {err}", output.display())),
+        None => {
+            // Terminates the last line of the textual formats printed to the terminal.
+            if matches!(format, OutputFormat::Text | OutputFormat::Typst)
+                && !drawing.ends_with(b"\n")
+            {
+                drawing.push(b'\n');
+            }
+            std::io::stdout()
+                .write_all(&drawing)
+                .map_err(|err| format!("Cannot write to stdout: {err}"))
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let result = parse_arguments(std::env::args().skip(1)).and_then(|arguments| {
+        if arguments.help {
+            print!("{USAGE}");
+            Ok(())
+        } else if arguments.version {
+            println!("roqollage {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        } else {
+            run(arguments)
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            eprintln!("Run `roqollage --help` for the list of options.");
+            ExitCode::FAILURE
+        }
+    }
+}
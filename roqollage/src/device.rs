@@ -0,0 +1,300 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::DynamicImage;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::{devices::Device, RoqoqoBackendError};
+
+use crate::{format_calculator, render_typst_str, typst_header, RenderOptions};
+
+/// Distance between two neighbouring qubits of a lattice layout, in points.
+const NODE_SPACING: f64 = 70.0;
+/// Radius of the qubit nodes, in points.
+const NODE_RADIUS: f64 = 11.0;
+/// Space left around the nodes, in points.
+const MARGIN: f64 = 30.0;
+/// Colour of the qubits without decoherence.
+const LOW_RATE_COLOUR: (f64, f64, f64) = (143.0, 214.0, 148.0);
+/// Colour of the qubits with the highest decoherence rate of the device.
+const HIGH_RATE_COLOUR: (f64, f64, f64) = (232.0, 120.0, 124.0);
+
+/// Placement of the qubits of a device drawing.
+struct NodeLayout {
+    /// The position of each qubit, in points.
+    positions: Vec<(f64, f64)>,
+    /// The width of the drawing, in points.
+    width: f64,
+    /// The height of the drawing, in points.
+    height: f64,
+    /// Whether the qubits are placed on a lattice or on a circle.
+    lattice: bool,
+}
+
+/// Finds the number of columns of a lattice layout of the connectivity graph.
+///
+/// The qubits are placed row by row, a layout is valid when every edge connects two neighbouring
+/// qubits of the same row or of the same column. This recovers the layout of a
+/// SquareLatticeDevice and of linear chains.
+///
+/// # Arguments
+///
+/// * `number_qubits` - The number of qubits of the device.
+/// * `edges` - The two-qubit edges of the device.
+///
+/// # Returns
+///
+/// * `Some(usize)` - The smallest number of columns of a valid lattice layout.
+/// * `None` - No lattice layout is valid, e.g. for an all-to-all connectivity.
+fn lattice_columns(number_qubits: usize, edges: &[(usize, usize)]) -> Option<usize> {
+    if edges.is_empty() {
+        return Some(number_qubits.max(1));
+    }
+    (2..=number_qubits).find(|&columns| {
+        edges.iter().all(|&(first, second)| {
+            let (low, high) = (first.min(second), first.max(second));
+            (high == low + 1 && low / columns == high / columns) || high == low + columns
+        })
+    })
+}
+
+/// Places the qubits on a lattice when the connectivity allows it and on a circle otherwise.
+///
+/// # Arguments
+///
+/// * `number_qubits` - The number of qubits of the device.
+/// * `edges` - The two-qubit edges of the device.
+fn node_layout(number_qubits: usize, edges: &[(usize, usize)]) -> NodeLayout {
+    match lattice_columns(number_qubits, edges) {
+        Some(columns) => {
+            let rows = number_qubits.div_ceil(columns).max(1);
+            NodeLayout {
+                positions: (0..number_qubits)
+                    .map(|qubit| {
+                        (
+                            MARGIN + (qubit % columns) as f64 * NODE_SPACING,
+                            MARGIN + (qubit / columns) as f64 * NODE_SPACING,
+                        )
+                    })
+                    .collect(),
+                width: 2.0 * MARGIN + (columns - 1) as f64 * NODE_SPACING,
+                height: 2.0 * MARGIN + (rows - 1) as f64 * NODE_SPACING,
+                lattice: true,
+            }
+        }
+        None => {
+            let radius = (number_qubits as f64 * NODE_SPACING / std::f64::consts::TAU)
+                .max(NODE_SPACING / 2.0);
+            let centre = MARGIN + radius;
+            NodeLayout {
+                positions: (0..number_qubits)
+                    .map(|qubit| {
+                        let angle = std::f64::consts::TAU * qubit as f64 / number_qubits as f64
+                            - std::f64::consts::FRAC_PI_2;
+                        (centre + radius * angle.cos(), centre + radius * angle.sin())
+                    })
+                    .collect(),
+                width: 2.0 * centre,
+                height: 2.0 * centre,
+                lattice: false,
+            }
+        }
+    }
+}
+
+/// Returns the total decoherence rate of a qubit, the trace of its decoherence rate matrix.
+///
+/// # Arguments
+///
+/// * `device` - The device.
+/// * `qubit` - The qubit.
+fn decoherence_rate<D: Device + ?Sized>(device: &D, qubit: usize) -> Option<f64> {
+    device
+        .qubit_decoherence_rates(&qubit)
+        .map(|rates| rates.diag().sum())
+}
+
+/// Interpolates the colour of a qubit between the low and the high decoherence rate colours.
+///
+/// # Arguments
+///
+/// * `rate` - The total decoherence rate of the qubit, if the device defines it.
+/// * `max_rate` - The highest total decoherence rate of the device.
+///
+/// # Returns
+///
+/// * `String` - The typst colour of the qubit, white when the rate is not defined.
+fn rate_colour(rate: Option<f64>, max_rate: f64) -> String {
+    let Some(rate) = rate else {
+        return "white".to_owned();
+    };
+    let fraction = if max_rate > 0.0 {
+        (rate / max_rate).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let mix = |low: f64, high: f64| (low + fraction * (high - low)).round() as u8;
+    format!(
+        "rgb({}, {}, {})",
+        mix(LOW_RATE_COLOUR.0, HIGH_RATE_COLOUR.0),
+        mix(LOW_RATE_COLOUR.1, HIGH_RATE_COLOUR.1),
+        mix(LOW_RATE_COLOUR.2, HIGH_RATE_COLOUR.2)
+    )
+}
+
+/// Lists the gate times of the two-qubit gates available on an edge.
+///
+/// # Arguments
+///
+/// * `device` - The device.
+/// * `edge` - The two qubits of the edge.
+/// * `rounding_accuracy` - The number of decimals of the gate times.
+///
+/// # Returns
+///
+/// * `Vec<String>` - One `gate: time` line of typst markup per available gate.
+fn edge_gate_times<D: Device + ?Sized>(
+    device: &D,
+    edge: (usize, usize),
+    rounding_accuracy: usize,
+) -> Vec<String> {
+    let mut gate_names = device.two_qubit_gate_names();
+    gate_names.sort();
+    gate_names
+        .into_iter()
+        .filter_map(|name| {
+            device
+                .two_qubit_gate_time(&name, &edge.0, &edge.1)
+                .or_else(|| device.two_qubit_gate_time(&name, &edge.1, &edge.0))
+                .map(|time| {
+                    format!(
+                        "{name}: ${}$",
+                        format_calculator(&CalculatorFloat::Float(time), rounding_accuracy)
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Converts a roqoqo device to a typst string drawing its two-qubit connectivity graph.
+///
+/// The qubits are drawn on a lattice when every edge connects neighbouring qubits of a row or of
+/// a column, e.g. for a SquareLatticeDevice, and on a circle otherwise. The qubits are coloured
+/// by their total decoherence rate and the edges are annotated with the gate times of the
+/// two-qubit gates.
+///
+/// # Arguments
+///
+/// * `device` - The device to draw.
+/// * `options` - The options of the representation, the gate times follow `rounding_accuracy`.
+///
+/// # Returns
+///
+/// * `String` - The typst string of the device.
+pub fn device_into_typst_str<D: Device + ?Sized>(device: &D, options: &RenderOptions) -> String {
+    let number_qubits = device.number_qubits();
+    let mut edges: Vec<(usize, usize)> = device
+        .two_qubit_edges()
+        .into_iter()
+        .map(|(first, second)| (first.min(second), first.max(second)))
+        .filter(|&(_, second)| second < number_qubits)
+        .collect();
+    edges.sort();
+    edges.dedup();
+    let layout = node_layout(number_qubits, &edges);
+    let rates: Vec<Option<f64>> = (0..number_qubits)
+        .map(|qubit| decoherence_rate(device, qubit))
+        .collect();
+    let max_rate = rates
+        .iter()
+        .flatten()
+        .fold(0.0_f64, |max, &rate| max.max(rate));
+    let offset = |(x, y): (f64, f64)| (x - layout.width / 2.0, y - layout.height / 2.0);
+
+    let mut single_qubit_gates = device.single_qubit_gate_names();
+    single_qubit_gates.sort();
+    let mut two_qubit_gates = device.two_qubit_gate_names();
+    two_qubit_gates.sort();
+    let gate_list = |gates: &[String]| {
+        if gates.is_empty() {
+            "none".to_owned()
+        } else {
+            gates.join(", ")
+        }
+    };
+    let mut typst_str = format!(
+        "{}*Device* with {number_qubits} qubits \\\nSingle-qubit gates: {} \\\nTwo-qubit gates: {}\n",
+        typst_header(options),
+        gate_list(&single_qubit_gates),
+        gate_list(&two_qubit_gates),
+    );
+    typst_str.push_str(&format!(
+        "#box(width: {:.1}pt, height: {:.1}pt)[\n",
+        layout.width, layout.height
+    ));
+    for &(first, second) in edges.iter() {
+        let (start, end) = (layout.positions[first], layout.positions[second]);
+        typst_str.push_str(&format!(
+            "#place(top + left, line(start: ({:.1}pt, {:.1}pt), end: ({:.1}pt, {:.1}pt), stroke: 0.8pt + gray))\n",
+            start.0, start.1, end.0, end.1
+        ));
+    }
+    // Labels are moved towards the first qubit on a circle, where the diagonals cross at the centre.
+    let label_fraction = if layout.lattice { 0.5 } else { 0.3 };
+    for &(first, second) in edges.iter() {
+        let gate_times = edge_gate_times(device, (first, second), options.rounding_accuracy);
+        if gate_times.is_empty() {
+            continue;
+        }
+        let (start, end) = (layout.positions[first], layout.positions[second]);
+        let (dx, dy) = offset((
+            start.0 + label_fraction * (end.0 - start.0),
+            start.1 + label_fraction * (end.1 - start.1),
+        ));
+        typst_str.push_str(&format!(
+            "#place(center + horizon, dx: {dx:.1}pt, dy: {dy:.1}pt, box(fill: white, inset: 1.5pt, text(size: 6pt, fill: black)[{}]))\n",
+            gate_times.join(" \\ ")
+        ));
+    }
+    for (qubit, (&position, rate)) in layout.positions.iter().zip(rates.iter()).enumerate() {
+        let (dx, dy) = offset(position);
+        typst_str.push_str(&format!(
+            "#place(center + horizon, dx: {dx:.1}pt, dy: {dy:.1}pt, circle(radius: {NODE_RADIUS}pt, fill: {}, stroke: 0.6pt, align(center + horizon, text(size: 8pt, fill: black)[{qubit}])))\n",
+            rate_colour(*rate, max_rate)
+        ));
+    }
+    typst_str.push_str("]\n");
+    if rates.iter().any(Option::is_some) {
+        typst_str.push_str(&format!(
+            "Qubit colour: decoherence rate from $0$ (green) to ${}$ (red)\n",
+            format_calculator(&CalculatorFloat::Float(max_rate), options.rounding_accuracy)
+        ));
+    }
+    typst_str
+}
+
+/// Converts a roqoqo device to an image of its two-qubit connectivity graph.
+///
+/// # Arguments
+///
+/// * `device` - The device to draw.
+/// * `options` - The options of the representation and of the rendering.
+///
+/// # Returns
+///
+/// * `Ok(DynamicImage)` - The image representing the device.
+/// * `Err(RoqoqoBackendError)` - The typst string of the device cannot be compiled.
+pub fn device_to_image<D: Device + ?Sized>(
+    device: &D,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    render_typst_str(device_into_typst_str(device, options), options)
+}
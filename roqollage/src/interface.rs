@@ -10,15 +10,25 @@
 // express or implied. See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::{collections::HashMap, sync::LazyLock};
+
 use num_complex::Complex64;
 use qoqo_calculator::CalculatorFloat;
-use roqoqo::{operations::*, RoqoqoBackendError, RoqoqoError};
+use regex::Regex;
+use roqoqo::{operations::*, Circuit, RoqoqoBackendError, RoqoqoError};
 use typst::foundations::Value::Symbol;
 
-use crate::RenderPragmas;
+use crate::{
+    effective_len, involved_modes, CircuitLayout, GateFamily, GateRenderer, GenericRenderer,
+    InitializationMode, LayoutItem, PreparedState, QubitArrangement, RenderOptions, WireRef,
+};
 
 const EPSILON: f64 = 1e-6;
 
+/// The symbols and the numbers in scientific notation of the symbolic parameters.
+static SYMBOLS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([a-zA-Z][\w.]+|-?[\d\.]+e-?\d)").unwrap());
+
 // Operations that are ignored by backend and do not throw an error.
 const ALLOWED_OPERATIONS: &[&str; 3] = &["DefinitionFloat", "DefinitionComplex", "DefinitionUsize"];
 
@@ -28,36 +38,14 @@ const ALLOWED_OPERATIONS: &[&str; 3] = &["DefinitionFloat", "DefinitionComplex",
 ///
 /// * `circuit_gates` - A vector of all the gates vectors of the circuit.
 /// * `qubits` - A vector of the qubits to represent.
-fn add_qubits_vec(circuit_gates: &mut Vec<Vec<String>>, qubits: &[usize]) {
-    while &circuit_gates.len() <= qubits.iter().max().unwrap_or(&0) {
-        circuit_gates.push(Vec::new());
+fn add_qubits_vec(circuit_gates: &mut Vec<Vec<LayoutItem>>, qubits: &[usize]) {
+    if let Some(&max) = qubits.iter().max() {
+        while circuit_gates.len() <= max {
+            circuit_gates.push(Vec::new());
+        }
     }
 }
 
-/// Calculates the length on the image since some gate are not represented in typst
-/// and therefore are not taking any space.
-///
-/// # Arguments
-///
-/// * `gates` - A vector of gates in typst representation.
-///
-/// # Returns
-///
-/// * `usize` - The total length the gates will take on the image.
-pub(crate) fn effective_len(gates: &[String]) -> usize {
-    gates.len()
-        - gates
-            .iter()
-            .filter(|gate| {
-                gate.contains("slice")
-                    || gate.contains("gategroup")
-                    || gate.contains("lstick")
-                    || gate.contains("setwire")
-            })
-            .collect::<Vec<&String>>()
-            .len()
-}
-
 /// Flattens the length of the gates vector for certain qubits in the circuit.
 /// Used before adding a multiqubit gate on these qubits.
 ///
@@ -65,7 +53,7 @@ pub(crate) fn effective_len(gates: &[String]) -> usize {
 ///
 /// * `circuit_gates` - A vector of all the gates vectors of the circuit.
 /// * `qubits` - A vector of the qubits to flatten.
-fn flatten_qubits(circuit_gates: &mut [Vec<String>], qubits: &[usize]) {
+fn flatten_qubits(circuit_gates: &mut [Vec<LayoutItem>], qubits: &[usize]) {
     let max_len = qubits
         .iter()
         .map(|&qubit| effective_len(&circuit_gates[qubit]))
@@ -78,7 +66,7 @@ fn flatten_qubits(circuit_gates: &mut [Vec<String>], qubits: &[usize]) {
     {
         for &qubit in qubits.iter() {
             if effective_len(&circuit_gates[qubit]) < max_len {
-                circuit_gates[qubit].push("1".to_owned());
+                circuit_gates[qubit].push(LayoutItem::Idle);
             }
         }
     }
@@ -91,9 +79,9 @@ fn flatten_qubits(circuit_gates: &mut [Vec<String>], qubits: &[usize]) {
 /// * `circuit_gates` - A vector of all the gates vectors of the circuit.
 /// * `min` - The minimum index of the circuit
 /// * `max` - The maximum index of the circuit
-fn push_ones(circuit_gates: &mut [Vec<String>], min: usize, max: usize) {
+fn push_ones(circuit_gates: &mut [Vec<LayoutItem>], min: usize, max: usize) {
     for gates in circuit_gates.iter_mut().take(max + 1).skip(min + 1) {
-        gates.push("1".to_owned());
+        gates.push(LayoutItem::Idle);
     }
 }
 
@@ -107,8 +95,8 @@ fn push_ones(circuit_gates: &mut [Vec<String>], min: usize, max: usize) {
 /// * `vec_1_ind` - A vector of the indexes to flatten.
 /// * `vec_2_ind` - A vector of the indexes to flatten.
 pub(crate) fn flatten_multiple_vec(
-    gate_vec_1: &mut [Vec<String>],
-    gate_vec_2: &mut [Vec<String>],
+    gate_vec_1: &mut [Vec<LayoutItem>],
+    gate_vec_2: &mut [Vec<LayoutItem>],
     vec_1_ind: &[usize],
     vec_2_ind: &[usize],
 ) {
@@ -134,12 +122,12 @@ pub(crate) fn flatten_multiple_vec(
     {
         for &index in vec_1_ind.iter() {
             if effective_len(&gate_vec_1[index]) < max_len {
-                gate_vec_1[index].push("1".to_owned());
+                gate_vec_1[index].push(LayoutItem::Idle);
             }
         }
         for &boson in vec_2_ind.iter() {
             if effective_len(&gate_vec_2[boson]) < max_len {
-                gate_vec_2[boson].push("1".to_owned());
+                gate_vec_2[boson].push(LayoutItem::Idle);
             }
         }
     }
@@ -191,7 +179,7 @@ fn count_digits(num: f64) -> usize {
 /// # Returns
 ///
 /// * `String` The calculator's typst representation.
-fn format_calculator(calculator: &CalculatorFloat, rounding_accuracy: usize) -> String {
+pub(crate) fn format_calculator(calculator: &CalculatorFloat, rounding_accuracy: usize) -> String {
     match calculator {
         CalculatorFloat::Float(float_value) => match float_value {
             v if (v - std::f64::consts::PI).abs() < EPSILON => "pi".to_owned(),
@@ -243,8 +231,8 @@ fn format_calculator(calculator: &CalculatorFloat, rounding_accuracy: usize) ->
                     value = value.strip_suffix(')').unwrap_or(value);
                 }
             }
-            let re = regex::Regex::new(r"([a-zA-Z][\w.]+|-?[\d\.]+e-?\d)").unwrap();
-            re.replace_all(value, |caps: &regex::Captures| format_symbol_str(&caps[0]))
+            SYMBOLS
+                .replace_all(value, |caps: &regex::Captures| format_symbol_str(&caps[0]))
                 .into()
         }
     }
@@ -267,58 +255,40 @@ fn format_complex_value(value: Complex64, rounding_accuracy: usize) -> String {
     )
 }
 
-/// Formats a qubit value to be displayed as an input in a quill multi qubit gate.
-///
-/// # Arguments
-///
-/// * `qubit` - The qubit acting on the gate.
-/// * `label` - The label to be displayed.
-///
-/// # Returns
-///
-/// * `String` - The formatted input string.
-fn format_qubit_input(qubit: usize, label: &str) -> String {
-    format!(r#"{qubit}, label: "{label}""#)
-}
-
 /// Prepares the circuit for a slice gate.
 ///
 /// # Arguments
 ///
 /// * `circuit_gates` - A vector of all the gates vectors of the circuit.
-fn prepare_for_slice(circuit_gates: &mut Vec<Vec<String>>, circuit_lock: &mut Vec<(usize, usize)>) {
+fn prepare_for_slice(
+    circuit_gates: &mut Vec<Vec<LayoutItem>>,
+    circuit_lock: &mut Vec<(usize, usize)>,
+) {
     add_qubits_vec(circuit_gates, &[0]);
     if circuit_gates[0].is_empty() {
-        circuit_gates[0].push("1".to_owned());
+        circuit_gates[0].push(LayoutItem::Idle);
         for qubit in 1..10 {
             circuit_lock.push((qubit, 0))
         }
     } else {
-        let last_slice = circuit_gates[0]
-            .iter()
-            .rfind(|gate| gate.contains("slice") || gate.contains("gategroup"));
+        let last_slice = circuit_gates[0].iter().rposition(|gate| {
+            matches!(
+                gate,
+                LayoutItem::Slice { .. } | LayoutItem::GateGroup { .. }
+            )
+        });
         if let Some(last_slice) = last_slice {
             let dist_to_max = circuit_gates
                 .iter()
-                .map(|gates: &Vec<String>| effective_len(gates) + 1)
+                .map(|gates: &Vec<LayoutItem>| effective_len(gates) + 1)
                 .max()
                 .unwrap_or(0)
                 - effective_len(&circuit_gates[0])
-                + if last_slice.contains("gategroup") {
-                    last_slice.split(",").nth(1).unwrap_or_default()
-                } else {
-                    Default::default()
-                }
-                .trim()
-                .parse::<usize>()
-                .unwrap_or_default();
-            let len_to_add = match circuit_gates[0]
-                .iter()
-                .rev()
-                .position(|gate| gate.eq(last_slice))
-                .unwrap()
-                + dist_to_max
-            {
+                + match circuit_gates[0][last_slice] {
+                    LayoutItem::GateGroup { n_columns, .. } => n_columns,
+                    _ => 0,
+                };
+            let len_to_add = match circuit_gates[0].len() - 1 - last_slice + dist_to_max {
                 0 => 5,
                 1 => 3,
                 2 => 1,
@@ -326,7 +296,7 @@ fn prepare_for_slice(circuit_gates: &mut Vec<Vec<String>>, circuit_lock: &mut Ve
             };
 
             for _ in 0..(len_to_add + dist_to_max) {
-                circuit_gates[0].push("1".to_owned());
+                circuit_gates[0].push(LayoutItem::Idle);
             }
         }
     }
@@ -339,7 +309,7 @@ fn prepare_for_slice(circuit_gates: &mut Vec<Vec<String>>, circuit_lock: &mut Ve
 /// * `circuit_gates` - A vector of all the gates vectors of the circuit.
 /// * `circuit_lock` - The list of all the emplacements of the circuit that are reserved for a control wire between two gates.
 fn prepare_for_ctrl(
-    circuit_gates: &mut Vec<Vec<String>>,
+    circuit_gates: &mut Vec<Vec<LayoutItem>>,
     circuit_lock: &mut Vec<(usize, usize)>,
     qubits: &[usize],
 ) {
@@ -350,7 +320,7 @@ fn prepare_for_ctrl(
     for qubit in min + 1..max {
         while circuit_lock.contains(&(qubit, effective_len(&circuit_gates[qubit]))) {
             circuit_lock.retain(|&val| val != (qubit, effective_len(&circuit_gates[qubit])));
-            circuit_gates[qubit].push("1".to_owned());
+            circuit_gates[qubit].push(LayoutItem::Idle);
         }
 
         if circuit_gates.len() > qubit
@@ -367,12 +337,12 @@ fn prepare_for_ctrl(
 
 fn prepare_for_bosonic(
     mode: usize,
-    bosonic_gates: &mut [Vec<String>],
+    bosonic_gates: &mut [Vec<LayoutItem>],
     bosonic_lock: &mut Vec<(usize, usize)>,
 ) {
     while bosonic_lock.contains(&(mode, effective_len(&bosonic_gates[mode]))) {
         bosonic_lock.retain(|&val| val != (mode, effective_len(&bosonic_gates[mode])));
-        bosonic_gates[mode].push("1".to_owned());
+        bosonic_gates[mode].push(LayoutItem::Idle);
     }
 }
 
@@ -386,7 +356,449 @@ fn qubit_range(qubits: &[usize]) -> usize {
     qubits.iter().max().unwrap() - qubits.iter().min().unwrap() + 1
 }
 
-/// Adds a gate to the circuit's typst representation.
+/// Adds the items rendered by a GateRenderer to the circuit, in a single column.
+///
+/// # Arguments
+///
+/// * `circuit_gates` - A vector of all the gates vectors of the circuit.
+/// * `items` - The item to place on each qubit, the other qubits in between are left idle.
+fn add_rendered_items(circuit_gates: &mut Vec<Vec<LayoutItem>>, items: Vec<(usize, LayoutItem)>) {
+    if items.is_empty() {
+        return;
+    }
+    let qubits: Vec<usize> = items.iter().map(|(qubit, _)| *qubit).collect();
+    let range = qubit_range_vec(&qubits);
+    add_qubits_vec(circuit_gates, &range);
+    flatten_qubits(circuit_gates, &range);
+    let mut items: HashMap<usize, LayoutItem> = items.into_iter().collect();
+    for qubit in range {
+        circuit_gates[qubit].push(items.remove(&qubit).unwrap_or(LayoutItem::Idle));
+    }
+}
+
+/// Adds a gate to the layout of the circuit.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the circuit.
+/// * `operation` - The operation to add to the circuit.
+/// * `options` - The pragmas to render and the rounding accuracy of the floats.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the operation was successfully added to the circuit.
+/// * Err(RoqoqoBackendError) - Operation not supported.
+pub fn add_gate(
+    layout: &mut CircuitLayout,
+    operation: &Operation,
+    options: &RenderOptions,
+) -> Result<(), RoqoqoBackendError> {
+    let lengths = |wires: &[Vec<LayoutItem>]| wires.iter().map(Vec::len).collect::<Vec<usize>>();
+    let previous_lengths = [
+        lengths(&layout.qubits),
+        lengths(&layout.bosons),
+        lengths(&layout.classical),
+    ];
+    add_gate_to_wires(
+        &mut layout.qubits,
+        &mut layout.bosons,
+        &mut layout.classical,
+        &mut layout.qubit_lock,
+        &mut layout.boson_lock,
+        &mut layout.classical_lock,
+        &mut layout.warnings,
+        operation,
+        options,
+    )?;
+    // The theme styles the items added for the operation, the new wires start empty.
+    if let Some(style) = options.theme.style(GateFamily::of(operation)) {
+        let groups = [
+            &mut layout.qubits,
+            &mut layout.bosons,
+            &mut layout.classical,
+        ];
+        for (wires, previous_lengths) in groups.into_iter().zip(previous_lengths.iter()) {
+            for (index, wire) in wires.iter_mut().enumerate() {
+                let previous_length = previous_lengths.get(index).copied().unwrap_or(0);
+                wire.iter_mut()
+                    .skip(previous_length)
+                    .for_each(|item| style.apply(item));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds a gate to the layout of the circuit inside a gate group, e.g. to outline it.
+///
+/// The group covers the wires from the lowest to the highest qubit of the operation, the
+/// operations without qubits are added without group.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the circuit.
+/// * `operation` - The operation to add to the circuit.
+/// * `options` - The pragmas to render and the rounding accuracy of the floats.
+/// * `label` - The text label of the group.
+/// * `stroke` - The typst stroke of the group box, e.g. `red + 1pt`.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the operation was successfully added to the circuit.
+/// * Err(RoqoqoBackendError) - Operation not supported.
+pub fn add_gate_in_group(
+    layout: &mut CircuitLayout,
+    operation: &Operation,
+    options: &RenderOptions,
+    label: &str,
+    stroke: &str,
+) -> Result<(), RoqoqoBackendError> {
+    let qubits: Vec<usize> = match operation.involved_qubits() {
+        InvolvedQubits::Set(qubits) if !qubits.is_empty() => {
+            qubit_range_vec(&qubits.into_iter().collect::<Vec<usize>>())
+        }
+        _ => return add_gate(layout, operation, options),
+    };
+    let min = qubits[0];
+    add_qubits_vec(&mut layout.qubits, &qubits);
+    flatten_qubits(&mut layout.qubits, &qubits);
+    layout.qubits[min].push(LayoutItem::GateGroup {
+        n_wires: qubits.len(),
+        n_columns: 0,
+        label: label.to_owned(),
+        stroke: Some(stroke.to_owned()),
+    });
+    let group_index = layout.qubits[min].len() - 1;
+    let old_len = qubits
+        .iter()
+        .map(|&qubit| effective_len(&layout.qubits[qubit]))
+        .collect::<Vec<usize>>();
+    add_gate(layout, operation, options)?;
+    let max_gates_len_diff = qubits
+        .iter()
+        .zip(old_len)
+        .map(|(&qubit, old_len)| effective_len(&layout.qubits[qubit]) - old_len)
+        .max()
+        .unwrap_or(0);
+    if let LayoutItem::GateGroup { n_columns, .. } = &mut layout.qubits[min][group_index] {
+        *n_columns = max_gates_len_diff;
+    }
+    flatten_qubits(&mut layout.qubits, &qubits);
+    Ok(())
+}
+
+/// Converts a qoqo circuit to the layout of its diagram.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The pragmas to render and the rounding accuracy of the floats. With the
+///   `inferred` initialization mode, the state preparations at the beginning of the circuit are
+///   drawn as the initial states of the wires instead of slices. The qubit wires are ordered,
+///   restricted to a subset or stripped of the idle qubits following the qubit options, and the
+///   operations are assigned to the columns following the layering strategy.
+///
+/// # Returns
+///
+/// * `Ok(CircuitLayout)` - The layout of the circuit, with all the wires of the same length.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub fn circuit_into_layout(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<CircuitLayout, RoqoqoBackendError> {
+    circuit_into_layout_with_outlines(circuit, options, &HashMap::new())
+}
+
+/// Converts a qoqo circuit to the layout of its diagram, with some operations outlined.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The options of the circuit representation, as for [circuit_into_layout].
+/// * `outlines` - The typst stroke of the box outlining an operation, by the index of the
+///   operation in the circuit.
+///
+/// # Returns
+///
+/// * `Ok(CircuitLayout)` - The layout of the circuit, with all the wires of the same length.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub(crate) fn circuit_into_layout_with_outlines(
+    circuit: &Circuit,
+    options: &RenderOptions,
+    outlines: &HashMap<usize, &str>,
+) -> Result<CircuitLayout, RoqoqoBackendError> {
+    let arrangement = QubitArrangement::new(circuit, options);
+    let (arranged_circuit, original_indices) = match &arrangement {
+        Some(arrangement) => {
+            let (arranged_circuit, original_indices) = arrangement.arrange(circuit)?;
+            (Some(arranged_circuit), Some(original_indices))
+        }
+        None => (None, None),
+    };
+    let circuit = arranged_circuit.as_ref().unwrap_or(circuit);
+    let mut layout = CircuitLayout::new();
+    let mut preparing = options.initialization_mode == InitializationMode::Inferred;
+    let mut register_lengths: HashMap<&str, usize> = HashMap::new();
+    let mut input_bits: Vec<(&str, Vec<bool>)> = Vec::new();
+    let mut operations: Vec<(usize, &Operation)> = Vec::new();
+    for (index, operation) in circuit.iter().enumerate() {
+        if preparing {
+            let prepared_state = match operation {
+                Operation::PragmaSetStateVector(op) => {
+                    Some(PreparedState::from_statevector(op.statevector()))
+                }
+                Operation::PragmaSetDensityMatrix(op) => {
+                    Some(PreparedState::from_density_matrix(op.density_matrix()))
+                }
+                _ => None,
+            };
+            if let Some(prepared_state) = prepared_state {
+                let n_qubits = match &prepared_state {
+                    PreparedState::Product(states) => states.len(),
+                    PreparedState::Global { n_qubits, .. } => *n_qubits,
+                };
+                if n_qubits > 0 {
+                    add_qubits_vec(&mut layout.qubits, &[n_qubits - 1]);
+                }
+                layout.prepared_state = Some(prepared_state);
+                continue;
+            }
+            match operation {
+                Operation::DefinitionBit(op) => {
+                    register_lengths.insert(op.name().as_str(), *op.length());
+                }
+                Operation::InputBit(op) if register_lengths.contains_key(op.name().as_str()) => {
+                    let position = match input_bits
+                        .iter()
+                        .position(|(name, _)| *name == op.name().as_str())
+                    {
+                        Some(position) => position,
+                        None => {
+                            input_bits.push((
+                                op.name().as_str(),
+                                vec![false; register_lengths[op.name().as_str()]],
+                            ));
+                            input_bits.len() - 1
+                        }
+                    };
+                    let bits = &mut input_bits[position].1;
+                    if bits.len() <= *op.index() {
+                        bits.resize(op.index() + 1, false);
+                    }
+                    bits[*op.index()] = *op.value();
+                    continue;
+                }
+                _ if operation.tags().contains(&"Definition") => (),
+                _ => preparing = false,
+            }
+        }
+        if options.render_pragmas.renders(operation.hqslang()) {
+            let index = original_indices
+                .as_ref()
+                .map_or(index, |original_indices| original_indices[index]);
+            operations.push((index, operation));
+        }
+    }
+    let layers = options.layering.layers(
+        &operations
+            .iter()
+            .map(|(_, operation)| *operation)
+            .collect::<Vec<&Operation>>(),
+    );
+    match layers {
+        None => {
+            for (index, operation) in operations.iter() {
+                add_gate_with_column(
+                    &mut layout,
+                    *index,
+                    operation,
+                    arrangement.as_ref(),
+                    outlines.get(index).copied(),
+                    options,
+                )?;
+            }
+        }
+        Some(layers) => {
+            // All the wires exist from the start so that the moments are aligned across them.
+            for (_, operation) in operations.iter() {
+                if let InvolvedQubits::Set(qubits) = operation.involved_qubits() {
+                    if let Some(&qubit) = qubits.iter().max() {
+                        add_qubits_vec(&mut layout.qubits, &[qubit]);
+                    }
+                }
+                if let InvolvedModes::Set(modes) = involved_modes(operation) {
+                    if let Some(&mode) = modes.iter().max() {
+                        add_qubits_vec(&mut layout.bosons, &[mode]);
+                    }
+                }
+            }
+            let mut order: Vec<usize> = (0..operations.len()).collect();
+            order.sort_by_key(|&index| layers[index]);
+            let mut current_layer = None;
+            for index in order {
+                if current_layer.is_some_and(|layer| layer != layers[index]) {
+                    flatten_layout(&mut layout);
+                }
+                current_layer = Some(layers[index]);
+                let (index, operation) = operations[index];
+                add_gate_with_column(
+                    &mut layout,
+                    index,
+                    operation,
+                    arrangement.as_ref(),
+                    outlines.get(&index).copied(),
+                    options,
+                )?;
+            }
+        }
+    }
+    for (_, operation) in operations.iter() {
+        if let InvolvedQubits::Set(qubits) = operation.involved_qubits() {
+            for qubit in qubits {
+                if layout.qubit_gate_counts.len() <= qubit {
+                    layout.qubit_gate_counts.resize(qubit + 1, 0);
+                }
+                layout.qubit_gate_counts[qubit] += 1;
+            }
+        }
+    }
+    for (register, bits) in input_bits {
+        for item in layout.classical.iter_mut().flatten() {
+            if let LayoutItem::RegisterLabel(name) = item {
+                if name.as_str() == register {
+                    *name = format!(
+                        "{} = {}",
+                        options.wire_labels.register_name(register),
+                        bits.iter()
+                            .map(|&bit| if bit { '1' } else { '0' })
+                            .collect::<String>()
+                    );
+                }
+            }
+        }
+    }
+    if let Some(arrangement) = arrangement {
+        arrangement.restrict(&mut layout);
+    }
+    flatten_layout(&mut layout);
+    layout.qubit_gate_counts.resize(layout.qubits.len(), 0);
+    Ok(layout)
+}
+
+/// Adds a gate to the layout of the circuit and records the column it is drawn in.
+///
+/// The column of the operation is the first column of its items, the padding added to align the
+/// wires is not part of the operation. The operations without any item are not recorded.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the circuit.
+/// * `index` - The index of the operation in the circuit.
+/// * `operation` - The operation to add to the circuit.
+/// * `arrangement` - The arrangement of the qubit wires, cutting the operations acting on hidden
+///   qubits.
+/// * `outline` - The typst stroke of the box outlining the operation, if any.
+/// * `options` - The rendering options.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the operation was successfully added to the circuit.
+/// * Err(RoqoqoBackendError) - Operation not supported.
+fn add_gate_with_column(
+    layout: &mut CircuitLayout,
+    index: usize,
+    operation: &Operation,
+    arrangement: Option<&QubitArrangement>,
+    outline: Option<&str>,
+    options: &RenderOptions,
+) -> Result<(), RoqoqoBackendError> {
+    let lengths = |wires: &[Vec<LayoutItem>]| wires.iter().map(Vec::len).collect::<Vec<usize>>();
+    let previous_lengths = [
+        lengths(&layout.qubits),
+        lengths(&layout.bosons),
+        lengths(&layout.classical),
+    ];
+    let cut = match arrangement {
+        Some(arrangement) => arrangement.cut(operation, options)?,
+        None => None,
+    };
+    match cut {
+        Some(mut items) => {
+            if let Some(style) = options.theme.style(GateFamily::of(operation)) {
+                items.iter_mut().for_each(|(_, item)| style.apply(item));
+            }
+            if let Some(outline) = outline {
+                for (_, item) in items.iter_mut() {
+                    if let LayoutItem::Gate { stroke, .. } = item {
+                        *stroke = Some(outline.to_owned());
+                    }
+                }
+            }
+            add_rendered_items(&mut layout.qubits, items);
+        }
+        None => match outline {
+            Some(outline) => add_gate_in_group(layout, operation, options, "", outline)?,
+            None => add_gate(layout, operation, options)?,
+        },
+    }
+    let column = [&layout.qubits, &layout.bosons, &layout.classical]
+        .into_iter()
+        .zip(previous_lengths.iter())
+        .flat_map(|(wires, previous_lengths)| {
+            wires.iter().enumerate().filter_map(move |(row, items)| {
+                let previous_length = previous_lengths.get(row).copied().unwrap_or(0);
+                items
+                    .iter()
+                    .enumerate()
+                    .skip(previous_length)
+                    .find(|(_, item)| {
+                        !matches!(
+                            item,
+                            LayoutItem::Idle
+                                | LayoutItem::RegisterLabel(_)
+                                | LayoutItem::ClassicalWire
+                        )
+                    })
+                    .map(|(position, _)| effective_len(&items[..position]))
+            })
+        })
+        .min();
+    if let Some(column) = column {
+        layout.operation_columns.insert(index, column);
+    }
+    Ok(())
+}
+
+/// Brings all the wires of a layout to the same length.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the circuit.
+pub(crate) fn flatten_layout(layout: &mut CircuitLayout) {
+    let n_qubits = layout.qubits.len();
+    let n_bosons = layout.bosons.len();
+    let n_classical = layout.classical.len();
+    flatten_multiple_vec(
+        &mut layout.qubits,
+        &mut layout.bosons,
+        (0..n_qubits).collect::<Vec<usize>>().as_slice(),
+        (0..n_bosons).collect::<Vec<usize>>().as_slice(),
+    );
+    flatten_multiple_vec(
+        &mut layout.qubits,
+        &mut layout.classical,
+        (0..n_qubits).collect::<Vec<usize>>().as_slice(),
+        (0..n_classical).collect::<Vec<usize>>().as_slice(),
+    );
+    flatten_multiple_vec(
+        &mut layout.bosons,
+        &mut layout.classical,
+        (0..n_bosons).collect::<Vec<usize>>().as_slice(),
+        (0..n_classical).collect::<Vec<usize>>().as_slice(),
+    );
+}
+
+/// Adds a gate to the wires of the circuit's layout.
 ///
 /// # Arguments
 ///
@@ -396,38 +808,29 @@ fn qubit_range(qubits: &[usize]) -> usize {
 /// * `circuit_lock` - The list of all the emplacements of the circuit that are reserved for a control wire between two gates.
 /// * `bosonic_lock` - The list of all the emplacements of the bosonic part of the circuit that are reserved for a control wire between two gates.
 /// * `classical_lock` - The list of all the emplacements of the classical part of the circuit that are reserved for a control wire between two gates.
+/// * `warnings` - The warnings emitted while drawing the circuit, e.g. the operations drawn by the fallback renderer.
 /// * `operation` - The operation to add to the circuit.
+/// * `options` - The pragmas to render and the rounding accuracy of the floats.
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If the operation was successfully added to the circuit.
 /// * Err(RoqoqoBackendError) - Operation not supported.
 #[allow(clippy::too_many_arguments)]
-pub fn add_gate(
-    circuit_gates: &mut Vec<Vec<String>>,
-    bosonic_gates: &mut Vec<Vec<String>>,
-    classical_gates: &mut Vec<Vec<String>>,
+fn add_gate_to_wires(
+    circuit_gates: &mut Vec<Vec<LayoutItem>>,
+    bosonic_gates: &mut Vec<Vec<LayoutItem>>,
+    classical_gates: &mut Vec<Vec<LayoutItem>>,
     circuit_lock: &mut Vec<(usize, usize)>,
     bosonic_lock: &mut Vec<(usize, usize)>,
     classical_lock: &mut Vec<(usize, usize)>,
+    warnings: &mut Vec<String>,
     operation: &Operation,
-    render_pragmas: &RenderPragmas,
-    rounding_accuracy: usize,
+    options: &RenderOptions,
 ) -> Result<(), RoqoqoBackendError> {
-    match render_pragmas {
-        RenderPragmas::All => (),
-        RenderPragmas::None => {
-            if operation.hqslang().starts_with("Pragma") {
-                return Ok(());
-            }
-        }
-        RenderPragmas::Partial(ref pragmas) => {
-            if operation.hqslang().starts_with("Pragma")
-                && !pragmas.contains(&operation.hqslang().to_owned())
-            {
-                return Ok(());
-            }
-        }
+    let rounding_accuracy = options.rounding_accuracy;
+    if !options.render_pragmas.renders(operation.hqslang()) {
+        return Ok(());
     }
     let mut used_qubits: Vec<usize> = Vec::new();
     match operation.involved_qubits() {
@@ -452,169 +855,206 @@ pub fn add_gate(
     for qubit in used_qubits.iter() {
         while circuit_lock.contains(&(*qubit, effective_len(&circuit_gates[*qubit]))) {
             circuit_lock.retain(|&val| val != (*qubit, effective_len(&circuit_gates[*qubit])));
-            circuit_gates[*qubit].push("1".to_owned());
+            circuit_gates[*qubit].push(LayoutItem::Idle);
         }
     }
+    if let Some(renderer) = options.gate_renderers.get(operation.hqslang()) {
+        let items = renderer.render(operation, circuit_gates.len(), options)?;
+        add_rendered_items(circuit_gates, items);
+        return Ok(());
+    }
+    if options.gate_renderers.is_unsupported(operation.hqslang()) {
+        return add_unsupported_gate(circuit_gates, warnings, operation, options);
+    }
     match operation {
         Operation::Hadamard(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ H $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("H"));
             Ok(())
         }
         Operation::CNOT(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push("targ()".to_owned());
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::Target);
 
             Ok(())
         }
         Operation::SingleQubitGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ U({}+{}i,{}+{}i,{}) $, label: \"SingleQubitGate\")",
-                format_calculator(&op.alpha_r(), rounding_accuracy),
-                format_calculator(&op.alpha_i(), rounding_accuracy),
-                format_calculator(&op.beta_r(), rounding_accuracy),
-                format_calculator(&op.beta_i(), rounding_accuracy),
-                format_calculator(&op.global_phase(), rounding_accuracy)
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "U({}+{}i,{}+{}i,{})",
+                    format_calculator(&op.alpha_r(), rounding_accuracy),
+                    format_calculator(&op.alpha_i(), rounding_accuracy),
+                    format_calculator(&op.beta_r(), rounding_accuracy),
+                    format_calculator(&op.beta_i(), rounding_accuracy),
+                    format_calculator(&op.global_phase(), rounding_accuracy)
+                ),
+                label: Some("SingleQubitGate".to_owned()),
+                fill: None,
+                stroke: None,
+            });
             Ok(())
         }
         Operation::RotateX(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Rx\"({}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"Rx\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::RotateY(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Ry\"({}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"Ry\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::RotateZ(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Rz\"({}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"Rz\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::PauliX(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ X $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("X"));
             Ok(())
         }
         Operation::PauliY(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ Y $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("Y"));
             Ok(())
         }
         Operation::PauliZ(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ Z $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("Z"));
             Ok(())
         }
         Operation::SqrtPauliX(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ sqrt(X) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("sqrt(X)"));
             Ok(())
         }
         Operation::InvSqrtPauliX(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ sqrt(X)^(dagger) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("sqrt(X)^(dagger)"));
             Ok(())
         }
         Operation::SGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ S $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("S"));
             Ok(())
         }
         Operation::TGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ T $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("T"));
             Ok(())
         }
         Operation::PhaseShiftState1(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"p1\"({}) $, label: \"PhaseShiftState1\")",
-                format_calculator(op.theta(), rounding_accuracy)
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"p1\"({})",
+                    format_calculator(op.theta(), rounding_accuracy)
+                ),
+                label: Some("PhaseShiftState1".to_owned()),
+                fill: None,
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PhaseShiftState0(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"p0\"({}) $, label: \"PhaseShiftState0\")",
-                format_calculator(op.theta(), rounding_accuracy)
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"p0\"({})",
+                    format_calculator(op.theta(), rounding_accuracy)
+                ),
+                label: Some("PhaseShiftState0".to_owned()),
+                fill: None,
+                stroke: None,
+            });
             Ok(())
         }
         Operation::RotateAroundSphericalAxis(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Rsph\"({},{},{}) $, label: \"RotateAroundSphericalAxis\")",
-                format_calculator(op.theta(), rounding_accuracy),
-                format_calculator(op.spherical_theta(), rounding_accuracy),
-                format_calculator(op.spherical_phi(), rounding_accuracy),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"Rsph\"({},{},{})",
+                    format_calculator(op.theta(), rounding_accuracy),
+                    format_calculator(op.spherical_theta(), rounding_accuracy),
+                    format_calculator(op.spherical_phi(), rounding_accuracy)
+                ),
+                label: Some("RotateAroundSphericalAxis".to_owned()),
+                fill: None,
+                stroke: None,
+            });
             Ok(())
         }
         Operation::RotateXY(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Rxy\"({},{}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"Rxy\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::PragmaSetNumberOfMeasurements(op) => {
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                "slice(label: $ \"Measurements\"\\ \"n={}\" $)",
-                op.number_measurements(),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!("\"Measurements\"\\ \"n={}\"", op.number_measurements()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaSetStateVector(op) => {
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "SetStatevector"\ [{}] $, stroke: (paint: black, thickness: 1pt, dash: "solid"))"#,
-                op.statevector().iter().map(|&complex| format_complex_value(complex, rounding_accuracy)).collect::<Vec<String>>().join(","),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(
+                    r#""SetStatevector"\ [{}]"#,
+                    op.statevector()
+                        .iter()
+                        .map(|&complex| format_complex_value(complex, rounding_accuracy))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+                stroke: Some("(paint: black, thickness: 1pt, dash: \"solid\")".to_owned()),
+            });
             Ok(())
         }
         Operation::PragmaSetDensityMatrix(op) => {
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "SetDensityMatrix"\ "{}" $, stroke: (paint: black, thickness: 1pt, dash: "solid"))"#,
-                op.density_matrix(),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(r#""SetDensityMatrix"\ "{}""#, op.density_matrix()),
+                stroke: Some("(paint: black, thickness: 1pt, dash: \"solid\")".to_owned()),
+            });
             Ok(())
         }
         Operation::PragmaRepeatGate(op) => {
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "RepeatNextGate"\ {}" times" $, stroke: (paint: black, thickness: 1pt, dash: "densely-dash-dotted"))"#,
-                op.repetition_coefficient(),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(
+                    r#""RepeatNextGate"\ {}" times""#,
+                    op.repetition_coefficient()
+                ),
+                stroke: Some(
+                    "(paint: black, thickness: 1pt, dash: \"densely-dash-dotted\")".to_owned(),
+                ),
+            });
             Ok(())
         }
         Operation::PragmaOverrotation(op) => {
@@ -628,14 +1068,20 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "Overrotation"\ ({},{})\ "\"{}\"" $, n: {}, width: 10em, fill: gray, inputs: ({}))"#,
-                format_calculator(&CalculatorFloat::Float(*op.amplitude()), rounding_accuracy),
-                format_calculator(&CalculatorFloat::Float(*op.variance()), rounding_accuracy),
-                op.gate_hqslang(),
-                qubits.len(),
-                op.qubits().iter().map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x"))).collect::<Vec<String>>().join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""Overrotation"\ ({},{})\ "\"{}\"""#,
+                    format_calculator(&CalculatorFloat::Float(*op.amplitude()), rounding_accuracy),
+                    format_calculator(&CalculatorFloat::Float(*op.variance()), rounding_accuracy),
+                    op.gate_hqslang()
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("10em".to_owned()),
+                extent: None,
+                fill: Some("gray".to_owned()),
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
@@ -643,10 +1089,13 @@ pub fn add_gate(
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "BoostNoise"\ n={} $)"#,
-                format_calculator(op.noise_coefficient(), rounding_accuracy),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(
+                    r#""BoostNoise"\ n={}"#,
+                    format_calculator(op.noise_coefficient(), rounding_accuracy)
+                ),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaStopParallelBlock(op) => {
@@ -660,12 +1109,18 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "StopParallelBlock"\ ({}) $, n: {}, width: 13em, fill: gray, inputs: ({}))"#,
-                format_calculator(op.execution_time(), rounding_accuracy),
-                qubits.len(),
-                op.qubits().iter().map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x"))).collect::<Vec<String>>().join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""StopParallelBlock"\ ({})"#,
+                    format_calculator(op.execution_time(), rounding_accuracy)
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("13em".to_owned()),
+                extent: None,
+                fill: Some("gray".to_owned()),
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
@@ -680,12 +1135,22 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "StartDecompositionBlock"\ "{}" $, n: {}, width: 14em, fill: gray, inputs: ({}))"#,
-                op.reordering_dictionary().iter().map(|(key, val)| format!("{key}:{val}")).collect::<Vec<String>>().join("\n"),
-                qubits.len(),
-                op.qubits().iter().map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x"))).collect::<Vec<String>>().join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""StartDecompositionBlock"\ "{}""#,
+                    op.reordering_dictionary()
+                        .iter()
+                        .map(|(key, val)| format!("{key}:{val}"))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("14em".to_owned()),
+                extent: None,
+                fill: Some("gray".to_owned()),
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
@@ -700,11 +1165,15 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "StopDecompositionBlock" $, n: {}, width: 13em, fill: gray, inputs: ({}))"#,
-                qubits.len(),
-                op.qubits().iter().map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x"))).collect::<Vec<String>>().join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: r#""StopDecompositionBlock""#.to_owned(),
+                n_wires: Some(qubits.len()),
+                width: Some("13em".to_owned()),
+                extent: None,
+                fill: Some("gray".to_owned()),
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
@@ -712,10 +1181,13 @@ pub fn add_gate(
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "GlobalPhase"\ {} $)"#,
-                format_calculator(op.phase(), rounding_accuracy),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(
+                    r#""GlobalPhase"\ {}"#,
+                    format_calculator(op.phase(), rounding_accuracy)
+                ),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaSleep(op) => {
@@ -729,68 +1201,100 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "Sleep"({}) $, n: {}, width: 7em, fill: gray, inputs: ({}))"#,
-                format_calculator(op.sleep_time(), rounding_accuracy),
-                qubits.len(),
-                op.qubits()
-                    .iter()
-                    .map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x")))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""Sleep"({})"#,
+                    format_calculator(op.sleep_time(), rounding_accuracy)
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("7em".to_owned()),
+                extent: None,
+                fill: Some("gray".to_owned()),
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
         Operation::PragmaActiveReset(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("gate($ \"Reset\" $, fill: gray)".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: "\"Reset\"".to_owned(),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaDamping(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Damping\"({},{}) $, fill: gray)",
-                format_calculator(op.gate_time(), rounding_accuracy),
-                format_calculator(op.rate(), rounding_accuracy),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"Damping\"({},{})",
+                    format_calculator(op.gate_time(), rounding_accuracy),
+                    format_calculator(op.rate(), rounding_accuracy)
+                ),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaDepolarising(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Depolarising\"({},{}) $, fill: gray)",
-                format_calculator(op.gate_time(), rounding_accuracy),
-                format_calculator(op.rate(), rounding_accuracy),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"Depolarising\"({},{})",
+                    format_calculator(op.gate_time(), rounding_accuracy),
+                    format_calculator(op.rate(), rounding_accuracy)
+                ),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaDephasing(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"Dephasing\"({},{}) $, fill: gray)",
-                format_calculator(op.gate_time(), rounding_accuracy),
-                format_calculator(op.rate(), rounding_accuracy),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"Dephasing\"({},{})",
+                    format_calculator(op.gate_time(), rounding_accuracy),
+                    format_calculator(op.rate(), rounding_accuracy)
+                ),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaRandomNoise(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"RandomNoise\"({},{},{}) $, fill: gray)",
-                format_calculator(op.gate_time(), rounding_accuracy),
-                format_calculator(op.depolarising_rate(), rounding_accuracy),
-                format_calculator(op.dephasing_rate(), rounding_accuracy),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"RandomNoise\"({},{},{})",
+                    format_calculator(op.gate_time(), rounding_accuracy),
+                    format_calculator(op.depolarising_rate(), rounding_accuracy),
+                    format_calculator(op.dephasing_rate(), rounding_accuracy)
+                ),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaGeneralNoise(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"GeneralNoise\"({},{}) $, fill: gray)",
-                format_calculator(op.gate_time(), rounding_accuracy),
-                op.rates(),
-            ));
+            circuit_gates[*op.qubit()].push(LayoutItem::Gate {
+                content: format!(
+                    "\"GeneralNoise\"({},{})",
+                    format_calculator(op.gate_time(), rounding_accuracy),
+                    op.rates()
+                ),
+                label: None,
+                fill: Some("gray".to_owned()),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaConditional(op) => {
@@ -824,28 +1328,32 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"Conditional: {}[{}]\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.condition_register(),
-                op.condition_index(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!(
+                    "Conditional: {}[{}]",
+                    op.condition_register(),
+                    op.condition_index()
+                ),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in op.circuit().iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -853,8 +1361,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -862,234 +1371,216 @@ pub fn add_gate(
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                r#"slice(label: $ "ChangeDevice" "{}" $)"#,
-                op.wrapped_hqslang,
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(r#""ChangeDevice" "{}""#, op.wrapped_hqslang),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::SWAP(op) => {
             let min = *op.control().min(op.target());
             let max = *op.control().max(op.target());
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[min].push(format!("swap({})", max - min));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: None,
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::ISwap(op) => {
             let min = *op.control().min(op.target());
             let max = *op.control().max(op.target());
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[min].push(format!("swap({}, label: \"ISwap\")", max - min));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: Some("\"ISwap\"".to_owned()),
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::FSwap(op) => {
             let min = *op.control().min(op.target());
             let max = *op.control().max(op.target());
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[min].push(format!("swap({}, label: \"FSwap\")", max - min));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: Some("\"FSwap\"".to_owned()),
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::SqrtISwap(op) => {
             let min = *op.control().min(op.target());
             let max = *op.control().max(op.target());
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[min].push(format!("swap({}, label: $ sqrt(\"ISwap\") $)", max - min));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: Some("sqrt(\"ISwap\")".to_owned()),
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::InvSqrtISwap(op) => {
             let min = *op.control().min(op.target());
             let max = *op.control().max(op.target());
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[min].push(format!(
-                "swap({}, label: $ sqrt(\"ISwap\")^(dagger) $)",
-                max - min
-            ));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: Some("sqrt(\"ISwap\")^(dagger)".to_owned()),
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::XY(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"XY\"({})$)",
-                format_calculator(op.theta(), rounding_accuracy),
-            ));
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"XY\"({})",
+                format_calculator(op.theta(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::ControlledPhaseShift(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShift\"({}) $)",
-                format_calculator(op.theta(), rounding_accuracy),
-            ));
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShift\"({})",
+                format_calculator(op.theta(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::ControlledPauliY(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push("gate($ \"Y\" $)".to_string());
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("\"Y\""));
             Ok(())
         }
         Operation::ControlledPauliZ(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push("gate($ \"Z\" $)".to_string());
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("\"Z\""));
             Ok(())
         }
         Operation::MolmerSorensenXX(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push("gate($ \"MolmerSorensenXX\" $)".to_owned());
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("\"MolmerSorensenXX\""));
             Ok(())
         }
         Operation::VariableMSXX(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"VariableMSXX\"({}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"VariableMSXX\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::GivensRotation(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"GivensRotation\"({},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"GivensRotation\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::GivensRotationLittleEndian(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"GivensRotationLE\"({},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"GivensRotationLE\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::Qsim(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"Qsim\"({},{},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"Qsim\"({},{},{})",
                 format_calculator(op.x(), rounding_accuracy),
                 format_calculator(op.y(), rounding_accuracy),
                 format_calculator(op.z(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::Fsim(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"Fsim\"({},{},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"Fsim\"({},{},{})",
                 format_calculator(op.t(), rounding_accuracy),
                 format_calculator(op.u(), rounding_accuracy),
-                format_calculator(op.delta(), rounding_accuracy),
-            ));
+                format_calculator(op.delta(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::SpinInteraction(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"SpinInteraction\"({},{},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"SpinInteraction\"({},{},{})",
                 format_calculator(op.x(), rounding_accuracy),
                 format_calculator(op.y(), rounding_accuracy),
                 format_calculator(op.z(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::Bogoliubov(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"Bogoliubov\"({},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"Bogoliubov\"({},{})",
                 format_calculator(op.delta_real(), rounding_accuracy),
-                format_calculator(op.delta_imag(), rounding_accuracy),
-            ));
+                format_calculator(op.delta_imag(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::PMInteraction(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PMInteraction\"({}) $)",
-                format_calculator(op.t(), rounding_accuracy),
-            ));
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PMInteraction\"({})",
+                format_calculator(op.t(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::ComplexPMInteraction(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"ComplexPMInteraction\"({},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"ComplexPMInteraction\"({},{})",
                 format_calculator(op.t_real(), rounding_accuracy),
-                format_calculator(op.t_imag(), rounding_accuracy),
-            ));
+                format_calculator(op.t_imag(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::PhaseShiftedControlledZ(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShiftedControlledZ\"({}) $)",
-                format_calculator(op.phi(), rounding_accuracy),
-            ));
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShiftedControlledZ\"({})",
+                format_calculator(op.phi(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::MultiQubitMS(op) => {
@@ -1103,18 +1594,20 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "MultiQubitMS"({}) $, n: {}, width: 11em, inputs: ({}))"#,
-                format_calculator(op.theta(), rounding_accuracy),
-                qubits.len(),
-                op.qubits()
-                    .iter()
-                    .map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x")))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""MultiQubitMS"({})"#,
+                    format_calculator(op.theta(), rounding_accuracy)
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("11em".to_owned()),
+                extent: None,
+                fill: None,
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             for gates in circuit_gates.iter_mut().take(max + 1).skip(min + 1) {
-                gates.push("1".to_owned());
+                gates.push(LayoutItem::Idle);
             }
             Ok(())
         }
@@ -1129,35 +1622,34 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "MultiQubitZZ"({}) $, n: {}, width: 11em, inputs: ({}))"#,
-                format_calculator(op.theta(), rounding_accuracy),
-                qubits.len(),
-                op.qubits()
-                    .iter()
-                    .map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x")))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    r#""MultiQubitZZ"({})"#,
+                    format_calculator(op.theta(), rounding_accuracy)
+                ),
+                n_wires: Some(qubits.len()),
+                width: Some("11em".to_owned()),
+                extent: None,
+                fill: None,
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             for gates in circuit_gates.iter_mut().take(max + 1).skip(min + 1) {
-                gates.push("1".to_owned());
+                gates.push(LayoutItem::Idle);
             }
             Ok(())
         }
         Operation::MeasureQubit(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            if let Some((index, _)) = classical_gates
-                .iter()
-                .cloned()
-                .enumerate()
-                .find(|(_i, gates)| gates[0].eq(&format!("lstick($ \"{} : \" $)", op.readout())))
-            {
+            if let Some(index) = classical_gates.iter().position(|gates| {
+                matches!(&gates[0], LayoutItem::RegisterLabel(name) if name == op.readout())
+            }) {
                 flatten_multiple_vec(circuit_gates, classical_gates, &[*op.qubit()], &[index]);
                 for qubit in *op.qubit()..circuit_gates.len() {
                     while circuit_lock.contains(&(qubit, effective_len(&circuit_gates[qubit]))) {
                         circuit_lock
                             .retain(|&val| val != (qubit, effective_len(&circuit_gates[qubit])));
-                        circuit_gates[qubit].push("1".to_owned());
+                        circuit_gates[qubit].push(LayoutItem::Idle);
                     }
                     if circuit_gates.len() > qubit
                         && effective_len(&circuit_gates[qubit])
@@ -1191,7 +1683,7 @@ pub fn add_gate(
                                 effective_len(&classical_gates[classical_index]),
                             )
                         });
-                        classical_gates[classical_index].push("1".to_owned());
+                        classical_gates[classical_index].push(LayoutItem::Idle);
                     }
                     if classical_gates.len() > classical_index
                         && effective_len(&classical_gates[classical_index])
@@ -1210,17 +1702,19 @@ pub fn add_gate(
                 for classical_index in 0..index {
                     classical_lock.push((classical_index, classical_gates[index].len()));
                 }
-                circuit_gates[*op.qubit()].push(format!(
-                    "meter(target:replace_by_classical_len_{}-{})",
-                    index,
-                    *op.qubit()
-                ));
-                classical_gates[index].push(format!(
-                    "ctrl(0, label: (content: $ {} $, pos: bottom))",
-                    op.readout_index()
-                ))
+                circuit_gates[*op.qubit()].push(LayoutItem::Meter {
+                    target: Some(WireRef::Classical(index)),
+                    fill: None,
+                });
+                classical_gates[index].push(LayoutItem::Control {
+                    target: None,
+                    label: Some(op.readout_index().to_string()),
+                })
             } else {
-                circuit_gates[*op.qubit()].push("meter()".to_owned());
+                circuit_gates[*op.qubit()].push(LayoutItem::Meter {
+                target: None,
+                fill: None,
+            });
             }
             Ok(())
         }
@@ -1262,27 +1756,28 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"GetStateVector: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.readout()
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("GetStateVector: {}", op.readout()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in circuit.iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1290,8 +1785,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -1333,27 +1829,28 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"GetDensityMatrix: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.readout(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("GetDensityMatrix: {}", op.readout()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in circuit.iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1361,8 +1858,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -1404,27 +1902,28 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"GetOccupationProbability: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.readout(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("GetOccupationProbability: {}", op.readout()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in circuit.iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1432,8 +1931,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -1484,27 +1984,28 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"GetPauliProduct: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.readout(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("GetPauliProduct: {}", op.readout()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in circuit.iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1512,8 +2013,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -1535,22 +2037,23 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, 1, label: \"Repeat {} times\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.number_measurements(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 1,
+                label: format!("Repeat {} times", op.number_measurements()),
+                stroke: None,
+            });
             for &qubit in used_qubits.iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     &Operation::from(MeasureQubit::new(qubit, "ro".to_owned(), qubit)),
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
@@ -1560,11 +2063,14 @@ pub fn add_gate(
             prepare_for_slice(circuit_gates, circuit_lock);
             let n_qubits = circuit_gates.len();
             flatten_qubits(circuit_gates, &(0..n_qubits).collect::<Vec<usize>>());
-            circuit_gates[0].push(format!(
-                "slice(label: $ \"Replace Symbole:\"\\ \"{}\"=>{} $)",
-                op.name(),
-                format_calculator(&CalculatorFloat::from(op.input()), rounding_accuracy),
-            ));
+            circuit_gates[0].push(LayoutItem::Slice {
+                label: format!(
+                    "\"Replace Symbole:\"\\ \"{}\"=>{}",
+                    op.name(),
+                    format_calculator(&CalculatorFloat::from(op.input()), rounding_accuracy)
+                ),
+                stroke: None,
+            });
             Ok(())
         }
         Operation::PragmaLoop(op) => {
@@ -1598,30 +2104,36 @@ pub fn add_gate(
             }
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"Loop: {} times\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                match op.repetitions() {
-                    CalculatorFloat::Float(float_value) => (float_value.floor() as usize).to_string(),
-                    _ => format_calculator(op.repetitions(), rounding_accuracy).replace('"', "")
-                }
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!(
+                    "Loop: {} times",
+                    match op.repetitions() {
+                        CalculatorFloat::Float(float_value) =>
+                            (float_value.floor() as usize).to_string(),
+                        _ =>
+                            format_calculator(op.repetitions(), rounding_accuracy).replace('"', ""),
+                    }
+                ),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in op.circuit().iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1629,47 +2141,39 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
         Operation::PhaseShiftedControlledPhase(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShiftControlledPhase\"({},{})$)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShiftControlledPhase\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::ControlledRotateX(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"Rx\"({}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"Rx\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::ControlledRotateXY(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"Rxy\"({},{}) $)",
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"Rxy\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
-                format_calculator(op.phi(), rounding_accuracy),
-            ));
+                format_calculator(op.phi(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::ControlledControlledPauliZ(op) => {
@@ -1678,15 +2182,9 @@ pub fn add_gate(
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
             flatten_qubits(circuit_gates, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.target()].push("gate($ Z $)".to_owned());
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("Z"));
             Ok(())
         }
         Operation::ControlledControlledPhaseShift(op) => {
@@ -1695,18 +2193,12 @@ pub fn add_gate(
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
             flatten_qubits(circuit_gates, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShift\"({}) $)",
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShift\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::Toffoli(op) => {
@@ -1714,31 +2206,25 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.target()].push("targ()".to_owned());
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::Target);
             Ok(())
         }
         Operation::GPi(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"GPi\"({}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"GPi\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::GPi2(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push(format!(
-                "gate($ \"GPi2\"({}) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::gate(format!(
+                "\"GPi2\"({})",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::PragmaControlledCircuit(op) => {
@@ -1774,27 +2260,28 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"ControlledCircuit by qubit: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.controlling_qubit(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("ControlledCircuit by qubit: {}", op.controlling_qubit()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in op.circuit().iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -1802,28 +2289,29 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
         Operation::Squeezing(op) => {
             add_qubits_vec(bosonic_gates, &[*op.mode()]);
             prepare_for_bosonic(*op.mode(), bosonic_gates, bosonic_lock);
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ \"Squeezing\"({},{}) $)",
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "\"Squeezing\"({},{})",
                 format_calculator(op.squeezing(), rounding_accuracy),
-                format_calculator(op.phase(), rounding_accuracy),
-            ));
+                format_calculator(op.phase(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::PhaseShift(op) => {
             add_qubits_vec(bosonic_gates, &[*op.mode()]);
             prepare_for_bosonic(*op.mode(), bosonic_gates, bosonic_lock);
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ \"PhaseShift\"({}) $)",
-                format_calculator(op.phase(), rounding_accuracy),
-            ));
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "\"PhaseShift\"({})",
+                format_calculator(op.phase(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::BeamSplitter(op) => {
@@ -1835,28 +2323,36 @@ pub fn add_gate(
                 prepare_for_bosonic(mode, bosonic_gates, bosonic_lock);
             }
             flatten_qubits(bosonic_gates, &modes);
-            bosonic_gates[min].push(format!(
-                "mqgate($ \"BeamSplitter\"\\ ({},{}) $, n: {}, width: 9em, inputs: ((qubit: {}), (qubit: {})))",
-                format_calculator(op.theta(), rounding_accuracy),
-                format_calculator(op.phi(), rounding_accuracy),
-                modes.len(),
-                format_qubit_input(*op.mode_0() - min, "x"),
-                format_qubit_input(*op.mode_1() - min, "x")
-            ));
+            bosonic_gates[min].push(LayoutItem::MultiGate {
+                content: format!(
+                    "\"BeamSplitter\"\\ ({},{})",
+                    format_calculator(op.theta(), rounding_accuracy),
+                    format_calculator(op.phi(), rounding_accuracy),
+                ),
+                n_wires: Some(modes.len()),
+                width: Some("9em".to_owned()),
+                extent: None,
+                fill: None,
+                inputs: vec![*op.mode_0() - min, *op.mode_1() - min],
+                target: None,
+            });
             for gates in circuit_gates.iter_mut().take(max + 1).skip(min + 1) {
-                gates.push("1".to_owned());
+                gates.push(LayoutItem::Idle);
             }
             Ok(())
         }
         Operation::PhotonDetection(op) => {
             add_qubits_vec(bosonic_gates, &[*op.mode()]);
             prepare_for_bosonic(*op.mode(), bosonic_gates, bosonic_lock);
-            bosonic_gates[*op.mode()].push("meter()".to_owned());
+            bosonic_gates[*op.mode()].push(LayoutItem::Meter {
+                target: None,
+                fill: None,
+            });
             Ok(())
         }
         Operation::Identity(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ I $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("I"));
             Ok(())
         }
         Operation::PragmaAnnotatedOp(op) => {
@@ -1889,42 +2385,40 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
-            circuit_gates[min].push(format!(
-                "gategroup({}, 1, label: \"{}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.annotation,
-            ));
-            add_gate(
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 1,
+                label: op.annotation.clone(),
+                stroke: None,
+            });
+            add_gate_to_wires(
                 circuit_gates,
                 bosonic_gates,
                 classical_gates,
                 circuit_lock,
                 bosonic_lock,
                 classical_lock,
+                warnings,
                 &op.operation,
-                render_pragmas,
-                rounding_accuracy,
+                options,
             )?;
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
         Operation::EchoCrossResonance(op) => {
             prepare_for_ctrl(circuit_gates, circuit_lock, &[*op.control(), *op.target()]);
-            circuit_gates[*op.control()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control() as i32
-            ));
-            circuit_gates[*op.target()].push("gate($ \"EchoCrossResonance\" $)".to_owned());
+            circuit_gates[*op.control()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("\"EchoCrossResonance\""));
             Ok(())
         }
         Operation::PhaseDisplacement(op) => {
             add_qubits_vec(bosonic_gates, &[*op.mode()]);
             prepare_for_bosonic(*op.mode(), bosonic_gates, bosonic_lock);
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ \"PhaseDisplacement\"({},{}) $)",
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "\"PhaseDisplacement\"({},{})",
                 format_calculator(op.displacement(), rounding_accuracy),
-                format_calculator(op.phase(), rounding_accuracy),
-            ));
+                format_calculator(op.phase(), rounding_accuracy)
+            )));
             Ok(())
         }
         #[cfg(feature = "unstable_operation_definition")]
@@ -1939,16 +2433,15 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                r#"mqgate($ "CallDefinedGate\n\"{}\"" $, n: {}, width: 11em, inputs: ({}))"#,
-                op.gate_name(),
-                qubits.len(),
-                op.qubits()
-                    .iter()
-                    .map(|qubit| format!("(qubit: {})", format_qubit_input(qubit - min, "x")))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            circuit_gates[min].push(LayoutItem::MultiGate {
+                content: format!(r#""CallDefinedGate\n\"{}\"""#, op.gate_name()),
+                n_wires: Some(qubits.len()),
+                width: Some("11em".to_owned()),
+                extent: None,
+                fill: None,
+                inputs: op.qubits().iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            });
             push_ones(circuit_gates, min, max);
             Ok(())
         }
@@ -1986,27 +2479,28 @@ pub fn add_gate(
             let qubits: Vec<usize> = (min..max + 1).collect();
             add_qubits_vec(circuit_gates, &qubits);
             flatten_qubits(circuit_gates, &qubits);
-            circuit_gates[min].push(format!(
-                "gategroup({}, replace_by_len, label: \"GateDefinition: {}\",  stroke: (dash: \"dotted\"))",
-                qubit_range(&qubits),
-                op.name(),
-            ));
+            circuit_gates[min].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(&qubits),
+                n_columns: 0,
+                label: format!("GateDefinition: {}", op.name()),
+                stroke: None,
+            });
             let group_index = circuit_gates[min].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in op.circuit().iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -2014,8 +2508,9 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[min][group_index] = circuit_gates[min][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } = &mut circuit_gates[min][group_index] {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(&qubits));
             Ok(())
         }
@@ -2031,16 +2526,19 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                "mqgate($ {} * X $, extent: 1.4em, target: replace_by_n_qubits_plus_{}-{})",
-                format_calculator(op.theta(), rounding_accuracy),
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ {}*(b^(dagger)+b) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::MultiGate {
+                content: format!("{} * X", format_calculator(op.theta(), rounding_accuracy)),
+                n_wires: None,
+                width: None,
+                extent: Some("1.4em".to_owned()),
+                fill: None,
+                inputs: vec![],
+                target: Some(WireRef::Boson(*op.mode())),
+            });
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "{}*(b^(dagger)+b)",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::LongitudinalCoupling(op) => {
@@ -2055,16 +2553,19 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                "mqgate($ {} * Z $, extent: 1.4em, target: replace_by_n_qubits_plus_{}-{})",
-                format_calculator(op.theta(), rounding_accuracy),
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ {}*(b^(dagger)+b) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::MultiGate {
+                content: format!("{} * Z", format_calculator(op.theta(), rounding_accuracy)),
+                n_wires: None,
+                width: None,
+                extent: Some("1.4em".to_owned()),
+                fill: None,
+                inputs: vec![],
+                target: Some(WireRef::Boson(*op.mode())),
+            });
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "{}*(b^(dagger)+b)",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::JaynesCummings(op) => {
@@ -2079,16 +2580,22 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                "mqgate($ {} * (sigma^-+sigma^+) $, extent: 1.4em, target: replace_by_n_qubits_plus_{}-{})",
-                format_calculator(op.theta(), rounding_accuracy),
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()].push(format!(
-                "gate($ {}*(b^(dagger)+b) $)",
+            circuit_gates[*op.qubit()].push(LayoutItem::MultiGate {
+                content: format!(
+                    "{} * (sigma^-+sigma^+)",
+                    format_calculator(op.theta(), rounding_accuracy)
+                ),
+                n_wires: None,
+                width: None,
+                extent: Some("1.4em".to_owned()),
+                fill: None,
+                inputs: vec![],
+                target: Some(WireRef::Boson(*op.mode())),
+            });
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(format!(
+                "{}*(b^(dagger)+b)",
                 format_calculator(op.theta(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::SingleExcitationStore(op) => {
@@ -2103,13 +2610,16 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                r#"mqgate($ alpha"|0>" + beta"|1>" -> "|0>" $, target: replace_by_n_qubits_plus_{}-{})"#,
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()]
-                .push(r#"gate($ "|0>" -> alpha"|0>" + beta"|1>" $)"#.to_string());
+            circuit_gates[*op.qubit()].push(LayoutItem::MultiGate {
+                content: r#"alpha"|0>" + beta"|1>" -> "|0>""#.to_owned(),
+                n_wires: None,
+                width: None,
+                extent: None,
+                fill: None,
+                inputs: vec![],
+                target: Some(WireRef::Boson(*op.mode())),
+            });
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(r#""|0>" -> alpha"|0>" + beta"|1>""#));
             Ok(())
         }
         Operation::SingleExcitationLoad(op) => {
@@ -2124,13 +2634,16 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                r#"mqgate($ "|0>" -> alpha"|0>" + beta"|1>" $, target: replace_by_n_qubits_plus_{}-{})"#,
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()]
-                .push(r#"gate($ alpha"|0>" + beta"|1>" -> "|0>" $)"#.to_string());
+            circuit_gates[*op.qubit()].push(LayoutItem::MultiGate {
+                content: r#""|0>" -> alpha"|0>" + beta"|1>""#.to_owned(),
+                n_wires: None,
+                width: None,
+                extent: None,
+                fill: None,
+                inputs: vec![],
+                target: Some(WireRef::Boson(*op.mode())),
+            });
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(r#"alpha"|0>" + beta"|1>" -> "|0>""#));
             Ok(())
         }
         Operation::CZQubitResonator(op) => {
@@ -2161,63 +2674,57 @@ pub fn add_gate(
             for mode in 0..*op.mode() {
                 bosonic_lock.push((mode, effective_len(&bosonic_gates[*op.mode()])));
             }
-            circuit_gates[*op.qubit()].push(format!(
-                r#"ctrl(replace_by_n_qubits_plus_{}-{})"#,
-                *op.mode(),
-                *op.qubit(),
-            ));
-            bosonic_gates[*op.mode()].push(r#"gate($ Z $)"#.to_string());
+            circuit_gates[*op.qubit()].push(LayoutItem::control(WireRef::Boson(*op.mode())));
+            bosonic_gates[*op.mode()].push(LayoutItem::gate(r#"Z"#));
             Ok(())
         }
         Operation::DefinitionBit(op) => {
             classical_gates.push(Vec::new());
             let index = classical_gates.len() - 1;
-            classical_gates[index].push(format!("lstick($ \"{} : \" $)", op.name()));
-            classical_gates[index].push(format!("setwire({})", 2));
+            classical_gates[index].push(LayoutItem::RegisterLabel(op.name().to_owned()));
+            classical_gates[index].push(LayoutItem::ClassicalWire);
             Ok(())
         }
         Operation::InputBit(op) => {
-            if let Some((index, _)) = classical_gates
-                .iter()
-                .enumerate()
-                .find(|(_ind, register)| register[0].contains(op.name()))
-            {
-                classical_gates[index].push(format!(
-                    "gate($ \"InputBit:\"\\ {}=>#{} $)",
+            if let Some(index) = classical_gates.iter().position(|register| {
+                matches!(&register[0], LayoutItem::RegisterLabel(name) if name == op.name())
+            }) {
+                classical_gates[index].push(LayoutItem::gate(format!(
+                    "\"InputBit:\"\\ {}=>#{}",
                     op.index(),
                     op.value()
-                ));
+                )));
             }
             Ok(())
         }
         Operation::SqrtPauliY(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ sqrt(Y) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("sqrt(Y)"));
             Ok(())
         }
         Operation::InvSqrtPauliY(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ sqrt(Y)^(dagger) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("sqrt(Y)^(dagger)"));
             Ok(())
         }
         Operation::InvSGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ S^(dagger) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("S^(dagger)"));
             Ok(())
         }
         Operation::InvTGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ T^(dagger) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("T^(dagger)"));
             Ok(())
         }
         Operation::SXGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ \"SX\" $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("\"SX\""));
             Ok(())
         }
         Operation::InvSXGate(op) => {
             add_qubits_vec(circuit_gates, &[*op.qubit()]);
-            circuit_gates[*op.qubit()].push("$ \"SX\"^(dagger) $".to_owned());
+            circuit_gates[*op.qubit()].push(LayoutItem::gate("\"SX\"^(dagger)"));
             Ok(())
         }
         Operation::ControlledSWAP(op) => {
@@ -2227,12 +2734,15 @@ pub fn add_gate(
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
             let min = op.target_0().min(op.target_1()).to_owned();
             let max = op.target_0().max(op.target_1()).to_owned();
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[min].push(format!("swap({})", max - min));
-            circuit_gates[max].push("swap()".to_owned());
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[min].push(LayoutItem::Swap {
+                partner: Some(WireRef::Qubit(max)),
+                label: None,
+            });
+            circuit_gates[max].push(LayoutItem::Swap {
+                partner: None,
+                label: None,
+            });
             Ok(())
         }
         Operation::PhaseShiftedControlledControlledZ(op) => {
@@ -2240,18 +2750,12 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShiftedControlledControlledZ\"({})$)",
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShiftedControlledControlledZ\"({})",
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::PhaseShiftedControlledControlledPhase(op) => {
@@ -2259,19 +2763,13 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"PhaseShiftedControlledControlledPhase\"({},{})$)",
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"PhaseShiftedControlledControlledPhase\"({},{})",
                 format_calculator(op.theta(), rounding_accuracy),
                 format_calculator(op.phi(), rounding_accuracy)
-            ));
+            )));
             Ok(())
         }
         Operation::TripleControlledPauliX(op) => {
@@ -2284,19 +2782,10 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.control_2()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_2() as i32
-            ));
-            circuit_gates[*op.target()].push("targ()".to_owned());
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_2()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::Target);
             Ok(())
         }
         Operation::TripleControlledPauliZ(op) => {
@@ -2309,19 +2798,10 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.control_2()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_2() as i32
-            ));
-            circuit_gates[*op.target()].push("$ Z $".to_owned());
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_2()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate("Z"));
             Ok(())
         }
         Operation::TripleControlledPhaseShift(op) => {
@@ -2334,22 +2814,13 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
-            circuit_gates[*op.control_0()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_0() as i32
-            ));
-            circuit_gates[*op.control_1()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_1() as i32
-            ));
-            circuit_gates[*op.control_2()].push(format!(
-                "ctrl({})",
-                *op.target() as i32 - *op.control_2() as i32
-            ));
-            circuit_gates[*op.target()].push(format!(
-                "gate($ \"TripleControlledPhaseShift\"({})$)",
-                format_calculator(op.theta(), rounding_accuracy),
-            ));
+            circuit_gates[*op.control_0()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_1()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.control_2()].push(LayoutItem::control(WireRef::Qubit(*op.target())));
+            circuit_gates[*op.target()].push(LayoutItem::gate(format!(
+                "\"TripleControlledPhaseShift\"({})",
+                format_calculator(op.theta(), rounding_accuracy)
+            )));
             Ok(())
         }
         Operation::MultiQubitCNOT(multi_qubit_cnot) => {
@@ -2363,10 +2834,9 @@ pub fn add_gate(
             flatten_qubits(circuit_gates, qubits);
             prepare_for_ctrl(circuit_gates, circuit_lock, qubits);
             for i in 0..qubits.len() - 1 {
-                circuit_gates[qubits[i]]
-                    .push(format!("ctrl({})", qubits[i + 1] as i32 - qubits[i] as i32));
+                circuit_gates[qubits[i]].push(LayoutItem::control(WireRef::Qubit(qubits[i + 1])));
             }
-            circuit_gates[*qubits.last().unwrap()].push("targ()".to_owned());
+            circuit_gates[*qubits.last().unwrap()].push(LayoutItem::Target);
             Ok(())
         }
         Operation::QFT(qft) => {
@@ -2379,26 +2849,28 @@ pub fn add_gate(
             add_qubits_vec(circuit_gates, qubits);
             flatten_qubits(circuit_gates, &qubit_range_vec(qubits));
             prepare_for_slice(circuit_gates, circuit_lock);
-            circuit_gates[qubits[0]].push(format!(
-                "gategroup({}, replace_by_len, label: \"QFT\",  stroke: (dash: \"dotted\"))",
-                qubit_range(qubits),
-            ));
+            circuit_gates[qubits[0]].push(LayoutItem::GateGroup {
+                n_wires: qubit_range(qubits),
+                n_columns: 0,
+                label: "QFT".to_owned(),
+                stroke: None,
+            });
             let group_index = circuit_gates[qubits[0]].len() - 1;
             let old_len = circuit_gates
                 .iter()
                 .map(|gates| effective_len(gates))
                 .collect::<Vec<usize>>();
             for operation in qft.circuit().iter() {
-                add_gate(
+                add_gate_to_wires(
                     circuit_gates,
                     bosonic_gates,
                     classical_gates,
                     circuit_lock,
                     bosonic_lock,
                     classical_lock,
+                    warnings,
                     operation,
-                    render_pragmas,
-                    rounding_accuracy,
+                    options,
                 )?;
             }
             let max_gates_len_diff = qubits
@@ -2406,8 +2878,11 @@ pub fn add_gate(
                 .map(|&qubit| effective_len(&circuit_gates[qubit]) - old_len[qubit])
                 .max()
                 .unwrap_or(0);
-            circuit_gates[qubits[0]][group_index] = circuit_gates[qubits[0]][group_index]
-                .replace("replace_by_len", &max_gates_len_diff.to_string());
+            if let LayoutItem::GateGroup { n_columns, .. } =
+                &mut circuit_gates[qubits[0]][group_index]
+            {
+                *n_columns = max_gates_len_diff;
+            }
             flatten_qubits(circuit_gates, &qubit_range_vec(qubits));
             Ok(())
         }
@@ -2415,11 +2890,50 @@ pub fn add_gate(
             if ALLOWED_OPERATIONS.contains(&operation.hqslang()) {
                 Ok(())
             } else {
-                Err(RoqoqoBackendError::OperationNotInBackend {
-                    backend: "TypstBackend",
-                    hqslang: operation.hqslang(),
-                })
+                add_unsupported_gate(circuit_gates, warnings, operation, options)
             }
         }
     }
 }
+
+/// Draws an operation that is not supported by the backend with the fallback renderer.
+///
+/// # Arguments
+///
+/// * `circuit_gates` - A vector of all the gates vectors of the circuit.
+/// * `warnings` - The warnings emitted while drawing the circuit, completed with the operation.
+/// * `operation` - The unsupported operation.
+/// * `options` - The fallback renderer and whether to fall back to the generic renderer.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the operation was drawn by the fallback renderer.
+/// * Err(RoqoqoBackendError) - Operation not supported and no fallback renderer.
+fn add_unsupported_gate(
+    circuit_gates: &mut Vec<Vec<LayoutItem>>,
+    warnings: &mut Vec<String>,
+    operation: &Operation,
+    options: &RenderOptions,
+) -> Result<(), RoqoqoBackendError> {
+    let renderer = options.gate_renderers.fallback().or_else(|| {
+        options
+            .fallback_unsupported
+            .then_some(&GenericRenderer as &dyn GateRenderer)
+    });
+    let Some(renderer) = renderer else {
+        return Err(RoqoqoBackendError::OperationNotInBackend {
+            backend: "TypstBackend",
+            hqslang: operation.hqslang(),
+        });
+    };
+    let items = renderer.render(operation, circuit_gates.len(), options)?;
+    add_rendered_items(circuit_gates, items);
+    let warning = format!(
+        "Operation {} is not supported by the TypstBackend and was drawn by the fallback renderer.",
+        operation.hqslang()
+    );
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+    Ok(())
+}
@@ -0,0 +1,301 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    escape_latex_text, typst_math_to_latex, InitializationMode, LayoutItem, PreparedState, WireRef,
+};
+
+/// Custom names and initial states of the wires of a circuit diagram.
+///
+/// The wires without a custom name are labelled `q[n]`, `b[n]` or with the name of their
+/// register, the wires without a custom initial state start in `|0>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WireLabels {
+    /// The names of the qubits, by qubit index.
+    pub qubits: HashMap<usize, String>,
+    /// The names of the bosonic modes, by mode index.
+    pub bosons: HashMap<usize, String>,
+    /// The names of the classical registers, by register name.
+    pub registers: HashMap<String, String>,
+    /// The initial states of the qubits as typst math, e.g. `+` or `psi`, by qubit index.
+    pub qubit_states: HashMap<usize, String>,
+    /// The initial states of the bosonic modes as typst math, by mode index.
+    pub boson_states: HashMap<usize, String>,
+}
+
+/// Name of a wire at the beginning of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WireName<'a> {
+    /// A custom name, drawn as text.
+    Custom(&'a str),
+    /// The default name, the prefix and the index of the wire, e.g. `q[2]`.
+    Index(char, usize),
+}
+
+impl WireLabels {
+    /// Creates new WireLabels without any custom name or initial state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of a qubit.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The index of the qubit.
+    /// * `name` - The name of the qubit, e.g. `ancilla`.
+    pub fn qubit(mut self, qubit: usize, name: &str) -> Self {
+        self.qubits.insert(qubit, name.to_owned());
+        self
+    }
+
+    /// Sets the name of a bosonic mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The index of the bosonic mode.
+    /// * `name` - The name of the mode, e.g. `cavity A`.
+    pub fn boson(mut self, mode: usize, name: &str) -> Self {
+        self.bosons.insert(mode, name.to_owned());
+        self
+    }
+
+    /// Sets the name displayed for a classical register.
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The name of the register in the circuit.
+    /// * `name` - The name to display.
+    pub fn register(mut self, register: &str, name: &str) -> Self {
+        self.registers.insert(register.to_owned(), name.to_owned());
+        self
+    }
+
+    /// Sets the initial state of a qubit.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The index of the qubit.
+    /// * `state` - The initial state as typst math, drawn as `|state>`, e.g. `+` or `psi`.
+    pub fn qubit_state(mut self, qubit: usize, state: &str) -> Self {
+        self.qubit_states.insert(qubit, state.to_owned());
+        self
+    }
+
+    /// Sets the initial state of a bosonic mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The index of the bosonic mode.
+    /// * `state` - The initial state as typst math, drawn as `|state>`, e.g. `alpha`.
+    pub fn boson_state(mut self, mode: usize, state: &str) -> Self {
+        self.boson_states.insert(mode, state.to_owned());
+        self
+    }
+
+    /// Returns the name displayed for a classical register.
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The name of the register in the circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The custom name of the register, or its name in the circuit.
+    pub fn register_name<'a>(&'a self, register: &'a str) -> &'a str {
+        self.registers
+            .get(register)
+            .map_or(register, String::as_str)
+    }
+
+    /// Returns the name of a qubit or bosonic wire.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The wire.
+    pub(crate) fn name(&self, wire: WireRef) -> WireName<'_> {
+        match wire {
+            WireRef::Qubit(index) => self
+                .qubits
+                .get(&index)
+                .map_or(WireName::Index('q', index), |name| WireName::Custom(name)),
+            WireRef::Boson(index) => self
+                .bosons
+                .get(&index)
+                .map_or(WireName::Index('b', index), |name| WireName::Custom(name)),
+            WireRef::Classical(index) => WireName::Index('c', index),
+        }
+    }
+
+    /// Returns the name and the initial state displayed at the beginning of a wire.
+    ///
+    /// The name is displayed in the `qubit` initialization mode or when it was customised, the
+    /// initial state in the `state` initialization mode or when it was customised.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The qubit or bosonic wire.
+    /// * `initialization_mode` - The initialization mode of the circuit representation.
+    /// * `prepared_state` - The state inferred from the state preparations of the circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `(Option<WireName>, Option<&str>)` - The name and the initial state to display.
+    pub(crate) fn start<'a>(
+        &'a self,
+        wire: WireRef,
+        initialization_mode: InitializationMode,
+        prepared_state: Option<&'a PreparedState>,
+    ) -> (Option<WireName<'a>>, Option<&'a str>) {
+        let name = self.name(wire);
+        let state = match wire {
+            WireRef::Qubit(index) => self
+                .qubit_states
+                .get(&index)
+                .map(String::as_str)
+                .or_else(|| prepared_state.and_then(|state| state.qubit_state(index))),
+            WireRef::Boson(index) => self.boson_states.get(&index).map(String::as_str),
+            WireRef::Classical(_) => None,
+        };
+        let show_name =
+            matches!(name, WireName::Custom(_)) || initialization_mode == InitializationMode::Qubit;
+        let state = match (state, initialization_mode) {
+            (Some(state), _) => Some(state),
+            (None, InitializationMode::Qubit) => None,
+            (None, _) => Some("0"),
+        };
+        (show_name.then_some(name), state)
+    }
+
+    /// Returns the typst math displayed at the beginning of a wire, e.g. `"ancilla": |+>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The qubit or bosonic wire.
+    /// * `initialization_mode` - The initialization mode of the circuit representation.
+    /// * `prepared_state` - The state inferred from the state preparations of the circuit.
+    pub(crate) fn typst_start(
+        &self,
+        wire: WireRef,
+        initialization_mode: InitializationMode,
+        prepared_state: Option<&PreparedState>,
+    ) -> String {
+        let (name, state) = self.start(wire, initialization_mode, prepared_state);
+        join_start(
+            name.map(WireName::to_typst),
+            state.map(|state| format!("|{state}>")),
+        )
+    }
+
+    /// Returns the LaTeX displayed at the beginning of a wire, e.g. `$\text{ancilla}: \ket{+}$`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The qubit or bosonic wire.
+    /// * `initialization_mode` - The initialization mode of the circuit representation.
+    /// * `prepared_state` - The state inferred from the state preparations of the circuit.
+    pub(crate) fn latex_start(
+        &self,
+        wire: WireRef,
+        initialization_mode: InitializationMode,
+        prepared_state: Option<&PreparedState>,
+    ) -> String {
+        let (name, state) = self.start(wire, initialization_mode, prepared_state);
+        let label = join_start(
+            name.map(WireName::to_latex),
+            state.map(|state| format!("\\ket{{{}}}", typst_math_to_latex(state))),
+        );
+        format!("${label}$")
+    }
+
+    /// Returns the plain text displayed at the beginning of a wire, e.g. `ancilla: |+⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The qubit or bosonic wire.
+    /// * `initialization_mode` - The initialization mode of the circuit representation.
+    /// * `prepared_state` - The state inferred from the state preparations of the circuit.
+    pub(crate) fn text_start(
+        &self,
+        wire: WireRef,
+        initialization_mode: InitializationMode,
+        prepared_state: Option<&PreparedState>,
+    ) -> String {
+        let (name, state) = self.start(wire, initialization_mode, prepared_state);
+        join_start(
+            name.map(WireName::to_text),
+            state.map(|state| format!("|{}⟩", state.replace(['"', '\\'], ""))),
+        )
+    }
+
+    /// Replaces the names of the classical registers of the wires by their custom names.
+    ///
+    /// # Arguments
+    ///
+    /// * `wires` - The classical wires of a layout.
+    pub(crate) fn rename_registers(&self, wires: &mut [Vec<LayoutItem>]) {
+        for item in wires.iter_mut().flatten() {
+            if let LayoutItem::RegisterLabel(name) = item {
+                if let Some(custom) = self.registers.get(name) {
+                    *name = custom.clone();
+                }
+            }
+        }
+    }
+}
+
+impl WireName<'_> {
+    /// Returns the name as typst math.
+    pub(crate) fn to_typst(self) -> String {
+        match self {
+            WireName::Custom(name) => {
+                format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            WireName::Index(prefix, index) => format!("{prefix}[{index}]"),
+        }
+    }
+
+    /// Returns the name as LaTeX math.
+    pub(crate) fn to_latex(self) -> String {
+        match self {
+            WireName::Custom(name) => format!("\\text{{{}}}", escape_latex_text(name)),
+            WireName::Index(prefix, index) => format!("{prefix}[{index}]"),
+        }
+    }
+
+    /// Returns the name as plain text.
+    pub(crate) fn to_text(self) -> String {
+        match self {
+            WireName::Custom(name) => name.to_owned(),
+            WireName::Index(prefix, index) => format!("{prefix}[{index}]"),
+        }
+    }
+}
+
+/// Joins the name and the initial state displayed at the beginning of a wire.
+///
+/// # Arguments
+///
+/// * `name` - The name of the wire, if displayed.
+/// * `state` - The initial state of the wire, if displayed.
+fn join_start(name: Option<String>, state: Option<String>) -> String {
+    match (name, state) {
+        (Some(name), Some(state)) => format!("{name}: {state}"),
+        (Some(label), None) | (None, Some(label)) => label,
+        (None, None) => String::new(),
+    }
+}
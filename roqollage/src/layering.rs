@@ -0,0 +1,338 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, str::FromStr};
+
+use roqoqo::{
+    operations::{
+        InvolveModes, InvolveQubits, InvolvedClassical, InvolvedModes, InvolvedQubits, Operate,
+        OperateSingleQubit, Operation,
+    },
+    Circuit, RoqoqoBackendError, RoqoqoError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Strategy assigning the operations of a circuit to the columns of its diagram.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayeringStrategy {
+    /// Every operation is appended to its wires in the order of the circuit, next to the previous
+    /// operations on the same wires.
+    #[default]
+    Greedy,
+    /// Every operation gets its own columns, in the order of the circuit.
+    Strict,
+    /// The operations are packed in moments, each operation in the first moment after the
+    /// previous operations on its wires.
+    Asap,
+    /// The operations are packed in moments, each operation in the last moment before the
+    /// following operations on its wires.
+    Alap,
+    /// As [LayeringStrategy::Asap], with the `PragmaStopParallelBlock` operations acting as
+    /// barriers across all the wires.
+    RespectBarriers,
+}
+
+/// Wire an operation of the circuit depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Resource {
+    /// A qubit.
+    Qubit(usize),
+    /// A bosonic mode.
+    Mode(usize),
+    /// A bit of a classical register, written by the measurements and read by the conditions.
+    Bit(String, usize),
+    /// A whole classical register.
+    Register(String),
+}
+
+/// Wires an operation depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Dependencies {
+    /// The operation only declares registers and is placed before the other operations.
+    Definition,
+    /// The operation acts on the given wires.
+    Wires(Vec<Resource>),
+    /// The operation acts on the whole circuit and synchronises all the wires.
+    Barrier,
+}
+
+impl FromStr for LayeringStrategy {
+    type Err = RoqoqoBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "greedy" | "default" | "" => Ok(LayeringStrategy::Greedy),
+            "strict" => Ok(LayeringStrategy::Strict),
+            "asap" => Ok(LayeringStrategy::Asap),
+            "alap" => Ok(LayeringStrategy::Alap),
+            "respect_barriers" | "barriers" => Ok(LayeringStrategy::RespectBarriers),
+            _ => Err(RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                msg: format!(
+                    r#"Invalid layering strategy: {s}, use `greedy`, `strict`, `asap`, `alap` or `respect_barriers`."#
+                ),
+            })),
+        }
+    }
+}
+
+impl LayeringStrategy {
+    /// Returns the moment of each operation of a circuit.
+    ///
+    /// The operations of the same moment act on different wires and are drawn starting in the
+    /// same column, the moments are drawn one after the other. The wires crossed by the lines and
+    /// boxes of an operation, e.g. between the control and the target of a CNOT or below a
+    /// measurement, are part of its moment, the drawing can thus be deeper than the circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `operations` - The operations to draw, in the order of the circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<usize>>` - The moment of each operation, `None` for the greedy strategy.
+    pub fn layers(&self, operations: &[&Operation]) -> Option<Vec<usize>> {
+        match self {
+            LayeringStrategy::Greedy => None,
+            LayeringStrategy::Strict => Some((0..operations.len()).collect()),
+            LayeringStrategy::Asap => Some(asap_layers(operations, false, true)),
+            LayeringStrategy::RespectBarriers => Some(asap_layers(operations, true, true)),
+            LayeringStrategy::Alap => {
+                let reversed: Vec<&Operation> = operations.iter().rev().copied().collect();
+                let mut layers = asap_layers(&reversed, false, true);
+                layers.reverse();
+                let depth = layers.iter().max().map_or(0, |&layer| layer + 1);
+                Some(
+                    operations
+                        .iter()
+                        .zip(layers)
+                        .map(
+                            |(operation, layer)| match dependencies(operation, false, None) {
+                                Dependencies::Definition => 0,
+                                _ => depth - 1 - layer,
+                            },
+                        )
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Returns the logical depth of a circuit, the number of moments of its operations.
+///
+/// Two operations are in different moments when they act on the same qubit, bosonic mode or
+/// classical bit, e.g. a measurement and the conditional operation reading its result, the
+/// operations acting on all the qubits or on none of them (except the definitions) are in a moment
+/// of their own.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit.
+///
+/// # Returns
+///
+/// * `usize` - The depth of the circuit.
+pub fn circuit_depth(circuit: &Circuit) -> usize {
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    asap_layers(&operations, false, false)
+        .into_iter()
+        .zip(operations)
+        .filter(|(_, operation)| dependencies(operation, false, None) != Dependencies::Definition)
+        .map(|(layer, _)| layer + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the bosonic modes an operation acts on.
+///
+/// The [Operation] enum does not implement [InvolveModes], the mode operations are matched here.
+///
+/// # Arguments
+///
+/// * `operation` - The operation.
+pub(crate) fn involved_modes(operation: &Operation) -> InvolvedModes {
+    match operation {
+        Operation::Squeezing(op) => op.involved_modes(),
+        Operation::PhaseDisplacement(op) => op.involved_modes(),
+        Operation::PhaseShift(op) => op.involved_modes(),
+        Operation::BeamSplitter(op) => op.involved_modes(),
+        Operation::PhotonDetection(op) => op.involved_modes(),
+        Operation::QuantumRabi(op) => op.involved_modes(),
+        Operation::LongitudinalCoupling(op) => op.involved_modes(),
+        Operation::JaynesCummings(op) => op.involved_modes(),
+        Operation::SingleExcitationStore(op) => op.involved_modes(),
+        Operation::SingleExcitationLoad(op) => op.involved_modes(),
+        Operation::CZQubitResonator(op) => op.involved_modes(),
+        _ => InvolvedModes::None,
+    }
+}
+
+/// Returns the wires an operation depends on.
+///
+/// # Arguments
+///
+/// * `operation` - The operation.
+/// * `barriers` - Whether the `PragmaStopParallelBlock` operations synchronise all the wires.
+/// * `extent` - The number of qubit and mode wires of the diagram, to also depend on the wires
+///   crossed by the lines and boxes of the operation, `None` for the logical dependencies.
+fn dependencies(
+    operation: &Operation,
+    barriers: bool,
+    extent: Option<(usize, usize)>,
+) -> Dependencies {
+    if operation.tags().contains(&"Definition") {
+        return Dependencies::Definition;
+    }
+    if barriers && matches!(operation, Operation::PragmaStopParallelBlock(_)) {
+        return Dependencies::Barrier;
+    }
+    let mut qubits: Vec<usize> = match operation.involved_qubits() {
+        InvolvedQubits::Set(qubits) => qubits.into_iter().collect(),
+        InvolvedQubits::All => return Dependencies::Barrier,
+        InvolvedQubits::None => Vec::new(),
+    };
+    let mut modes: Vec<usize> = match involved_modes(operation) {
+        InvolvedModes::Set(modes) => modes.into_iter().collect(),
+        InvolvedModes::All => return Dependencies::Barrier,
+        InvolvedModes::None => Vec::new(),
+    };
+    if let Some((n_qubits, n_modes)) = extent {
+        // The measurements are linked to the classical wires below all the qubits and modes.
+        if let Operation::MeasureQubit(op) = operation {
+            qubits = (*op.qubit()..n_qubits.max(op.qubit() + 1)).collect();
+            modes = (0..n_modes).collect();
+        }
+        qubits = spanned(&qubits);
+        modes = spanned(&modes);
+    }
+    let mut resources: Vec<Resource> = qubits
+        .into_iter()
+        .map(Resource::Qubit)
+        .chain(modes.into_iter().map(Resource::Mode))
+        .collect();
+    if resources.is_empty() {
+        return Dependencies::Barrier;
+    }
+    match operation.involved_classical() {
+        InvolvedClassical::Set(bits) => resources.extend(
+            bits.into_iter()
+                .map(|(register, index)| Resource::Bit(register, index)),
+        ),
+        InvolvedClassical::All(register) | InvolvedClassical::AllQubits(register) => {
+            resources.push(Resource::Register(register))
+        }
+        InvolvedClassical::None => (),
+    }
+    Dependencies::Wires(resources)
+}
+
+/// Places every operation in the first moment after the previous operations on its wires.
+///
+/// # Arguments
+///
+/// * `operations` - The operations, in the order of the circuit.
+/// * `barriers` - Whether the `PragmaStopParallelBlock` operations synchronise all the wires.
+/// * `drawn` - Whether the operations also depend on the wires their lines and boxes cross in the
+///   diagram, e.g. the wires between the control and the target of a CNOT.
+///
+/// # Returns
+///
+/// * `Vec<usize>` - The moment of each operation, the definitions are in the first moment.
+fn asap_layers(operations: &[&Operation], barriers: bool, drawn: bool) -> Vec<usize> {
+    let extent = drawn.then(|| {
+        operations
+            .iter()
+            .fold((0, 0), |(n_qubits, n_modes), operation| {
+                let n_qubits = match operation.involved_qubits() {
+                    InvolvedQubits::Set(qubits) => qubits
+                        .into_iter()
+                        .fold(n_qubits, |n_qubits, qubit| n_qubits.max(qubit + 1)),
+                    _ => n_qubits,
+                };
+                let n_modes = match involved_modes(operation) {
+                    InvolvedModes::Set(modes) => modes
+                        .into_iter()
+                        .fold(n_modes, |n_modes, mode| n_modes.max(mode + 1)),
+                    _ => n_modes,
+                };
+                (n_qubits, n_modes)
+            })
+    });
+    // The first free moment of each wire, and the first moment after the last barrier.
+    let mut clocks: HashMap<Resource, usize> = HashMap::new();
+    let mut barrier = 0;
+    let mut end = 0;
+    operations
+        .iter()
+        .map(
+            |operation| match dependencies(operation, barriers, extent) {
+                Dependencies::Definition => 0,
+                Dependencies::Wires(resources) => {
+                    let layer = resources
+                        .iter()
+                        .map(|resource| clock(&clocks, resource))
+                        .fold(barrier, usize::max);
+                    for resource in resources {
+                        clocks.insert(resource, layer + 1);
+                    }
+                    end = end.max(layer + 1);
+                    layer
+                }
+                Dependencies::Barrier => {
+                    let layer = end.max(barrier);
+                    barrier = layer + 1;
+                    end = barrier;
+                    layer
+                }
+            },
+        )
+        .collect()
+}
+
+/// Returns the first free moment of a wire.
+///
+/// A bit is busy until the last operation on its register as a whole, and a register until the
+/// last operation on any of its bits.
+///
+/// # Arguments
+///
+/// * `clocks` - The first free moment of each wire.
+/// * `resource` - The wire.
+fn clock(clocks: &HashMap<Resource, usize>, resource: &Resource) -> usize {
+    match resource {
+        Resource::Bit(register, _) => clocks
+            .get(resource)
+            .copied()
+            .max(clocks.get(&Resource::Register(register.clone())).copied()),
+        Resource::Register(register) => clocks
+            .iter()
+            .filter(|(other, _)| {
+                matches!(other, Resource::Bit(name, _) | Resource::Register(name) if name == register)
+            })
+            .map(|(_, &clock)| clock)
+            .max(),
+        _ => clocks.get(resource).copied(),
+    }
+    .unwrap_or(0)
+}
+
+/// Returns all the wires from the lowest to the highest of the given wires.
+///
+/// # Arguments
+///
+/// * `wires` - The wires.
+fn spanned(wires: &[usize]) -> Vec<usize> {
+    match (wires.iter().min(), wires.iter().max()) {
+        (Some(&min), Some(&max)) => (min..=max).collect(),
+        _ => Vec::new(),
+    }
+}
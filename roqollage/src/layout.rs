@@ -0,0 +1,726 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use crate::PreparedState;
+
+/// Reference to a wire of the circuit diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WireRef {
+    /// The wire of the qubit with the given index.
+    Qubit(usize),
+    /// The wire of the bosonic mode with the given index.
+    Boson(usize),
+    /// The wire of the classical register with the given index.
+    Classical(usize),
+}
+
+/// Item placed on a wire of the circuit diagram.
+///
+/// The contents and labels are typst math expressions, they are written between `$` by the
+/// typst emitter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutItem {
+    /// An empty column of the wire.
+    Idle,
+    /// A gate box on a single wire.
+    Gate {
+        /// The content of the gate box.
+        content: String,
+        /// The text label displayed above the gate box.
+        label: Option<String>,
+        /// The fill colour of the gate box.
+        fill: Option<String>,
+        /// The stroke of the gate box, quill's default box is drawn when not set.
+        stroke: Option<String>,
+    },
+    /// A gate box spanning several consecutive wires, starting on the wire it is placed on.
+    MultiGate {
+        /// The content of the gate box.
+        content: String,
+        /// The number of wires covered by the gate box.
+        n_wires: Option<usize>,
+        /// The width of the gate box.
+        width: Option<String>,
+        /// The extent of the gate box beyond the wire.
+        extent: Option<String>,
+        /// The fill colour of the gate box.
+        fill: Option<String>,
+        /// The wires marked as inputs, relative to the first wire of the gate box.
+        inputs: Vec<usize>,
+        /// The wire the gate box is connected to by a vertical line.
+        target: Option<WireRef>,
+    },
+    /// A control dot, connected to the target wire by a vertical line.
+    Control {
+        /// The wire the control is connected to.
+        target: Option<WireRef>,
+        /// The label displayed below the control dot.
+        label: Option<String>,
+    },
+    /// A CNOT target.
+    Target,
+    /// A swap cross, connected to the partner wire by a vertical line.
+    Swap {
+        /// The wire of the other swap cross.
+        partner: Option<WireRef>,
+        /// The label of the swap.
+        label: Option<String>,
+    },
+    /// A measurement, connected to the target wire by a classical line.
+    Meter {
+        /// The wire the measurement result is stored on.
+        target: Option<WireRef>,
+        /// The fill colour of the measurement box.
+        fill: Option<String>,
+    },
+    /// A vertical slice across all the wires.
+    Slice {
+        /// The label displayed above the slice.
+        label: String,
+        /// The stroke of the slice.
+        stroke: Option<String>,
+    },
+    /// A dotted box around a group of gates.
+    GateGroup {
+        /// The number of wires covered by the group.
+        n_wires: usize,
+        /// The number of columns covered by the group.
+        n_columns: usize,
+        /// The text label of the group.
+        label: String,
+        /// The stroke of the group box, dotted when not set.
+        stroke: Option<String>,
+    },
+    /// The name of a classical register, displayed at the beginning of its wire.
+    RegisterLabel(String),
+    /// Turns the wire into a classical (double) wire.
+    ClassicalWire,
+    /// Raw quill code, written as is.
+    Typst(String),
+}
+
+impl LayoutItem {
+    /// Creates a gate box without label nor fill.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The content of the gate box.
+    pub fn gate(content: impl Into<String>) -> Self {
+        LayoutItem::Gate {
+            content: content.into(),
+            label: None,
+            fill: None,
+            stroke: None,
+        }
+    }
+
+    /// Creates a control dot connected to the given wire.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The wire the control is connected to.
+    pub fn control(target: WireRef) -> Self {
+        LayoutItem::Control {
+            target: Some(target),
+            label: None,
+        }
+    }
+
+    /// Returns whether the item takes a column on its wire.
+    ///
+    /// Slices, gate groups, register labels and wire settings are attached to the next column
+    /// and do not take any space on their own.
+    pub fn is_column(&self) -> bool {
+        !matches!(
+            self,
+            LayoutItem::Slice { .. }
+                | LayoutItem::GateGroup { .. }
+                | LayoutItem::RegisterLabel(_)
+                | LayoutItem::ClassicalWire
+        )
+    }
+
+    /// Serializes the item to its quill representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The wire the item is placed on.
+    /// * `rows` - The rows of the wires in the diagram.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The quill representation of the item.
+    pub fn to_typst(&self, wire: WireRef, rows: &WireRows) -> String {
+        let offset = |target: &WireRef| rows.offset(wire, *target);
+        match self {
+            LayoutItem::Idle => "1".to_owned(),
+            LayoutItem::Gate {
+                content,
+                label: None,
+                fill: None,
+                stroke: None,
+            } => format!("$ {content} $"),
+            // quill has no stroke argument, the content is drawn in a stroked box over the wire.
+            LayoutItem::Gate {
+                content,
+                label,
+                fill,
+                stroke: Some(stroke),
+            } => {
+                let mut output = format!(
+                    "gate(box(fill: {}, stroke: {stroke}, inset: (x: 0.4em, y: 0.5em))[$ {content} $], box: false",
+                    fill.as_deref().unwrap_or("white")
+                );
+                if let Some(label) = label {
+                    output.push_str(&format!(", label: \"{label}\""));
+                }
+                output.push(')');
+                output
+            }
+            LayoutItem::Gate {
+                content,
+                label,
+                fill,
+                stroke: None,
+            } => {
+                let mut output = format!("gate($ {content} $");
+                if let Some(label) = label {
+                    output.push_str(&format!(", label: \"{label}\""));
+                }
+                if let Some(fill) = fill {
+                    output.push_str(&format!(", fill: {fill}"));
+                }
+                output.push(')');
+                output
+            }
+            LayoutItem::MultiGate {
+                content,
+                n_wires,
+                width,
+                extent,
+                fill,
+                inputs,
+                target,
+            } => {
+                let mut output = format!("mqgate($ {content} $");
+                if let Some(n_wires) = n_wires {
+                    output.push_str(&format!(", n: {n_wires}"));
+                }
+                if let Some(width) = width {
+                    output.push_str(&format!(", width: {width}"));
+                }
+                if let Some(extent) = extent {
+                    output.push_str(&format!(", extent: {extent}"));
+                }
+                if let Some(fill) = fill {
+                    output.push_str(&format!(", fill: {fill}"));
+                }
+                if !inputs.is_empty() {
+                    output.push_str(&format!(
+                        ", inputs: ({}{})",
+                        inputs
+                            .iter()
+                            .map(|input| format!("(qubit: {input}, label: \"x\")"))
+                            .collect::<Vec<String>>()
+                            .join(","),
+                        if inputs.len() == 1 { "," } else { "" }
+                    ));
+                }
+                if let Some(target) = target {
+                    output.push_str(&format!(", target: {}", offset(target)));
+                }
+                output.push(')');
+                output
+            }
+            LayoutItem::Control { target, label } => {
+                let mut output = format!("ctrl({}", target.as_ref().map_or(0, offset));
+                if let Some(label) = label {
+                    output.push_str(&format!(", label: (content: $ {label} $, pos: bottom)"));
+                }
+                output.push(')');
+                output
+            }
+            LayoutItem::Target => "targ()".to_owned(),
+            LayoutItem::Swap { partner, label } => {
+                let mut output = format!(
+                    "swap({}",
+                    partner
+                        .as_ref()
+                        .map(|partner| offset(partner).to_string())
+                        .unwrap_or_default()
+                );
+                if let Some(label) = label {
+                    output.push_str(&format!(", label: $ {label} $"));
+                }
+                output.push(')');
+                output
+            }
+            LayoutItem::Meter { target, fill } => {
+                let mut arguments = Vec::new();
+                if let Some(target) = target {
+                    arguments.push(format!("target: {}", offset(target)));
+                }
+                if let Some(fill) = fill {
+                    arguments.push(format!("fill: {fill}"));
+                }
+                format!("meter({})", arguments.join(", "))
+            }
+            LayoutItem::Slice {
+                label,
+                stroke: None,
+            } => format!("slice(label: $ {label} $)"),
+            LayoutItem::Slice {
+                label,
+                stroke: Some(stroke),
+            } => format!("slice(label: $ {label} $, stroke: {stroke})"),
+            LayoutItem::GateGroup {
+                n_wires,
+                n_columns,
+                label,
+                stroke: None,
+            } => format!(
+                "gategroup({n_wires}, {n_columns}, label: \"{label}\",  stroke: (dash: \"dotted\"))"
+            ),
+            LayoutItem::GateGroup {
+                n_wires,
+                n_columns,
+                label,
+                stroke: Some(stroke),
+            } => format!("gategroup({n_wires}, {n_columns}, label: \"{label}\", stroke: {stroke})"),
+            LayoutItem::RegisterLabel(name) => format!("lstick($ \"{name} : \" $)"),
+            LayoutItem::ClassicalWire => "setwire(2)".to_owned(),
+            LayoutItem::Typst(code) => code.clone(),
+        }
+    }
+
+    /// Serializes the item to its quantikz representation.
+    ///
+    /// The typst math contents are converted to LaTeX. The quill-specific styling (labels of the
+    /// gates, widths, extents and strokes) and the raw quill code have no quantikz equivalent
+    /// and are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The wire the item is placed on.
+    /// * `rows` - The rows of the wires in the diagram.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The quantikz representation of the item.
+    pub fn to_quantikz(&self, wire: WireRef, rows: &WireRows) -> String {
+        let offset = |target: &WireRef| rows.offset(wire, *target);
+        match self {
+            LayoutItem::Idle
+            | LayoutItem::RegisterLabel(_)
+            | LayoutItem::ClassicalWire
+            | LayoutItem::Typst(_) => String::new(),
+            LayoutItem::Gate { content, fill, .. } => match quantikz_fill(fill) {
+                Some(style) => format!("\\gate[{style}]{{{}}}", typst_math_to_latex(content)),
+                None => format!("\\gate{{{}}}", typst_math_to_latex(content)),
+            },
+            LayoutItem::MultiGate {
+                content,
+                n_wires,
+                fill,
+                target,
+                ..
+            } => {
+                let options: Vec<String> = n_wires
+                    .map(|n_wires| n_wires.to_string())
+                    .into_iter()
+                    .chain(quantikz_fill(fill))
+                    .collect();
+                let mut output = if options.is_empty() {
+                    format!("\\gate{{{}}}", typst_math_to_latex(content))
+                } else {
+                    format!(
+                        "\\gate[{}]{{{}}}",
+                        options.join(","),
+                        typst_math_to_latex(content)
+                    )
+                };
+                if let Some(target) = target {
+                    output.push_str(&format!(" \\vqw{{{}}}", offset(target)));
+                }
+                output
+            }
+            LayoutItem::Control {
+                target: Some(target),
+                ..
+            } => format!("\\ctrl{{{}}}", offset(target)),
+            LayoutItem::Control {
+                target: None,
+                label: Some(label),
+            } => format!("\\push{{${}$}}", typst_math_to_latex(label)),
+            LayoutItem::Control {
+                target: None,
+                label: None,
+            } => "\\ctrl{}".to_owned(),
+            LayoutItem::Target => "\\targ{}".to_owned(),
+            LayoutItem::Swap {
+                partner: Some(partner),
+                label: None,
+            } => format!("\\swap{{{}}}", offset(partner)),
+            LayoutItem::Swap {
+                partner: Some(partner),
+                label: Some(label),
+            } => format!(
+                "\\gate{{{}}} \\vqw{{{}}}",
+                typst_math_to_latex(label),
+                offset(partner)
+            ),
+            LayoutItem::Swap { partner: None, .. } => "\\targX{}".to_owned(),
+            LayoutItem::Meter { target: None, .. } => "\\meter{}".to_owned(),
+            LayoutItem::Meter {
+                target: Some(target),
+                ..
+            } => format!("\\meter{{}} \\vcw{{{}}}", offset(target)),
+            LayoutItem::Slice { label, .. } => {
+                format!("\\slice{{${}$}}", typst_math_to_latex(label))
+            }
+            LayoutItem::GateGroup {
+                n_wires,
+                n_columns,
+                label,
+                ..
+            } => format!(
+                "\\gategroup[{n_wires},steps={n_columns},style={{dashed,rounded corners,inner sep=2pt}}]{{{}}}",
+                escape_latex_text(label)
+            ),
+        }
+    }
+}
+
+/// Converts a quill fill colour to a quantikz gate style.
+///
+/// Only the named colours are converted, they are lightened as in the typst diagrams.
+///
+/// # Arguments
+///
+/// * `fill` - The fill colour of the gate.
+fn quantikz_fill(fill: &Option<String>) -> Option<String> {
+    fill.as_ref()
+        .filter(|fill| !fill.is_empty() && fill.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|fill| format!("style={{fill={fill}!20}}"))
+}
+
+/// Escapes the characters of a text that are special in LaTeX.
+///
+/// # Arguments
+///
+/// * `text` - The text to escape.
+pub(crate) fn escape_latex_text(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\\' => output.push_str("\\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                output.push('\\');
+                output.push(character);
+            }
+            '^' => output.push_str("\\textasciicircum{}"),
+            '~' => output.push_str("\\textasciitilde{}"),
+            '<' => output.push_str("\\textless{}"),
+            '>' => output.push_str("\\textgreater{}"),
+            '|' => output.push_str("\\textbar{}"),
+            _ => output.push(character),
+        }
+    }
+    output
+}
+
+/// Returns the index of the parenthesis closing the one at the given index.
+///
+/// # Arguments
+///
+/// * `chars` - The characters of the expression.
+/// * `open` - The index of the opening parenthesis.
+fn closing_parenthesis(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, character) in chars.iter().enumerate().skip(open) {
+        match character {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Converts a typst math expression, as produced by the circuit layout, to LaTeX math.
+///
+/// Strings become `\text`, the typst symbols become LaTeX commands, `sqrt(..)` becomes `\sqrt{..}`
+/// and the parenthesized sub- and superscripts become braced ones.
+///
+/// # Arguments
+///
+/// * `content` - The typst math expression.
+///
+/// # Returns
+///
+/// * `String` - The LaTeX math expression.
+pub(crate) fn typst_math_to_latex(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut output = String::with_capacity(content.len());
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '"' => {
+                let mut text = String::new();
+                index += 1;
+                while index < chars.len() && chars[index] != '"' {
+                    if chars[index] == '\\' && index + 1 < chars.len() {
+                        index += 1;
+                        text.push(if chars[index] == 'n' {
+                            ' '
+                        } else {
+                            chars[index]
+                        });
+                    } else {
+                        text.push(chars[index]);
+                    }
+                    index += 1;
+                }
+                output.push_str(&format!("\\text{{{}}}", escape_latex_text(&text)));
+                index += 1;
+            }
+            '\\' => {
+                match chars.get(index + 1) {
+                    Some(' ') | None => output.push_str("\\ "),
+                    Some(character) => output.push_str(&escape_latex_text(&character.to_string())),
+                }
+                index += 2;
+            }
+            character @ ('^' | '_') if chars.get(index + 1) == Some(&'(') => {
+                let close = closing_parenthesis(&chars, index + 1).unwrap_or(chars.len());
+                let inner: String = chars[index + 2..close.max(index + 2)].iter().collect();
+                output.push(character);
+                output.push_str(&format!("{{{}}}", typst_math_to_latex(&inner)));
+                index = close + 1;
+            }
+            '-' if chars.get(index + 1) == Some(&'>') => {
+                output.push_str("\\rightarrow ");
+                index += 2;
+            }
+            character if character.is_ascii_alphabetic() => {
+                let start = index;
+                while index < chars.len() && chars[index].is_ascii_alphabetic() {
+                    index += 1;
+                }
+                let word: String = chars[start..index].iter().collect();
+                if word.len() == 1 {
+                    output.push_str(&word);
+                    continue;
+                }
+                // The variants of the typst symbols, e.g. `theta.alt`, are drawn as the main symbol.
+                while chars.get(index) == Some(&'.')
+                    && chars.get(index + 1).is_some_and(char::is_ascii_alphabetic)
+                {
+                    index += 1;
+                    while index < chars.len() && chars[index].is_ascii_alphabetic() {
+                        index += 1;
+                    }
+                }
+                if word == "sqrt" && chars.get(index) == Some(&'(') {
+                    let close = closing_parenthesis(&chars, index).unwrap_or(chars.len());
+                    let inner: String = chars[index + 1..close.max(index + 1)].iter().collect();
+                    output.push_str(&format!("\\sqrt{{{}}}", typst_math_to_latex(&inner)));
+                    index = close + 1;
+                } else {
+                    output.push_str(&format!("\\{word} "));
+                }
+            }
+            character => {
+                output.push(character);
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Calculates the length on the image since some items are not represented by a column.
+///
+/// # Arguments
+///
+/// * `items` - The items of a wire.
+///
+/// # Returns
+///
+/// * `usize` - The total length the items will take on the image.
+pub(crate) fn effective_len(items: &[LayoutItem]) -> usize {
+    items.iter().filter(|item| item.is_column()).count()
+}
+
+/// Typed layout of a circuit diagram.
+///
+/// Every wire is a list of items, one per column except for the items that are not columns
+/// (see [LayoutItem::is_column]).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CircuitLayout {
+    /// The items of each qubit wire.
+    pub qubits: Vec<Vec<LayoutItem>>,
+    /// The items of each bosonic mode wire.
+    pub bosons: Vec<Vec<LayoutItem>>,
+    /// The items of each classical register wire.
+    pub classical: Vec<Vec<LayoutItem>>,
+    /// The emplacements of the qubit wires that are reserved for a vertical line between two items.
+    pub(crate) qubit_lock: Vec<(usize, usize)>,
+    /// The emplacements of the bosonic wires that are reserved for a vertical line between two items.
+    pub(crate) boson_lock: Vec<(usize, usize)>,
+    /// The emplacements of the classical wires that are reserved for a vertical line between two items.
+    pub(crate) classical_lock: Vec<(usize, usize)>,
+    /// The warnings emitted while building the layout, e.g. the operations drawn by the fallback renderer.
+    pub warnings: Vec<String>,
+    /// The state prepared at the beginning of the qubit wires, with the `inferred` initialization mode.
+    pub prepared_state: Option<PreparedState>,
+    /// The qubit drawn on each qubit wire when the wires are rearranged, empty when the wire `n`
+    /// draws the qubit `n`.
+    pub qubit_indices: Vec<usize>,
+    /// The column of each drawn operation, by index of the operation in the circuit (definitions
+    /// first, as iterated by `Circuit::iter`).
+    pub operation_columns: HashMap<usize, usize>,
+    /// The number of operations drawn on each qubit wire.
+    pub qubit_gate_counts: Vec<usize>,
+}
+
+impl CircuitLayout {
+    /// Creates a new empty CircuitLayout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the qubit drawn on a qubit wire.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The index of the qubit wire.
+    pub fn qubit_index(&self, row: usize) -> usize {
+        self.qubit_indices.get(row).copied().unwrap_or(row)
+    }
+
+    /// Returns how a qubit wire starts when the qubits are prepared in a global state.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The index of the qubit wire.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<GlobalStart>` - The start of the wire, `None` when its qubit is not part of a
+    ///   global state.
+    pub(crate) fn global_start(&self, row: usize) -> Option<GlobalStart> {
+        let Some(PreparedState::Global { n_qubits, mixed }) = self.prepared_state else {
+            return None;
+        };
+        if self.qubit_index(row) >= n_qubits {
+            return None;
+        }
+        let rows: Vec<usize> = (0..self.qubits.len())
+            .filter(|&other| self.qubit_index(other) < n_qubits)
+            .collect();
+        let first = rows[0];
+        let last = rows[rows.len() - 1];
+        Some(if last - first + 1 != rows.len() {
+            GlobalStart::Alone { mixed }
+        } else if row == first {
+            GlobalStart::First {
+                n_rows: rows.len(),
+                mixed,
+            }
+        } else {
+            GlobalStart::Spanned { last: row == last }
+        })
+    }
+
+    /// Returns the depth of the drawing, the number of columns of its longest wire.
+    pub fn depth(&self) -> usize {
+        self.qubits
+            .iter()
+            .chain(self.bosons.iter())
+            .chain(self.classical.iter())
+            .map(|items| effective_len(items))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the rows of the wires of the layout, qubits first then bosons then classical registers.
+    pub fn wire_rows(&self) -> WireRows {
+        WireRows {
+            n_qubits: self.qubits.len(),
+            n_bosons: self.bosons.len(),
+        }
+    }
+}
+
+/// Start of a qubit wire whose qubit is part of a global prepared state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GlobalStart {
+    /// The first wire of the state, labelled with a brace across the wires of the state.
+    First {
+        /// The number of wires of the state.
+        n_rows: usize,
+        /// Whether the state is a density matrix.
+        mixed: bool,
+    },
+    /// A following wire of the state, covered by the brace of the first one.
+    Spanned {
+        /// Whether the wire is the last one of the state.
+        last: bool,
+    },
+    /// A wire of a state whose wires are not consecutive, labelled on its own.
+    Alone {
+        /// Whether the state is a density matrix.
+        mixed: bool,
+    },
+}
+
+/// Position of the wires in the diagram, used to resolve the [WireRef] of the items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireRows {
+    /// The number of qubit wires, drawn first.
+    n_qubits: usize,
+    /// The number of bosonic mode wires, drawn after the qubits.
+    n_bosons: usize,
+}
+
+impl WireRows {
+    /// Returns the row of the wire in the diagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - The wire to locate.
+    pub fn row(&self, wire: WireRef) -> usize {
+        match wire {
+            WireRef::Qubit(qubit) => qubit,
+            WireRef::Boson(mode) => self.n_qubits + mode,
+            WireRef::Classical(index) => self.n_qubits + self.n_bosons + index,
+        }
+    }
+
+    /// Returns the number of rows from one wire to another.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The wire to start from.
+    /// * `to` - The wire to go to.
+    pub fn offset(&self, from: WireRef, to: WireRef) -> i64 {
+        self.row(to) as i64 - self.row(from) as i64
+    }
+}
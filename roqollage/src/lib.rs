@@ -23,7 +23,39 @@
 //!
 //! Translates qoqo operations and circuits to Typst operations via the interface, and Create a Typst file with TypstBackend.
 
+mod arrangement;
+pub use arrangement::*;
 mod backend;
 pub use backend::*;
+mod batch;
+pub use batch::*;
+mod device;
+pub use device::*;
 mod interface;
 pub use interface::*;
+mod labels;
+pub use labels::*;
+mod layering;
+pub use layering::*;
+mod layout;
+pub use layout::*;
+mod measurement;
+pub use measurement::*;
+mod options;
+pub use options::*;
+mod program;
+pub use program::*;
+mod qasm;
+pub use qasm::*;
+mod renderer;
+pub use renderer::*;
+mod routing;
+pub use routing::*;
+mod states;
+pub use states::*;
+mod text;
+pub use text::*;
+mod theme;
+pub use theme::*;
+mod timeline;
+pub use timeline::*;
@@ -0,0 +1,315 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use qoqo_calculator::CalculatorFloat;
+use regex::Regex;
+use roqoqo::{
+    measurements::{CheatedPauliZProductInput, PauliProductsToExpVal, PauliZProductInput},
+    operations::Operation,
+    Circuit,
+};
+
+use crate::{format_calculator, typst_header, typst_raw, RenderOptions, PANEL_STYLE};
+
+/// The quoted variables `"p0"`, `"p1"`, ... of the formulas, standing for the Pauli products.
+static PAULI_PRODUCTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""p(\d+)""#).unwrap());
+
+/// Formats a Pauli product as a typst math expression, e.g. `Z_(0) Z_(1)`.
+///
+/// # Arguments
+///
+/// * `paulis` - The qubits of the product with the name of the Pauli operator acting on them.
+///
+/// # Returns
+///
+/// * `String` - The typst representation of the product, `I` when no qubit is involved.
+fn format_pauli_product(paulis: &[(usize, &str)]) -> String {
+    if paulis.is_empty() {
+        return "I".to_owned();
+    }
+    paulis
+        .iter()
+        .map(|(qubit, pauli)| format!("{pauli}_({qubit})"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Formats the linear combination of Pauli products of an expectation value.
+///
+/// # Arguments
+///
+/// * `coefficients` - The coefficient of each Pauli product index.
+/// * `rounding_accuracy` - The number of decimals of the coefficients.
+///
+/// # Returns
+///
+/// * `String` - The typst math representation of the combination, e.g. `0.5 P_(0) - P_(1)`.
+fn format_linear_exp_val(coefficients: &HashMap<usize, f64>, rounding_accuracy: usize) -> String {
+    let mut coefficients: Vec<(&usize, &f64)> = coefficients.iter().collect();
+    coefficients.sort_by_key(|(index, _)| **index);
+    let mut formula = String::new();
+    for (index, &coefficient) in coefficients {
+        let sign = match (formula.is_empty(), coefficient < 0.0) {
+            (true, false) => "",
+            (true, true) => "-",
+            (false, false) => " + ",
+            (false, true) => " - ",
+        };
+        let magnitude = if coefficient.abs() == 1.0 {
+            String::new()
+        } else {
+            format!(
+                "{} ",
+                format_calculator(
+                    &CalculatorFloat::Float(coefficient.abs()),
+                    rounding_accuracy
+                )
+            )
+        };
+        formula.push_str(&format!("{sign}{magnitude}P_({index})"));
+    }
+    if formula.is_empty() {
+        "0".to_owned()
+    } else {
+        formula
+    }
+}
+
+/// Formats the symbolic combination of Pauli products of an expectation value.
+///
+/// The expression is formatted like the symbolic parameters of the gates, the `p0`, `p1`, ...
+/// variables standing for the Pauli products are then written as `P_(0)`, `P_(1)`, ...
+///
+/// # Arguments
+///
+/// * `expression` - The symbolic expression of the expectation value.
+/// * `rounding_accuracy` - The number of decimals of the numbers of the expression.
+///
+/// # Returns
+///
+/// * `String` - The typst math representation of the expression.
+fn format_symbolic_exp_val(expression: &CalculatorFloat, rounding_accuracy: usize) -> String {
+    let formula = format_calculator(expression, rounding_accuracy);
+    PAULI_PRODUCTS.replace_all(&formula, "P_($1)").into()
+}
+
+/// Lists the formulas of the expectation values, sorted by name.
+///
+/// # Arguments
+///
+/// * `measured_exp_vals` - The expectation values of the measurement input.
+/// * `rounding_accuracy` - The number of decimals of the numbers of the formulas.
+///
+/// # Returns
+///
+/// * `Vec<String>` - One line of typst markup per expectation value.
+fn exp_val_lines(
+    measured_exp_vals: &HashMap<String, PauliProductsToExpVal>,
+    rounding_accuracy: usize,
+) -> Vec<String> {
+    let mut exp_vals: Vec<(&String, &PauliProductsToExpVal)> = measured_exp_vals.iter().collect();
+    exp_vals.sort_by_key(|(name, _)| *name);
+    exp_vals
+        .into_iter()
+        .map(|(name, exp_val)| {
+            let formula = match exp_val {
+                PauliProductsToExpVal::Linear(coefficients) => {
+                    format_linear_exp_val(coefficients, rounding_accuracy)
+                }
+                PauliProductsToExpVal::Symbolic(expression) => {
+                    format_symbolic_exp_val(expression, rounding_accuracy)
+                }
+            };
+            format!(
+                "$\"{}\" = {formula}$",
+                name.replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        })
+        .collect()
+}
+
+/// Assembles the panel of a measurement input from its product table and its formulas.
+///
+/// # Arguments
+///
+/// * `rows` - The register, the product index and the Pauli product of each table row.
+/// * `notes` - Lines of typst markup written between the table and the formulas.
+/// * `formulas` - The lines of the expectation value formulas.
+fn input_panel(rows: &[(String, usize, String)], notes: &[String], formulas: &[String]) -> String {
+    let mut panel = format!("#block({PANEL_STYLE})[\n*Measurement input*\n");
+    panel.push_str(
+        "#table(columns: 3, stroke: 0.5pt + gray, inset: 4pt,\n[*Register*], [*Product*], [*Pauli product*],\n",
+    );
+    for (register, index, product) in rows {
+        panel.push_str(&format!(
+            "[{}], [$P_({index})$], [${product}$],\n",
+            typst_raw(register)
+        ));
+    }
+    panel.push_str(")\n");
+    for note in notes {
+        panel.push_str(&format!("{note} \\\n"));
+    }
+    panel.push_str("*Expectation values* \\\n");
+    if formulas.is_empty() {
+        panel.push_str("none\n");
+    } else {
+        panel.push_str(&formulas.join(" \\\n"));
+        panel.push('\n');
+    }
+    panel.push_str("]\n");
+    panel
+}
+
+/// Draws the product table and the expectation value formulas of a PauliZProduct measurement
+/// input as a panel.
+///
+/// # Arguments
+///
+/// * `input` - The input of the PauliZProduct measurement.
+/// * `options` - The options of the representation, the formulas follow `rounding_accuracy`.
+///
+/// # Returns
+///
+/// * `String` - The typst markup of the panel.
+pub(crate) fn pauli_z_product_input_panel(
+    input: &PauliZProductInput,
+    options: &RenderOptions,
+) -> String {
+    let mut rows: Vec<(String, usize, String)> = input
+        .pauli_product_qubit_masks
+        .iter()
+        .flat_map(|(register, masks)| {
+            masks.iter().map(move |(index, qubits)| {
+                let paulis: Vec<(usize, &str)> = qubits.iter().map(|&qubit| (qubit, "Z")).collect();
+                (register.clone(), *index, format_pauli_product(&paulis))
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+    let notes = if input.use_flipped_measurement {
+        vec!["Flipped measurement: also measured with all qubits flipped".to_owned()]
+    } else {
+        Vec::new()
+    };
+    input_panel(
+        &rows,
+        &notes,
+        &exp_val_lines(&input.measured_exp_vals, options.rounding_accuracy),
+    )
+}
+
+/// Draws the product table and the expectation value formulas of a CheatedPauliZProduct
+/// measurement input as a panel.
+///
+/// The Pauli product read into each register is taken from the PragmaGetPauliProduct operations
+/// of the circuits, a `?` is written when no such operation is found.
+///
+/// # Arguments
+///
+/// * `input` - The input of the CheatedPauliZProduct measurement.
+/// * `circuits` - The circuits of the measurement.
+/// * `options` - The options of the representation, the formulas follow `rounding_accuracy`.
+///
+/// # Returns
+///
+/// * `String` - The typst markup of the panel.
+pub(crate) fn cheated_pauli_z_product_input_panel(
+    input: &CheatedPauliZProductInput,
+    circuits: &[&Circuit],
+    options: &RenderOptions,
+) -> String {
+    let mut rows: Vec<(String, usize, String)> = input
+        .pauli_product_keys
+        .iter()
+        .map(|(register, &index)| {
+            let product = circuits
+                .iter()
+                .flat_map(|circuit| circuit.iter())
+                .find_map(|operation| match operation {
+                    Operation::PragmaGetPauliProduct(op) if op.readout() == register => {
+                        let mut paulis: Vec<(usize, &str)> = op
+                            .qubit_paulis()
+                            .iter()
+                            .filter_map(|(&qubit, pauli)| match pauli {
+                                1 => Some((qubit, "X")),
+                                2 => Some((qubit, "Y")),
+                                3 => Some((qubit, "Z")),
+                                _ => None,
+                            })
+                            .collect();
+                        paulis.sort();
+                        Some(format_pauli_product(&paulis))
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| "?".to_owned());
+            (register.clone(), index, product)
+        })
+        .collect();
+    rows.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+    input_panel(
+        &rows,
+        &[],
+        &exp_val_lines(&input.measured_exp_vals, options.rounding_accuracy),
+    )
+}
+
+/// Converts the input of a PauliZProduct measurement to a typst string.
+///
+/// The Pauli products measured in each register are drawn as a table, followed by the formula
+/// combining them into each expectation value.
+///
+/// # Arguments
+///
+/// * `input` - The input of the PauliZProduct measurement.
+/// * `options` - The options of the representation.
+///
+/// # Returns
+///
+/// * `String` - The typst string of the measurement input.
+pub fn pauli_z_product_input_into_typst_str(
+    input: &PauliZProductInput,
+    options: &RenderOptions,
+) -> String {
+    format!(
+        "{}{}",
+        typst_header(options),
+        pauli_z_product_input_panel(input, options)
+    )
+}
+
+/// Converts the input of a CheatedPauliZProduct measurement to a typst string.
+///
+/// # Arguments
+///
+/// * `input` - The input of the CheatedPauliZProduct measurement.
+/// * `circuits` - The circuits of the measurement, giving the Pauli product read into each register.
+/// * `options` - The options of the representation.
+///
+/// # Returns
+///
+/// * `String` - The typst string of the measurement input.
+pub fn cheated_pauli_z_product_input_into_typst_str(
+    input: &CheatedPauliZProductInput,
+    circuits: &[Circuit],
+    options: &RenderOptions,
+) -> String {
+    let circuits: Vec<&Circuit> = circuits.iter().collect();
+    format!(
+        "{}{}",
+        typst_header(options),
+        cheated_pauli_z_product_input_panel(input, &circuits, options)
+    )
+}
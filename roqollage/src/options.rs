@@ -0,0 +1,288 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Background, GateRendererRegistry, InitializationMode, LayeringStrategy, QubitOrder,
+    RenderPragmas, Theme, TypstBackendConfig, WireLabels,
+};
+
+/// Options of the circuit rendering, shared by all the entry points of roqollage.
+///
+/// The options can be (de)serialized, missing fields take their default value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// The pixel per point ratio of the png images.
+    pub pixels_per_point: f32,
+    /// Whether to render Pragma Operations or not.
+    pub render_pragmas: RenderPragmas,
+    /// What to display at the beginning of the circuit.
+    pub initialization_mode: InitializationMode,
+    /// The maximum length of a circuit line before splitting it into multiple lines.
+    pub max_length: Option<usize>,
+    /// The number of digits to round to when displaying floats.
+    pub rounding_accuracy: usize,
+    /// The configuration of the TypstBackend.
+    pub backend_config: TypstBackendConfig,
+    /// Whether to draw the unsupported operations as a generic box labelled with their name
+    /// instead of returning an error.
+    pub fallback_unsupported: bool,
+    /// The colours of the gates, per gate family.
+    pub theme: Theme,
+    /// The background of the page.
+    pub background: Background,
+    /// The margin around the circuit, in points.
+    pub page_margin: f32,
+    /// The custom names and initial states of the wires.
+    pub wire_labels: WireLabels,
+    /// The order in which the qubit wires are drawn.
+    pub qubit_order: QubitOrder,
+    /// The qubits to draw, all the qubits up to the highest one used when not set.
+    pub qubit_subset: Option<Vec<usize>>,
+    /// Whether to hide the wires of the qubits without any operation.
+    pub hide_idle_qubits: bool,
+    /// How the operations are assigned to the columns of the diagram.
+    pub layering: LayeringStrategy,
+    /// Whether to number the columns above the diagram.
+    pub column_numbers: bool,
+    /// Whether to add a footer with the depth of the diagram and the gate count of each qubit.
+    pub depth_footer: bool,
+    /// The custom renderers of the operations, not serialized.
+    #[serde(skip)]
+    pub gate_renderers: GateRendererRegistry,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            pixels_per_point: 3.0,
+            render_pragmas: RenderPragmas::All,
+            initialization_mode: InitializationMode::State,
+            max_length: None,
+            rounding_accuracy: 3,
+            backend_config: TypstBackendConfig::default(),
+            fallback_unsupported: false,
+            theme: Theme::default(),
+            background: Background::default(),
+            page_margin: 5.0,
+            wire_labels: WireLabels::default(),
+            qubit_order: QubitOrder::default(),
+            qubit_subset: None,
+            hide_idle_qubits: false,
+            layering: LayeringStrategy::default(),
+            column_numbers: false,
+            depth_footer: false,
+            gate_renderers: GateRendererRegistry::default(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Creates new RenderOptions with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the pixel per point ratio of the png images.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels_per_point` - The pixel per point ratio.
+    pub fn pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+        self.pixels_per_point = pixels_per_point;
+        self
+    }
+
+    /// Sets whether to render Pragma Operations or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `render_pragmas` - The Pragma Operations to render.
+    pub fn render_pragmas(mut self, render_pragmas: RenderPragmas) -> Self {
+        self.render_pragmas = render_pragmas;
+        self
+    }
+
+    /// Sets what to display at the beginning of the circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `initialization_mode` - The initialization mode of the circuit representation.
+    pub fn initialization_mode(mut self, initialization_mode: InitializationMode) -> Self {
+        self.initialization_mode = initialization_mode;
+        self
+    }
+
+    /// Sets the maximum length of a circuit line.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length` - The maximum length of a circuit line, `None` to never split the lines.
+    pub fn max_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Sets the number of digits to round to when displaying floats.
+    ///
+    /// # Arguments
+    ///
+    /// * `rounding_accuracy` - The number of digits.
+    pub fn rounding_accuracy(mut self, rounding_accuracy: usize) -> Self {
+        self.rounding_accuracy = rounding_accuracy;
+        self
+    }
+
+    /// Sets the configuration of the TypstBackend.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend_config` - The cache directory and network policy of the backend.
+    pub fn backend_config(mut self, backend_config: TypstBackendConfig) -> Self {
+        self.backend_config = backend_config;
+        self
+    }
+
+    /// Sets whether to draw the unsupported operations as a generic box labelled with their name.
+    ///
+    /// The operations drawn this way are listed in the warnings of the layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `fallback_unsupported` - Whether to draw the unsupported operations instead of returning an error.
+    pub fn fallback_unsupported(mut self, fallback_unsupported: bool) -> Self {
+        self.fallback_unsupported = fallback_unsupported;
+        self
+    }
+
+    /// Sets the colours of the gates.
+    ///
+    /// # Arguments
+    ///
+    /// * `theme` - The theme, e.g. [Theme::light] or a theme with custom styles per gate family.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the background of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `background` - The background, the text and the wires are drawn in white on dark fills.
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Sets the margin around the circuit.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_margin` - The margin, in points.
+    pub fn page_margin(mut self, page_margin: f32) -> Self {
+        self.page_margin = page_margin;
+        self
+    }
+
+    /// Sets the custom names and initial states of the wires.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire_labels` - The names of the qubits, bosonic modes and registers and the initial states.
+    pub fn wire_labels(mut self, wire_labels: WireLabels) -> Self {
+        self.wire_labels = wire_labels;
+        self
+    }
+
+    /// Sets the order in which the qubit wires are drawn.
+    ///
+    /// The controls, swaps and multi-qubit boxes follow the wires of their qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit_order` - The order, e.g. [QubitOrder::Reversed] to draw the highest qubit at the top.
+    pub fn qubit_order(mut self, qubit_order: QubitOrder) -> Self {
+        self.qubit_order = qubit_order;
+        self
+    }
+
+    /// Sets the qubits to draw.
+    ///
+    /// The operations acting only on other qubits are not drawn, the lines of the operations
+    /// acting on drawn and other qubits are cut at the boundary of the drawn wires.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit_subset` - The qubits to draw, `None` to draw all the qubits.
+    pub fn qubit_subset(mut self, qubit_subset: Option<Vec<usize>>) -> Self {
+        self.qubit_subset = qubit_subset;
+        self
+    }
+
+    /// Sets whether to hide the wires of the qubits without any operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `hide_idle_qubits` - Whether to hide the idle qubits, e.g. to draw a circuit on qubits 0
+    ///   and 40 with two wires.
+    pub fn hide_idle_qubits(mut self, hide_idle_qubits: bool) -> Self {
+        self.hide_idle_qubits = hide_idle_qubits;
+        self
+    }
+
+    /// Sets how the operations are assigned to the columns of the diagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `layering` - The layering strategy, e.g. [LayeringStrategy::Asap] to pack the operations
+    ///   in moments.
+    pub fn layering(mut self, layering: LayeringStrategy) -> Self {
+        self.layering = layering;
+        self
+    }
+
+    /// Sets whether to number the columns above the diagram.
+    ///
+    /// The numbers are the columns of the operations in
+    /// [CircuitLayout::operation_columns](crate::CircuitLayout::operation_columns).
+    ///
+    /// # Arguments
+    ///
+    /// * `column_numbers` - Whether to draw the column numbers.
+    pub fn column_numbers(mut self, column_numbers: bool) -> Self {
+        self.column_numbers = column_numbers;
+        self
+    }
+
+    /// Sets whether to add a footer with the depth of the diagram and the gate count of each qubit.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth_footer` - Whether to draw the footer.
+    pub fn depth_footer(mut self, depth_footer: bool) -> Self {
+        self.depth_footer = depth_footer;
+        self
+    }
+
+    /// Sets the custom renderers of the operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate_renderers` - The renderers, keyed by the hqslang name of the operations.
+    pub fn gate_renderers(mut self, gate_renderers: GateRendererRegistry) -> Self {
+        self.gate_renderers = gate_renderers;
+        self
+    }
+}
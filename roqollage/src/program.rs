@@ -0,0 +1,286 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::DynamicImage;
+use roqoqo::{
+    measurements::{Measure, PauliZProductInput},
+    operations::{Define, Operation},
+    Circuit, QuantumProgram, RoqoqoBackendError,
+};
+
+use crate::{
+    cheated_pauli_z_product_input_panel, circuit_into_typst_block, pauli_z_product_input_panel,
+    render_typst_str, typst_header, RenderOptions,
+};
+
+/// Typst settings of the panels drawing the circuits of a QuantumProgram.
+pub(crate) const PANEL_STYLE: &str = "stroke: 0.5pt + gray, inset: 6pt, radius: 3pt";
+
+/// Formats a text as raw typst text, e.g. the name of a parameter or of a register.
+///
+/// # Arguments
+///
+/// * `text` - The text to format.
+pub(crate) fn typst_raw(text: &str) -> String {
+    format!(
+        "#raw(\"{}\")",
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Describes the Pauli products measured by a circuit of a PauliZProduct measurement.
+///
+/// # Arguments
+///
+/// * `circuit` - The measurement circuit.
+/// * `input` - The input of the PauliZProduct measurement.
+///
+/// # Returns
+///
+/// * `Vec<String>` - One line of typst markup per readout register of the circuit.
+fn measured_products(circuit: &Circuit, input: &PauliZProductInput) -> Vec<String> {
+    let mut registers: Vec<&String> = circuit
+        .definitions()
+        .iter()
+        .filter_map(|definition| match definition {
+            Operation::DefinitionBit(definition) => Some(definition.name()),
+            _ => None,
+        })
+        .filter(|name| input.pauli_product_qubit_masks.contains_key(*name))
+        .collect();
+    registers.sort();
+    registers.dedup();
+    registers
+        .into_iter()
+        .map(|register| {
+            let mut products: Vec<(&usize, &Vec<usize>)> =
+                input.pauli_product_qubit_masks[register].iter().collect();
+            products.sort();
+            let products: Vec<String> = products
+                .into_iter()
+                .map(|(index, qubits)| {
+                    let product = if qubits.is_empty() {
+                        "I".to_owned()
+                    } else {
+                        qubits
+                            .iter()
+                            .map(|qubit| format!("Z_({qubit})"))
+                            .collect::<Vec<String>>()
+                            .join(" ")
+                    };
+                    format!("$P_({index}) = {product}$")
+                })
+                .collect();
+            format!(
+                "Measured products in {}: {}",
+                typst_raw(register),
+                products.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// Draws a circuit as a labelled panel.
+///
+/// # Arguments
+///
+/// * `title` - The title of the panel.
+/// * `lines` - The lines of typst markup written between the title and the circuit.
+/// * `circuit` - The circuit to draw.
+/// * `options` - The options of the circuit representation.
+/// * `warnings` - The warnings emitted while drawing the circuits.
+fn circuit_panel(
+    title: &str,
+    lines: &[String],
+    circuit: &Circuit,
+    options: &RenderOptions,
+    warnings: &mut Vec<String>,
+) -> Result<String, RoqoqoBackendError> {
+    let (block, circuit_warnings) = circuit_into_typst_block(circuit, options)?;
+    warnings.extend(
+        circuit_warnings
+            .into_iter()
+            .map(|warning| format!("{title}: {warning}")),
+    );
+    let mut panel = format!("#block({PANEL_STYLE})[\n*{title}* \\\n");
+    for line in lines {
+        panel.push_str(&format!("{line} \\\n"));
+    }
+    panel.push_str(&block);
+    panel.push_str("]\n");
+    Ok(panel)
+}
+
+/// Converts a QuantumProgram to a typst string, with the warnings emitted during the conversion.
+///
+/// The constant circuit and each measurement circuit are drawn as labelled panels below a header
+/// naming the measurement and the input parameters of the program. The panels of a
+/// PauliZProduct measurement list the Pauli products measured by their circuit. For PauliZProduct
+/// and CheatedPauliZProduct measurements, the table of the Pauli products and the formulas of the
+/// expectation values are drawn next to the circuits.
+///
+/// # Arguments
+///
+/// * `program` - The QuantumProgram to convert.
+/// * `options` - The options of the circuit representation.
+///
+/// # Returns
+///
+/// * `Ok((String, Vec<String>))` - The typst string of the QuantumProgram and the warnings.
+/// * `Err(RoqoqoBackendError)` - A circuit of the QuantumProgram cannot be converted.
+pub fn quantum_program_into_typst_str_with_warnings(
+    program: &QuantumProgram,
+    options: &RenderOptions,
+) -> Result<(String, Vec<String>), RoqoqoBackendError> {
+    // The Measure trait is not object safe, the circuits are collected in each arm.
+    let (measurement_name, parameters, constant_circuit, circuits): (
+        &str,
+        &Vec<String>,
+        &Option<Circuit>,
+        Vec<&Circuit>,
+    ) = match program {
+        QuantumProgram::PauliZProduct {
+            measurement,
+            input_parameter_names,
+        } => (
+            "PauliZProduct",
+            input_parameter_names,
+            measurement.constant_circuit(),
+            measurement.circuits().collect(),
+        ),
+        QuantumProgram::CheatedPauliZProduct {
+            measurement,
+            input_parameter_names,
+        } => (
+            "CheatedPauliZProduct",
+            input_parameter_names,
+            measurement.constant_circuit(),
+            measurement.circuits().collect(),
+        ),
+        QuantumProgram::Cheated {
+            measurement,
+            input_parameter_names,
+        } => (
+            "Cheated",
+            input_parameter_names,
+            measurement.constant_circuit(),
+            measurement.circuits().collect(),
+        ),
+        QuantumProgram::ClassicalRegister {
+            measurement,
+            input_parameter_names,
+        } => (
+            "ClassicalRegister",
+            input_parameter_names,
+            measurement.constant_circuit(),
+            measurement.circuits().collect(),
+        ),
+        _ => {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: "QuantumProgram variant is not supported by qollage.".to_owned(),
+            })
+        }
+    };
+    let pauli_z_product_input = match program {
+        QuantumProgram::PauliZProduct { measurement, .. } => Some(&measurement.input),
+        _ => None,
+    };
+    let input_panel = match program {
+        QuantumProgram::PauliZProduct { measurement, .. } => {
+            Some(pauli_z_product_input_panel(&measurement.input, options))
+        }
+        QuantumProgram::CheatedPauliZProduct { measurement, .. } => Some(
+            cheated_pauli_z_product_input_panel(&measurement.input, &circuits, options),
+        ),
+        _ => None,
+    };
+
+    let mut typst_str = typst_header(options);
+    typst_str.push_str(&format!(
+        "*QuantumProgram* with a {measurement_name} measurement \\\nInput parameters: {}\n#v(4pt)\n",
+        if parameters.is_empty() {
+            "none".to_owned()
+        } else {
+            parameters
+                .iter()
+                .map(|parameter| typst_raw(parameter))
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+    ));
+    let mut warnings = Vec::new();
+    let mut panels = String::new();
+    if let Some(constant_circuit) = constant_circuit {
+        panels.push_str(&circuit_panel(
+            "Constant circuit",
+            &[],
+            constant_circuit,
+            options,
+            &mut warnings,
+        )?);
+    }
+    for (index, circuit) in circuits.into_iter().enumerate() {
+        let lines = pauli_z_product_input
+            .map(|input| measured_products(circuit, input))
+            .unwrap_or_default();
+        panels.push_str(&circuit_panel(
+            &format!("Circuit {index}"),
+            &lines,
+            circuit,
+            options,
+            &mut warnings,
+        )?);
+    }
+    match input_panel {
+        Some(input_panel) => typst_str.push_str(&format!(
+            "#grid(columns: 2, column-gutter: 8pt, [\n{panels}], [\n{input_panel}])\n"
+        )),
+        None => typst_str.push_str(&panels),
+    }
+    Ok((typst_str, warnings))
+}
+
+/// Converts a QuantumProgram to a typst string.
+///
+/// # Arguments
+///
+/// * `program` - The QuantumProgram to convert.
+/// * `options` - The options of the circuit representation.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The typst string of the QuantumProgram.
+/// * `Err(RoqoqoBackendError)` - A circuit of the QuantumProgram cannot be converted.
+pub fn quantum_program_into_typst_str(
+    program: &QuantumProgram,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    quantum_program_into_typst_str_with_warnings(program, options).map(|(typst_str, _)| typst_str)
+}
+
+/// Converts a QuantumProgram to an image of its circuits.
+///
+/// # Arguments
+///
+/// * `program` - The QuantumProgram to convert.
+/// * `options` - The options of the circuit representation and of the rendering.
+///
+/// # Returns
+///
+/// * `Ok(DynamicImage)` - The image representing the QuantumProgram.
+/// * `Err(RoqoqoBackendError)` - The QuantumProgram cannot be converted or compiled.
+pub fn quantum_program_to_image(
+    program: &QuantumProgram,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    render_typst_str(quantum_program_into_typst_str(program, options)?, options)
+}
@@ -0,0 +1,811 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use image::DynamicImage;
+use qoqo_calculator::{Calculator, CalculatorFloat};
+use regex::Regex;
+use roqoqo::{operations::*, Circuit, RoqoqoBackendError};
+
+use crate::{circuit_to_image, RenderOptions};
+
+/// The maximum nesting depth of the gate definitions, to stop recursive definitions.
+const MAX_GATE_DEPTH: usize = 64;
+
+/// The mathematical constants of the parameter expressions.
+static CONSTANTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(pi|tau|euler)\b").unwrap());
+
+/// The identifiers of the parameter expressions, replaced by their values in the gate bodies.
+static IDENTIFIERS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").unwrap());
+
+/// A gate defined in the QASM input, expanded at each call.
+#[derive(Debug, Clone)]
+struct GateDefinition {
+    /// The names of the parameters of the gate.
+    parameters: Vec<String>,
+    /// The names of the qubit arguments of the gate.
+    qubits: Vec<String>,
+    /// The statements of the body of the gate.
+    body: Vec<String>,
+}
+
+/// Converter of an OpenQASM program to a roqoqo circuit.
+#[derive(Debug)]
+struct QasmConverter {
+    /// The first qubit and the size of each qubit register.
+    qubit_registers: HashMap<String, (usize, usize)>,
+    /// The number of qubits declared so far.
+    n_qubits: usize,
+    /// The size of each bit register.
+    bit_registers: HashMap<String, usize>,
+    /// The gates defined in the program.
+    gates: HashMap<String, GateDefinition>,
+    /// The converted circuit.
+    circuit: Circuit,
+}
+
+/// Creates the error returned for an invalid or unsupported QASM input.
+///
+/// # Arguments
+///
+/// * `msg` - The description of the error.
+fn qasm_error(msg: impl Into<String>) -> RoqoqoBackendError {
+    RoqoqoBackendError::GenericError { msg: msg.into() }
+}
+
+/// Removes the line and block comments of a QASM program.
+///
+/// # Arguments
+///
+/// * `qasm` - The QASM program.
+fn strip_comments(qasm: &str) -> String {
+    let mut output = String::with_capacity(qasm.len());
+    let mut rest = qasm;
+    while !rest.is_empty() {
+        match (rest.find("//"), rest.find("/*")) {
+            (Some(line), block) if !matches!(block, Some(block) if block < line) => {
+                output.push_str(&rest[..line]);
+                rest = rest[line..]
+                    .find('\n')
+                    .map_or("", |end| &rest[line + end..]);
+            }
+            (_, Some(block)) => {
+                output.push_str(&rest[..block]);
+                output.push(' ');
+                rest = rest[block + 2..]
+                    .find("*/")
+                    .map_or("", |end| &rest[block + 2 + end + 2..]);
+            }
+            _ => {
+                output.push_str(rest);
+                rest = "";
+            }
+        }
+    }
+    output
+}
+
+/// Splits a QASM program into statements, a gate definition being a single statement.
+///
+/// # Arguments
+///
+/// * `qasm` - The QASM program without comments.
+fn split_statements(qasm: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0_usize;
+    for character in qasm.chars() {
+        match character {
+            ';' if depth == 0 => {
+                statements.push(std::mem::take(&mut current));
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    current.push(character);
+                    statements.push(std::mem::take(&mut current));
+                    continue;
+                }
+            }
+            _ => (),
+        }
+        current.push(character);
+    }
+    statements.push(current);
+    statements
+        .into_iter()
+        .map(|statement| {
+            statement
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+/// Splits a list on the commas that are not inside parentheses.
+///
+/// # Arguments
+///
+/// * `list` - The comma-separated list.
+fn split_list(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0_usize;
+    for character in list.chars() {
+        match character {
+            ',' if depth == 0 => {
+                items.push(std::mem::take(&mut current));
+                continue;
+            }
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        current.push(character);
+    }
+    items.push(current);
+    items
+        .into_iter()
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Splits a gate call or definition into its name, its parameters and its arguments.
+///
+/// # Arguments
+///
+/// * `statement` - The statement, e.g. `rx(pi/2) q[0]`.
+fn split_call(statement: &str) -> Result<(String, Vec<String>, Vec<String>), RoqoqoBackendError> {
+    let name_end = statement
+        .find(|c: char| c == '(' || c.is_whitespace())
+        .unwrap_or(statement.len());
+    let name = statement[..name_end].to_owned();
+    let mut rest = statement[name_end..].trim_start();
+    let mut parameters = Vec::new();
+    if rest.starts_with('(') {
+        let mut depth = 0_usize;
+        let close = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => (),
+                }
+                depth == 0
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| qasm_error(format!("Unbalanced parentheses in `{statement}`.")))?;
+        parameters = split_list(&rest[1..close]);
+        rest = &rest[close + 1..];
+    }
+    Ok((name, parameters, split_list(rest)))
+}
+
+/// Converts a QASM parameter expression to a CalculatorFloat.
+///
+/// The expressions without free symbols are evaluated, the others are kept symbolic.
+///
+/// # Arguments
+///
+/// * `expression` - The parameter expression, e.g. `-pi/4`.
+fn parse_parameter(expression: &str) -> CalculatorFloat {
+    let expression = expression.trim().replace('π', "pi").replace('τ', "tau");
+    let numeric = CONSTANTS.replace_all(&expression, |caps: &regex::Captures| match &caps[0] {
+        "pi" => format!("({})", std::f64::consts::PI),
+        "tau" => format!("({})", std::f64::consts::TAU),
+        _ => format!("({})", std::f64::consts::E),
+    });
+    match Calculator::new().parse_str(&numeric) {
+        Ok(value) => CalculatorFloat::Float(value),
+        Err(_) => CalculatorFloat::Str(expression),
+    }
+}
+
+impl QasmConverter {
+    /// Creates a new converter without registers nor gate definitions.
+    fn new() -> Self {
+        Self {
+            qubit_registers: HashMap::new(),
+            n_qubits: 0,
+            bit_registers: HashMap::new(),
+            gates: HashMap::new(),
+            circuit: Circuit::new(),
+        }
+    }
+
+    /// Handles a statement of the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement.
+    fn statement(&mut self, statement: &str) -> Result<(), RoqoqoBackendError> {
+        let keyword = statement
+            .split(|c: char| c.is_whitespace() || c == '[' || c == '(')
+            .next()
+            .unwrap_or_default();
+        match keyword {
+            "OPENQASM" | "include" | "opaque" => Ok(()),
+            "qreg" | "creg" => {
+                let (name, size) = statement[keyword.len()..]
+                    .trim()
+                    .trim_end_matches(']')
+                    .split_once('[')
+                    .ok_or_else(|| qasm_error(format!("Invalid register `{statement}`.")))?;
+                let size = size
+                    .trim()
+                    .parse()
+                    .map_err(|_| qasm_error(format!("Invalid register size in `{statement}`.")))?;
+                self.declare(keyword == "qreg", name.trim(), size)
+            }
+            "qubit" | "bit" => {
+                let rest = statement[keyword.len()..].trim();
+                let (size, name) = match rest.strip_prefix('[') {
+                    Some(rest) => {
+                        let (size, name) = rest.split_once(']').ok_or_else(|| {
+                            qasm_error(format!("Invalid register `{statement}`."))
+                        })?;
+                        let size = size.trim().parse().map_err(|_| {
+                            qasm_error(format!("Invalid register size in `{statement}`."))
+                        })?;
+                        (size, name.trim())
+                    }
+                    None => (1, rest),
+                };
+                // QASM 3 allows initializing the bits with a measurement, e.g. `bit[2] c = measure q`.
+                match name.split_once('=') {
+                    Some((name, value)) => {
+                        self.declare(keyword == "qubit", name.trim(), size)?;
+                        self.assign(name.trim(), value)
+                    }
+                    None => self.declare(keyword == "qubit", name, size),
+                }
+            }
+            "gate" => self.define_gate(statement),
+            "barrier" => {
+                let qubits: Vec<usize> = split_list(&statement[keyword.len()..])
+                    .iter()
+                    .map(|argument| self.qubits(argument))
+                    .collect::<Result<Vec<Vec<usize>>, RoqoqoBackendError>>()?
+                    .concat();
+                let qubits = if qubits.is_empty() {
+                    (0..self.n_qubits).collect()
+                } else {
+                    qubits
+                };
+                self.circuit
+                    .add_operation(PragmaStopParallelBlock::new(qubits, CalculatorFloat::ZERO));
+                Ok(())
+            }
+            "reset" => {
+                for qubit in self.qubits(statement[keyword.len()..].trim())? {
+                    self.circuit.add_operation(PragmaActiveReset::new(qubit));
+                }
+                Ok(())
+            }
+            "measure" => {
+                let (qubits, bits) =
+                    statement[keyword.len()..].split_once("->").ok_or_else(|| {
+                        qasm_error(format!("Measurement without target bits `{statement}`."))
+                    })?;
+                self.measure(qubits.trim(), bits.trim())
+            }
+            "if" => self.conditional(statement),
+            "for" | "while" | "def" | "defcal" | "cal" | "box" | "let" | "const" | "input"
+            | "output" | "float" | "int" | "uint" | "angle" | "bool" | "duration" | "stretch"
+            | "delay" | "return" | "switch" => Err(qasm_error(format!(
+                "The QASM statement `{keyword}` is not supported."
+            ))),
+            _ if statement.contains('@') => Err(qasm_error(format!(
+                "Gate modifiers are not supported: `{statement}`."
+            ))),
+            _ if statement.contains('=') => {
+                let (bits, value) = statement.split_once('=').unwrap_or_default();
+                self.assign(bits.trim(), value)
+            }
+            _ => {
+                let (name, parameters, arguments) = split_call(statement)?;
+                let parameters: Vec<CalculatorFloat> =
+                    parameters.iter().map(|p| parse_parameter(p)).collect();
+                let arguments: Vec<Vec<usize>> = arguments
+                    .iter()
+                    .map(|argument| self.qubits(argument))
+                    .collect::<Result<_, _>>()?;
+                // Gates applied to whole registers are applied to each of their qubits.
+                let n_calls = arguments.iter().map(Vec::len).max().unwrap_or(1);
+                if arguments
+                    .iter()
+                    .any(|qubits| qubits.len() != 1 && qubits.len() != n_calls)
+                {
+                    return Err(qasm_error(format!(
+                        "The registers of `{statement}` have different sizes."
+                    )));
+                }
+                for call in 0..n_calls {
+                    let qubits: Vec<usize> = arguments
+                        .iter()
+                        .map(|qubits| qubits[if qubits.len() == 1 { 0 } else { call }])
+                        .collect();
+                    self.apply_gate(&name, &parameters, &qubits, 0)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Declares a qubit or bit register.
+    ///
+    /// # Arguments
+    ///
+    /// * `quantum` - Whether the register is a qubit register.
+    /// * `name` - The name of the register.
+    /// * `size` - The size of the register.
+    fn declare(
+        &mut self,
+        quantum: bool,
+        name: &str,
+        size: usize,
+    ) -> Result<(), RoqoqoBackendError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(qasm_error(format!("Invalid register name `{name}`.")));
+        }
+        if quantum {
+            self.qubit_registers
+                .insert(name.to_owned(), (self.n_qubits, size));
+            self.n_qubits += size;
+        } else {
+            self.bit_registers.insert(name.to_owned(), size);
+            self.circuit
+                .add_operation(DefinitionBit::new(name.to_owned(), size, true));
+        }
+        Ok(())
+    }
+
+    /// Resolves an argument to the qubits it refers to.
+    ///
+    /// # Arguments
+    ///
+    /// * `argument` - The argument, a register or an element of a register.
+    fn qubits(&self, argument: &str) -> Result<Vec<usize>, RoqoqoBackendError> {
+        let (name, index) = split_indexed(argument)?;
+        let &(start, size) = self
+            .qubit_registers
+            .get(name)
+            .ok_or_else(|| qasm_error(format!("Unknown qubit register `{name}`.")))?;
+        match index {
+            Some(index) if index < size => Ok(vec![start + index]),
+            Some(index) => Err(qasm_error(format!(
+                "Qubit {index} is out of the range of register `{name}`."
+            ))),
+            None => Ok((start..start + size).collect()),
+        }
+    }
+
+    /// Handles an assignment to bits, only measurements are supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The assigned bits.
+    /// * `value` - The assigned value, e.g. `measure q[0]`.
+    fn assign(&mut self, bits: &str, value: &str) -> Result<(), RoqoqoBackendError> {
+        match value.trim().strip_prefix("measure") {
+            Some(qubits) => self.measure(qubits.trim(), bits),
+            None => Err(qasm_error(format!(
+                "Only measurements can be assigned to bits: `{bits} = {value}`."
+            ))),
+        }
+    }
+
+    /// Adds the measurements of qubits into bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubits` - The measured qubits.
+    /// * `bits` - The bits storing the results.
+    fn measure(&mut self, qubits: &str, bits: &str) -> Result<(), RoqoqoBackendError> {
+        let qubits = self.qubits(qubits)?;
+        let (register, index) = split_indexed(bits)?;
+        let size = *self
+            .bit_registers
+            .get(register)
+            .ok_or_else(|| qasm_error(format!("Unknown bit register `{register}`.")))?;
+        let bits: Vec<usize> = match index {
+            Some(index) => vec![index],
+            None => (0..size).collect(),
+        };
+        if qubits.len() != bits.len() || bits.iter().any(|&bit| bit >= size) {
+            return Err(qasm_error(format!(
+                "The measurement of `{register}` does not match the size of the register."
+            )));
+        }
+        for (qubit, bit) in qubits.into_iter().zip(bits) {
+            self.circuit
+                .add_operation(MeasureQubit::new(qubit, register.to_owned(), bit));
+        }
+        Ok(())
+    }
+
+    /// Adds a statement executed when a classical bit is set, as a PragmaConditional.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The conditional statement, e.g. `if(c==1) x q[0]` or
+    ///   `if (c[1]) { x q[0]; }`.
+    fn conditional(&mut self, statement: &str) -> Result<(), RoqoqoBackendError> {
+        let rest = statement["if".len()..].trim_start();
+        let close = rest
+            .strip_prefix('(')
+            .and_then(|condition| condition.find(')'))
+            .ok_or_else(|| qasm_error(format!("Invalid condition in `{statement}`.")))?;
+        let (register, index) = self.condition(&rest[1..close + 1])?;
+        let body = rest[close + 2..].trim();
+        let statements = match body.strip_prefix('{') {
+            Some(block) => split_statements(block.trim_end_matches('}')),
+            None => split_statements(body),
+        };
+        if statements.is_empty() {
+            return Err(qasm_error(format!("Condition without body `{statement}`.")));
+        }
+        // The body is converted on its own circuit, the registers are shared.
+        let outer = std::mem::replace(&mut self.circuit, Circuit::new());
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.statement(statement));
+        let body = std::mem::replace(&mut self.circuit, outer);
+        result?;
+        self.circuit
+            .add_operation(PragmaConditional::new(register, index, body));
+        Ok(())
+    }
+
+    /// Resolves a condition to the bit it tests.
+    ///
+    /// PragmaConditional tests a single bit, the conditions are therefore limited to a bit being
+    /// set: `c[i]`, `c[i]==1` or `c==1` for a register `c` of a single bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - The condition, without the parentheses.
+    fn condition(&self, condition: &str) -> Result<(String, usize), RoqoqoBackendError> {
+        let (bits, value) = condition.split_once("==").unwrap_or((condition, "1"));
+        let (register, index) = split_indexed(bits)?;
+        let size = *self
+            .bit_registers
+            .get(register)
+            .ok_or_else(|| qasm_error(format!("Unknown bit register `{register}`.")))?;
+        let index = match index {
+            Some(index) if index < size => index,
+            Some(index) => {
+                return Err(qasm_error(format!(
+                    "Bit {index} is out of the range of register `{register}`."
+                )))
+            }
+            None if size == 1 => 0,
+            None => {
+                return Err(qasm_error(format!(
+                    "The condition `{condition}` tests a register of {size} bits, only conditions on a single bit can be converted to a PragmaConditional."
+                )))
+            }
+        };
+        match value.trim() {
+            "1" | "true" => Ok((register.to_owned(), index)),
+            _ => Err(qasm_error(format!(
+                "The condition `{condition}` does not test a bit being set, it cannot be converted to a PragmaConditional."
+            ))),
+        }
+    }
+
+    /// Stores a gate definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The gate definition, e.g. `gate g(theta) a, b { rx(theta) a; cx a, b; }`.
+    fn define_gate(&mut self, statement: &str) -> Result<(), RoqoqoBackendError> {
+        let (signature, body) = statement["gate".len()..]
+            .trim()
+            .split_once('{')
+            .ok_or_else(|| qasm_error(format!("Gate definition without body `{statement}`.")))?;
+        let (name, parameters, qubits) = split_call(signature.trim())?;
+        let body = body.trim().trim_end_matches('}');
+        self.gates.insert(
+            name,
+            GateDefinition {
+                parameters,
+                qubits,
+                body: split_statements(body),
+            },
+        );
+        Ok(())
+    }
+
+    /// Applies a gate, either a standard gate or a gate defined in the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the gate.
+    /// * `parameters` - The parameters of the gate.
+    /// * `qubits` - The qubits of the gate.
+    /// * `depth` - The nesting depth of the gate definitions.
+    fn apply_gate(
+        &mut self,
+        name: &str,
+        parameters: &[CalculatorFloat],
+        qubits: &[usize],
+        depth: usize,
+    ) -> Result<(), RoqoqoBackendError> {
+        if let Some(operations) = standard_gate(name, parameters, qubits)? {
+            for operation in operations {
+                self.circuit.add_operation(operation);
+            }
+            return Ok(());
+        }
+        let definition = self
+            .gates
+            .get(name)
+            .cloned()
+            .ok_or_else(|| qasm_error(format!("The QASM gate `{name}` is not supported.")))?;
+        if depth > MAX_GATE_DEPTH {
+            return Err(qasm_error(format!(
+                "The definition of the QASM gate `{name}` is recursive."
+            )));
+        }
+        if definition.parameters.len() != parameters.len()
+            || definition.qubits.len() != qubits.len()
+        {
+            return Err(qasm_error(format!(
+                "The QASM gate `{name}` is called with the wrong number of arguments."
+            )));
+        }
+        let values: HashMap<&str, String> = definition
+            .parameters
+            .iter()
+            .map(String::as_str)
+            .zip(parameters.iter().map(|parameter| match parameter {
+                CalculatorFloat::Float(value) => format!("({value})"),
+                CalculatorFloat::Str(value) => format!("({value})"),
+            }))
+            .collect();
+        for statement in definition.body.iter() {
+            if statement.starts_with("barrier") {
+                continue;
+            }
+            let (gate, gate_parameters, arguments) = split_call(statement)?;
+            let gate_parameters: Vec<CalculatorFloat> = gate_parameters
+                .iter()
+                .map(|parameter| {
+                    parse_parameter(&IDENTIFIERS.replace_all(
+                        parameter,
+                        |caps: &regex::Captures| {
+                            values
+                                .get(&caps[0])
+                                .cloned()
+                                .unwrap_or_else(|| caps[0].to_owned())
+                        },
+                    ))
+                })
+                .collect();
+            let gate_qubits: Vec<usize> = arguments
+                .iter()
+                .map(|argument| {
+                    definition
+                        .qubits
+                        .iter()
+                        .position(|qubit| qubit == argument)
+                        .map(|position| qubits[position])
+                        .ok_or_else(|| {
+                            qasm_error(format!("Unknown qubit `{argument}` in gate `{name}`."))
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+            self.apply_gate(&gate, &gate_parameters, &gate_qubits, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits an argument into its register name and its optional index.
+///
+/// # Arguments
+///
+/// * `argument` - The argument, e.g. `q[2]` or `q`.
+fn split_indexed(argument: &str) -> Result<(&str, Option<usize>), RoqoqoBackendError> {
+    match argument.trim().trim_end_matches(']').split_once('[') {
+        Some((name, index)) => Ok((
+            name.trim(),
+            Some(
+                index
+                    .trim()
+                    .parse()
+                    .map_err(|_| qasm_error(format!("Invalid index in `{argument}`.")))?,
+            ),
+        )),
+        None => Ok((argument.trim(), None)),
+    }
+}
+
+/// Converts a gate of the OpenQASM standard libraries (`qelib1.inc` and `stdgates.inc`) to
+/// roqoqo operations.
+///
+/// # Arguments
+///
+/// * `name` - The name of the gate.
+/// * `parameters` - The parameters of the gate.
+/// * `qubits` - The qubits of the gate.
+///
+/// # Returns
+///
+/// * `Ok(Some(Vec<Operation>))` - The operations of the gate.
+/// * `Ok(None)` - The gate is not a standard gate.
+/// * `Err(RoqoqoBackendError)` - The gate is called with the wrong number of arguments.
+fn standard_gate(
+    name: &str,
+    parameters: &[CalculatorFloat],
+    qubits: &[usize],
+) -> Result<Option<Vec<Operation>>, RoqoqoBackendError> {
+    let (n_parameters, n_qubits) = match name {
+        "id" | "x" | "y" | "z" | "h" | "s" | "sdg" | "t" | "tdg" | "sx" | "sxdg" => (0, 1),
+        "rx" | "ry" | "rz" | "p" | "phase" | "u1" => (1, 1),
+        "u2" => (2, 1),
+        "u" | "U" | "u3" => (3, 1),
+        "u0" => (1, 1),
+        "cx" | "CX" | "cy" | "cz" | "ch" | "csx" | "swap" | "ecr" | "iswap" => (0, 2),
+        "cp" | "cphase" | "cu1" | "crx" | "cry" | "crz" | "rxx" | "rzz" => (1, 2),
+        "cu3" => (3, 2),
+        "cu" => (4, 2),
+        "ccx" | "cswap" | "ccz" => (0, 3),
+        "gphase" => (1, 0),
+        _ => return Ok(None),
+    };
+    if parameters.len() != n_parameters || qubits.len() != n_qubits {
+        return Err(qasm_error(format!(
+            "The QASM gate `{name}` takes {n_parameters} parameters and {n_qubits} qubits."
+        )));
+    }
+    let parameter = |index: usize| parameters[index].clone();
+    // U(theta, phi, lambda) = Rz(phi) Ry(theta) Rz(lambda), up to a global phase.
+    let u3 = |theta: CalculatorFloat, phi: CalculatorFloat, lambda: CalculatorFloat| {
+        vec![
+            Operation::from(RotateZ::new(qubits[0], lambda)),
+            Operation::from(RotateY::new(qubits[0], theta)),
+            Operation::from(RotateZ::new(qubits[0], phi)),
+        ]
+    };
+    // The controlled gates without a roqoqo equivalent control a circuit on the target qubit.
+    let controlled = |operations: Vec<Operation>| {
+        let mut circuit = Circuit::new();
+        for operation in operations {
+            circuit.add_operation(operation);
+        }
+        Operation::from(PragmaControlledCircuit::new(qubits[0], circuit))
+    };
+    // cu(theta, phi, lambda, gamma) controls exp(i gamma) U(theta, phi, lambda), the global phase
+    // of U(theta, phi, lambda) = exp(i (phi + lambda) / 2) Rz(phi) Ry(theta) Rz(lambda) becomes a
+    // phase of the control qubit.
+    let cu = |theta: CalculatorFloat,
+              phi: CalculatorFloat,
+              lambda: CalculatorFloat,
+              gamma: CalculatorFloat| {
+        let phase = (phi.clone() + lambda.clone()) / 2.0 + gamma;
+        vec![
+            controlled(vec![
+                RotateZ::new(qubits[1], lambda).into(),
+                RotateY::new(qubits[1], theta).into(),
+                RotateZ::new(qubits[1], phi).into(),
+            ]),
+            PhaseShiftState1::new(qubits[0], phase).into(),
+        ]
+    };
+    Ok(Some(match name {
+        "id" | "u0" => vec![Identity::new(qubits[0]).into()],
+        "x" => vec![PauliX::new(qubits[0]).into()],
+        "y" => vec![PauliY::new(qubits[0]).into()],
+        "z" => vec![PauliZ::new(qubits[0]).into()],
+        "h" => vec![Hadamard::new(qubits[0]).into()],
+        "s" => vec![SGate::new(qubits[0]).into()],
+        "sdg" => vec![InvSGate::new(qubits[0]).into()],
+        "t" => vec![TGate::new(qubits[0]).into()],
+        "tdg" => vec![InvTGate::new(qubits[0]).into()],
+        "sx" => vec![SXGate::new(qubits[0]).into()],
+        "sxdg" => vec![InvSXGate::new(qubits[0]).into()],
+        "rx" => vec![RotateX::new(qubits[0], parameter(0)).into()],
+        "ry" => vec![RotateY::new(qubits[0], parameter(0)).into()],
+        "rz" => vec![RotateZ::new(qubits[0], parameter(0)).into()],
+        "p" | "phase" | "u1" => vec![PhaseShiftState1::new(qubits[0], parameter(0)).into()],
+        "u2" => u3(CalculatorFloat::FRAC_PI_2, parameter(0), parameter(1)),
+        "u" | "U" | "u3" => u3(parameter(0), parameter(1), parameter(2)),
+        "cx" | "CX" => vec![CNOT::new(qubits[0], qubits[1]).into()],
+        "cy" => vec![ControlledPauliY::new(qubits[0], qubits[1]).into()],
+        "cz" => vec![ControlledPauliZ::new(qubits[0], qubits[1]).into()],
+        "swap" => vec![SWAP::new(qubits[0], qubits[1]).into()],
+        "iswap" => vec![ISwap::new(qubits[0], qubits[1]).into()],
+        "ecr" => vec![EchoCrossResonance::new(qubits[0], qubits[1]).into()],
+        "cp" | "cphase" | "cu1" => {
+            vec![ControlledPhaseShift::new(qubits[0], qubits[1], parameter(0)).into()]
+        }
+        "crx" => vec![ControlledRotateX::new(qubits[0], qubits[1], parameter(0)).into()],
+        "cry" => vec![ControlledRotateXY::new(
+            qubits[0],
+            qubits[1],
+            parameter(0),
+            CalculatorFloat::FRAC_PI_2,
+        )
+        .into()],
+        "crz" => vec![controlled(vec![
+            RotateZ::new(qubits[1], parameter(0)).into()
+        ])],
+        "ch" => vec![controlled(vec![Hadamard::new(qubits[1]).into()])],
+        "csx" => vec![controlled(vec![SXGate::new(qubits[1]).into()])],
+        "cu3" => cu(
+            parameter(0),
+            parameter(1),
+            parameter(2),
+            CalculatorFloat::ZERO,
+        ),
+        "cu" => cu(parameter(0), parameter(1), parameter(2), parameter(3)),
+        "rxx" => vec![VariableMSXX::new(qubits[0], qubits[1], parameter(0)).into()],
+        "rzz" => vec![MultiQubitZZ::new(vec![qubits[0], qubits[1]], parameter(0)).into()],
+        "ccx" => vec![Toffoli::new(qubits[0], qubits[1], qubits[2]).into()],
+        "cswap" => vec![ControlledSWAP::new(qubits[0], qubits[1], qubits[2]).into()],
+        "ccz" => vec![ControlledControlledPauliZ::new(qubits[0], qubits[1], qubits[2]).into()],
+        _ => vec![PragmaGlobalPhase::new(parameter(0)).into()],
+    }))
+}
+
+/// Converts an OpenQASM 2 or 3 program to a roqoqo circuit.
+///
+/// The gates of the standard libraries (`qelib1.inc` and `stdgates.inc`) are mapped to the
+/// corresponding operations, `U`/`u3`/`u2` being decomposed into Z and Y rotations. The gates
+/// defined in the program are expanded, the registers are laid out one after the other in
+/// declaration order. The `if` statements testing a single bit are converted to
+/// PragmaConditional operations, the other classical control flow and the gate modifiers are not
+/// supported.
+///
+/// # Arguments
+///
+/// * `qasm` - The QASM program.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit of the program.
+/// * `Err(RoqoqoBackendError)` - The program is invalid or uses an unsupported feature.
+pub fn qasm_to_circuit(qasm: &str) -> Result<Circuit, RoqoqoBackendError> {
+    let mut converter = QasmConverter::new();
+    for statement in split_statements(&strip_comments(qasm)) {
+        converter.statement(&statement)?;
+    }
+    Ok(converter.circuit)
+}
+
+/// Converts an OpenQASM 2 or 3 program to an image of its circuit.
+///
+/// # Arguments
+///
+/// * `qasm` - The QASM program.
+/// * `options` - The options of the circuit representation and of the rendering.
+///
+/// # Returns
+///
+/// * `Ok(DynamicImage)` - The image representing the circuit.
+/// * `Err(RoqoqoBackendError)` - The program cannot be converted or drawn.
+pub fn qasm_to_image(
+    qasm: &str,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    circuit_to_image(&qasm_to_circuit(qasm)?, options)
+}
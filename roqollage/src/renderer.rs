@@ -0,0 +1,378 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc,
+};
+
+use roqoqo::{
+    operations::{InvolveQubits, InvolvedQubits, Operate, Operation},
+    RoqoqoBackendError,
+};
+
+use crate::{LayoutItem, RenderOptions};
+
+/// Renders an operation into the items of a single column of the circuit diagram.
+///
+/// Renderers are registered by hqslang name in a [GateRendererRegistry] and take precedence over
+/// the built-in rendering of the operation.
+pub trait GateRenderer: Debug + Send + Sync {
+    /// Renders the operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to render.
+    /// * `n_qubits` - The number of qubit wires already in the diagram.
+    /// * `options` - The options of the circuit representation.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<(usize, LayoutItem)>)` - The item to place on each qubit wire, the other wires
+    ///   between the smallest and largest qubit are left idle.
+    /// * `Err(RoqoqoBackendError)` - The operation cannot be rendered.
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError>;
+}
+
+/// Returns the qubits an operation acts on, sorted.
+///
+/// Operations acting on all the qubits, or on none of them, are drawn across all the qubit
+/// wires of the diagram.
+///
+/// # Arguments
+///
+/// * `operation` - The operation.
+/// * `n_qubits` - The number of qubit wires already in the diagram.
+pub fn operation_qubits(operation: &Operation, n_qubits: usize) -> Vec<usize> {
+    match operation.involved_qubits() {
+        InvolvedQubits::Set(qubits) => {
+            let mut qubits: Vec<usize> = qubits.into_iter().collect();
+            qubits.sort_unstable();
+            qubits
+        }
+        InvolvedQubits::All | InvolvedQubits::None => (0..n_qubits.max(1)).collect(),
+    }
+}
+
+/// Returns a box with the given content on a single qubit, spanning all the qubits otherwise.
+///
+/// # Arguments
+///
+/// * `content` - The typst math content of the box.
+/// * `qubits` - The sorted qubits of the box.
+fn spanning_box(content: String, qubits: &[usize]) -> Vec<(usize, LayoutItem)> {
+    match qubits {
+        [] => Vec::new(),
+        [qubit] => vec![(*qubit, LayoutItem::gate(content))],
+        [min, .., max] => {
+            let n_wires = max - min + 1;
+            vec![(
+                *min,
+                LayoutItem::MultiGate {
+                    content,
+                    n_wires: Some(n_wires),
+                    width: None,
+                    extent: None,
+                    fill: None,
+                    inputs: if n_wires == qubits.len() {
+                        Vec::new()
+                    } else {
+                        qubits.iter().map(|qubit| qubit - min).collect()
+                    },
+                    target: None,
+                },
+            )]
+        }
+    }
+}
+
+/// Draws a box with a text label across the qubits of the operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelRenderer {
+    /// The text of the box.
+    label: String,
+}
+
+impl LabelRenderer {
+    /// Creates a new LabelRenderer.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The text of the box.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+        }
+    }
+}
+
+impl GateRenderer for LabelRenderer {
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        _options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError> {
+        Ok(spanning_box(
+            format!("\"{}\"", self.label.replace('"', "\\\"")),
+            &operation_qubits(operation, n_qubits),
+        ))
+    }
+}
+
+/// Draws a box with the same typst math content on each qubit of the operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxRenderer {
+    /// The typst math content of the boxes.
+    content: String,
+}
+
+impl BoxRenderer {
+    /// Creates a new BoxRenderer.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The typst math content of the boxes.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+        }
+    }
+}
+
+impl GateRenderer for BoxRenderer {
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        _options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError> {
+        Ok(operation_qubits(operation, n_qubits)
+            .into_iter()
+            .map(|qubit| (qubit, LayoutItem::gate(self.content.clone())))
+            .collect())
+    }
+}
+
+/// Draws a multi-qubit gate with a typst math content from the smallest to the largest qubit
+/// of the operation, marking the qubits of the operation as inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiQubitGateRenderer {
+    /// The typst math content of the gate.
+    content: String,
+}
+
+impl MultiQubitGateRenderer {
+    /// Creates a new MultiQubitGateRenderer.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The typst math content of the gate.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+        }
+    }
+}
+
+impl GateRenderer for MultiQubitGateRenderer {
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        _options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError> {
+        let qubits = operation_qubits(operation, n_qubits);
+        let min = match qubits.first() {
+            Some(&min) => min,
+            None => return Ok(Vec::new()),
+        };
+        let max = qubits.last().copied().unwrap_or(min);
+        Ok(vec![(
+            min,
+            LayoutItem::MultiGate {
+                content: self.content.clone(),
+                n_wires: Some(max - min + 1),
+                width: None,
+                extent: None,
+                fill: None,
+                inputs: qubits.iter().map(|qubit| qubit - min).collect(),
+                target: None,
+            },
+        )])
+    }
+}
+
+/// Places raw quill code on the qubits of the operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypstRenderer {
+    /// The quill code placed on each qubit of the operation, in increasing qubit order.
+    items: Vec<String>,
+}
+
+impl TypstRenderer {
+    /// Creates a new TypstRenderer.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The quill code placed on each qubit of the operation, in increasing qubit
+    ///   order, e.g. `["ctrl(1)", "gate($X$)"]`.
+    pub fn new(items: Vec<String>) -> Self {
+        Self { items }
+    }
+}
+
+impl GateRenderer for TypstRenderer {
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        _options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError> {
+        let qubits = operation_qubits(operation, n_qubits);
+        if qubits.len() != self.items.len() {
+            return Err(RoqoqoBackendError::GenericError {
+                msg: format!(
+                    "The Typst renderer of {} has {} items for {} qubits.",
+                    operation.hqslang(),
+                    self.items.len(),
+                    qubits.len()
+                ),
+            });
+        }
+        Ok(qubits
+            .into_iter()
+            .zip(self.items.iter())
+            .map(|(qubit, item)| (qubit, LayoutItem::Typst(item.clone())))
+            .collect())
+    }
+}
+
+/// Draws a box labelled with the hqslang name of the operation across its qubits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GenericRenderer;
+
+impl GateRenderer for GenericRenderer {
+    fn render(
+        &self,
+        operation: &Operation,
+        n_qubits: usize,
+        options: &RenderOptions,
+    ) -> Result<Vec<(usize, LayoutItem)>, RoqoqoBackendError> {
+        LabelRenderer::new(operation.hqslang()).render(operation, n_qubits, options)
+    }
+}
+
+/// Registry of the gate renderers, keyed by the hqslang name of the operations.
+#[derive(Debug, Clone, Default)]
+pub struct GateRendererRegistry {
+    /// The renderers of the operations.
+    renderers: HashMap<String, Arc<dyn GateRenderer>>,
+    /// The renderer of the operations that are not supported by the backend.
+    fallback: Option<Arc<dyn GateRenderer>>,
+    /// The operations whose built-in drawing is disabled, handled as unsupported operations.
+    unsupported: HashSet<String>,
+}
+
+impl PartialEq for GateRendererRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.renderers.len() == other.renderers.len()
+            && self.renderers.iter().all(|(hqslang, renderer)| {
+                other
+                    .renderers
+                    .get(hqslang)
+                    .is_some_and(|other_renderer| Arc::ptr_eq(renderer, other_renderer))
+            })
+            && match (&self.fallback, &other.fallback) {
+                (Some(fallback), Some(other_fallback)) => Arc::ptr_eq(fallback, other_fallback),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.unsupported == other.unsupported
+    }
+}
+
+impl GateRendererRegistry {
+    /// Creates a new empty GateRendererRegistry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the renderer of an operation, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    /// * `renderer` - The renderer of the operation.
+    pub fn register(&mut self, hqslang: impl Into<String>, renderer: impl GateRenderer + 'static) {
+        self.renderers.insert(hqslang.into(), Arc::new(renderer));
+    }
+
+    /// Removes the renderer of an operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    pub fn unregister(&mut self, hqslang: &str) {
+        self.renderers.remove(hqslang);
+    }
+
+    /// Sets the renderer of the operations that are not supported by the backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `renderer` - The fallback renderer, `None` to return an error for these operations.
+    pub fn set_fallback(&mut self, renderer: Option<Arc<dyn GateRenderer>>) {
+        self.fallback = renderer;
+    }
+
+    /// Disables the built-in drawing of an operation.
+    ///
+    /// Without a registered renderer, the operation is handled as an operation not supported by
+    /// the backend: it is drawn by the fallback renderer with a warning, or returns an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    pub fn mark_unsupported(&mut self, hqslang: impl Into<String>) {
+        self.unsupported.insert(hqslang.into());
+    }
+
+    /// Returns whether the built-in drawing of an operation is disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    pub fn is_unsupported(&self, hqslang: &str) -> bool {
+        self.unsupported.contains(hqslang)
+    }
+
+    /// Returns the renderer registered for an operation, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `hqslang` - The hqslang name of the operation.
+    pub fn get(&self, hqslang: &str) -> Option<&dyn GateRenderer> {
+        self.renderers.get(hqslang).map(Arc::as_ref)
+    }
+
+    /// Returns the renderer of the operations that are not supported by the backend, if any.
+    pub fn fallback(&self) -> Option<&dyn GateRenderer> {
+        self.fallback.as_deref()
+    }
+}
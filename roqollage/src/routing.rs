@@ -0,0 +1,281 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fmt};
+
+use image::DynamicImage;
+use roqoqo::{
+    devices::Device,
+    operations::{InvolveQubits, InvolvedQubits, Operate, Operation},
+    Circuit, RoqoqoBackendError,
+};
+
+use crate::{
+    circuit_into_layout_with_outlines, layout_into_typst_block, render_typst_str, typst_header,
+    CircuitLayout, RenderOptions,
+};
+
+/// Typst stroke of the gate groups outlining the operations violating the device constraints.
+const VIOLATION_STROKE: &str = "red + 1pt";
+
+/// Reason why an operation of a circuit cannot run as is on a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoutingViolationKind {
+    /// A qubit of the operation is not a qubit of the device.
+    QubitOutOfRange,
+    /// The gate is not in the gate set of the device.
+    UnsupportedGate,
+    /// The two qubits of the gate are not connected in the device.
+    NotConnected,
+}
+
+impl fmt::Display for RoutingViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingViolationKind::QubitOutOfRange => write!(f, "qubit not in the device"),
+            RoutingViolationKind::UnsupportedGate => write!(f, "gate not in the device's gate set"),
+            RoutingViolationKind::NotConnected => write!(f, "qubits not connected in the device"),
+        }
+    }
+}
+
+/// Operation of a circuit that cannot run as is on a device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingViolation {
+    /// The index of the operation in the circuit.
+    pub index: usize,
+    /// The hqslang name of the operation.
+    pub hqslang: String,
+    /// The qubits of the operation, sorted.
+    pub qubits: Vec<usize>,
+    /// The reason of the violation.
+    pub kind: RoutingViolationKind,
+}
+
+impl fmt::Display for RoutingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on qubit{} {}: {}",
+            self.hqslang,
+            if self.qubits.len() == 1 { "" } else { "s" },
+            self.qubits
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.kind
+        )
+    }
+}
+
+/// Checks whether an operation can run as is on a device.
+///
+/// All the operations must act on qubits of the device. The gates must also be in the gate set
+/// of the device and the two-qubit gates must act on qubits connected by an edge of the device.
+///
+/// # Arguments
+///
+/// * `operation` - The operation to check.
+/// * `device` - The device the operation should run on.
+///
+/// # Returns
+///
+/// * `Some(RoutingViolationKind)` - The reason why the operation cannot run on the device.
+/// * `None` - The operation can run on the device.
+pub fn check_routing<D: Device + ?Sized>(
+    operation: &Operation,
+    device: &D,
+) -> Option<RoutingViolationKind> {
+    let InvolvedQubits::Set(qubits) = operation.involved_qubits() else {
+        return None;
+    };
+    let mut qubits: Vec<usize> = qubits.into_iter().collect();
+    qubits.sort_unstable();
+    if qubits.iter().any(|&qubit| qubit >= device.number_qubits()) {
+        return Some(RoutingViolationKind::QubitOutOfRange);
+    }
+    if !operation.tags().contains(&"GateOperation") {
+        return None;
+    }
+    let gate_names = match qubits.len() {
+        0 => return None,
+        1 => device.single_qubit_gate_names(),
+        2 => device.two_qubit_gate_names(),
+        _ => device.multi_qubit_gate_names(),
+    };
+    if !gate_names.iter().any(|name| name == operation.hqslang()) {
+        return Some(RoutingViolationKind::UnsupportedGate);
+    }
+    if qubits.len() == 2
+        && !device
+            .two_qubit_edges()
+            .into_iter()
+            .any(|(first, second)| first.min(second) == qubits[0] && first.max(second) == qubits[1])
+    {
+        return Some(RoutingViolationKind::NotConnected);
+    }
+    None
+}
+
+/// Lists the operations of a circuit that cannot run as is on a device.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to check.
+/// * `device` - The device the circuit should run on.
+///
+/// # Returns
+///
+/// * `Vec<RoutingViolation>` - The violations, in the order of the operations.
+pub fn routing_violations<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+) -> Vec<RoutingViolation> {
+    circuit
+        .iter()
+        .enumerate()
+        .filter_map(|(index, operation)| {
+            check_routing(operation, device).map(|kind| {
+                let mut qubits: Vec<usize> = match operation.involved_qubits() {
+                    InvolvedQubits::Set(qubits) => qubits.into_iter().collect(),
+                    _ => Vec::new(),
+                };
+                qubits.sort_unstable();
+                RoutingViolation {
+                    index,
+                    hqslang: operation.hqslang().to_owned(),
+                    qubits,
+                    kind,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Converts a qoqo circuit to the layout of its diagram, with the operations that cannot run as
+/// is on a device outlined in red.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device the circuit should run on.
+/// * `options` - The options of the circuit representation, as for
+///   [circuit_into_layout](crate::circuit_into_layout).
+///
+/// # Returns
+///
+/// * `Ok((CircuitLayout, Vec<RoutingViolation>))` - The layout of the circuit and the violations.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub fn circuit_into_layout_on_device<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> Result<(CircuitLayout, Vec<RoutingViolation>), RoqoqoBackendError> {
+    let violations = routing_violations(circuit, device);
+    let outlines: HashMap<usize, &str> = violations
+        .iter()
+        .map(|violation| (violation.index, VIOLATION_STROKE))
+        .collect();
+    let layout = circuit_into_layout_with_outlines(circuit, options, &outlines)?;
+    Ok((layout, violations))
+}
+
+/// Converts a qoqo circuit to a typst string highlighting the operations that cannot run as is
+/// on a device, with the warnings emitted during the conversion.
+///
+/// The operations acting on qubits outside of the device, the gates that are not in the gate set
+/// of the device and the two-qubit gates acting on qubits that are not connected are outlined in
+/// red, a legend below the circuit lists them.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device the circuit should run on.
+/// * `options` - The options of the circuit representation.
+///
+/// # Returns
+///
+/// * `Ok((String, Vec<String>))` - The typst string of the circuit and the warnings.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub fn circuit_on_device_into_typst_str_with_warnings<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> Result<(String, Vec<String>), RoqoqoBackendError> {
+    let (layout, violations) = circuit_into_layout_on_device(circuit, device, options)?;
+    let (block, warnings) = layout_into_typst_block(layout, options);
+    let mut typst_str = format!("{}{block}", typst_header(options));
+    if violations.is_empty() {
+        typst_str.push_str("No routing violation on the device\n");
+    } else {
+        typst_str.push_str(&format!(
+            "#text(fill: red)[*Routing violations: {}*] \\\n",
+            violations.len()
+        ));
+        typst_str.push_str(
+            &violations
+                .iter()
+                .map(|violation| format!("Operation {}: {violation}", violation.index))
+                .collect::<Vec<String>>()
+                .join(" \\\n"),
+        );
+        typst_str.push('\n');
+    }
+    Ok((typst_str, warnings))
+}
+
+/// Converts a qoqo circuit to a typst string highlighting the operations that cannot run as is
+/// on a device.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device the circuit should run on.
+/// * `options` - The options of the circuit representation.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The typst string of the circuit.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub fn circuit_on_device_into_typst_str<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    circuit_on_device_into_typst_str_with_warnings(circuit, device, options)
+        .map(|(typst_str, _)| typst_str)
+}
+
+/// Converts a qoqo circuit to an image highlighting the operations that cannot run as is on a
+/// device.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device the circuit should run on.
+/// * `options` - The options of the circuit representation and of the rendering.
+///
+/// # Returns
+///
+/// * `Ok(DynamicImage)` - The image representing the circuit.
+/// * `Err(RoqoqoBackendError)` - The circuit cannot be converted or compiled.
+pub fn circuit_on_device_to_image<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    render_typst_str(
+        circuit_on_device_into_typst_str(circuit, device, options)?,
+        options,
+    )
+}
@@ -0,0 +1,199 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ndarray::{Array1, Array2};
+use num_complex::Complex64;
+
+/// Tolerance of the comparisons of the purities and fidelities.
+const TOLERANCE: f64 = 1e-6;
+
+/// State prepared at the beginning of the qubit wires, inferred from a `PragmaSetStateVector` or
+/// a `PragmaSetDensityMatrix` at the beginning of the circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreparedState {
+    /// Product of known single-qubit states, the typst math state of each qubit, e.g. `+` or `1`.
+    Product(Vec<String>),
+    /// Any other state, drawn with a brace across the qubits.
+    Global {
+        /// The number of qubits of the state.
+        n_qubits: usize,
+        /// Whether the state is a density matrix, drawn as `rho` instead of `|psi>`.
+        mixed: bool,
+    },
+}
+
+impl PreparedState {
+    /// Infers the prepared state of a `PragmaSetStateVector`.
+    ///
+    /// # Arguments
+    ///
+    /// * `statevector` - The statevector, the index of an amplitude is the binary number whose
+    ///   bit `n` is the state of qubit `n`.
+    ///
+    /// # Returns
+    ///
+    /// * `PreparedState` - The product state when every qubit is in `|0>`, `|1>`, `|+>`, `|->`,
+    ///   `|+i>` or `|-i>`, a global `|psi>` otherwise.
+    pub fn from_statevector(statevector: &Array1<Complex64>) -> Self {
+        let n_qubits = n_qubits(statevector.len());
+        let product = if statevector.len() == 1 << n_qubits {
+            single_qubit_states(n_qubits, |row, column| {
+                statevector[row] * statevector[column].conj()
+            })
+        } else {
+            None
+        };
+        product.map_or(
+            PreparedState::Global {
+                n_qubits,
+                mixed: false,
+            },
+            PreparedState::Product,
+        )
+    }
+
+    /// Infers the prepared state of a `PragmaSetDensityMatrix`.
+    ///
+    /// # Arguments
+    ///
+    /// * `density_matrix` - The density matrix, in the basis of [PreparedState::from_statevector].
+    ///
+    /// # Returns
+    ///
+    /// * `PreparedState` - The product state when the density matrix is a pure product of known
+    ///   single-qubit states, a global `rho` otherwise.
+    pub fn from_density_matrix(density_matrix: &Array2<Complex64>) -> Self {
+        let dimension = density_matrix.nrows();
+        let n_qubits = n_qubits(dimension);
+        let trace: f64 = (0..dimension)
+            .map(|index| density_matrix[[index, index]].re)
+            .sum();
+        let purity: f64 = density_matrix
+            .iter()
+            .map(|value| value.norm_sqr())
+            .sum::<f64>()
+            / (trace * trace);
+        let product = if dimension == 1 << n_qubits
+            && density_matrix.ncols() == dimension
+            && trace > TOLERANCE
+            && (purity - 1.0).abs() < TOLERANCE
+        {
+            single_qubit_states(n_qubits, |row, column| density_matrix[[row, column]])
+        } else {
+            None
+        };
+        product.map_or(
+            PreparedState::Global {
+                n_qubits,
+                mixed: true,
+            },
+            PreparedState::Product,
+        )
+    }
+
+    /// Returns the typst math state of a qubit prepared in a product state.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The index of the qubit.
+    pub fn qubit_state(&self, qubit: usize) -> Option<&str> {
+        match self {
+            PreparedState::Product(states) => states.get(qubit).map(String::as_str),
+            PreparedState::Global { .. } => None,
+        }
+    }
+}
+
+/// Returns the number of qubits of a state of the given dimension, rounded up.
+///
+/// # Arguments
+///
+/// * `dimension` - The dimension of the Hilbert space.
+fn n_qubits(dimension: usize) -> usize {
+    dimension.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+/// Returns the known single-qubit states of the qubits of a product state.
+///
+/// # Arguments
+///
+/// * `n_qubits` - The number of qubits.
+/// * `element` - The elements of the (unnormalized) density matrix of the state.
+///
+/// # Returns
+///
+/// * `Some(Vec<String>)` - The state of each qubit, when the single-qubit reduced density
+///   matrices are all pure and known.
+/// * `None` - The state is entangled or one of the qubits is in an unknown state.
+fn single_qubit_states(
+    n_qubits: usize,
+    element: impl Fn(usize, usize) -> Complex64,
+) -> Option<Vec<String>> {
+    (0..n_qubits)
+        .map(|qubit| {
+            let mask = 1 << qubit;
+            let mut reduced = [[Complex64::new(0.0, 0.0); 2]; 2];
+            for row in 0..1_usize << n_qubits {
+                let bit = (row & mask) >> qubit;
+                for (column_bit, value) in reduced[bit].iter_mut().enumerate() {
+                    *value += element(row, (row & !mask) | (column_bit << qubit));
+                }
+            }
+            single_qubit_state(&reduced).map(str::to_owned)
+        })
+        .collect()
+}
+
+/// Returns the name of a pure single-qubit state.
+///
+/// # Arguments
+///
+/// * `reduced` - The (unnormalized) density matrix of the qubit.
+///
+/// # Returns
+///
+/// * `Some(&str)` - The typst math name of the state, e.g. `+i`.
+/// * `None` - The state is mixed or not one of the eigenstates of the Pauli matrices.
+fn single_qubit_state(reduced: &[[Complex64; 2]; 2]) -> Option<&'static str> {
+    let trace = reduced[0][0].re + reduced[1][1].re;
+    if trace < TOLERANCE {
+        return None;
+    }
+    let purity = reduced
+        .iter()
+        .flatten()
+        .map(|value| value.norm_sqr())
+        .sum::<f64>()
+        / (trace * trace);
+    if (purity - 1.0).abs() > TOLERANCE {
+        return None;
+    }
+    let half = std::f64::consts::FRAC_1_SQRT_2;
+    let candidates = [
+        ("0", [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)]),
+        ("1", [Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0)]),
+        ("+", [Complex64::new(half, 0.0), Complex64::new(half, 0.0)]),
+        ("-", [Complex64::new(half, 0.0), Complex64::new(-half, 0.0)]),
+        ("+i", [Complex64::new(half, 0.0), Complex64::new(0.0, half)]),
+        (
+            "-i",
+            [Complex64::new(half, 0.0), Complex64::new(0.0, -half)],
+        ),
+    ];
+    candidates.into_iter().find_map(|(name, state)| {
+        let fidelity: Complex64 = (0..2)
+            .flat_map(|row| (0..2).map(move |column| (row, column)))
+            .map(|(row, column)| state[row].conj() * reduced[row][column] * state[column])
+            .sum();
+        ((fidelity.re / trace - 1.0).abs() < TOLERANCE).then_some(name)
+    })
+}
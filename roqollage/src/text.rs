@@ -0,0 +1,493 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use roqoqo::{Circuit, RoqoqoBackendError};
+
+use crate::{circuit_into_layout, CircuitLayout, LayoutItem, RenderOptions};
+use crate::{GlobalStart, WireLabels, WireRef, WireRows};
+
+/// A column of the text diagram.
+#[derive(Debug, Default)]
+struct TextColumn {
+    /// The text drawn on each row, `None` for an idle wire.
+    cells: Vec<Option<String>>,
+    /// The vertical lines of the column: first row, second row and whether the line is classical.
+    links: Vec<(usize, usize, bool)>,
+    /// The first and last rows of the multi-qubit boxes of the column.
+    boxes: Vec<(usize, usize)>,
+    /// The label displayed above the column.
+    header: Option<String>,
+    /// The number of the column of the layout, displayed above the labels.
+    number: Option<usize>,
+    /// Whether the column is a slice or gate group marker rather than a column of the layout.
+    is_marker: bool,
+}
+
+/// Converts the content of a typst math expression to plain text.
+///
+/// # Arguments
+///
+/// * `content` - The typst math expression.
+fn plain_text(content: &str) -> String {
+    content.replace(['"', '\\'], "")
+}
+
+/// Returns the character of a horizontal wire crossed or ended by a vertical line.
+///
+/// # Arguments
+///
+/// * `horizontal` - The character of the horizontal wire.
+/// * `classical_link` - Whether the vertical line is a classical (double) line.
+/// * `up` - Whether the vertical line goes up from the wire.
+/// * `down` - Whether the vertical line goes down from the wire.
+fn junction(horizontal: char, classical_link: bool, up: bool, down: bool) -> char {
+    let double_wire = horizontal == '═';
+    match (double_wire, classical_link, up, down) {
+        (false, false, true, true) => '┼',
+        (false, false, false, true) => '┬',
+        (false, false, true, false) => '┴',
+        (false, true, true, true) => '╫',
+        (false, true, false, true) => '╥',
+        (false, true, true, false) => '╨',
+        (true, false, true, true) => '╪',
+        (true, false, false, true) => '╤',
+        (true, false, true, false) => '╧',
+        (true, true, true, true) => '╬',
+        (true, true, false, true) => '╦',
+        (true, true, true, false) => '╩',
+        _ => horizontal,
+    }
+}
+
+/// Splits the items of a wire into its columns, with the non-column items placed before each one.
+///
+/// # Arguments
+///
+/// * `items` - The items of the wire.
+///
+/// # Returns
+///
+/// * `(Vec<(Vec<&LayoutItem>, &LayoutItem)>, Vec<&LayoutItem>)` - The columns with their
+///   preceding markers, and the markers after the last column.
+#[allow(clippy::type_complexity)]
+fn wire_columns(items: &[LayoutItem]) -> (Vec<(Vec<&LayoutItem>, &LayoutItem)>, Vec<&LayoutItem>) {
+    let mut columns = Vec::new();
+    let mut markers = Vec::new();
+    for item in items {
+        if item.is_column() {
+            columns.push((std::mem::take(&mut markers), item));
+        } else {
+            markers.push(item);
+        }
+    }
+    (columns, markers)
+}
+
+/// Builds the marker columns of the slices and gate groups placed on a row.
+///
+/// # Arguments
+///
+/// * `markers` - The non-column items of the row.
+/// * `row` - The row of the items.
+/// * `n_rows` - The number of rows of the diagram.
+/// * `labels` - The labels of the wires, updated with the register labels.
+/// * `register_names` - The custom names of the registers.
+fn marker_columns(
+    markers: &[&LayoutItem],
+    row: usize,
+    n_rows: usize,
+    labels: &mut [String],
+    register_names: &WireLabels,
+) -> Vec<TextColumn> {
+    let mut columns = Vec::new();
+    for marker in markers {
+        let (header, rows) = match marker {
+            LayoutItem::Slice { label, .. } => (plain_text(label), 0..n_rows),
+            LayoutItem::GateGroup { n_wires, label, .. } => {
+                (label.clone(), row..(row + n_wires).min(n_rows))
+            }
+            LayoutItem::RegisterLabel(name) => {
+                labels[row] = register_names.register_name(name).to_owned();
+                continue;
+            }
+            _ => continue,
+        };
+        let mut cells = vec![None; n_rows];
+        for cell in cells[rows].iter_mut() {
+            *cell = Some("┊".to_owned());
+        }
+        columns.push(TextColumn {
+            cells,
+            header: Some(header),
+            is_marker: true,
+            ..Default::default()
+        });
+    }
+    columns
+}
+
+/// Builds the text column of a column of the layout.
+///
+/// # Arguments
+///
+/// * `items` - The item of each row in the column.
+/// * `rows` - The rows of the wires in the diagram.
+fn gate_column(items: &[&LayoutItem], rows: &WireRows) -> TextColumn {
+    let mut column = TextColumn {
+        cells: vec![None; items.len()],
+        ..Default::default()
+    };
+    for (row, item) in items.iter().enumerate() {
+        let cell = match item {
+            LayoutItem::Gate { content, .. } => format!("┤{}├", plain_text(content)),
+            LayoutItem::Typst(code) => format!("┤{code}├"),
+            LayoutItem::MultiGate {
+                content,
+                n_wires,
+                target,
+                ..
+            } => {
+                let text = plain_text(content);
+                let last_row = (row + n_wires.unwrap_or(1)).min(items.len()) - 1;
+                for cell in column.cells[row + 1..=last_row].iter_mut() {
+                    *cell = Some(format!("┤{}├", " ".repeat(text.chars().count())));
+                }
+                if last_row > row {
+                    column.boxes.push((row, last_row));
+                }
+                if let Some(target) = target {
+                    column.links.push((row, rows.row(*target), false));
+                }
+                format!("┤{text}├")
+            }
+            // The readout index of a measurement, on its classical register.
+            LayoutItem::Control {
+                target: None,
+                label: Some(label),
+            } => plain_text(label),
+            LayoutItem::Control { target, .. } => {
+                if let Some(target) = target {
+                    column.links.push((row, rows.row(*target), false));
+                }
+                "■".to_owned()
+            }
+            LayoutItem::Target => "⊕".to_owned(),
+            LayoutItem::Swap { partner, .. } => {
+                if let Some(partner) = partner {
+                    column.links.push((row, rows.row(*partner), false));
+                }
+                "╳".to_owned()
+            }
+            LayoutItem::Meter { target, .. } => {
+                if let Some(target) = target {
+                    column.links.push((row, rows.row(*target), true));
+                }
+                "┤M├".to_owned()
+            }
+            _ => continue,
+        };
+        column.cells[row] = Some(cell);
+    }
+    column
+}
+
+/// Draws text columns into lines of characters.
+///
+/// # Arguments
+///
+/// * `columns` - The columns to draw.
+/// * `labels` - The label of each wire.
+/// * `wires` - The wire of each row.
+/// * `continued` - Whether the diagram continues after these columns.
+fn draw_columns(
+    columns: &[TextColumn],
+    labels: &[String],
+    wires: &[WireRef],
+    continued: bool,
+) -> Vec<String> {
+    let n_rows = wires.len();
+    let label_width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let has_numbers = columns.iter().any(|column| column.number.is_some());
+    let has_header = columns.iter().any(|column| column.header.is_some());
+    let header_line = usize::from(has_numbers);
+    let header_offset = header_line + usize::from(has_header);
+    let mut lines: Vec<Vec<char>> = vec![Vec::new(); header_offset + 2 * n_rows - 1];
+    for (row, label) in labels.iter().enumerate() {
+        let line = &mut lines[header_offset + 2 * row];
+        line.extend(label.chars());
+        line.resize(label_width, ' ');
+    }
+    for line in lines.iter_mut() {
+        line.resize(label_width, ' ');
+    }
+    for column in columns {
+        let width = column
+            .cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or(1);
+        let start = lines[header_offset].len();
+        let center = start + 1 + (width - 1) / 2;
+        for (row, wire) in wires.iter().enumerate() {
+            let wire_char = if matches!(wire, WireRef::Classical(_)) {
+                '═'
+            } else {
+                '─'
+            };
+            let line = &mut lines[header_offset + 2 * row];
+            let cell = column.cells[row].as_deref().unwrap_or("");
+            let cell_width = cell.chars().count();
+            let left = (width - cell_width) / 2;
+            line.push(wire_char);
+            line.extend(std::iter::repeat(wire_char).take(left));
+            line.extend(cell.chars());
+            line.extend(std::iter::repeat(wire_char).take(width - cell_width - left));
+            line.push(wire_char);
+        }
+        for line in lines.iter_mut() {
+            if line.len() < start + width + 2 {
+                line.resize(start + width + 2, ' ');
+            }
+        }
+        let number = column.number.map(|number| number.to_string());
+        for (line_index, label) in [(0, number), (header_line, column.header.clone())] {
+            for (index, character) in label.unwrap_or_default().chars().enumerate() {
+                if let Some(position) = lines[line_index].get_mut(start + 1 + index) {
+                    *position = character;
+                } else {
+                    lines[line_index].push(character);
+                }
+            }
+        }
+        for &(first, last) in column.boxes.iter() {
+            let box_width = column.cells[first]
+                .as_deref()
+                .map_or(0, |cell| cell.chars().count());
+            let left = start + 1 + (width - box_width) / 2;
+            for gap in first..last {
+                let line = &mut lines[header_offset + 2 * gap + 1];
+                line[left] = '│';
+                line[left + box_width - 1] = '│';
+            }
+        }
+        for &(from, to, classical) in column.links.iter() {
+            let (top, bottom) = (from.min(to), from.max(to));
+            for line_index in 2 * top..=2 * bottom {
+                let line = &mut lines[header_offset + line_index];
+                let character = line[center];
+                line[center] = if line_index % 2 == 1 {
+                    if classical {
+                        '║'
+                    } else {
+                        '│'
+                    }
+                } else if character == '─' || character == '═' {
+                    junction(
+                        character,
+                        classical,
+                        line_index > 2 * top,
+                        line_index < 2 * bottom,
+                    )
+                } else {
+                    character
+                };
+            }
+        }
+    }
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let mut line: String = line.into_iter().collect();
+            if continued && index >= header_offset && (index - header_offset) % 2 == 0 {
+                line.push_str(" ···");
+            }
+            line.trim_end().to_owned()
+        })
+        .collect()
+}
+
+/// Converts the layout of a circuit to a text diagram drawn with box-drawing characters.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the circuit.
+/// * `options` - The initialization mode and the maximum length of a circuit line.
+///
+/// # Returns
+///
+/// * `String` - The text diagram of the circuit.
+pub fn layout_to_text(layout: &CircuitLayout, options: &RenderOptions) -> String {
+    let rows = layout.wire_rows();
+    let wires: Vec<WireRef> = (0..layout.qubits.len())
+        .map(WireRef::Qubit)
+        .chain((0..layout.bosons.len()).map(WireRef::Boson))
+        .chain((0..layout.classical.len()).map(WireRef::Classical))
+        .collect();
+    if wires.is_empty() {
+        return String::new();
+    }
+    let mut labels: Vec<String> = wires
+        .iter()
+        .map(|wire| match wire {
+            WireRef::Classical(index) => format!("c[{index}]"),
+            WireRef::Qubit(row) => match layout.global_start(*row) {
+                Some(start) => {
+                    let (bracket, state) = match start {
+                        GlobalStart::First { n_rows: 1, mixed } => ("", Some(mixed)),
+                        GlobalStart::First { mixed, .. } => ("┌", Some(mixed)),
+                        GlobalStart::Spanned { last: true } => ("└", None),
+                        GlobalStart::Spanned { last: false } => ("│", None),
+                        GlobalStart::Alone { mixed } => ("", Some(mixed)),
+                    };
+                    let state = match state {
+                        Some(true) => "rho",
+                        Some(false) => "|psi⟩",
+                        None => "",
+                    };
+                    format!("{bracket}{state}")
+                }
+                None => options.wire_labels.text_start(
+                    WireRef::Qubit(layout.qubit_index(*row)),
+                    options.initialization_mode,
+                    layout.prepared_state.as_ref(),
+                ),
+            },
+            WireRef::Boson(_) => {
+                options
+                    .wire_labels
+                    .text_start(*wire, options.initialization_mode, None)
+            }
+        })
+        .collect();
+    let split_wires: Vec<_> = layout
+        .qubits
+        .iter()
+        .chain(layout.bosons.iter())
+        .chain(layout.classical.iter())
+        .map(|items| wire_columns(items))
+        .collect();
+    let n_columns = split_wires
+        .iter()
+        .map(|(wire, _)| wire.len())
+        .max()
+        .unwrap_or(0);
+    let mut columns = Vec::new();
+    for index in 0..=n_columns {
+        for (row, (wire, trailing_markers)) in split_wires.iter().enumerate() {
+            if index > wire.len() {
+                continue;
+            }
+            let markers = wire
+                .get(index)
+                .map_or(trailing_markers.as_slice(), |(markers, _)| {
+                    markers.as_slice()
+                });
+            columns.extend(marker_columns(
+                markers,
+                row,
+                wires.len(),
+                &mut labels,
+                &options.wire_labels,
+            ));
+        }
+        if index < n_columns {
+            let items: Vec<&LayoutItem> = split_wires
+                .iter()
+                .map(|(wire, _)| wire.get(index).map_or(&LayoutItem::Idle, |(_, item)| *item))
+                .collect();
+            let mut column = gate_column(&items, &rows);
+            if options.column_numbers {
+                column.number = Some(index);
+            }
+            columns.push(column);
+        }
+    }
+    let max_length = options.max_length.unwrap_or(usize::MAX).max(1);
+    let mut chunks: Vec<&[TextColumn]> = Vec::new();
+    let mut chunk_start = 0;
+    let mut chunk_len = 0;
+    // The markers before a column are moved to the next line together with the column.
+    let mut markers_start = None;
+    for (index, column) in columns.iter().enumerate() {
+        if column.is_marker {
+            markers_start.get_or_insert(index);
+        } else {
+            if chunk_len == max_length {
+                let split = markers_start.unwrap_or(index);
+                chunks.push(&columns[chunk_start..split]);
+                chunk_start = split;
+                chunk_len = 0;
+            }
+            chunk_len += 1;
+            markers_start = None;
+        }
+    }
+    chunks.push(&columns[chunk_start..]);
+    let n_chunks = chunks.len();
+    let mut text = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| draw_columns(chunk, &labels, &wires, index + 1 < n_chunks).join("\n"))
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    if options.depth_footer {
+        text.push_str(&format!("\n\nDepth: {}", layout.depth()));
+        if !layout.qubit_gate_counts.is_empty() {
+            text.push_str(&format!(
+                "\nGates: {}",
+                layout
+                    .qubit_gate_counts
+                    .iter()
+                    .enumerate()
+                    .map(|(row, count)| format!(
+                        "{}: {count}",
+                        options
+                            .wire_labels
+                            .name(WireRef::Qubit(layout.qubit_index(row)))
+                            .to_text()
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+    }
+    text
+}
+
+/// Converts a qoqo circuit to a text diagram drawn with box-drawing characters, e.g. to print it
+/// in a terminal or a log.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `options` - The pragmas to render, the initialization mode, the maximum length of a circuit
+///   line and the rounding accuracy of the circuit representation.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The text diagram of the circuit.
+/// * `Err(RoqoqoBackendError)` - Operation not supported.
+pub fn circuit_to_text(
+    circuit: &Circuit,
+    options: &RenderOptions,
+) -> Result<String, RoqoqoBackendError> {
+    let layout = circuit_into_layout(circuit, options)?;
+    Ok(layout_to_text(&layout, options))
+}
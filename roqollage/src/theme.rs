@@ -0,0 +1,327 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use roqoqo::{
+    operations::{Operate, Operation},
+    RoqoqoBackendError, RoqoqoError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::LayoutItem;
+
+/// Family of operations sharing the same style in a [Theme].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GateFamily {
+    /// Single-qubit rotations and the other single-qubit gates that are not Clifford gates.
+    Rotation,
+    /// Single-qubit Clifford gates, e.g. Hadamard, PauliX or SGate.
+    Clifford,
+    /// Gates acting on two or more qubits, e.g. CNOT or MolmerSorensenXX.
+    Entangler,
+    /// Measurements and readout pragmas.
+    Measurement,
+    /// Noise pragmas, e.g. PragmaDamping, and PragmaActiveReset.
+    Noise,
+    /// Operations acting on bosonic modes.
+    Bosonic,
+    /// The other operations, e.g. definitions and pragmas, which are never styled.
+    Other,
+}
+
+impl GateFamily {
+    /// Returns the family of an operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to classify.
+    pub fn of(operation: &Operation) -> Self {
+        match operation {
+            Operation::Hadamard(_)
+            | Operation::PauliX(_)
+            | Operation::PauliY(_)
+            | Operation::PauliZ(_)
+            | Operation::SGate(_)
+            | Operation::InvSGate(_)
+            | Operation::SqrtPauliX(_)
+            | Operation::InvSqrtPauliX(_)
+            | Operation::SqrtPauliY(_)
+            | Operation::InvSqrtPauliY(_)
+            | Operation::SXGate(_)
+            | Operation::InvSXGate(_)
+            | Operation::Identity(_) => GateFamily::Clifford,
+            Operation::MeasureQubit(_)
+            | Operation::PragmaRepeatedMeasurement(_)
+            | Operation::PragmaGetStateVector(_)
+            | Operation::PragmaGetDensityMatrix(_)
+            | Operation::PragmaGetOccupationProbability(_)
+            | Operation::PragmaGetPauliProduct(_) => GateFamily::Measurement,
+            Operation::PragmaDamping(_)
+            | Operation::PragmaDepolarising(_)
+            | Operation::PragmaDephasing(_)
+            | Operation::PragmaRandomNoise(_)
+            | Operation::PragmaGeneralNoise(_)
+            | Operation::PragmaOverrotation(_)
+            | Operation::PragmaBoostNoise(_)
+            | Operation::PragmaActiveReset(_) => GateFamily::Noise,
+            Operation::Squeezing(_)
+            | Operation::PhaseShift(_)
+            | Operation::BeamSplitter(_)
+            | Operation::PhotonDetection(_)
+            | Operation::PhaseDisplacement(_)
+            | Operation::QuantumRabi(_)
+            | Operation::LongitudinalCoupling(_)
+            | Operation::JaynesCummings(_)
+            | Operation::SingleExcitationStore(_)
+            | Operation::SingleExcitationLoad(_)
+            | Operation::CZQubitResonator(_) => GateFamily::Bosonic,
+            _ => {
+                let tags = operation.tags();
+                if tags.contains(&"SingleQubitGateOperation") {
+                    GateFamily::Rotation
+                } else if tags.contains(&"TwoQubitGateOperation")
+                    || tags.contains(&"ThreeQubitGateOperation")
+                    || tags.contains(&"MultiQubitGateOperation")
+                {
+                    GateFamily::Entangler
+                } else {
+                    GateFamily::Other
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for GateFamily {
+    type Err = RoqoqoBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rotation" => Ok(GateFamily::Rotation),
+            "clifford" => Ok(GateFamily::Clifford),
+            "entangler" => Ok(GateFamily::Entangler),
+            "measurement" => Ok(GateFamily::Measurement),
+            "noise" => Ok(GateFamily::Noise),
+            "bosonic" => Ok(GateFamily::Bosonic),
+            _ => Err(RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                msg: format!(
+                    r#"Invalid gate family: {s}, use `rotation`, `clifford`, `entangler`, `measurement`, `noise` or `bosonic`."#
+                ),
+            })),
+        }
+    }
+}
+
+/// Fill and stroke of the gates of a family.
+///
+/// The colours are typst expressions, e.g. `blue`, `rgb("#dbe9f6")` or `1.5pt + black`. The
+/// fields that are not set keep the style chosen by the layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateStyle {
+    /// The fill colour of the gate boxes and of the measurements.
+    pub fill: Option<String>,
+    /// The stroke of the single-qubit gate boxes.
+    pub stroke: Option<String>,
+}
+
+impl GateStyle {
+    /// Creates a new GateStyle keeping the style chosen by the layout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the fill colour of the gate boxes and of the measurements.
+    ///
+    /// # Arguments
+    ///
+    /// * `fill` - The typst colour.
+    pub fn fill(mut self, fill: impl Into<String>) -> Self {
+        self.fill = Some(fill.into());
+        self
+    }
+
+    /// Sets the stroke of the single-qubit gate boxes.
+    ///
+    /// # Arguments
+    ///
+    /// * `stroke` - The typst stroke.
+    pub fn stroke(mut self, stroke: impl Into<String>) -> Self {
+        self.stroke = Some(stroke.into());
+        self
+    }
+
+    /// Applies the style to an item of the layout.
+    ///
+    /// The fill applies to the gate boxes, the multi-qubit gate boxes and the measurements, the
+    /// stroke to the gate boxes. The other items are left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - The item to style.
+    pub fn apply(&self, item: &mut LayoutItem) {
+        match item {
+            LayoutItem::Gate { fill, stroke, .. } => {
+                if self.fill.is_some() {
+                    fill.clone_from(&self.fill);
+                }
+                if self.stroke.is_some() {
+                    stroke.clone_from(&self.stroke);
+                }
+            }
+            LayoutItem::MultiGate { fill, .. } | LayoutItem::Meter { fill, .. }
+                if self.fill.is_some() =>
+            {
+                fill.clone_from(&self.fill);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Colours of the gates of a circuit diagram, per gate family.
+///
+/// The default theme keeps quill's defaults, with the noise pragmas filled in gray.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// The style of the single-qubit rotations.
+    pub rotation: GateStyle,
+    /// The style of the single-qubit Clifford gates.
+    pub clifford: GateStyle,
+    /// The style of the gates acting on two or more qubits.
+    pub entangler: GateStyle,
+    /// The style of the measurements.
+    pub measurement: GateStyle,
+    /// The style of the noise pragmas.
+    pub noise: GateStyle,
+    /// The style of the bosonic operations.
+    pub bosonic: GateStyle,
+}
+
+impl Theme {
+    /// Creates a new Theme keeping quill's defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pastel fills for light backgrounds.
+    pub fn light() -> Self {
+        Self {
+            rotation: GateStyle::new().fill(r##"rgb("#dbe9f6")"##),
+            clifford: GateStyle::new().fill(r##"rgb("#e2f0d9")"##),
+            entangler: GateStyle::new().fill(r##"rgb("#fde9c9")"##),
+            measurement: GateStyle::new().fill(r##"rgb("#eeeeee")"##),
+            noise: GateStyle::new().fill(r##"rgb("#d9d9d9")"##),
+            bosonic: GateStyle::new().fill(r##"rgb("#eadcf2")"##),
+        }
+    }
+
+    /// Saturated fills standing out on dark backgrounds.
+    pub fn dark() -> Self {
+        Self {
+            rotation: GateStyle::new().fill(r##"rgb("#6d9eeb")"##),
+            clifford: GateStyle::new().fill(r##"rgb("#6fbf73")"##),
+            entangler: GateStyle::new().fill(r##"rgb("#f0a848")"##),
+            measurement: GateStyle::new().fill(r##"rgb("#b0b0b0")"##),
+            noise: GateStyle::new().fill(r##"rgb("#808080")"##),
+            bosonic: GateStyle::new().fill(r##"rgb("#b48ad6")"##),
+        }
+    }
+
+    /// Bright fills and thick black outlines.
+    pub fn high_contrast() -> Self {
+        let outline = "1.5pt + black";
+        Self {
+            rotation: GateStyle::new().fill("white").stroke(outline),
+            clifford: GateStyle::new().fill(r##"rgb("#ffd700")"##).stroke(outline),
+            entangler: GateStyle::new().fill(r##"rgb("#00bfff")"##),
+            measurement: GateStyle::new().fill("white"),
+            noise: GateStyle::new().fill(r##"rgb("#ff69b4")"##).stroke(outline),
+            bosonic: GateStyle::new().fill(r##"rgb("#7fff00")"##).stroke(outline),
+        }
+    }
+
+    /// Lightened Okabe-Ito colours, distinguishable with the common colour vision deficiencies.
+    pub fn colour_blind() -> Self {
+        let okabe_ito = |colour: &str| format!(r#"rgb("{colour}").lighten(40%)"#);
+        Self {
+            rotation: GateStyle::new().fill(okabe_ito("#56b4e9")),
+            clifford: GateStyle::new().fill(okabe_ito("#009e73")),
+            entangler: GateStyle::new().fill(okabe_ito("#e69f00")),
+            measurement: GateStyle::new().fill(okabe_ito("#f0e442")),
+            noise: GateStyle::new().fill(okabe_ito("#d55e00")),
+            bosonic: GateStyle::new().fill(okabe_ito("#cc79a7")),
+        }
+    }
+
+    /// Sets the style of a gate family.
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The gate family, the style of [GateFamily::Other] cannot be set.
+    /// * `style` - The style of the gates of the family.
+    pub fn family_style(mut self, family: GateFamily, style: GateStyle) -> Self {
+        match family {
+            GateFamily::Rotation => self.rotation = style,
+            GateFamily::Clifford => self.clifford = style,
+            GateFamily::Entangler => self.entangler = style,
+            GateFamily::Measurement => self.measurement = style,
+            GateFamily::Noise => self.noise = style,
+            GateFamily::Bosonic => self.bosonic = style,
+            GateFamily::Other => (),
+        }
+        self
+    }
+
+    /// Returns the style of a gate family.
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The gate family.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&GateStyle)` - The style of the gates of the family.
+    /// * `None` - The gates of the family are never styled.
+    pub fn style(&self, family: GateFamily) -> Option<&GateStyle> {
+        match family {
+            GateFamily::Rotation => Some(&self.rotation),
+            GateFamily::Clifford => Some(&self.clifford),
+            GateFamily::Entangler => Some(&self.entangler),
+            GateFamily::Measurement => Some(&self.measurement),
+            GateFamily::Noise => Some(&self.noise),
+            GateFamily::Bosonic => Some(&self.bosonic),
+            GateFamily::Other => None,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = RoqoqoBackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "default" | "" => Ok(Theme::default()),
+            "light" => Ok(Theme::light()),
+            "dark" => Ok(Theme::dark()),
+            "high_contrast" => Ok(Theme::high_contrast()),
+            "colour_blind" | "color_blind" => Ok(Theme::colour_blind()),
+            _ => Err(RoqoqoBackendError::RoqoqoError(RoqoqoError::GenericError {
+                msg: format!(
+                    r#"Invalid theme: {s}, use `default`, `light`, `dark`, `high_contrast` or `colour_blind`."#
+                ),
+            })),
+        }
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::DynamicImage;
+use qoqo_calculator::CalculatorFloat;
+use roqoqo::{
+    devices::Device,
+    operations::{InvolveQubits, InvolvedQubits, Operate, Operation},
+    Circuit, RoqoqoBackendError,
+};
+
+use crate::{
+    add_gate, render_typst_str, typst_header, CircuitLayout, LayoutItem, RenderOptions, WireRef,
+};
+
+/// Space left of the wires for the qubit names, in points.
+const LEFT_MARGIN: f64 = 36.0;
+/// Space right of the wires, in points.
+const RIGHT_MARGIN: f64 = 16.0;
+/// Space above the first wire for the labels of the instantaneous operations, in points.
+const TOP_MARGIN: f64 = 24.0;
+/// Distance between two wires, in points.
+const ROW_HEIGHT: f64 = 32.0;
+/// Height of the gate boxes, in points.
+const BOX_HEIGHT: f64 = 18.0;
+/// Width of the shortest operation of the circuit, in points.
+const MIN_BOX_WIDTH: f64 = 28.0;
+/// Largest width of the time axis, in points.
+const MAX_AXIS_WIDTH: f64 = 800.0;
+/// Width of the time axis when the circuit has no duration, in points.
+const EMPTY_AXIS_WIDTH: f64 = 100.0;
+/// Durations below this value are considered to be zero.
+const EPSILON: f64 = 1e-12;
+
+/// Operation of a circuit placed on the timeline of its qubits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedOperation {
+    /// The index of the operation in the circuit.
+    pub index: usize,
+    /// The hqslang name of the operation.
+    pub hqslang: String,
+    /// The qubits of the operation, sorted.
+    pub qubits: Vec<usize>,
+    /// The time at which the operation starts.
+    pub start: f64,
+    /// The duration of the operation, zero for the instantaneous operations.
+    pub duration: f64,
+}
+
+impl TimedOperation {
+    /// Returns the time at which the operation ends.
+    pub fn end(&self) -> f64 {
+        self.start + self.duration
+    }
+}
+
+/// Returns the duration of an operation on a device.
+///
+/// The gates last their gate time on the device, PragmaSleep and PragmaStopParallelBlock their
+/// time argument. The other operations, e.g. the measurements, are instantaneous.
+///
+/// # Arguments
+///
+/// * `operation` - The operation.
+/// * `qubits` - The sorted qubits of the operation.
+/// * `device` - The device giving the gate times.
+///
+/// # Returns
+///
+/// * `Ok(f64)` - The duration of the operation.
+/// * `Err(String)` - The duration is unknown, the operation is considered instantaneous.
+fn operation_duration<D: Device + ?Sized>(
+    operation: &Operation,
+    qubits: &[usize],
+    device: &D,
+) -> Result<f64, String> {
+    let time = match operation {
+        Operation::PragmaSleep(op) => Some(op.sleep_time()),
+        Operation::PragmaStopParallelBlock(op) => Some(op.execution_time()),
+        _ => None,
+    };
+    if let Some(time) = time {
+        return match time {
+            CalculatorFloat::Float(time) => Ok(*time),
+            CalculatorFloat::Str(time) => Err(format!(
+                "{} has the symbolic duration {time}, drawn as instantaneous",
+                operation.hqslang()
+            )),
+        };
+    }
+    if !operation.tags().contains(&"GateOperation") {
+        return Ok(0.0);
+    }
+    let hqslang = operation.hqslang();
+    let time = match qubits {
+        [] => Some(0.0),
+        [qubit] => device.single_qubit_gate_time(hqslang, qubit),
+        [control, target] => device
+            .two_qubit_gate_time(hqslang, control, target)
+            .or_else(|| device.two_qubit_gate_time(hqslang, target, control)),
+        [first, second, third] => device
+            .three_qubit_gate_time(hqslang, first, second, third)
+            .or_else(|| device.multi_qubit_gate_time(hqslang, qubits)),
+        _ => device.multi_qubit_gate_time(hqslang, qubits),
+    };
+    time.ok_or_else(|| {
+        format!(
+            "{hqslang} on qubits {qubits:?} has no gate time on the device, drawn as instantaneous"
+        )
+    })
+}
+
+/// Schedules the operations of a circuit as soon as their qubits are available.
+///
+/// Every qubit has its own clock, an operation starts when the last of its qubits is done. The
+/// operations acting on all the qubits synchronise all the clocks.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to schedule.
+/// * `device` - The device giving the gate times.
+///
+/// # Returns
+///
+/// * `(Vec<TimedOperation>, Vec<String>)` - The operations acting on qubits, in the order of the
+///   circuit, and the warnings for the operations with an unknown duration.
+pub fn circuit_schedule<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+) -> (Vec<TimedOperation>, Vec<String>) {
+    let n_qubits = circuit
+        .iter()
+        .filter_map(|operation| match operation.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.into_iter().max(),
+            _ => None,
+        })
+        .max()
+        .map_or(0, |qubit| qubit + 1);
+    let mut clocks = vec![0.0_f64; n_qubits];
+    let mut schedule = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, operation) in circuit.iter().enumerate() {
+        let mut qubits: Vec<usize> = match operation.involved_qubits() {
+            InvolvedQubits::Set(qubits) => qubits.into_iter().collect(),
+            InvolvedQubits::All => (0..n_qubits).collect(),
+            InvolvedQubits::None => continue,
+        };
+        if qubits.is_empty() {
+            continue;
+        }
+        qubits.sort_unstable();
+        let duration = operation_duration(operation, &qubits, device).unwrap_or_else(|warning| {
+            warnings.push(warning);
+            0.0
+        });
+        let start = qubits
+            .iter()
+            .map(|&qubit| clocks[qubit])
+            .fold(0.0_f64, f64::max);
+        for &qubit in qubits.iter() {
+            clocks[qubit] = start + duration;
+        }
+        schedule.push(TimedOperation {
+            index,
+            hqslang: operation.hqslang().to_owned(),
+            qubits,
+            start,
+            duration,
+        });
+    }
+    (schedule, warnings)
+}
+
+/// Returns the label of an operation on the timeline, taken from its circuit diagram.
+///
+/// # Arguments
+///
+/// * `operation` - The operation.
+/// * `options` - The options of the representation, e.g. the pragmas to render.
+///
+/// # Returns
+///
+/// * `Some(String)` - The typst markup of the label, the content of the gate box or the name of
+///   the operation.
+/// * `None` - The operation is not drawn, e.g. a pragma that is not rendered.
+fn operation_label(operation: &Operation, options: &RenderOptions) -> Option<String> {
+    let mut layout = CircuitLayout::new();
+    if add_gate(&mut layout, operation, options).is_err() {
+        return Some(operation.hqslang().to_owned());
+    }
+    let items: Vec<&LayoutItem> = layout
+        .qubits
+        .iter()
+        .chain(layout.bosons.iter())
+        .chain(layout.classical.iter())
+        .flatten()
+        .filter(|item| item.is_column() && !matches!(item, LayoutItem::Idle))
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            LayoutItem::Gate { content, .. } | LayoutItem::MultiGate { content, .. } => {
+                Some(format!("$ {content} $"))
+            }
+            _ => None,
+        })
+        .or_else(|| Some(operation.hqslang().to_owned()))
+}
+
+/// Chooses the step between two ticks of the time axis, 1, 2 or 5 times a power of ten.
+///
+/// # Arguments
+///
+/// * `total` - The total duration of the circuit.
+///
+/// # Returns
+///
+/// * `(f64, usize)` - The step and the number of decimals of the tick labels.
+fn tick_step(total: f64) -> (f64, usize) {
+    let raw = total / 6.0;
+    let magnitude = 10_f64.powf(raw.log10().floor());
+    let step = magnitude
+        * match raw / magnitude {
+            norm if norm < 1.5 => 1.0,
+            norm if norm < 3.5 => 2.0,
+            norm if norm < 7.5 => 5.0,
+            _ => 10.0,
+        };
+    (step, (-step.log10().floor()).max(0.0) as usize)
+}
+
+/// Lists the periods during which a qubit is idle.
+///
+/// # Arguments
+///
+/// * `schedule` - The scheduled operations.
+/// * `qubit` - The qubit.
+/// * `total` - The total duration of the circuit.
+///
+/// # Returns
+///
+/// * `Vec<(f64, f64)>` - The start and the end of each idle period.
+fn idle_periods(schedule: &[TimedOperation], qubit: usize, total: f64) -> Vec<(f64, f64)> {
+    let mut busy: Vec<(f64, f64)> = schedule
+        .iter()
+        .filter(|timed| timed.duration > EPSILON && timed.qubits.contains(&qubit))
+        .map(|timed| (timed.start, timed.end()))
+        .collect();
+    busy.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut periods = Vec::new();
+    let mut time = 0.0;
+    for (start, end) in busy {
+        if start - time > EPSILON {
+            periods.push((time, start));
+        }
+        time = f64::max(time, end);
+    }
+    if total - time > EPSILON {
+        periods.push((time, total));
+    }
+    periods
+}
+
+/// Converts a qoqo circuit to a typst string drawing its operations on a timeline, with the
+/// warnings emitted during the conversion.
+///
+/// The width of the operations is proportional to their duration, taken from the gate times of
+/// the device and from the time of the PragmaSleep and PragmaStopParallelBlock operations. The
+/// instantaneous operations, e.g. the measurements, are drawn as markers. The idle periods of the
+/// qubits are shaded and a time axis is drawn below the circuit.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device giving the gate times.
+/// * `options` - The options of the representation, e.g. the pragmas to render.
+///
+/// # Returns
+///
+/// * `(String, Vec<String>)` - The typst string of the timeline and the warnings for the
+///   operations with an unknown duration.
+pub fn circuit_into_timeline_typst_str_with_warnings<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> (String, Vec<String>) {
+    let (schedule, warnings) = circuit_schedule(circuit, device);
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    let n_qubits = schedule
+        .iter()
+        .flat_map(|timed| timed.qubits.iter())
+        .max()
+        .map_or(0, |qubit| qubit + 1);
+    let total = schedule.iter().map(TimedOperation::end).fold(0.0, f64::max);
+    let min_duration = schedule
+        .iter()
+        .map(|timed| timed.duration)
+        .filter(|&duration| duration > EPSILON)
+        .fold(f64::INFINITY, f64::min);
+    let scale = if total > EPSILON {
+        (MIN_BOX_WIDTH / min_duration).min(MAX_AXIS_WIDTH / total)
+    } else {
+        0.0
+    };
+    let axis_width = if total > EPSILON {
+        total * scale
+    } else {
+        EMPTY_AXIS_WIDTH
+    };
+    let wire_y = |qubit: usize| TOP_MARGIN + qubit as f64 * ROW_HEIGHT;
+    let time_x = |time: f64| LEFT_MARGIN + time * scale;
+    let axis_y = wire_y(n_qubits.max(1) - 1) + BOX_HEIGHT;
+
+    let mut typst_str = format!(
+        "{}#box(width: {:.1}pt, height: {:.1}pt)[\n",
+        typst_header(options),
+        LEFT_MARGIN + axis_width + RIGHT_MARGIN,
+        axis_y + 28.0
+    );
+    for qubit in 0..n_qubits {
+        let y = wire_y(qubit);
+        typst_str.push_str(&format!(
+            "#place(top + left, dx: 2pt, dy: {:.1}pt, text(size: 8pt)[${}$])\n",
+            y - 5.0,
+            options.wire_labels.name(WireRef::Qubit(qubit)).to_typst()
+        ));
+        typst_str.push_str(&format!(
+            "#place(top + left, line(start: ({LEFT_MARGIN:.1}pt, {y:.1}pt), end: ({:.1}pt, {y:.1}pt), stroke: 0.6pt))\n",
+            LEFT_MARGIN + axis_width
+        ));
+        for (start, end) in idle_periods(&schedule, qubit, total) {
+            typst_str.push_str(&format!(
+                "#place(top + left, dx: {:.1}pt, dy: {:.1}pt, rect(width: {:.1}pt, height: 6pt, fill: rgb(255, 183, 77, 120), stroke: none))\n",
+                time_x(start),
+                y - 3.0,
+                (end - start) * scale
+            ));
+        }
+    }
+    // The multi-qubit operations are drawn as a box on each of their qubits, joined by a line
+    // drawn below all the boxes, as the qubits in between may be busy at the same time.
+    let mut links = String::new();
+    let mut boxes = String::new();
+    let mut markers = Vec::new();
+    for timed in schedule.iter() {
+        let Some(label) = operation_label(operations[timed.index], options) else {
+            continue;
+        };
+        let top = wire_y(timed.qubits[0]);
+        let bottom = wire_y(*timed.qubits.last().unwrap_or(&timed.qubits[0]));
+        let x = time_x(timed.start);
+        if timed.duration > EPSILON {
+            let stroke = if matches!(operations[timed.index], Operation::PragmaSleep(_)) {
+                "(thickness: 0.6pt, dash: \"dashed\")"
+            } else {
+                "0.6pt"
+            };
+            let width = timed.duration * scale;
+            if bottom > top {
+                links.push_str(&format!(
+                    "#place(top + left, line(start: ({0:.1}pt, {top:.1}pt), end: ({0:.1}pt, {bottom:.1}pt), stroke: {stroke}))\n",
+                    x + width / 2.0
+                ));
+            }
+            for &qubit in timed.qubits.iter() {
+                boxes.push_str(&format!(
+                    "#place(top + left, dx: {x:.1}pt, dy: {:.1}pt, rect(width: {width:.1}pt, height: {BOX_HEIGHT:.1}pt, fill: rgb(220, 232, 250), stroke: {stroke}, inset: 1pt, align(center + horizon, text(size: 7pt, fill: black)[{label}])))\n",
+                    wire_y(qubit) - BOX_HEIGHT / 2.0
+                ));
+            }
+        } else {
+            markers.push((x, top, bottom, label));
+        }
+    }
+    typst_str.push_str(&links);
+    typst_str.push_str(&boxes);
+    let marker_fill = if options.background.is_dark() {
+        "white"
+    } else {
+        "black"
+    };
+    for (x, top, bottom, label) in markers {
+        typst_str.push_str(&format!(
+            "#place(top + left, dx: {:.1}pt, dy: {:.1}pt, rect(width: 2pt, height: {:.1}pt, fill: {marker_fill}))\n",
+            x - 1.0,
+            top - BOX_HEIGHT / 2.0,
+            bottom - top + BOX_HEIGHT
+        ));
+        typst_str.push_str(&format!(
+            "#place(top + left, dx: {:.1}pt, dy: {:.1}pt, box(width: 60pt, align(center, text(size: 6pt)[{label}])))\n",
+            x - 30.0,
+            top - BOX_HEIGHT / 2.0 - 12.0
+        ));
+    }
+    typst_str.push_str(&format!(
+        "#place(top + left, line(start: ({LEFT_MARGIN:.1}pt, {axis_y:.1}pt), end: ({:.1}pt, {axis_y:.1}pt), stroke: 0.6pt))\n",
+        LEFT_MARGIN + axis_width
+    ));
+    if total > EPSILON {
+        let (step, decimals) = tick_step(total);
+        let mut tick = 0;
+        while tick as f64 * step <= total * (1.0 + 1e-9) {
+            let time = tick as f64 * step;
+            let x = time_x(time);
+            typst_str.push_str(&format!(
+                "#place(top + left, line(start: ({x:.1}pt, {axis_y:.1}pt), end: ({x:.1}pt, {:.1}pt), stroke: 0.6pt))\n",
+                axis_y + 4.0
+            ));
+            typst_str.push_str(&format!(
+                "#place(top + left, dx: {:.1}pt, dy: {:.1}pt, box(width: 30pt, align(center, text(size: 6pt)[{time:.decimals$}])))\n",
+                x - 15.0,
+                axis_y + 6.0
+            ));
+            tick += 1;
+        }
+    }
+    typst_str.push_str(&format!(
+        "#place(top + left, dx: {:.1}pt, dy: {:.1}pt, text(size: 7pt)[time])\n",
+        LEFT_MARGIN + axis_width - 16.0,
+        axis_y + 16.0
+    ));
+    typst_str.push_str("]\n");
+    (typst_str, warnings)
+}
+
+/// Converts a qoqo circuit to a typst string drawing its operations on a timeline.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device giving the gate times.
+/// * `options` - The options of the representation, e.g. the pragmas to render.
+///
+/// # Returns
+///
+/// * `String` - The typst string of the timeline.
+pub fn circuit_into_timeline_typst_str<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> String {
+    circuit_into_timeline_typst_str_with_warnings(circuit, device, options).0
+}
+
+/// Converts a qoqo circuit to an image of its operations on a timeline.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to convert.
+/// * `device` - The device giving the gate times.
+/// * `options` - The options of the representation and of the rendering.
+///
+/// # Returns
+///
+/// * `Ok(DynamicImage)` - The image of the timeline.
+/// * `Err(RoqoqoBackendError)` - The typst string of the timeline cannot be compiled.
+pub fn circuit_to_timeline_image<D: Device + ?Sized>(
+    circuit: &Circuit,
+    device: &D,
+    options: &RenderOptions,
+) -> Result<DynamicImage, RoqoqoBackendError> {
+    render_typst_str(
+        circuit_into_timeline_typst_str(circuit, device, options),
+        options,
+    )
+}
@@ -0,0 +1,268 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the subset, order and hiding of the qubit wires
+
+use std::str::FromStr;
+
+use roqollage::{
+    circuit_into_layout, circuit_into_quantikz_str, circuit_into_typst_str, circuit_to_image,
+    circuit_to_text, GateRendererRegistry, LayoutItem, QubitOrder, RenderOptions, TypstRenderer,
+    WireLabels, WireRef,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+use test_case::test_case;
+
+use crate::common::qubit_mode;
+
+fn assert_labels(text: &str, labels: &[&str]) {
+    let lines: Vec<&str> = text.lines().step_by(2).collect();
+    assert_eq!(lines.len(), labels.len());
+    for (line, label) in lines.iter().zip(labels) {
+        assert!(line.starts_with(label));
+    }
+}
+
+/// Test that the idle qubits are hidden
+#[test]
+fn test_hide_idle_qubits() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 40));
+
+    let layout = circuit_into_layout(&circuit, &RenderOptions::new()).unwrap();
+    assert_eq!(layout.qubits.len(), 41);
+
+    let options = qubit_mode().hide_idle_qubits(true);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubits.len(), 2);
+    assert_eq!(layout.qubit_indices, vec![0, 40]);
+    assert!(layout.qubits[0].contains(&LayoutItem::control(WireRef::Qubit(1))));
+
+    let typst_str = circuit_into_typst_str(&circuit, &options).unwrap();
+    assert!(typst_str.contains("lstick($q[0]$, label: \"Qubits\")"));
+    assert!(typst_str.contains("lstick($q[40]$)"));
+    assert!(!typst_str.contains("q[1]"));
+    assert_labels(
+        &circuit_to_text(&circuit, &options).unwrap(),
+        &["q[0]", "q[40]"],
+    );
+}
+
+/// Test that the controls and the multi-qubit boxes follow the reordered wires
+#[test]
+fn test_qubit_order() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(MultiQubitMS::new(vec![1, 2], 0.1.into()));
+
+    let options = qubit_mode().qubit_order(QubitOrder::Reversed);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubit_indices, vec![2, 1, 0]);
+    assert!(layout.qubits[2].contains(&LayoutItem::control(WireRef::Qubit(1))));
+    assert!(layout.qubits[1].contains(&LayoutItem::Target));
+    assert!(layout.qubits[0]
+        .iter()
+        .any(|item| matches!(item, LayoutItem::MultiGate { .. })));
+    assert_labels(
+        &circuit_to_text(&circuit, &options).unwrap(),
+        &["q[2]", "q[1]", "q[0]"],
+    );
+
+    let options = qubit_mode()
+        .qubit_order(QubitOrder::Custom(vec![1, 7]))
+        .wire_labels(WireLabels::new().qubit(1, "ancilla"));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubit_indices, vec![1, 0, 2]);
+    assert!(layout.qubits[1].contains(&LayoutItem::control(WireRef::Qubit(0))));
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &options).unwrap();
+    assert!(quantikz_str.starts_with("\\begin{quantikz}\n\\lstick{$\\text{ancilla}$}"));
+}
+
+/// Test that the operations are cut at the boundary of the qubit subset
+#[test]
+fn test_qubit_subset() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PauliX::new(2));
+    circuit.add_operation(MeasureQubit::new(2, "ro".to_owned(), 0));
+
+    let options = qubit_mode().qubit_subset(Some(vec![1, 0]));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubit_indices, vec![0, 1]);
+    let typst_str = circuit_into_typst_str(&circuit, &options).unwrap();
+    assert!(!typst_str.contains("gate($X$"));
+    assert!(!typst_str.contains("meter"));
+
+    // The CNOT with a hidden control is drawn as a single-wire box on its target.
+    let options = qubit_mode().qubit_subset(Some(vec![1]));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubits.len(), 1);
+    assert_eq!(
+        layout.qubits[0],
+        vec![LayoutItem::Gate {
+            content: "\"CNOT\"".to_owned(),
+            label: Some("with q[0]".to_owned()),
+            fill: None,
+            stroke: None,
+        }]
+    );
+    let typst_str = circuit_into_typst_str(&circuit, &options).unwrap();
+    assert!(typst_str.contains(r#"gate($ "CNOT" $, label: "with q[0]")"#));
+    assert!(!typst_str.contains("ctrl("));
+    assert!(!typst_str.contains("targ("));
+
+    // The wires between the drawn qubit and the hidden partner are left free.
+    let mut crossing = Circuit::new();
+    crossing.add_operation(CNOT::new(0, 2));
+    crossing.add_operation(SWAP::new(2, 1));
+    crossing.add_operation(Hadamard::new(1));
+    let options = qubit_mode().qubit_subset(Some(vec![0, 1]));
+    let layout = circuit_into_layout(&crossing, &options).unwrap();
+    assert_eq!(layout.qubits[0].len(), 2);
+    assert_eq!(layout.qubits[0][1], LayoutItem::Idle);
+    assert!(matches!(
+        &layout.qubits[0][0],
+        LayoutItem::Gate { content, label: Some(label), .. }
+            if content == "\"CNOT\"" && label == "with q[2]"
+    ));
+    assert!(matches!(
+        &layout.qubits[1][0],
+        LayoutItem::Gate { content, label: Some(label), .. }
+            if content == "\"SWAP\"" && label == "with q[2]"
+    ));
+    assert!(layout.qubits.iter().flatten().all(|item| !matches!(
+        item,
+        LayoutItem::Control { .. } | LayoutItem::Swap { .. } | LayoutItem::Target
+    )));
+
+    // An idle qubit of the subset is drawn unless the idle qubits are hidden.
+    let options = qubit_mode().qubit_subset(Some(vec![0, 5]));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.qubit_indices, vec![0, 5]);
+    let layout = circuit_into_layout(&circuit, &options.hide_idle_qubits(true)).unwrap();
+    assert_eq!(layout.qubit_indices, vec![0]);
+}
+
+/// Test that the boxes of the operations with hidden qubits keep the content of the gates
+#[test]
+fn test_qubit_subset_cut_content() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(MultiQubitMS::new(vec![0, 1, 2], 1.0.into()));
+    circuit.add_operation(ControlledPhaseShift::new(0, 2, 0.5.into()));
+    circuit.add_operation(ControlledPauliY::new(2, 1));
+    let options = qubit_mode().qubit_subset(Some(vec![0, 1]));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    let contents: Vec<(usize, &str)> = layout
+        .qubits
+        .iter()
+        .enumerate()
+        .flat_map(|(wire, items)| {
+            items.iter().filter_map(move |item| match item {
+                LayoutItem::Gate {
+                    content,
+                    label: Some(label),
+                    ..
+                } if label == "with q[2]" => Some((wire, content.as_str())),
+                _ => None,
+            })
+        })
+        .collect();
+    assert_eq!(
+        contents,
+        vec![
+            (0, r#""MultiQubitMS"(1.0)"#),
+            (0, r#""ControlledPhaseShift"(0.5)"#),
+            (1, r#""MultiQubitMS"(1.0)"#),
+            (1, r#""ControlledPauliY""#),
+        ]
+    );
+}
+
+/// Test that the raw Typst items are only drawn when the arrangement keeps their wire offsets
+#[test]
+fn test_arrangement_typst_renderer() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(CNOT::new(1, 2));
+    let mut registry = GateRendererRegistry::new();
+    registry.register(
+        "CNOT",
+        TypstRenderer::new(vec!["ctrl(1)".to_owned(), "targ()".to_owned()]),
+    );
+    let options = qubit_mode().gate_renderers(registry);
+
+    let typst_str =
+        circuit_into_typst_str(&circuit, &options.clone().hide_idle_qubits(true)).unwrap();
+    assert!(typst_str.contains("ctrl(1)"));
+    assert!(
+        circuit_into_typst_str(&circuit, &options.clone().qubit_subset(Some(vec![1, 2]))).is_ok()
+    );
+
+    let result =
+        circuit_into_typst_str(&circuit, &options.clone().qubit_order(QubitOrder::Reversed));
+    assert!(result.is_err());
+    assert!(format!("{:?}", result.unwrap_err()).contains("raw Typst items of CNOT"));
+    let mut separated = Circuit::new();
+    separated.add_operation(CNOT::new(0, 2));
+    assert!(circuit_into_typst_str(&separated, &options.clone().hide_idle_qubits(true)).is_err());
+
+    // The operations cut at the drawn qubits are drawn as boxes instead.
+    let typst_str = circuit_into_typst_str(&circuit, &options.qubit_subset(Some(vec![1]))).unwrap();
+    assert!(typst_str.contains(r#"gate($ "CNOT" $, label: "with q[2]")"#));
+}
+
+/// Test the parsing of the qubit orders
+#[test_case("ascending", QubitOrder::Ascending; "ascending")]
+#[test_case("Reversed", QubitOrder::Reversed; "reversed")]
+#[test_case("descending", QubitOrder::Reversed; "descending")]
+#[test_case("2, 0,1", QubitOrder::Custom(vec![2, 0, 1]); "custom")]
+fn test_qubit_order_from_str(order: &str, expected: QubitOrder) {
+    assert_eq!(QubitOrder::from_str(order).unwrap(), expected);
+}
+
+/// Test that the invalid qubit orders are rejected
+#[test]
+fn test_qubit_order_from_str_error() {
+    assert!(QubitOrder::from_str("upside-down").is_err());
+    assert!(QubitOrder::from_str("0,-1").is_err());
+}
+
+/// Test the (de)serialization of the qubit options
+#[test]
+fn test_arrangement_serde() {
+    let options = RenderOptions::new()
+        .qubit_order(QubitOrder::Custom(vec![3, 1]))
+        .qubit_subset(Some(vec![1, 3]))
+        .hide_idle_qubits(true);
+    let serialized = serde_json::to_string(&options).unwrap();
+    let deserialized: RenderOptions = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized.qubit_order, QubitOrder::Custom(vec![3, 1]));
+    assert_eq!(deserialized.qubit_subset, Some(vec![1, 3]));
+    assert!(deserialized.hide_idle_qubits);
+}
+
+/// Test that the rearranged circuit renders
+#[test]
+#[serial]
+fn test_arrangement_image() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Toffoli::new(0, 3, 6));
+    circuit.add_operation(SWAP::new(6, 9));
+    let options = RenderOptions::new()
+        .qubit_order(QubitOrder::Reversed)
+        .qubit_subset(Some(vec![0, 3, 6]))
+        .hide_idle_qubits(true);
+    assert!(circuit_to_image(&circuit, &options).is_ok());
+}
@@ -16,10 +16,14 @@ use std::f32::consts::PI;
 
 use qoqo_calculator::CalculatorFloat;
 use roqollage::{
-    circuit_into_typst_str, circuit_to_image, InitializationMode, RenderPragmas, TypstBackend,
+    circuit_into_quantikz_str, circuit_into_typst_str, circuit_into_typst_str_with_warnings,
+    circuit_to_image, circuit_to_pdf, circuit_to_svg, Background, CacheDirectory,
+    GateRendererRegistry, GenericRenderer, InitializationMode, NetworkPolicy, RenderOptions,
+    RenderPragmas, TypstBackend, TypstBackendConfig,
 };
 use roqoqo::{operations::*, Circuit};
 use serial_test::serial;
+use std::{path::PathBuf, str::FromStr};
 use typst::layout::PagedDocument;
 
 #[test]
@@ -47,13 +51,16 @@ fn test_str() {
     circuit.add_operation(SWAP::new(2, 1));
     circuit.add_operation(Toffoli::new(0, 1, 4));
 
-    circuit_into_typst_str(&circuit, RenderPragmas::None, None, None, None).unwrap();
     circuit_into_typst_str(
         &circuit,
-        RenderPragmas::None,
-        Some(InitializationMode::Qubit),
-        None,
-        None,
+        &RenderOptions::new().render_pragmas(RenderPragmas::None),
+    )
+    .unwrap();
+    circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::Qubit),
     )
     .unwrap();
 }
@@ -77,15 +84,65 @@ fn test_max_len() {
     circuit.add_operation(SWAP::new(2, 1));
     circuit.add_operation(Toffoli::new(0, 1, 4));
 
-    circuit_into_typst_str(&circuit, RenderPragmas::None, None, None, None).unwrap();
     circuit_into_typst_str(
         &circuit,
-        RenderPragmas::None,
-        Some(InitializationMode::Qubit),
-        Some(20),
-        None,
+        &RenderOptions::new().render_pragmas(RenderPragmas::None),
+    )
+    .unwrap();
+    circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::Qubit)
+            .max_length(Some(20)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_quantikz() {
+    let mut circuit = Circuit::new();
+    let mut loop_circuit = Circuit::new();
+    loop_circuit.add_operation(Hadamard::new(1));
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(RotateX::new(1, CalculatorFloat::FRAC_PI_2));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(SWAP::new(0, 1));
+    circuit.add_operation(PragmaGlobalPhase::new(CalculatorFloat::from("theta")));
+    circuit.add_operation(PragmaLoop::new(CalculatorFloat::from(2.0), loop_circuit));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 1));
+
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &RenderOptions::new()).unwrap();
+    assert!(quantikz_str.starts_with("\\begin{quantikz}\n\\lstick{$\\ket{0}$} & \\gate{H}"));
+    assert!(quantikz_str.ends_with("\\end{quantikz}\n"));
+    assert!(quantikz_str.contains("\\gate{\\text{Rx}(\\pi /2)}"));
+    assert!(quantikz_str.contains("\\ctrl{1}"));
+    assert!(quantikz_str.contains("\\targ{}"));
+    assert!(quantikz_str.contains("\\swap{1}"));
+    assert!(quantikz_str.contains("\\targX{}"));
+    assert!(quantikz_str.contains("\\slice{$\\text{GlobalPhase}\\ \\theta $}"));
+    assert!(quantikz_str.contains("\\gategroup[1,steps="));
+    assert!(quantikz_str.contains("\\meter{} \\vcw{2}"));
+    assert!(quantikz_str.contains("\\lstick{ro} \\setwiretype{c}"));
+    assert!(quantikz_str.contains("\\push{$1$}"));
+    assert_eq!(quantikz_str.matches("\\begin{quantikz}").count(), 1);
+    assert_eq!(quantikz_str.matches(" \\\\\n").count(), 2);
+
+    let quantikz_str = circuit_into_quantikz_str(
+        &circuit,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::Qubit)
+            .max_length(Some(2)),
     )
     .unwrap();
+    assert!(quantikz_str.starts_with("\\begin{quantikz}\n\\lstick{$q[0]$}"));
+    assert!(!quantikz_str.contains("GlobalPhase"));
+    assert!(quantikz_str.matches("\\begin{quantikz}").count() > 1);
+    assert!(quantikz_str.contains("\\rstick{$\\cdots$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\cdots q[0]$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\cdots \\text{ro}$} \\setwiretype{c}"));
 }
 
 #[test]
@@ -135,25 +192,220 @@ fn test_image() {
 
     circuit_to_image(
         &circuit,
-        None,
-        RenderPragmas::None,
-        Some(InitializationMode::State),
-        None,
-        None,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::State),
     )
     .unwrap();
 
     circuit_to_image(
         &circuit,
-        None,
-        RenderPragmas::Partial(vec!["PragmaLoop".to_owned()]),
-        Some(InitializationMode::Qubit),
-        None,
-        None,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::Partial(vec!["PragmaLoop".to_owned()]))
+            .initialization_mode(InitializationMode::Qubit),
     )
     .unwrap();
 }
 
+#[test]
+#[serial]
+fn test_svg() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(RotateX::new(1, CalculatorFloat::from("theta")));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 1));
+
+    let svg = circuit_to_svg(
+        &circuit,
+        &RenderOptions::new().initialization_mode(InitializationMode::State),
+    )
+    .unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+#[serial]
+fn test_pdf() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(RotateX::new(1, CalculatorFloat::from("theta")));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 1));
+
+    let pdf = circuit_to_pdf(
+        &circuit,
+        &RenderOptions::new().initialization_mode(InitializationMode::Qubit),
+    )
+    .unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+}
+
+#[test]
+fn test_backend_config_from_str() {
+    assert_eq!(
+        CacheDirectory::from_str("default").unwrap(),
+        CacheDirectory::Default
+    );
+    assert_eq!(
+        CacheDirectory::from_str("user").unwrap(),
+        CacheDirectory::User
+    );
+    assert_eq!(
+        CacheDirectory::from_str("/tmp/qollage").unwrap(),
+        CacheDirectory::Path(PathBuf::from("/tmp/qollage"))
+    );
+    assert_eq!(
+        NetworkPolicy::from_str("allow").unwrap(),
+        NetworkPolicy::Allow
+    );
+    assert_eq!(
+        NetworkPolicy::from_str("Deny").unwrap(),
+        NetworkPolicy::Deny
+    );
+    assert_eq!(
+        NetworkPolicy::from_str("http://localhost:8080/").unwrap(),
+        NetworkPolicy::Registry("http://localhost:8080/".to_owned())
+    );
+    assert!(NetworkPolicy::from_str("sometimes").is_err());
+
+    let config = TypstBackendConfig::new()
+        .cache_directory(CacheDirectory::Path(PathBuf::from("custom_cache")))
+        .network_policy(NetworkPolicy::Deny);
+    assert_eq!(config.cache_path().unwrap(), PathBuf::from("custom_cache"));
+}
+
+#[test]
+fn test_render_options_serde() {
+    let options = RenderOptions::new()
+        .pixels_per_point(2.0)
+        .render_pragmas(RenderPragmas::Partial(vec!["PragmaLoop".to_owned()]))
+        .initialization_mode(InitializationMode::Qubit)
+        .max_length(Some(10))
+        .rounding_accuracy(5)
+        .backend_config(TypstBackendConfig::new().network_policy(NetworkPolicy::Deny));
+    let serialized = serde_json::to_string(&options).unwrap();
+    let deserialized: RenderOptions = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, options);
+
+    let preset: RenderOptions =
+        serde_json::from_str(r#"{"initialization_mode": "Qubit", "max_length": 20}"#).unwrap();
+    assert_eq!(
+        preset,
+        RenderOptions::new()
+            .initialization_mode(InitializationMode::Qubit)
+            .max_length(Some(20))
+    );
+    assert_eq!(
+        serde_json::from_str::<RenderOptions>("{}").unwrap(),
+        RenderOptions::default()
+    );
+}
+
+#[test]
+#[serial]
+fn test_network_policy_deny() {
+    let cache_dir = std::env::temp_dir().join("qollage_test_network_policy_deny");
+    if cache_dir.exists() {
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    let config = TypstBackendConfig::new()
+        .cache_directory(CacheDirectory::Path(cache_dir.clone()))
+        .network_policy(NetworkPolicy::Deny);
+
+    let result = circuit_to_image(&circuit, &RenderOptions::new().backend_config(config));
+    #[cfg(not(feature = "vendored_quill"))]
+    assert!(result.is_err());
+    #[cfg(feature = "vendored_quill")]
+    assert!(result.is_ok());
+    assert!(!cache_dir.join("preview").exists());
+}
+
+#[cfg(feature = "vendored_quill")]
+#[test]
+#[serial]
+fn test_vendored_quill() {
+    let cache_dir = std::env::temp_dir().join("qollage_test_vendored_quill");
+    if cache_dir.exists() {
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    let options = RenderOptions::new().backend_config(
+        TypstBackendConfig::new()
+            .cache_directory(CacheDirectory::Path(cache_dir.clone()))
+            .network_policy(NetworkPolicy::Deny),
+    );
+
+    circuit_to_image(&circuit, &options).unwrap();
+    assert!(circuit_to_svg(&circuit, &options)
+        .unwrap()
+        .starts_with("<svg"));
+    assert!(circuit_to_pdf(&circuit, &options)
+        .unwrap()
+        .starts_with(b"%PDF"));
+    assert!(!cache_dir.exists());
+}
+
+#[test]
+fn test_fallback_unsupported() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(GPi2::new(1, CalculatorFloat::from(0.5)));
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(CNOT::new(1, 2));
+
+    let mut registry = GateRendererRegistry::new();
+    registry.mark_unsupported("CNOT");
+    assert!(circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new().gate_renderers(registry.clone())
+    )
+    .is_err());
+    let (typst_str, warnings) = circuit_into_typst_str_with_warnings(
+        &circuit,
+        &RenderOptions::new()
+            .gate_renderers(registry)
+            .fallback_unsupported(true),
+    )
+    .unwrap();
+    assert!(typst_str.contains(
+        r#"mqgate($ "CNOT" $, n: 3, inputs: ((qubit: 0, label: "x"),(qubit: 2, label: "x")))"#
+    ));
+    assert!(typst_str.contains(r#"$ "GPi2"(0.5) $"#));
+    assert!(!typst_str.contains("targ()"));
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("CNOT"));
+
+    let mut registry = GateRendererRegistry::new();
+    registry.register("GPi2", GenericRenderer);
+    let (typst_str, warnings) = circuit_into_typst_str_with_warnings(
+        &circuit,
+        &RenderOptions::new()
+            .gate_renderers(registry)
+            .fallback_unsupported(true),
+    )
+    .unwrap();
+    assert!(typst_str.contains(r#"$ "GPi2" $"#));
+    assert!(warnings.is_empty());
+
+    let mut circuit = Circuit::new();
+    circuit.add_operation(MultiQubitMS::new(vec![], CalculatorFloat::from(0.5)));
+    assert!(circuit_into_typst_str_with_warnings(
+        &circuit,
+        &RenderOptions::new().fallback_unsupported(true),
+    )
+    .is_err());
+}
+
 #[test]
 #[serial]
 fn test_flatten() {
@@ -170,11 +422,9 @@ fn test_flatten() {
 
     circuit_to_image(
         &circuit,
-        None,
-        RenderPragmas::None,
-        Some(InitializationMode::State),
-        None,
-        None,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::State),
     )
     .unwrap();
 }
@@ -206,11 +456,9 @@ fn test_flatten_boson() {
 
     circuit_to_image(
         &circuit,
-        None,
-        RenderPragmas::None,
-        Some(InitializationMode::State),
-        None,
-        None,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::State),
     )
     .unwrap();
 }
@@ -232,11 +480,9 @@ fn test_resonator() {
 
     circuit_to_image(
         &circuit,
-        None,
-        RenderPragmas::None,
-        Some(InitializationMode::State),
-        None,
-        None,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::State),
     )
     .unwrap();
 }
@@ -272,7 +518,11 @@ fn test_values() {
         CalculatorFloat::Float((-3.0 * PI / 4.0).into()),
     ));
 
-    circuit_into_typst_str(&circuit, RenderPragmas::None, None, None, None).unwrap();
+    circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new().render_pragmas(RenderPragmas::None),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -283,13 +533,19 @@ fn test_rounding() {
     circuit.add_operation(RotateY::new(1, -CalculatorFloat::from(0.65468211)));
     circuit.add_operation(RotateX::new(0, -CalculatorFloat::FRAC_PI_2));
 
-    circuit_into_typst_str(&circuit, RenderPragmas::None, None, None, Some(6)).unwrap();
     circuit_into_typst_str(
         &circuit,
-        RenderPragmas::None,
-        Some(InitializationMode::Qubit),
-        None,
-        Some(10),
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .rounding_accuracy(6),
+    )
+    .unwrap();
+    circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new()
+            .render_pragmas(RenderPragmas::None)
+            .initialization_mode(InitializationMode::Qubit)
+            .rounding_accuracy(10),
     )
     .unwrap();
 }
@@ -301,3 +557,84 @@ fn test_backend_today() {
     let res: Result<PagedDocument, _> = typst::compile(&backend).output;
     assert!(res.is_ok());
 }
+
+#[test]
+fn test_background_str() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+
+    let typst_str = circuit_into_typst_str(&circuit, &RenderOptions::new()).unwrap();
+    assert!(typst_str.starts_with("#set page(width: auto, height: auto, margin: 5pt)\n"));
+    assert!(!typst_str.contains("wire: .7pt + white"));
+
+    let options = RenderOptions::new()
+        .background(Background::Transparent)
+        .page_margin(12.5);
+    let typst_str = circuit_into_typst_str(&circuit, &options).unwrap();
+    assert!(
+        typst_str.starts_with("#set page(width: auto, height: auto, margin: 12.5pt, fill: none)\n")
+    );
+    assert!(!typst_str.contains("#set text(fill: white)"));
+
+    let options = RenderOptions::new().background(Background::Fill(r##"rgb("#1e1e1e")"##.into()));
+    let typst_str = circuit_into_typst_str(&circuit, &options).unwrap();
+    assert!(typst_str.contains(r##"fill: rgb("#1e1e1e"))"##));
+    assert!(typst_str.contains("#set text(fill: white)"));
+    assert!(typst_str.contains(r##"wire: .7pt + white, color: white, fill: rgb("#1e1e1e"),"##));
+}
+
+#[test]
+fn test_background_is_dark() {
+    for dark in [
+        "black",
+        "navy",
+        "#000",
+        "#1e1e1e",
+        "rgb(\"#202124\")",
+        "rgb(10, 20, 30)",
+        "rgb(10%, 20%, 30%)",
+        "luma(40)",
+    ] {
+        assert!(Background::Fill(dark.to_owned()).is_dark(), "{dark}");
+    }
+    for light in [
+        "white",
+        "yellow",
+        "#fafafa",
+        "rgb(\"#eeeeee\")",
+        "rgb(250, 250, 250, 50%)",
+        "luma(90%)",
+        "gradient.linear(red, blue)",
+    ] {
+        assert!(!Background::Fill(light.to_owned()).is_dark(), "{light}");
+    }
+    assert!(!Background::White.is_dark());
+    assert!(!Background::Transparent.is_dark());
+    assert_eq!(
+        Background::from_str("Transparent").unwrap(),
+        Background::Transparent
+    );
+    assert_eq!(Background::from_str("white").unwrap(), Background::White);
+    assert_eq!(
+        Background::from_str(" navy ").unwrap(),
+        Background::Fill("navy".to_owned())
+    );
+}
+
+#[test]
+#[serial]
+fn test_background_image() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+
+    let image = circuit_to_image(&circuit, &RenderOptions::new()).unwrap();
+    assert_eq!(image.to_rgba8().get_pixel(0, 0).0, [255, 255, 255, 255]);
+
+    let options = RenderOptions::new().background(Background::Transparent);
+    let image = circuit_to_image(&circuit, &options).unwrap();
+    assert_eq!(image.to_rgba8().get_pixel(0, 0).0[3], 0);
+
+    let options = RenderOptions::new().background(Background::Fill(r##"rgb("#1e1e1e")"##.into()));
+    let image = circuit_to_image(&circuit, &options).unwrap();
+    assert_eq!(image.to_rgba8().get_pixel(0, 0).0, [30, 30, 30, 255]);
+}
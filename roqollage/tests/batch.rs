@@ -0,0 +1,72 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the roqollage Renderer
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{circuit_to_image, InitializationMode, RenderOptions, Renderer};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn test_renderer() {
+    let options = RenderOptions::new().initialization_mode(InitializationMode::Qubit);
+    let renderer = Renderer::new(options.clone()).unwrap();
+    assert_eq!(renderer.options(), &options);
+
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    let image = renderer.circuit_to_image(&circuit).unwrap();
+    assert_eq!(image, circuit_to_image(&circuit, &options).unwrap());
+    assert!(renderer
+        .circuit_to_svg(&circuit)
+        .unwrap()
+        .starts_with("<svg"));
+    assert!(renderer
+        .circuit_to_pdf(&circuit)
+        .unwrap()
+        .starts_with(b"%PDF"));
+}
+
+#[test]
+#[serial]
+fn test_render_many() {
+    let renderer = Renderer::new(RenderOptions::new()).unwrap();
+    let circuits: Vec<Circuit> = (0..8)
+        .map(|index| {
+            let mut circuit = Circuit::new();
+            circuit.add_operation(RotateX::new(
+                index % 3,
+                CalculatorFloat::from(0.1 * index as f64),
+            ));
+            circuit
+        })
+        .collect();
+    let images = renderer.render_many(&circuits);
+    assert_eq!(images.len(), circuits.len());
+    for (image, circuit) in images.into_iter().zip(circuits.iter()) {
+        assert_eq!(image.unwrap(), renderer.circuit_to_image(circuit).unwrap());
+    }
+
+    let mut invalid = Circuit::new();
+    invalid.add_operation(MultiQubitMS::new(vec![], CalculatorFloat::from(0.5)));
+    let images = renderer.render_many(&[circuits[0].clone(), invalid, circuits[1].clone()]);
+    assert!(images[0].is_ok());
+    assert!(images[1].is_err());
+    assert!(images[2].is_ok());
+
+    assert!(renderer.render_many(&[]).is_empty());
+    let svgs = renderer.render_many_with(&circuits, Renderer::circuit_to_svg);
+    assert!(svgs.iter().all(Result::is_ok));
+}
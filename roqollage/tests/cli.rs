@@ -0,0 +1,276 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the roqollage command-line interface
+#![cfg(feature = "cli")]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use roqoqo::{measurements::ClassicalRegister, operations::*, Circuit, QuantumProgram};
+
+// helper functions
+fn run_cli(arguments: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_roqollage"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The process may exit on invalid arguments before reading its input.
+    let _ = child.stdin.take().unwrap().write_all(input);
+    child.wait_with_output().unwrap()
+}
+
+fn test_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit
+}
+
+#[test]
+fn test_cli_text() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    let output = run_cli(&[], &json);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("|0⟩"));
+    assert!(text.contains("┤H├"));
+    assert!(text.contains('⊕'));
+    assert!(text.ends_with('\n'));
+
+    let bincode = bincode::serde::encode_to_vec(test_circuit(), bincode::config::legacy()).unwrap();
+    let output = run_cli(&["-", "--initialization-mode=qubit"], &bincode);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("q[0]"));
+    assert!(text.contains("┤H├"));
+
+    let output = run_cli(&["--input-format", "json"], &bincode);
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("JSON deserialization failed as a Circuit: "));
+    assert!(error.contains(", as a QuantumProgram: "));
+}
+
+#[test]
+fn test_cli_labels() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    let output = run_cli(
+        &["--qubit-label", "0=ancilla", "--qubit-state=1=psi"],
+        &json,
+    );
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("ancilla: |0⟩"));
+    assert!(lines[2].starts_with("|psi⟩"));
+}
+
+#[test]
+fn test_cli_qubit_arrangement() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    let output = run_cli(
+        &["--initialization-mode", "qubit", "--qubit-order=reversed"],
+        &json,
+    );
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("q[1]"));
+    assert!(lines[2].starts_with("q[0]"));
+
+    let output = run_cli(
+        &[
+            "--initialization-mode",
+            "qubit",
+            "--qubit-subset",
+            "1",
+            "--hide-idle-qubits",
+        ],
+        &json,
+    );
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("q[1]"));
+    assert!(!text.contains("q[0]"));
+
+    let output = run_cli(&["--qubit-subset", "0,one"], &json);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_layering() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    let output = run_cli(
+        &["--initialization-mode", "qubit", "--layering=strict"],
+        &json,
+    );
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("q[0]"));
+}
+
+#[test]
+fn test_cli_column_numbers() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    let output = run_cli(
+        &[
+            "--initialization-mode=qubit",
+            "--column-numbers",
+            "--depth-footer",
+        ],
+        &json,
+    );
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(
+        lines
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<&str>>(),
+        vec!["0", "1"]
+    );
+    assert!(lines.next().unwrap().starts_with("q[0]"));
+    assert!(text.contains("Depth: 2\nGates: q[0]: 2, q[1]: 1"));
+}
+
+#[test]
+fn test_cli_typst_file() {
+    let directory = std::env::temp_dir().join("roqollage_cli_test");
+    std::fs::create_dir_all(&directory).unwrap();
+    let input = directory.join("circuit.json");
+    let output_path = directory.join("circuit.typ");
+    std::fs::write(&input, serde_json::to_vec(&test_circuit()).unwrap()).unwrap();
+
+    let output = run_cli(
+        &[
+            input.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+            "--render-pragmas",
+            "none",
+            "--rounding-accuracy",
+            "2",
+            "--theme",
+            "light",
+            "--background",
+            "transparent",
+            "--page-margin",
+            "8",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+    let typst_str = std::fs::read_to_string(&output_path).unwrap();
+    assert!(typst_str.contains("quantum-circuit("));
+    assert!(typst_str.contains(r##"gate($ H $, fill: rgb("#e2f0d9"))"##));
+    assert!(typst_str.contains("margin: 8pt, fill: none)"));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_cli_quantum_program() {
+    let mut constant_circuit = Circuit::new();
+    constant_circuit.add_operation(Hadamard::new(0));
+    let mut first = Circuit::new();
+    first.add_operation(PauliX::new(1));
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![first, test_circuit()],
+        },
+        input_parameter_names: vec![],
+    };
+    let json = serde_json::to_vec(&program).unwrap();
+
+    let output = run_cli(&["--circuit", "1"], &json);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(text.matches("┤H├").count(), 2);
+    assert!(text.contains('⊕'));
+
+    let output = run_cli(&["--circuit", "2"], &json);
+    assert!(!output.status.success());
+
+    // All the circuits are drawn as panels, the text format requires selecting one of them.
+    let output = run_cli(&["--format", "typst"], &json);
+    assert!(output.status.success());
+    let typst_str = String::from_utf8(output.stdout).unwrap();
+    assert!(typst_str.contains("*Constant circuit*"));
+    assert!(typst_str.contains("*Circuit 0*"));
+    assert!(typst_str.contains("*Circuit 1*"));
+    assert!(typst_str.ends_with('\n'));
+    assert_eq!(typst_str.matches("quantum-circuit(").count(), 3);
+
+    let output = run_cli(&["--format", "typst", "--circuit", "0"], &json);
+    assert!(output.status.success());
+    let typst_str = String::from_utf8(output.stdout).unwrap();
+    assert!(!typst_str.contains("*Circuit 0*"));
+    assert_eq!(typst_str.matches("quantum-circuit(").count(), 1);
+
+    let output = run_cli(&[], &json);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--circuit <INDEX>"));
+
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![test_circuit()],
+        },
+        input_parameter_names: vec![],
+    };
+    let output = run_cli(&[], &serde_json::to_vec(&program).unwrap());
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains('⊕'));
+}
+
+#[test]
+fn test_cli_errors() {
+    let json = serde_json::to_vec(&test_circuit()).unwrap();
+    for arguments in [
+        vec!["--format", "jpeg"],
+        vec!["--unknown"],
+        vec!["--max-length"],
+        vec!["--max-length", "two"],
+        vec!["--initialization-mode", "not_a_mode"],
+        vec!["--theme", "neon"],
+        vec!["--page-margin", "wide"],
+        vec!["--qubit-label", "ancilla"],
+        vec!["--qubit-state", "first=psi"],
+        vec!["--layering", "sideways"],
+        vec!["--output", "circuit.unknown"],
+        vec!["first.json", "second.json"],
+    ] {
+        let output = run_cli(&arguments, &json);
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("error:"));
+    }
+
+    let output = run_cli(&["--help"], &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Usage: roqollage"));
+    let output = run_cli(&["not json"], b"{}");
+    assert!(!output.status.success());
+}
@@ -0,0 +1,142 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the column numbers, the depth footer and the columns of the operations
+
+use std::collections::HashMap;
+
+use roqollage::{
+    circuit_into_layout, circuit_into_typst_str, circuit_to_image, circuit_to_text,
+    LayeringStrategy, QubitOrder, RenderOptions,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+use test_case::test_case;
+
+use crate::common::{layered_circuit, qubit_mode};
+
+fn bell_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit
+}
+
+/// Test the columns of the operations with the layering strategies
+#[test_case(LayeringStrategy::Greedy, vec![0, 1, 0, 1, 2, 3, 2]; "greedy")]
+#[test_case(LayeringStrategy::Strict, vec![0, 1, 2, 3, 4, 5, 6]; "strict")]
+#[test_case(LayeringStrategy::Asap, vec![0, 1, 0, 1, 2, 3, 2]; "asap")]
+#[test_case(LayeringStrategy::Alap, vec![1, 2, 0, 1, 2, 3, 3]; "alap")]
+fn test_operation_columns(layering: LayeringStrategy, columns: Vec<usize>) {
+    let layout =
+        circuit_into_layout(&layered_circuit(), &RenderOptions::new().layering(layering)).unwrap();
+    let expected: HashMap<usize, usize> = columns.into_iter().enumerate().collect();
+    assert_eq!(layout.operation_columns, expected);
+    assert_eq!(layout.qubit_gate_counts, vec![3, 2, 4]);
+}
+
+/// Test that the operations are indexed in the original circuit, definitions first
+#[test]
+fn test_operation_columns_arranged() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PauliX::new(2));
+    circuit.add_operation(MeasureQubit::new(2, "ro".to_owned(), 0));
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+
+    let layout = circuit_into_layout(&circuit, &RenderOptions::new()).unwrap();
+    assert_eq!(
+        layout.operation_columns,
+        HashMap::from([(1, 0), (2, 1), (3, 0), (4, 1)])
+    );
+    assert_eq!(layout.qubit_gate_counts, vec![2, 1, 2]);
+
+    let options = RenderOptions::new()
+        .qubit_subset(Some(vec![0, 1]))
+        .qubit_order(QubitOrder::Reversed);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(layout.operation_columns, HashMap::from([(1, 0), (2, 1)]));
+    assert_eq!(layout.qubit_gate_counts, vec![1, 2]);
+}
+
+/// Test the depth of the drawing
+#[test]
+fn test_layout_depth() {
+    let layout = circuit_into_layout(&layered_circuit(), &RenderOptions::new()).unwrap();
+    assert_eq!(layout.depth(), 4);
+    let options = RenderOptions::new().layering(LayeringStrategy::Strict);
+    let layout = circuit_into_layout(&layered_circuit(), &options).unwrap();
+    assert_eq!(layout.depth(), 7);
+    let layout = circuit_into_layout(&Circuit::new(), &RenderOptions::new()).unwrap();
+    assert_eq!(layout.depth(), 0);
+}
+
+/// Test the column numbers and the footer of the typst diagram
+#[test]
+fn test_typst_column_numbers() {
+    let typst_str = circuit_into_typst_str(&bell_circuit(), &RenderOptions::new()).unwrap();
+    assert!(!typst_str.contains("setwire(0)"));
+    assert!(!typst_str.contains("Depth"));
+
+    let options = qubit_mode().column_numbers(true).depth_footer(true);
+    let typst_str = circuit_into_typst_str(&bell_circuit(), &options).unwrap();
+    assert!(typst_str.contains(
+        "       setwire(0), lstick([]), gate(text(size: 0.7em)[0], box: false), gate(text(size: 0.7em)[1], box: false), [\\ ],\n       lstick($q[0]$, label: \"Qubits\")"
+    ));
+    assert!(typst_str
+        .ends_with(")\n}\n#text(size: 0.8em)[Depth: 2 \\\n Gates: $q[0]$: 2, $q[1]$: 1]\n"));
+
+    // The numbering goes on across the lines of the diagram.
+    let typst_str = circuit_into_typst_str(&bell_circuit(), &options.max_length(Some(1))).unwrap();
+    assert_eq!(typst_str.matches("setwire(0)").count(), 2);
+    assert!(typst_str.contains("lstick([]), gate(text(size: 0.7em)[0], box: false), [\\ ]"));
+    assert!(typst_str.contains("lstick([]), gate(text(size: 0.7em)[1], box: false), [\\ ]"));
+}
+
+/// Test the column numbers and the footer of the text diagram
+#[test]
+fn test_text_column_numbers() {
+    let options = qubit_mode().column_numbers(true).depth_footer(true);
+    let text = circuit_to_text(&bell_circuit(), &options).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<&str>>(),
+        vec!["0", "1"]
+    );
+    assert!(lines[1].starts_with("q[0]"));
+    assert!(text.ends_with("\n\nDepth: 2\nGates: q[0]: 2, q[1]: 1"));
+
+    // The numbers are above the labels of the slices.
+    let mut circuit = bell_circuit();
+    circuit.add_operation(PragmaGlobalPhase::new(0.5.into()));
+    let text = circuit_to_text(&circuit, &options).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<&str>>(),
+        vec!["0", "1"]
+    );
+    assert!(!lines[1].starts_with("q[0]"));
+    assert!(lines[2].starts_with("q[0]"));
+}
+
+/// Test that the annotated circuits render
+#[test]
+#[serial]
+fn test_column_numbers_image() {
+    let mut circuit = layered_circuit();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 3, true));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 1));
+    let options = RenderOptions::new().column_numbers(true).depth_footer(true);
+    assert!(circuit_to_image(&circuit, &options).is_ok());
+    assert!(circuit_to_image(&circuit, &options.max_length(Some(3))).is_ok());
+}
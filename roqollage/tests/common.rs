@@ -0,0 +1,34 @@
+// Copyright © 2022-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Fixtures shared by the test modules
+
+use roqollage::{InitializationMode, RenderOptions};
+use roqoqo::{operations::*, Circuit};
+
+/// Returns a circuit on three qubits whose operations can be packed in different columns.
+pub fn layered_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PauliX::new(2));
+    circuit.add_operation(PauliX::new(2));
+    circuit.add_operation(PauliX::new(2));
+    circuit.add_operation(CNOT::new(1, 2));
+    circuit.add_operation(Hadamard::new(0));
+    circuit
+}
+
+/// Returns the rendering options labelling the wires with their qubits.
+pub fn qubit_mode() -> RenderOptions {
+    RenderOptions::new().initialization_mode(InitializationMode::Qubit)
+}
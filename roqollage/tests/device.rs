@@ -0,0 +1,97 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the drawing of devices
+
+use ndarray::array;
+use roqollage::{device_into_typst_str, device_to_image, Background, RenderOptions};
+use roqoqo::devices::{AllToAllDevice, GenericDevice, SquareLatticeDevice};
+use serial_test::serial;
+
+// helper functions
+fn square_lattice_device() -> SquareLatticeDevice {
+    SquareLatticeDevice::new(
+        2,
+        3,
+        &["RotateZ".to_owned(), "RotateX".to_owned()],
+        &["CNOT".to_owned()],
+        1.0,
+    )
+}
+
+#[test]
+fn test_square_lattice_device() {
+    let typst_str = device_into_typst_str(&square_lattice_device(), &RenderOptions::new());
+    assert!(typst_str.starts_with("#set page"));
+    assert!(typst_str.contains("*Device* with 6 qubits"));
+    assert!(typst_str.contains("Single-qubit gates: RotateX, RotateZ"));
+    assert!(typst_str.contains("Two-qubit gates: CNOT"));
+    assert!(typst_str.contains("#box(width: 200.0pt, height: 130.0pt)"));
+    assert_eq!(typst_str.matches("line(start:").count(), 7);
+    assert_eq!(typst_str.matches("CNOT: $1.0$").count(), 7);
+    assert_eq!(typst_str.matches("circle(radius:").count(), 6);
+}
+
+#[test]
+fn test_all_to_all_device() {
+    let device = AllToAllDevice::new(4, &["RotateZ".to_owned()], &["CZ".to_owned()], 0.5);
+    let typst_str = device_into_typst_str(&device, &RenderOptions::new());
+    assert!(typst_str.contains("*Device* with 4 qubits"));
+    assert_eq!(typst_str.matches("line(start:").count(), 6);
+    assert_eq!(typst_str.matches("CZ: $0.5$").count(), 6);
+}
+
+#[test]
+fn test_generic_device_decoherence() {
+    let mut device = GenericDevice::new(3);
+    device.set_two_qubit_gate_time("CNOT", 0, 1, 0.5).unwrap();
+    device.set_two_qubit_gate_time("CNOT", 2, 1, 0.5).unwrap();
+    device.set_two_qubit_gate_time("CZ", 1, 2, 2.0).unwrap();
+    device
+        .set_qubit_decoherence_rates(0, array![[0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]])
+        .unwrap();
+    device
+        .set_qubit_decoherence_rates(1, array![[0.5, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]])
+        .unwrap();
+    let typst_str = device_into_typst_str(&device, &RenderOptions::new());
+    assert_eq!(typst_str.matches("line(start:").count(), 2);
+    assert!(typst_str.contains(r"[CNOT: $0.5$ \ CZ: $2.0$]"));
+    assert!(typst_str.contains("fill: rgb(143, 214, 148)"));
+    assert!(typst_str.contains("fill: rgb(232, 120, 124)"));
+    assert!(typst_str.contains("decoherence rate from $0$ (green) to $0.5$ (red)"));
+}
+
+#[test]
+fn test_device_dark_background() {
+    let options = RenderOptions::new().background(Background::Fill(r##"rgb("#1e1e1e")"##.into()));
+    let typst_str = device_into_typst_str(&square_lattice_device(), &options);
+    assert!(typst_str.contains("#set text(fill: white)"));
+    // The labels of the edges and of the qubits keep a dark text on their light fill.
+    assert_eq!(
+        typst_str
+            .matches("box(fill: white, inset: 1.5pt, text(size: 6pt, fill: black)[CNOT: $1.0$])")
+            .count(),
+        7
+    );
+    assert_eq!(
+        typst_str
+            .matches("align(center + horizon, text(size: 8pt, fill: black)")
+            .count(),
+        6
+    );
+}
+
+#[test]
+#[serial]
+fn test_device_image() {
+    device_to_image(&square_lattice_device(), &RenderOptions::new()).unwrap();
+}
@@ -15,10 +15,15 @@
 use ndarray::array;
 use num_complex::Complex64;
 use qoqo_calculator::CalculatorFloat;
-use roqollage::{add_gate, circuit_to_image};
+use roqollage::{
+    add_gate, circuit_into_layout, circuit_to_image, CircuitLayout, GateRendererRegistry,
+    GenericRenderer, LabelRenderer, LayoutItem, MultiQubitGateRenderer, RenderOptions,
+    TypstRenderer, WireRef,
+};
 use roqoqo::{operations::*, Circuit};
 use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::sync::Arc;
 use test_case::test_case;
 
 // / Test that all operations return the correct gate declaration
@@ -132,35 +137,11 @@ use test_case::test_case;
 #[test_case(Operation::from(MultiQubitCNOT::new([0, 1, 2, 3].to_vec())); "MultiQubitCNOT")]
 #[test_case(Operation::from(QFT::new([0, 1, 2, 3].to_vec(), false, false)); "QFT")]
 fn test_add_gate(operation: Operation) {
-    let mut circuit_gates: Vec<Vec<String>> = Vec::new();
-    let mut bosonic_gates: Vec<Vec<String>> = Vec::new();
-    let mut classical_gates: Vec<Vec<String>> = Vec::new();
-    let mut circuit_lock: Vec<(usize, usize)> = Vec::new();
-    let mut bosonic_lock: Vec<(usize, usize)> = Vec::new();
-    let mut classical_lock: Vec<(usize, usize)> = Vec::new();
-    assert!(add_gate(
-        &mut circuit_gates,
-        &mut bosonic_gates,
-        &mut classical_gates,
-        &mut circuit_lock,
-        &mut bosonic_lock,
-        &mut classical_lock,
-        &operation,
-        &roqollage::RenderPragmas::All,
-        3
-    )
-    .is_ok());
+    let mut layout = CircuitLayout::new();
+    assert!(add_gate(&mut layout, &operation, &RenderOptions::new()).is_ok());
     let mut circuit = roqoqo::Circuit::new();
     circuit += operation;
-    assert!(circuit_to_image(
-        &circuit,
-        None,
-        roqollage::RenderPragmas::All,
-        None,
-        None,
-        None
-    )
-    .is_ok())
+    assert!(circuit_to_image(&circuit, &RenderOptions::new()).is_ok())
 }
 
 #[test_case(Operation::from(PragmaStartDecompositionBlock::new(vec![], HashMap::new())); "PragmaStartDecompositionBlock")]
@@ -181,81 +162,117 @@ fn test_add_gate(operation: Operation) {
 #[test_case(Operation::from(MultiQubitCNOT::new([].to_vec())); "MultiQubitCNOT")]
 #[test_case(Operation::from(QFT::new([].to_vec(), false, false)); "QFT")]
 fn test_add_gate_errors(operation: Operation) {
-    let mut circuit_gates: Vec<Vec<String>> = Vec::new();
-    let mut bosonic_gates: Vec<Vec<String>> = Vec::new();
-    let mut classical_gates: Vec<Vec<String>> = Vec::new();
-    let mut circuit_lock: Vec<(usize, usize)> = Vec::new();
-    let mut bosonic_lock: Vec<(usize, usize)> = Vec::new();
-    let mut classical_lock: Vec<(usize, usize)> = Vec::new();
-    assert!(add_gate(
-        &mut circuit_gates,
-        &mut bosonic_gates,
-        &mut classical_gates,
-        &mut circuit_lock,
-        &mut bosonic_lock,
-        &mut classical_lock,
-        &operation,
-        &roqollage::RenderPragmas::All,
-        3
-    )
-    .is_err(),);
+    let mut layout = CircuitLayout::new();
+    assert!(add_gate(&mut layout, &operation, &RenderOptions::new()).is_err(),);
 }
 
 #[cfg(feature = "unstable_operation_definition")]
 #[test_case(Operation::from(GateDefinition::new(vec![Operation::from(RotateX::new(0, CalculatorFloat::from("theta"))), Operation::from(RotateX::new(1, CalculatorFloat::PI))].into_iter().collect(), "test_gate".to_owned(), vec![0, 1], vec!["theta".to_owned()])); "GateDefinition")]
 #[test_case(Operation::from(CallDefinedGate::new("test".to_owned(), vec![0, 1], vec![CalculatorFloat::from("3.14")])); "CallDefinedGate")]
 fn test_add_gate_unstable(operation: Operation) {
-    let mut circuit_gates: Vec<Vec<String>> = Vec::new();
-    let mut bosonic_gates: Vec<Vec<String>> = Vec::new();
-    let mut classical_gates: Vec<Vec<String>> = Vec::new();
-    let mut circuit_lock: Vec<(usize, usize)> = Vec::new();
-    let mut bosonic_lock: Vec<(usize, usize)> = Vec::new();
-    let mut classical_lock: Vec<(usize, usize)> = Vec::new();
-    assert!(add_gate(
-        &mut circuit_gates,
-        &mut bosonic_gates,
-        &mut classical_gates,
-        &mut circuit_lock,
-        &mut bosonic_lock,
-        &mut classical_lock,
-        &operation,
-        &roqollage::RenderPragmas::All,
-        3
-    )
-    .is_ok());
+    let mut layout = CircuitLayout::new();
+    assert!(add_gate(&mut layout, &operation, &RenderOptions::new()).is_ok());
     let mut circuit = roqoqo::Circuit::new();
     circuit += operation;
-    assert!(circuit_to_image(
-        &circuit,
-        None,
-        roqollage::RenderPragmas::All,
-        None,
-        None,
-        None
-    )
-    .is_ok());
+    assert!(circuit_to_image(&circuit, &RenderOptions::new()).is_ok());
 }
 
 #[cfg(feature = "unstable_operation_definition")]
 #[test_case(Operation::from(GateDefinition::new(vec![Operation::from(InputBit::new("ro".to_owned(), 0, true))].into_iter().collect(), "test_gate".to_owned(), vec![0, 1], vec!["theta".to_owned()])); "GateDefinition")]
 #[test_case(Operation::from(CallDefinedGate::new("test".to_owned(), vec![], vec![CalculatorFloat::from("3.14")])); "CallDefinedGate")]
 fn test_add_gate_unstable_errors(operation: Operation) {
-    let mut circuit_gates: Vec<Vec<String>> = Vec::new();
-    let mut bosonic_gates: Vec<Vec<String>> = Vec::new();
-    let mut classical_gates: Vec<Vec<String>> = Vec::new();
-    let mut circuit_lock: Vec<(usize, usize)> = Vec::new();
-    let mut bosonic_lock: Vec<(usize, usize)> = Vec::new();
-    let mut classical_lock: Vec<(usize, usize)> = Vec::new();
-    assert!(add_gate(
-        &mut circuit_gates,
-        &mut bosonic_gates,
-        &mut classical_gates,
-        &mut circuit_lock,
-        &mut bosonic_lock,
-        &mut classical_lock,
-        &operation,
-        &roqollage::RenderPragmas::All,
-        3
-    )
-    .is_err(),);
+    let mut layout = CircuitLayout::new();
+    assert!(add_gate(&mut layout, &operation, &RenderOptions::new()).is_err(),);
+}
+
+#[test]
+fn test_circuit_into_layout() {
+    let mut circuit = Circuit::new();
+    circuit += DefinitionBit::new("ro".to_owned(), 1, true);
+    circuit += CNOT::new(0, 2);
+    circuit += MeasureQubit::new(2, "ro".to_owned(), 0);
+    let layout = circuit_into_layout(&circuit, &RenderOptions::new()).unwrap();
+    let rows = layout.wire_rows();
+
+    assert_eq!(layout.qubits.len(), 3);
+    assert_eq!(layout.classical.len(), 1);
+    assert_eq!(layout.qubits[0][0], LayoutItem::control(WireRef::Qubit(2)));
+    assert_eq!(layout.qubits[2][0], LayoutItem::Target);
+    assert!(layout.qubits[2].contains(&LayoutItem::Meter {
+        target: Some(WireRef::Classical(0)),
+        fill: None,
+    }));
+    assert_eq!(
+        layout.qubits[0][0].to_typst(WireRef::Qubit(0), &rows),
+        "ctrl(2)"
+    );
+    assert_eq!(
+        LayoutItem::Meter {
+            target: Some(WireRef::Classical(0)),
+            fill: None,
+        }
+        .to_typst(WireRef::Qubit(2), &rows),
+        "meter(target: 1)"
+    );
+    assert_eq!(
+        layout.classical[0][..2],
+        [
+            LayoutItem::RegisterLabel("ro".to_owned()),
+            LayoutItem::ClassicalWire
+        ]
+    );
+}
+
+#[test]
+fn test_layout_gate_name_containing_slice() {
+    let mut circuit = Circuit::new();
+    circuit += PragmaOverrotation::new("slice".to_owned(), vec![0], 0.1, 0.1);
+    circuit += Hadamard::new(0);
+    let layout = circuit_into_layout(&circuit, &RenderOptions::new()).unwrap();
+
+    assert_eq!(layout.qubits[0].len(), 2);
+    assert!(layout.qubits[0].iter().all(LayoutItem::is_column));
+}
+
+#[test]
+fn test_gate_renderers() {
+    let mut circuit = Circuit::new();
+    circuit += Hadamard::new(0);
+    circuit += GPi2::new(2, CalculatorFloat::from(0.5));
+    circuit += CNOT::new(0, 2);
+    circuit += MultiQubitZZ::new(vec![1, 3], CalculatorFloat::PI);
+
+    let mut registry = GateRendererRegistry::new();
+    registry.register("Hadamard", LabelRenderer::new("Had"));
+    registry.register("GPi2", GenericRenderer);
+    registry.register(
+        "CNOT",
+        TypstRenderer::new(vec!["ctrl(2)".to_owned(), "targ()".to_owned()]),
+    );
+    registry.register("MultiQubitZZ", MultiQubitGateRenderer::new("Z Z"));
+    registry.set_fallback(Some(Arc::new(GenericRenderer)));
+    let options = RenderOptions::new().gate_renderers(registry);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+
+    assert_eq!(layout.qubits[0][0], LayoutItem::gate("\"Had\""));
+    assert_eq!(layout.qubits[2][0], LayoutItem::gate("\"GPi2\""));
+    assert_eq!(layout.qubits[0][1], LayoutItem::Typst("ctrl(2)".to_owned()));
+    assert_eq!(layout.qubits[1][1], LayoutItem::Idle);
+    assert_eq!(layout.qubits[2][1], LayoutItem::Typst("targ()".to_owned()));
+    assert!(layout.qubits[1].contains(&LayoutItem::MultiGate {
+        content: "Z Z".to_owned(),
+        n_wires: Some(3),
+        width: None,
+        extent: None,
+        fill: None,
+        inputs: vec![0, 2],
+        target: None,
+    }));
+    assert!(circuit_to_image(&circuit, &options).is_ok());
+
+    let mut registry = GateRendererRegistry::new();
+    registry.register("CNOT", TypstRenderer::new(vec!["ctrl(2)".to_owned()]));
+    let mut circuit = Circuit::new();
+    circuit += CNOT::new(0, 2);
+    assert!(circuit_into_layout(&circuit, &RenderOptions::new().gate_renderers(registry)).is_err());
 }
@@ -0,0 +1,145 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the custom wire labels
+
+use roqollage::{
+    circuit_into_quantikz_str, circuit_into_typst_str, circuit_to_image, circuit_to_text,
+    InitializationMode, RenderOptions, WireLabels,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+
+fn labelled_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(Squeezing::new(0, 0.1.into(), 0.0.into()));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 0));
+    circuit
+}
+
+fn labels() -> WireLabels {
+    WireLabels::new()
+        .qubit(0, "ancilla")
+        .qubit_state(1, "psi")
+        .boson(0, "cavity A")
+        .register("ro", "readout")
+}
+
+/// Test the labels of the typst representation
+#[test]
+fn test_labels_typst() {
+    let circuit = labelled_circuit();
+    let typst_str =
+        circuit_into_typst_str(&circuit, &RenderOptions::new().wire_labels(labels())).unwrap();
+    assert!(typst_str.contains("lstick($\"ancilla\": |0>$, label: \"Qubits\")"));
+    assert!(typst_str.contains("lstick($|psi>$)"));
+    assert!(typst_str.contains("lstick($\"cavity A\": |0>$, label: \"Bosons\")"));
+    assert!(typst_str.contains("\"readout : \""));
+    assert!(!typst_str.contains("\"ro : \""));
+
+    let typst_str = circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new()
+            .wire_labels(labels())
+            .initialization_mode(InitializationMode::Qubit)
+            .max_length(Some(1)),
+    )
+    .unwrap();
+    assert!(typst_str.contains("lstick($\"ancilla\"$, label: \"Qubits\")"));
+    assert!(typst_str.contains("lstick($q[1]: |psi>$)"));
+    assert!(typst_str.contains("lstick($···\"ancilla\"$)"));
+    assert!(typst_str.contains("lstick($···q[1]$)"));
+    assert!(typst_str.contains("lstick($···\"cavity A\"$)"));
+}
+
+/// Test that the bosonic modes are labelled b[n] by default
+#[test]
+fn test_labels_default_bosons() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Squeezing::new(0, 0.1.into(), 0.0.into()));
+    let typst_str = circuit_into_typst_str(
+        &circuit,
+        &RenderOptions::new().initialization_mode(InitializationMode::Qubit),
+    )
+    .unwrap();
+    assert!(typst_str.contains("lstick($b[0]$, label: \"Bosons\")"));
+    assert!(!typst_str.contains("lstick($q[0]$"));
+}
+
+/// Test the labels of the quantikz and text representations
+#[test]
+fn test_labels_quantikz_text() {
+    let circuit = labelled_circuit();
+    let options = RenderOptions::new().wire_labels(labels());
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &options).unwrap();
+    assert!(quantikz_str.contains("\\lstick{$\\text{ancilla}: \\ket{0}$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\ket{\\psi }$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\text{cavity A}: \\ket{0}$}"));
+    assert!(quantikz_str.contains("\\lstick{readout} \\setwiretype{c}"));
+
+    let text = circuit_to_text(&circuit, &options).unwrap();
+    let labels: Vec<&str> = text.lines().filter(|line| !line.starts_with(' ')).collect();
+    assert!(labels[0].starts_with("ancilla: |0⟩"));
+    assert!(labels[1].starts_with("|psi⟩"));
+    assert!(labels[2].starts_with("cavity A: |0⟩"));
+    assert!(labels[3].starts_with("readout"));
+
+    // The wrapped continuation lines keep the names of the wires.
+    let options = options.max_length(Some(1));
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &options).unwrap();
+    assert!(quantikz_str.contains("\\lstick{$\\cdots \\text{ancilla}$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\cdots q[1]$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\cdots \\text{cavity A}$}"));
+    assert!(quantikz_str.contains("\\lstick{$\\cdots \\text{readout}$} \\setwiretype{c}"));
+    assert!(!quantikz_str.contains("\\lstick{$\\cdots$}"));
+
+    let text = circuit_to_text(&circuit, &options).unwrap();
+    let labels: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with(' '))
+        .collect();
+    assert!(labels.len() > 4);
+    for (index, label) in labels.iter().enumerate() {
+        let name = ["ancilla", "|psi⟩", "cavity A", "readout"][index % 4];
+        assert!(label.starts_with(name), "{label}");
+    }
+}
+
+/// Test the (de)serialization of the labels
+#[test]
+fn test_labels_serde() {
+    let options = RenderOptions::new().wire_labels(labels());
+    let serialized = serde_json::to_string(&options).unwrap();
+    let deserialized: RenderOptions = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized.wire_labels, labels());
+
+    let deserialized: RenderOptions =
+        serde_json::from_str(r#"{"wire_labels": {"qubits": {"2": "data_3"}}}"#).unwrap();
+    assert_eq!(
+        deserialized.wire_labels,
+        WireLabels::new().qubit(2, "data_3")
+    );
+}
+
+/// Test that the labelled circuit renders
+#[test]
+#[serial]
+fn test_labels_image() {
+    let circuit = labelled_circuit();
+    let options = RenderOptions::new()
+        .wire_labels(labels())
+        .max_length(Some(2));
+    assert!(circuit_to_image(&circuit, &options).is_ok());
+}
@@ -0,0 +1,305 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the layering strategies of the circuit layout
+
+use std::str::FromStr;
+
+use roqollage::{
+    circuit_depth, circuit_into_layout, circuit_to_image, CircuitLayout, LayeringStrategy,
+    LayoutItem, RenderOptions,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+use test_case::test_case;
+
+use crate::common::layered_circuit;
+
+fn n_columns(layout: &CircuitLayout) -> usize {
+    layout
+        .qubits
+        .iter()
+        .map(|items| items.iter().filter(|item| item.is_column()).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the columns of the gates with the given content on a wire.
+fn gate_columns(items: &[LayoutItem], gate: &str) -> Vec<usize> {
+    items
+        .iter()
+        .filter(|item| item.is_column())
+        .enumerate()
+        .filter(|(_, item)| matches!(item, LayoutItem::Gate { content, .. } if content == gate))
+        .map(|(column, _)| column)
+        .collect()
+}
+
+/// Test the number of columns drawn by each strategy
+#[test_case(LayeringStrategy::Greedy, 4; "greedy")]
+#[test_case(LayeringStrategy::Strict, 7; "strict")]
+#[test_case(LayeringStrategy::Asap, 4; "asap")]
+#[test_case(LayeringStrategy::Alap, 4; "alap")]
+#[test_case(LayeringStrategy::RespectBarriers, 4; "respect barriers")]
+fn test_layering_depth(layering: LayeringStrategy, expected: usize) {
+    let layout =
+        circuit_into_layout(&layered_circuit(), &RenderOptions::new().layering(layering)).unwrap();
+    assert_eq!(n_columns(&layout), expected);
+}
+
+/// Test that the moments stay aligned when a gate crosses the wire of another one
+#[test_case(LayeringStrategy::Asap, 1; "asap")]
+#[test_case(LayeringStrategy::Alap, 2; "alap")]
+#[test_case(LayeringStrategy::RespectBarriers, 1; "respect barriers")]
+fn test_layering_crossing_gates(layering: LayeringStrategy, x_layer: usize) {
+    let options = RenderOptions::new().layering(layering);
+    let mut circuit = Circuit::new();
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(PauliX::new(0));
+    circuit.add_operation(Hadamard::new(1));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(circuit_depth(&circuit), 2);
+    assert_eq!(n_columns(&layout), circuit_depth(&circuit));
+
+    // The line of the CNOT crosses the first H, the X is drawn in the moment of one of the H.
+    let mut circuit = Circuit::new();
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(Hadamard::new(1));
+    circuit.add_operation(Hadamard::new(1));
+    circuit.add_operation(PauliX::new(0));
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    assert_eq!(layering.layers(&operations), Some(vec![0, 1, 2, x_layer]));
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(n_columns(&layout), 3);
+    assert_eq!(gate_columns(&layout.qubits[1], "H"), vec![1, 2]);
+    assert_eq!(gate_columns(&layout.qubits[0], "X"), vec![x_layer]);
+}
+
+/// Test the logical depth of fixed circuits
+#[test]
+fn test_circuit_depth() {
+    let depth = |operations: Vec<Operation>| {
+        let mut circuit = Circuit::new();
+        for operation in operations {
+            circuit.add_operation(operation);
+        }
+        circuit_depth(&circuit)
+    };
+    assert_eq!(depth(vec![]), 0);
+    assert_eq!(
+        depth(vec![DefinitionBit::new("ro".to_owned(), 1, true).into()]),
+        0
+    );
+    assert_eq!(
+        depth(vec![Hadamard::new(0).into(), Hadamard::new(1).into()]),
+        1
+    );
+    assert_eq!(
+        depth(vec![
+            Hadamard::new(0).into(),
+            CNOT::new(0, 1).into(),
+            Hadamard::new(1).into(),
+        ]),
+        3
+    );
+    // The operations acting on all the qubits are in a moment of their own.
+    assert_eq!(
+        depth(vec![
+            Hadamard::new(0).into(),
+            PragmaGlobalPhase::new(0.5.into()).into(),
+            Hadamard::new(1).into(),
+        ]),
+        3
+    );
+    assert_eq!(
+        depth(vec![
+            Squeezing::new(0, 0.1.into(), 0.0.into()).into(),
+            BeamSplitter::new(0, 1, 0.1.into(), 0.2.into()).into(),
+            PauliX::new(0).into(),
+        ]),
+        2
+    );
+    assert_eq!(circuit_depth(&layered_circuit()), 4);
+}
+
+/// Test that the strict strategy draws every operation in its own column
+#[test]
+fn test_layering_strict() {
+    let circuit = layered_circuit();
+    let options = RenderOptions::new().layering(LayeringStrategy::Strict);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(n_columns(&layout), circuit.len());
+    assert_eq!(gate_columns(&layout.qubits[0], "H"), vec![0, 6]);
+    assert_eq!(gate_columns(&layout.qubits[2], "X"), vec![2, 3, 4]);
+}
+
+/// Test that the operations are packed as soon or as late as possible
+#[test]
+fn test_layering_asap_alap() {
+    let circuit = layered_circuit();
+    let layout = circuit_into_layout(
+        &circuit,
+        &RenderOptions::new().layering(LayeringStrategy::Asap),
+    )
+    .unwrap();
+    assert_eq!(gate_columns(&layout.qubits[0], "H"), vec![0, 2]);
+    assert_eq!(gate_columns(&layout.qubits[2], "X"), vec![0, 1, 2]);
+
+    let layout = circuit_into_layout(
+        &circuit,
+        &RenderOptions::new().layering(LayeringStrategy::Alap),
+    )
+    .unwrap();
+    assert_eq!(gate_columns(&layout.qubits[0], "H"), vec![1, 3]);
+    assert_eq!(gate_columns(&layout.qubits[2], "X"), vec![0, 1, 2]);
+}
+
+/// Test that the moments of the operations follow their dependencies
+#[test]
+fn test_layers() {
+    let circuit = layered_circuit();
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    assert_eq!(LayeringStrategy::Greedy.layers(&operations), None);
+    assert_eq!(
+        LayeringStrategy::Strict.layers(&operations),
+        Some(vec![0, 1, 2, 3, 4, 5, 6])
+    );
+    assert_eq!(
+        LayeringStrategy::Asap.layers(&operations),
+        Some(vec![0, 1, 0, 1, 2, 3, 2])
+    );
+    assert_eq!(
+        LayeringStrategy::Alap.layers(&operations),
+        Some(vec![1, 2, 0, 1, 2, 3, 3])
+    );
+
+    // The definitions stay before the other operations.
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    assert_eq!(
+        LayeringStrategy::Alap.layers(&operations),
+        Some(vec![0, 0, 1])
+    );
+    assert_eq!(circuit_depth(&circuit), 2);
+}
+
+/// Test that the conditional operations follow the measurements of their condition
+#[test]
+fn test_layers_classical() {
+    let mut flipped = Circuit::new();
+    flipped.add_operation(PauliX::new(1));
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit.add_operation(PragmaConditional::new("ro".to_owned(), 0, flipped.clone()));
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    for layering in [
+        LayeringStrategy::Asap,
+        LayeringStrategy::Alap,
+        LayeringStrategy::RespectBarriers,
+    ] {
+        assert_eq!(layering.layers(&operations), Some(vec![0, 0, 1, 2, 3]));
+    }
+    assert_eq!(circuit_depth(&circuit), 4);
+
+    // The condition is read before the bit is measured again.
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(PragmaConditional::new("ro".to_owned(), 0, flipped));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit.add_operation(MeasureQubit::new(2, "ro".to_owned(), 1));
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    // The measurements of different bits are in the same logical moment, but the line of the first
+    // one crosses the wire of the second one in the diagram.
+    assert_eq!(
+        LayeringStrategy::Asap.layers(&operations),
+        Some(vec![0, 0, 0, 1, 2, 3])
+    );
+    assert_eq!(
+        LayeringStrategy::Alap.layers(&operations),
+        Some(vec![0, 1, 0, 1, 2, 3])
+    );
+    assert_eq!(circuit_depth(&circuit), 3);
+}
+
+/// Test that the parallel blocks are barriers with the respect_barriers strategy
+#[test]
+fn test_layering_respect_barriers() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(PragmaStopParallelBlock::new(vec![0], 0.0.into()));
+    circuit.add_operation(Hadamard::new(1));
+    assert_eq!(circuit_depth(&circuit), 2);
+
+    let operations: Vec<&Operation> = circuit.iter().collect();
+    assert_eq!(
+        LayeringStrategy::Asap.layers(&operations),
+        Some(vec![0, 1, 0])
+    );
+    assert_eq!(
+        LayeringStrategy::RespectBarriers.layers(&operations),
+        Some(vec![0, 1, 2])
+    );
+
+    let options = RenderOptions::new().layering(LayeringStrategy::Asap);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(n_columns(&layout), 2);
+    let options = RenderOptions::new().layering(LayeringStrategy::RespectBarriers);
+    let layout = circuit_into_layout(&circuit, &options).unwrap();
+    assert_eq!(n_columns(&layout), 3);
+    assert_eq!(gate_columns(&layout.qubits[1], "H"), vec![2]);
+}
+
+/// Test the parsing of the layering strategies
+#[test_case("greedy", LayeringStrategy::Greedy; "greedy")]
+#[test_case("Strict", LayeringStrategy::Strict; "strict")]
+#[test_case("asap", LayeringStrategy::Asap; "asap")]
+#[test_case("ALAP", LayeringStrategy::Alap; "alap")]
+#[test_case("respect-barriers", LayeringStrategy::RespectBarriers; "respect barriers")]
+fn test_layering_from_str(strategy: &str, expected: LayeringStrategy) {
+    assert_eq!(LayeringStrategy::from_str(strategy).unwrap(), expected);
+    let options: RenderOptions = serde_json::from_str(
+        &serde_json::to_string(&RenderOptions::new().layering(expected)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(options.layering, expected);
+}
+
+/// Test that the unknown layering strategies are rejected
+#[test]
+fn test_layering_from_str_error() {
+    assert!(LayeringStrategy::from_str("sideways").is_err());
+}
+
+/// Test that the layered circuits render
+#[test]
+#[serial]
+fn test_layering_image() {
+    let mut circuit = layered_circuit();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 3, true));
+    circuit.add_operation(PragmaGlobalPhase::new(0.5.into()));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 1));
+    for layering in [
+        LayeringStrategy::Strict,
+        LayeringStrategy::Asap,
+        LayeringStrategy::Alap,
+    ] {
+        assert!(circuit_to_image(&circuit, &RenderOptions::new().layering(layering)).is_ok());
+    }
+}
@@ -15,3 +15,48 @@ mod interface;
 
 #[cfg(test)]
 mod backend;
+
+#[cfg(test)]
+mod arrangement;
+
+#[cfg(test)]
+mod batch;
+
+#[cfg(test)]
+mod cli;
+
+#[cfg(test)]
+mod columns;
+
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod device;
+
+#[cfg(test)]
+mod labels;
+
+#[cfg(test)]
+mod layering;
+
+#[cfg(test)]
+mod program;
+
+#[cfg(test)]
+mod qasm;
+
+#[cfg(test)]
+mod routing;
+
+#[cfg(test)]
+mod states;
+
+#[cfg(test)]
+mod text;
+
+#[cfg(test)]
+mod theme;
+
+#[cfg(test)]
+mod timeline;
@@ -0,0 +1,181 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the drawing of QuantumPrograms
+
+use std::collections::HashMap;
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{
+    cheated_pauli_z_product_input_into_typst_str, pauli_z_product_input_into_typst_str,
+    quantum_program_into_typst_str, quantum_program_into_typst_str_with_warnings,
+    quantum_program_to_image, RenderOptions,
+};
+use roqoqo::{
+    measurements::{
+        CheatedPauliZProduct, CheatedPauliZProductInput, ClassicalRegister, PauliZProduct,
+        PauliZProductInput,
+    },
+    operations::*,
+    Circuit, QuantumProgram,
+};
+use serial_test::serial;
+
+// helper functions
+fn pauli_z_product_program() -> QuantumProgram {
+    let mut constant_circuit = Circuit::new();
+    constant_circuit.add_operation(RotateX::new(0, CalculatorFloat::from("theta")));
+    constant_circuit.add_operation(CNOT::new(0, 1));
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit.add_operation(MeasureQubit::new(1, "ro".to_owned(), 1));
+    let mut input = PauliZProductInput::new(2, false);
+    input
+        .add_pauliz_product("ro".to_owned(), vec![0, 1])
+        .unwrap();
+    input.add_pauliz_product("ro".to_owned(), vec![1]).unwrap();
+    input
+        .add_linear_exp_val("energy".to_owned(), HashMap::from([(0, 0.5), (1, -1.0)]))
+        .unwrap();
+    input
+        .add_symbolic_exp_val("ratio".to_owned(), CalculatorFloat::from("p0 / p1"))
+        .unwrap();
+    QuantumProgram::PauliZProduct {
+        measurement: PauliZProduct {
+            constant_circuit: Some(constant_circuit),
+            circuits: vec![circuit],
+            input,
+        },
+        input_parameter_names: vec!["theta".to_owned()],
+    }
+}
+
+fn cheated_pauli_z_product_program() -> QuantumProgram {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionFloat::new("ro_xz".to_owned(), 1, true));
+    circuit.add_operation(PragmaGetPauliProduct::new(
+        HashMap::from([(0, 1), (1, 3)]),
+        "ro_xz".to_owned(),
+        Circuit::new(),
+    ));
+    let mut input = CheatedPauliZProductInput::new();
+    input.add_pauliz_product("ro_xz".to_owned());
+    input
+        .add_linear_exp_val("energy".to_owned(), HashMap::from([(0, 2.0)]))
+        .unwrap();
+    QuantumProgram::CheatedPauliZProduct {
+        measurement: CheatedPauliZProduct {
+            constant_circuit: None,
+            circuits: vec![circuit],
+            input,
+        },
+        input_parameter_names: vec![],
+    }
+}
+
+#[test]
+fn test_quantum_program_typst_str() {
+    let typst_str =
+        quantum_program_into_typst_str(&pauli_z_product_program(), &RenderOptions::new()).unwrap();
+    assert!(typst_str.starts_with("#set page"));
+    assert!(typst_str.contains("PauliZProduct measurement"));
+    assert!(typst_str.contains(r#"Input parameters: #raw("theta")"#));
+    assert!(typst_str.contains("*Constant circuit*"));
+    assert!(typst_str.contains("*Circuit 0*"));
+    assert!(typst_str
+        .contains(r#"Measured products in #raw("ro"): $P_(0) = Z_(0) Z_(1)$, $P_(1) = Z_(1)$"#));
+    assert_eq!(typst_str.matches("quantum-circuit(").count(), 2);
+    assert!(typst_str.contains("#grid(columns: 2"));
+    assert!(typst_str.contains("*Measurement input*"));
+
+    let mut first = Circuit::new();
+    first.add_operation(Hadamard::new(0));
+    let mut second = Circuit::new();
+    second.add_operation(GPi2::new(0, CalculatorFloat::from(0.5)));
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![first.clone(), second],
+        },
+        input_parameter_names: vec![],
+    };
+    let (typst_str, warnings) = quantum_program_into_typst_str_with_warnings(
+        &program,
+        &RenderOptions::new().fallback_unsupported(true),
+    )
+    .unwrap();
+    assert!(typst_str.contains("Input parameters: none"));
+    assert!(!typst_str.contains("Constant circuit"));
+    assert!(!typst_str.contains("Measured products"));
+    assert!(typst_str.contains("*Circuit 1*"));
+    assert!(warnings.is_empty());
+
+    let mut invalid = Circuit::new();
+    invalid.add_operation(MultiQubitMS::new(vec![], CalculatorFloat::from(0.5)));
+    let program = QuantumProgram::ClassicalRegister {
+        measurement: ClassicalRegister {
+            constant_circuit: None,
+            circuits: vec![first, invalid],
+        },
+        input_parameter_names: vec![],
+    };
+    assert!(quantum_program_into_typst_str(&program, &RenderOptions::new()).is_err());
+}
+
+#[test]
+#[serial]
+fn test_quantum_program_image() {
+    quantum_program_to_image(&pauli_z_product_program(), &RenderOptions::new()).unwrap();
+}
+
+#[test]
+fn test_measurement_input_typst_str() {
+    let QuantumProgram::PauliZProduct { measurement, .. } = pauli_z_product_program() else {
+        panic!("Expected a PauliZProduct program")
+    };
+    let typst_str = pauli_z_product_input_into_typst_str(&measurement.input, &RenderOptions::new());
+    assert!(typst_str.starts_with("#set page"));
+    assert!(typst_str.contains(r#"[#raw("ro")], [$P_(0)$], [$Z_(0) Z_(1)$],"#));
+    assert!(typst_str.contains(r#"[#raw("ro")], [$P_(1)$], [$Z_(1)$],"#));
+    assert!(typst_str.contains(r#"$"energy" = 0.5 P_(0) - P_(1)$"#));
+    assert!(typst_str.contains(r#"$"ratio" = P_(0) / P_(1)$"#));
+    assert!(!typst_str.contains("Flipped measurement"));
+
+    let QuantumProgram::CheatedPauliZProduct { measurement, .. } =
+        cheated_pauli_z_product_program()
+    else {
+        panic!("Expected a CheatedPauliZProduct program")
+    };
+    let typst_str = cheated_pauli_z_product_input_into_typst_str(
+        &measurement.input,
+        &measurement.circuits,
+        &RenderOptions::new(),
+    );
+    assert!(typst_str.contains(r#"[#raw("ro_xz")], [$P_(0)$], [$X_(0) Z_(1)$],"#));
+    assert!(typst_str.contains(r#"$"energy" = 2.0 P_(0)$"#));
+    let typst_str = cheated_pauli_z_product_input_into_typst_str(
+        &measurement.input,
+        &[],
+        &RenderOptions::new(),
+    );
+    assert!(typst_str.contains(r#"[#raw("ro_xz")], [$P_(0)$], [$?$],"#));
+}
+
+#[test]
+#[serial]
+fn test_cheated_quantum_program_image() {
+    let program = cheated_pauli_z_product_program();
+    let typst_str = quantum_program_into_typst_str(&program, &RenderOptions::new()).unwrap();
+    assert!(typst_str.contains("*Measurement input*"));
+    quantum_program_to_image(&program, &RenderOptions::new()).unwrap();
+}
@@ -0,0 +1,220 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the OpenQASM input
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{circuit_into_typst_str, qasm_to_circuit, RenderOptions};
+use roqoqo::{operations::*, Circuit};
+use test_case::test_case;
+
+#[test]
+fn test_qasm2() {
+    let qasm = r#"OPENQASM 2.0;
+include "qelib1.inc";
+// Two registers are laid out one after the other.
+qreg a[2];
+qreg b[1];
+creg c[3];
+h a[0];
+cx a[0], b[0];
+rx(pi/2) a[1]; /* inline comment */
+cp(-pi/4) a[1], b[0];
+u1(theta) a[0];
+barrier a, b;
+reset b[0];
+x a;
+measure a -> c[0:1];
+"#;
+    assert!(qasm_to_circuit(qasm).is_err());
+
+    let qasm = qasm.replace(
+        "measure a -> c[0:1];",
+        "measure a[1] -> c[2];\nmeasure b -> c;",
+    );
+    assert!(qasm_to_circuit(&qasm).is_err());
+
+    let qasm = qasm.replace("measure b -> c;", "measure b[0] -> c[0];");
+    let circuit = qasm_to_circuit(&qasm).unwrap();
+    let mut expected = Circuit::new();
+    expected.add_operation(DefinitionBit::new("c".to_owned(), 3, true));
+    expected.add_operation(Hadamard::new(0));
+    expected.add_operation(CNOT::new(0, 2));
+    expected.add_operation(RotateX::new(1, CalculatorFloat::FRAC_PI_2));
+    expected.add_operation(ControlledPhaseShift::new(
+        1,
+        2,
+        CalculatorFloat::from(-std::f64::consts::FRAC_PI_4),
+    ));
+    expected.add_operation(PhaseShiftState1::new(0, CalculatorFloat::from("theta")));
+    expected.add_operation(PragmaStopParallelBlock::new(
+        vec![0, 1, 2],
+        CalculatorFloat::ZERO,
+    ));
+    expected.add_operation(PragmaActiveReset::new(2));
+    expected.add_operation(PauliX::new(0));
+    expected.add_operation(PauliX::new(1));
+    expected.add_operation(MeasureQubit::new(1, "c".to_owned(), 2));
+    expected.add_operation(MeasureQubit::new(2, "c".to_owned(), 0));
+    assert_eq!(circuit, expected);
+
+    circuit_into_typst_str(&circuit, &RenderOptions::new()).unwrap();
+}
+
+#[test]
+fn test_qasm3() {
+    let qasm = r#"OPENQASM 3.0;
+include "stdgates.inc";
+qubit[2] q;
+qubit r;
+bit[2] c;
+gate rot(alpha, beta) x, y {
+    rz(alpha) x;
+    cx x, y;
+    ry(beta / 2) y;
+}
+rot(pi, phi) q[0], r;
+U(0.1, 0.2, 0.3) q[1];
+c[0] = measure q[0];
+bit d = measure r;
+"#;
+    let circuit = qasm_to_circuit(qasm).unwrap();
+    let mut expected = Circuit::new();
+    expected.add_operation(DefinitionBit::new("c".to_owned(), 2, true));
+    expected.add_operation(RotateZ::new(0, CalculatorFloat::PI));
+    expected.add_operation(CNOT::new(0, 2));
+    expected.add_operation(RotateY::new(2, CalculatorFloat::from("(phi) / 2")));
+    expected.add_operation(RotateZ::new(1, CalculatorFloat::from(0.3)));
+    expected.add_operation(RotateY::new(1, CalculatorFloat::from(0.1)));
+    expected.add_operation(RotateZ::new(1, CalculatorFloat::from(0.2)));
+    expected.add_operation(MeasureQubit::new(0, "c".to_owned(), 0));
+    expected.add_operation(DefinitionBit::new("d".to_owned(), 1, true));
+    expected.add_operation(MeasureQubit::new(2, "d".to_owned(), 0));
+    assert_eq!(circuit, expected);
+}
+
+#[test]
+fn test_qasm_controlled_gates() {
+    let qasm = r#"OPENQASM 2.0;
+include "qelib1.inc";
+qreg q[3];
+cry(pi/2) q[0], q[1];
+crz(theta) q[1], q[2];
+ch q[2], q[0];
+csx q[0], q[2];
+cu3(0.1, 0.2, 0.3) q[0], q[1];
+cu(0.1, 0.2, 0.3, 0.4) q[1], q[0];
+rxx(0.5) q[0], q[2];
+rzz(0.6) q[1], q[2];
+"#;
+    let circuit = qasm_to_circuit(qasm).unwrap();
+    let controlled = |control: usize, operations: Vec<Operation>| {
+        let mut circuit = Circuit::new();
+        for operation in operations {
+            circuit.add_operation(operation);
+        }
+        PragmaControlledCircuit::new(control, circuit)
+    };
+    let mut expected = Circuit::new();
+    expected.add_operation(ControlledRotateXY::new(
+        0,
+        1,
+        CalculatorFloat::FRAC_PI_2,
+        CalculatorFloat::FRAC_PI_2,
+    ));
+    expected.add_operation(controlled(
+        1,
+        vec![RotateZ::new(2, CalculatorFloat::from("theta")).into()],
+    ));
+    expected.add_operation(controlled(2, vec![Hadamard::new(0).into()]));
+    expected.add_operation(controlled(0, vec![SXGate::new(2).into()]));
+    expected.add_operation(controlled(
+        0,
+        vec![
+            RotateZ::new(1, CalculatorFloat::from(0.3)).into(),
+            RotateY::new(1, CalculatorFloat::from(0.1)).into(),
+            RotateZ::new(1, CalculatorFloat::from(0.2)).into(),
+        ],
+    ));
+    expected.add_operation(PhaseShiftState1::new(0, CalculatorFloat::from(0.25)));
+    expected.add_operation(controlled(
+        1,
+        vec![
+            RotateZ::new(0, CalculatorFloat::from(0.3)).into(),
+            RotateY::new(0, CalculatorFloat::from(0.1)).into(),
+            RotateZ::new(0, CalculatorFloat::from(0.2)).into(),
+        ],
+    ));
+    expected.add_operation(PhaseShiftState1::new(1, CalculatorFloat::from(0.65)));
+    expected.add_operation(VariableMSXX::new(0, 2, CalculatorFloat::from(0.5)));
+    expected.add_operation(MultiQubitZZ::new(vec![1, 2], CalculatorFloat::from(0.6)));
+    assert_eq!(circuit.len(), expected.len());
+    for (operation, expected_operation) in circuit.iter().zip(expected.iter()) {
+        match (operation, expected_operation) {
+            (Operation::PhaseShiftState1(op), Operation::PhaseShiftState1(expected_op)) => {
+                assert_eq!(op.qubit(), expected_op.qubit());
+                assert!(
+                    (op.theta().float().unwrap() - expected_op.theta().float().unwrap()).abs()
+                        < 1e-12
+                );
+            }
+            _ => assert_eq!(operation, expected_operation),
+        }
+    }
+
+    circuit_into_typst_str(&circuit, &RenderOptions::new()).unwrap();
+}
+
+#[test]
+fn test_qasm_conditional() {
+    let qasm = r#"OPENQASM 2.0;
+include "qelib1.inc";
+qreg q[2];
+creg c[1];
+creg d[2];
+measure q[0] -> c[0];
+if(c==1) x q[1];
+if (d[1] == 1) { h q[0]; cx q[0], q[1]; }
+"#;
+    let circuit = qasm_to_circuit(qasm).unwrap();
+    let mut expected = Circuit::new();
+    expected.add_operation(DefinitionBit::new("c".to_owned(), 1, true));
+    expected.add_operation(DefinitionBit::new("d".to_owned(), 2, true));
+    expected.add_operation(MeasureQubit::new(0, "c".to_owned(), 0));
+    let mut body = Circuit::new();
+    body.add_operation(PauliX::new(1));
+    expected.add_operation(PragmaConditional::new("c".to_owned(), 0, body));
+    let mut body = Circuit::new();
+    body.add_operation(Hadamard::new(0));
+    body.add_operation(CNOT::new(0, 1));
+    expected.add_operation(PragmaConditional::new("d".to_owned(), 1, body));
+    assert_eq!(circuit, expected);
+
+    let typst_str = circuit_into_typst_str(&circuit, &RenderOptions::new()).unwrap();
+    assert!(typst_str.contains(r#"label: "Conditional: c[0]""#));
+    assert!(typst_str.contains(r#"label: "Conditional: d[1]""#));
+}
+
+#[test_case("qreg q[2]; foo q[0];"; "unknown gate")]
+#[test_case("qreg q[2]; h p[0];"; "unknown register")]
+#[test_case("qreg q[2]; h q[2];"; "out of range")]
+#[test_case("qreg q[2]; cx q[0];"; "wrong number of qubits")]
+#[test_case("qreg q[2]; creg c[1]; if(c==0) x q[0];"; "condition on unset bit")]
+#[test_case("qreg q[2]; creg c[2]; if(c==1) x q[0];"; "condition on register")]
+#[test_case("qreg q[2]; creg c[1]; if(e==1) x q[0];"; "condition on unknown register")]
+#[test_case("qreg q[2]; creg c[1]; if(c==1) foo q[0];"; "condition with unknown gate")]
+#[test_case("qubit[2] q; ctrl @ x q[0], q[1];"; "modifier")]
+#[test_case("qreg q[2]; qreg r[3]; cx q, r;"; "register sizes")]
+#[test_case("qreg q[1]; gate g a { g a; } g q[0];"; "recursive gate")]
+fn test_qasm_error(qasm: &str) {
+    assert!(qasm_to_circuit(qasm).is_err());
+}
@@ -0,0 +1,159 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the highlighting of the routing violations of a circuit on a device
+
+use std::collections::HashMap;
+
+use ndarray::array;
+use num_complex::Complex64;
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{
+    circuit_into_layout_on_device, circuit_on_device_into_typst_str, circuit_on_device_to_image,
+    routing_violations, InitializationMode, LayeringStrategy, PreparedState, RenderOptions,
+    RoutingViolation, RoutingViolationKind,
+};
+use roqoqo::{devices::SquareLatticeDevice, operations::*, Circuit};
+use serial_test::serial;
+
+// helper functions
+fn linear_device() -> SquareLatticeDevice {
+    SquareLatticeDevice::new(
+        1,
+        3,
+        &["RotateX".to_owned(), "RotateZ".to_owned()],
+        &["CNOT".to_owned()],
+        1.0,
+    )
+}
+
+fn routed_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(RotateX::new(0, CalculatorFloat::from(0.5)));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(CNOT::new(1, 2));
+    circuit
+}
+
+#[test]
+fn test_routing_violations() {
+    let mut circuit = routed_circuit();
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(Hadamard::new(1));
+    circuit.add_operation(ControlledPauliZ::new(1, 2));
+    circuit.add_operation(RotateZ::new(4, CalculatorFloat::from(0.5)));
+    circuit.add_operation(PragmaSetNumberOfMeasurements::new(10, "ro".to_owned()));
+
+    let violations = routing_violations(&circuit, &linear_device());
+    assert_eq!(
+        violations
+            .iter()
+            .map(|violation| (violation.index, violation.kind))
+            .collect::<Vec<(usize, RoutingViolationKind)>>(),
+        vec![
+            (3, RoutingViolationKind::NotConnected),
+            (4, RoutingViolationKind::UnsupportedGate),
+            (5, RoutingViolationKind::UnsupportedGate),
+            (6, RoutingViolationKind::QubitOutOfRange),
+        ]
+    );
+    assert_eq!(
+        violations[0],
+        RoutingViolation {
+            index: 3,
+            hqslang: "CNOT".to_owned(),
+            qubits: vec![0, 2],
+            kind: RoutingViolationKind::NotConnected,
+        }
+    );
+    assert_eq!(
+        violations[0].to_string(),
+        "CNOT on qubits 0, 2: qubits not connected in the device"
+    );
+
+    let typst_str =
+        circuit_on_device_into_typst_str(&circuit, &linear_device(), &RenderOptions::new())
+            .unwrap();
+    assert_eq!(typst_str.matches("stroke: red + 1pt").count(), 4);
+    assert!(typst_str.contains("*Routing violations: 4*"));
+    assert!(
+        typst_str.contains("Operation 3: CNOT on qubits 0, 2: qubits not connected in the device")
+    );
+    assert!(typst_str.contains("Operation 6: RotateZ on qubit 4: qubit not in the device"));
+}
+
+#[test]
+fn test_no_routing_violation() {
+    assert!(routing_violations(&routed_circuit(), &linear_device()).is_empty());
+    let typst_str = circuit_on_device_into_typst_str(
+        &routed_circuit(),
+        &linear_device(),
+        &RenderOptions::new(),
+    )
+    .unwrap();
+    assert!(!typst_str.contains("stroke: red"));
+    assert!(typst_str.contains("No routing violation on the device"));
+}
+
+#[test]
+fn test_routing_render_options() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(PragmaSetStateVector::new(array![
+        Complex64::new(1.0, 0.0),
+        Complex64::new(0.0, 0.0)
+    ]));
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(InputBit::new("ro".to_owned(), 0, true));
+    for operation in routed_circuit().iter() {
+        circuit.add_operation(operation.clone());
+    }
+    circuit.add_operation(CNOT::new(0, 2));
+
+    let options = RenderOptions::new()
+        .initialization_mode(InitializationMode::Inferred)
+        .layering(LayeringStrategy::Strict)
+        .depth_footer(true);
+    let (layout, violations) =
+        circuit_into_layout_on_device(&circuit, &linear_device(), &options).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(
+        layout.prepared_state,
+        Some(PreparedState::Product(vec!["0".to_owned()]))
+    );
+    assert_eq!(
+        layout.operation_columns,
+        HashMap::from([(3, 0), (4, 1), (5, 2), (6, 3)])
+    );
+    assert_eq!(layout.qubit_gate_counts, vec![3, 2, 2]);
+    let typst_str = circuit_on_device_into_typst_str(&circuit, &linear_device(), &options).unwrap();
+    assert!(typst_str.contains("ro = 1"));
+    assert!(typst_str.contains("Gates: $q[0]$: 3, $q[1]$: 2, $q[2]$: 2"));
+    assert_eq!(typst_str.matches("stroke: red + 1pt").count(), 1);
+
+    // The violation crossing the qubit subset is outlined on its drawn wire.
+    let options = RenderOptions::new()
+        .initialization_mode(InitializationMode::Qubit)
+        .qubit_subset(Some(vec![0, 1]));
+    let typst_str = circuit_on_device_into_typst_str(&circuit, &linear_device(), &options).unwrap();
+    assert!(!typst_str.contains("lstick($q[2]$"));
+    assert!(typst_str.contains("stroke: red + 1pt"));
+    assert!(typst_str.contains(r#"label: "with q[2]""#));
+}
+
+#[test]
+#[serial]
+fn test_routing_image() {
+    let mut circuit = routed_circuit();
+    circuit.add_operation(CNOT::new(2, 0));
+    circuit.add_operation(Hadamard::new(1));
+    circuit_on_device_to_image(&circuit, &linear_device(), &RenderOptions::new()).unwrap();
+}
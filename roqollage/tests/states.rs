@@ -0,0 +1,169 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the inference of the initial states
+
+use ndarray::{array, Array1, Array2};
+use num_complex::Complex64;
+use roqollage::{
+    circuit_into_layout, circuit_into_quantikz_str, circuit_into_typst_str, circuit_to_image,
+    circuit_to_text, InitializationMode, PreparedState, RenderOptions,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+use test_case::test_case;
+
+fn complex(re: f64, im: f64) -> Complex64 {
+    Complex64::new(re, im)
+}
+
+fn inferred() -> RenderOptions {
+    RenderOptions::new().initialization_mode(InitializationMode::Inferred)
+}
+
+/// Test the states of the qubits of product statevectors
+#[test_case(array![complex(1.0, 0.0), complex(0.0, 0.0)], &["0"]; "zero")]
+#[test_case(array![complex(0.0, 0.0), complex(0.0, 1.0)], &["1"]; "one with phase")]
+#[test_case(array![complex(0.5, 0.0), complex(-0.5, 0.0)], &["-"]; "minus unnormalized")]
+#[test_case(array![complex(0.0, 0.0), complex(0.0, 0.0), complex(1.0, 0.0), complex(0.0, 0.0)], &["0", "1"]; "qubit order")]
+#[test_case(array![complex(0.5, 0.0), complex(0.5, 0.0), complex(0.0, 0.5), complex(0.0, 0.5)], &["+", "+i"]; "plus plus_i")]
+fn test_product_statevector(statevector: Array1<Complex64>, states: &[&str]) {
+    assert_eq!(
+        PreparedState::from_statevector(&statevector),
+        PreparedState::Product(states.iter().map(|state| state.to_string()).collect())
+    );
+}
+
+/// Test that the entangled and unknown states are global states
+#[test_case(array![complex(1.0, 0.0), complex(0.0, 0.0), complex(0.0, 0.0), complex(1.0, 0.0)]; "bell")]
+#[test_case(array![complex(0.6, 0.0), complex(0.8, 0.0)]; "unknown single qubit state")]
+#[test_case(array![complex(1.0, 0.0), complex(0.0, 0.0), complex(0.0, 0.0)]; "not a power of two")]
+fn test_global_statevector(statevector: Array1<Complex64>) {
+    assert!(matches!(
+        PreparedState::from_statevector(&statevector),
+        PreparedState::Global { mixed: false, .. }
+    ));
+}
+
+/// Test the states inferred from density matrices
+#[test]
+fn test_density_matrix() {
+    let plus: Array2<Complex64> = array![
+        [complex(0.5, 0.0), complex(0.5, 0.0)],
+        [complex(0.5, 0.0), complex(0.5, 0.0)]
+    ];
+    assert_eq!(
+        PreparedState::from_density_matrix(&plus),
+        PreparedState::Product(vec!["+".to_owned()])
+    );
+    let mixed: Array2<Complex64> = array![
+        [complex(0.5, 0.0), complex(0.0, 0.0)],
+        [complex(0.0, 0.0), complex(0.5, 0.0)]
+    ];
+    assert_eq!(
+        PreparedState::from_density_matrix(&mixed),
+        PreparedState::Global {
+            n_qubits: 1,
+            mixed: true
+        }
+    );
+}
+
+/// Test that the state preparations at the beginning of the circuit become initial states
+#[test]
+fn test_inferred_product_state() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 3, true));
+    circuit.add_operation(InputBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(PragmaSetStateVector::new(array![
+        complex(0.0, 0.0),
+        complex(0.0, 0.0),
+        complex(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+        complex(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+        complex(0.0, 0.0),
+        complex(0.0, 0.0),
+        complex(0.0, 0.0),
+        complex(0.0, 0.0)
+    ]));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(InputBit::new("ro".to_owned(), 2, true));
+
+    let layout = circuit_into_layout(&circuit, &inferred()).unwrap();
+    assert_eq!(layout.qubits.len(), 3);
+    assert_eq!(
+        layout.prepared_state,
+        Some(PreparedState::Product(vec![
+            "+".to_owned(),
+            "1".to_owned(),
+            "0".to_owned()
+        ]))
+    );
+
+    let typst_str = circuit_into_typst_str(&circuit, &inferred()).unwrap();
+    assert!(typst_str.contains("lstick($|+>$, label: \"Qubits\")"));
+    assert!(typst_str.contains("lstick($|1>$)"));
+    assert!(typst_str.contains("lstick($|0>$)"));
+    assert!(typst_str.contains("\"ro = 010 : \""));
+    assert!(!typst_str.contains("SetStatevector"));
+    // Only the InputBit at the beginning of the circuit is an initial state.
+    assert!(typst_str.contains("InputBit"));
+
+    let typst_str = circuit_into_typst_str(&circuit, &RenderOptions::new()).unwrap();
+    assert!(typst_str.contains("SetStatevector"));
+    assert!(typst_str.contains("\"ro : \""));
+
+    let text = circuit_to_text(&circuit, &inferred()).unwrap();
+    assert!(text.starts_with("|+⟩"));
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &inferred()).unwrap();
+    assert!(quantikz_str.contains("\\lstick{$\\ket{1}$}"));
+    assert!(quantikz_str.contains("\\lstick{ro = 010}"));
+}
+
+/// Test that the entangled states are drawn across all the qubits
+#[test]
+fn test_inferred_global_state() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(PragmaSetStateVector::new(array![
+        complex(std::f64::consts::FRAC_1_SQRT_2, 0.0),
+        complex(0.0, 0.0),
+        complex(0.0, 0.0),
+        complex(std::f64::consts::FRAC_1_SQRT_2, 0.0)
+    ]));
+    circuit.add_operation(CNOT::new(0, 2));
+
+    let typst_str = circuit_into_typst_str(&circuit, &inferred()).unwrap();
+    assert!(typst_str.contains("lstick($|psi>$, n: 2, label: \"Qubits\")"));
+    assert!(typst_str.contains("lstick([])"));
+    assert!(typst_str.contains("lstick($|0>$)"));
+
+    let quantikz_str = circuit_into_quantikz_str(&circuit, &inferred()).unwrap();
+    assert!(quantikz_str.starts_with("\\begin{quantikz}\n\\lstick[2]{$\\ket{\\psi}$}"));
+
+    let text = circuit_to_text(&circuit, &inferred()).unwrap();
+    let labels: Vec<&str> = text.lines().step_by(2).collect();
+    assert!(labels[0].starts_with("┌|psi⟩"));
+    assert!(labels[1].starts_with('└'));
+    assert!(labels[2].starts_with("|0⟩"));
+}
+
+/// Test that the inferred initial states render
+#[test]
+#[serial]
+fn test_inferred_image() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(PragmaSetDensityMatrix::new(array![
+        [complex(0.5, 0.0), complex(0.0, 0.0)],
+        [complex(0.0, 0.0), complex(0.5, 0.0)]
+    ]));
+    circuit.add_operation(CNOT::new(0, 1));
+    assert!(circuit_to_image(&circuit, &inferred()).is_ok());
+}
@@ -0,0 +1,110 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the roqollage backend
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{circuit_to_text, InitializationMode, RenderOptions, RenderPragmas};
+use roqoqo::{operations::*, Circuit};
+
+#[test]
+fn test_text() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 2, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(SWAP::new(1, 2));
+    circuit.add_operation(RotateX::new(1, CalculatorFloat::from("theta")));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 1));
+
+    let text = circuit_to_text(
+        &circuit,
+        &RenderOptions::new().initialization_mode(InitializationMode::Qubit),
+    )
+    .unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 7);
+    assert!(lines[0].starts_with("q[0]"));
+    assert!(lines[2].starts_with("q[1]"));
+    assert!(lines[4].starts_with("q[2]"));
+    assert!(lines[6].starts_with("ro"));
+    assert!(lines[0].contains("┤H├"));
+    assert!(lines[0].contains('■'));
+    assert!(lines[0].contains("┤M├"));
+    assert!(lines[1].contains('│'));
+    assert!(lines[1].contains('║'));
+    assert!(lines[2].contains('┼'));
+    assert!(lines[2].contains('╳'));
+    assert!(lines[2].contains("┤Rx("));
+    assert!(lines[4].contains('⊕'));
+    assert!(lines[4].contains('╳'));
+    assert!(lines[6].contains('═'));
+    assert!(lines[6].contains('1'));
+
+    let text = circuit_to_text(&circuit, &RenderOptions::new()).unwrap();
+    assert!(text.lines().next().unwrap().starts_with("|0⟩"));
+}
+
+#[test]
+fn test_text_bosons() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Squeezing::new(0, 0.1.into(), 0.0.into()));
+    circuit.add_operation(BeamSplitter::new(0, 1, 0.5.into(), 0.2.into()));
+    circuit.add_operation(PhotonDetection::new(1, "ro".to_owned(), 0));
+
+    let text = circuit_to_text(&circuit, &RenderOptions::new()).unwrap();
+    assert!(text.contains("┤M├"));
+    assert!(text.lines().count() >= 3);
+}
+
+#[test]
+fn test_text_pragmas() {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(PragmaGlobalPhase::new(CalculatorFloat::from(0.5)));
+    circuit.add_operation(PauliX::new(0));
+
+    let all = circuit_to_text(&circuit, &RenderOptions::new()).unwrap();
+    let none = circuit_to_text(
+        &circuit,
+        &RenderOptions::new().render_pragmas(RenderPragmas::None),
+    )
+    .unwrap();
+    assert!(all.contains("GlobalPhase"));
+    assert!(!none.contains("GlobalPhase"));
+    assert!(none.contains("┤H├"));
+    assert!(none.contains("┤X├"));
+}
+
+#[test]
+fn test_text_max_length() {
+    let mut circuit = Circuit::new();
+    for _ in 0..6 {
+        circuit.add_operation(Hadamard::new(0));
+    }
+    circuit.add_operation(CNOT::new(0, 1));
+
+    let text = circuit_to_text(&circuit, &RenderOptions::new()).unwrap();
+    assert_eq!(text.split("\n\n").count(), 1);
+    assert!(!text.contains("···"));
+
+    let text = circuit_to_text(&circuit, &RenderOptions::new().max_length(Some(3))).unwrap();
+    // Each chunk draws the two wires, the wires of the last chunk are not continued.
+    let wires: Vec<&str> = text
+        .lines()
+        .filter(|line| line.starts_with("|0⟩"))
+        .collect();
+    assert_eq!(wires.len(), 6);
+    assert!(wires[..4].iter().all(|wire| wire.ends_with("···")));
+    assert!(wires[4..].iter().all(|wire| !wire.contains("···")));
+    assert!(text.contains('⊕'));
+}
@@ -0,0 +1,175 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the colour themes of the gates
+
+use std::str::FromStr;
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{
+    circuit_into_layout, circuit_to_image, GateFamily, GateStyle, LayoutItem, RenderOptions, Theme,
+    WireRef,
+};
+use roqoqo::{operations::*, Circuit};
+use serial_test::serial;
+use test_case::test_case;
+
+// helper functions
+fn themed_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(RotateX::new(1, CalculatorFloat::from(0.5)));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PragmaDamping::new(
+        1,
+        CalculatorFloat::from(1.0),
+        CalculatorFloat::from(0.5),
+    ));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit
+}
+
+/// Returns the quill representation of the first item of the wire matching the filter.
+fn first_typst(
+    options: &RenderOptions,
+    qubit: usize,
+    filter: impl Fn(&LayoutItem) -> bool,
+) -> String {
+    let layout = circuit_into_layout(&themed_circuit(), options).unwrap();
+    let rows = layout.wire_rows();
+    layout.qubits[qubit]
+        .iter()
+        .find(|item| filter(item))
+        .unwrap()
+        .to_typst(WireRef::Qubit(qubit), &rows)
+}
+
+fn is_gate(item: &LayoutItem) -> bool {
+    matches!(item, LayoutItem::Gate { .. })
+}
+
+fn is_meter(item: &LayoutItem) -> bool {
+    matches!(item, LayoutItem::Meter { .. })
+}
+
+#[test_case(Hadamard::new(0).into(), GateFamily::Clifford; "clifford")]
+#[test_case(SGate::new(0).into(), GateFamily::Clifford; "sgate")]
+#[test_case(RotateX::new(0, 0.1.into()).into(), GateFamily::Rotation; "rotation")]
+#[test_case(TGate::new(0).into(), GateFamily::Rotation; "tgate")]
+#[test_case(CNOT::new(0, 1).into(), GateFamily::Entangler; "cnot")]
+#[test_case(Toffoli::new(0, 1, 2).into(), GateFamily::Entangler; "toffoli")]
+#[test_case(MeasureQubit::new(0, "ro".to_owned(), 0).into(), GateFamily::Measurement; "measure")]
+#[test_case(PragmaDephasing::new(0, 1.0.into(), 0.1.into()).into(), GateFamily::Noise; "noise")]
+#[test_case(PhaseShift::new(0, 0.1.into()).into(), GateFamily::Bosonic; "bosonic")]
+#[test_case(DefinitionBit::new("ro".to_owned(), 1, true).into(), GateFamily::Other; "other")]
+fn test_gate_family(operation: Operation, family: GateFamily) {
+    assert_eq!(GateFamily::of(&operation), family);
+}
+
+#[test]
+fn test_default_theme() {
+    let options = RenderOptions::new();
+    assert_eq!(first_typst(&options, 0, is_gate), "$ H $");
+    assert_eq!(
+        first_typst(&options, 1, |item| matches!(
+            item,
+            LayoutItem::Gate { fill: Some(_), .. }
+        )),
+        "gate($ \"Damping\"(1.0,0.5) $, fill: gray)"
+    );
+    assert_eq!(first_typst(&options, 0, is_meter), "meter(target: 2)");
+}
+
+#[test]
+fn test_light_theme() {
+    let options = RenderOptions::new().theme(Theme::light());
+    assert_eq!(
+        first_typst(&options, 0, is_gate),
+        "gate($ H $, fill: rgb(\"#e2f0d9\"))"
+    );
+    assert_eq!(
+        first_typst(&options, 1, is_gate),
+        "gate($ \"Rx\"(0.5) $, fill: rgb(\"#dbe9f6\"))"
+    );
+    assert_eq!(
+        first_typst(&options, 0, is_meter),
+        "meter(target: 2, fill: rgb(\"#eeeeee\"))"
+    );
+    let layout = circuit_into_layout(&themed_circuit(), &options).unwrap();
+    assert!(layout.qubits[1].contains(&LayoutItem::Gate {
+        content: "\"Damping\"(1.0,0.5)".to_owned(),
+        label: None,
+        fill: Some("rgb(\"#d9d9d9\")".to_owned()),
+        stroke: None,
+    }));
+}
+
+#[test]
+fn test_custom_theme() {
+    let theme = Theme::new()
+        .family_style(
+            GateFamily::Clifford,
+            GateStyle::new().fill("yellow").stroke("red + 1pt"),
+        )
+        .family_style(GateFamily::Noise, GateStyle::new().stroke("blue"));
+    let options = RenderOptions::new().theme(theme.clone());
+    assert_eq!(
+        first_typst(&options, 0, is_gate),
+        "gate(box(fill: yellow, stroke: red + 1pt, inset: (x: 0.4em, y: 0.5em))[$ H $], box: false)"
+    );
+    // The fill of the noise pragmas is kept when the theme only sets the stroke.
+    assert_eq!(
+        first_typst(&options, 1, |item| matches!(
+            item,
+            LayoutItem::Gate { fill: Some(_), .. }
+        )),
+        "gate(box(fill: gray, stroke: blue, inset: (x: 0.4em, y: 0.5em))[$ \"Damping\"(1.0,0.5) $], box: false)"
+    );
+    assert_eq!(theme.style(GateFamily::Other), None);
+    assert_eq!(
+        theme.style(GateFamily::Noise),
+        Some(&GateStyle::new().stroke("blue"))
+    );
+
+    let serialized = serde_json::to_string(&options).unwrap();
+    let deserialized: RenderOptions = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized.theme, theme);
+}
+
+#[test_case("default", Theme::new(); "default")]
+#[test_case("Light", Theme::light(); "light")]
+#[test_case("dark", Theme::dark(); "dark")]
+#[test_case("high-contrast", Theme::high_contrast(); "high_contrast")]
+#[test_case("color_blind", Theme::colour_blind(); "colour_blind")]
+fn test_theme_from_str(name: &str, theme: Theme) {
+    assert_eq!(Theme::from_str(name).unwrap(), theme);
+}
+
+#[test]
+fn test_theme_from_str_error() {
+    assert!(Theme::from_str("neon").is_err());
+    assert!(GateFamily::from_str("other").is_err());
+    assert_eq!(
+        GateFamily::from_str("Entangler").unwrap(),
+        GateFamily::Entangler
+    );
+}
+
+#[test_case(Theme::light(); "light")]
+#[test_case(Theme::dark(); "dark")]
+#[test_case(Theme::high_contrast(); "high_contrast")]
+#[test_case(Theme::colour_blind(); "colour_blind")]
+#[serial]
+fn test_theme_image(theme: Theme) {
+    circuit_to_image(&themed_circuit(), &RenderOptions::new().theme(theme)).unwrap();
+}
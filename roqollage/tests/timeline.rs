@@ -0,0 +1,167 @@
+// Copyright © 2021-2024 HQS Quantum Simulations GmbH. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the
+// License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+// express or implied. See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Testing the timeline rendering of circuits
+
+use qoqo_calculator::CalculatorFloat;
+use roqollage::{
+    circuit_into_timeline_typst_str_with_warnings, circuit_schedule, circuit_to_timeline_image,
+    Background, RenderOptions,
+};
+use roqoqo::{devices::GenericDevice, operations::*, Circuit};
+use serial_test::serial;
+
+// helper functions
+fn timed_device() -> GenericDevice {
+    let mut device = GenericDevice::new(3);
+    device
+        .set_single_qubit_gate_time("Hadamard", 0, 1.0)
+        .unwrap();
+    device.set_two_qubit_gate_time("CNOT", 0, 1, 2.0).unwrap();
+    device
+}
+
+fn timed_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add_operation(DefinitionBit::new("ro".to_owned(), 1, true));
+    circuit.add_operation(Hadamard::new(0));
+    circuit.add_operation(CNOT::new(0, 1));
+    circuit.add_operation(PragmaSleep::new(vec![2], CalculatorFloat::from(1.5)));
+    circuit.add_operation(RotateX::new(2, CalculatorFloat::from(0.5)));
+    circuit.add_operation(MeasureQubit::new(0, "ro".to_owned(), 0));
+    circuit
+}
+
+#[test]
+fn test_circuit_schedule() {
+    let (schedule, warnings) = circuit_schedule(&timed_circuit(), &timed_device());
+    assert_eq!(
+        schedule
+            .iter()
+            .map(|timed| (timed.hqslang.as_str(), timed.start, timed.duration))
+            .collect::<Vec<(&str, f64, f64)>>(),
+        vec![
+            ("Hadamard", 0.0, 1.0),
+            ("CNOT", 1.0, 2.0),
+            ("PragmaSleep", 0.0, 1.5),
+            ("RotateX", 1.5, 0.0),
+            ("MeasureQubit", 3.0, 0.0),
+        ]
+    );
+    assert_eq!(schedule[1].qubits, vec![0, 1]);
+    assert_eq!(schedule[1].end(), 3.0);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("RotateX on qubits [2] has no gate time"));
+}
+
+#[test]
+fn test_timeline_typst_str() {
+    let (typst_str, warnings) = circuit_into_timeline_typst_str_with_warnings(
+        &timed_circuit(),
+        &timed_device(),
+        &RenderOptions::new(),
+    );
+    assert_eq!(warnings.len(), 1);
+    assert!(typst_str.starts_with("#set page"));
+    // The shortest operation lasts 1.0 and is 28pt wide.
+    assert!(typst_str.contains("rect(width: 28.0pt, height: 18.0pt"));
+    // The CNOT is drawn as a box on each of its qubits, joined by a line.
+    assert_eq!(
+        typst_str
+            .matches("rect(width: 56.0pt, height: 18.0pt")
+            .count(),
+        2
+    );
+    assert!(
+        typst_str.contains("line(start: (92.0pt, 24.0pt), end: (92.0pt, 56.0pt), stroke: 0.6pt)")
+    );
+    assert!(typst_str.contains("dash: \"dashed\""));
+    // Qubit 1 is idle before the CNOT, qubit 2 after the sleep.
+    assert_eq!(typst_str.matches("fill: rgb(255, 183, 77, 120)").count(), 2);
+    assert!(typst_str.contains("rect(width: 28.0pt, height: 6pt"));
+    assert!(typst_str.contains("rect(width: 42.0pt, height: 6pt"));
+    assert!(typst_str.contains("text(size: 6pt)[0.5]"));
+    assert!(typst_str.contains("text(size: 6pt)[3.0]"));
+    assert!(typst_str.contains("text(size: 7pt)[time]"));
+}
+
+#[test]
+fn test_timeline_crossing_gate() {
+    let mut device = GenericDevice::new(3);
+    device.set_two_qubit_gate_time("CNOT", 0, 2, 2.0).unwrap();
+    device
+        .set_single_qubit_gate_time("Hadamard", 1, 1.0)
+        .unwrap();
+    let mut circuit = Circuit::new();
+    circuit.add_operation(CNOT::new(0, 2));
+    circuit.add_operation(Hadamard::new(1));
+
+    // The Hadamard runs during the CNOT, on the qubit between its control and its target.
+    let (schedule, _) = circuit_schedule(&circuit, &device);
+    assert_eq!(schedule[1].start, 0.0);
+    let (typst_str, warnings) =
+        circuit_into_timeline_typst_str_with_warnings(&circuit, &device, &RenderOptions::new());
+    assert!(warnings.is_empty());
+    // No box covers the wire of the Hadamard (dy: 47.0pt), the line of the CNOT is drawn below it.
+    assert_eq!(
+        typst_str
+            .matches("dy: 15.0pt, rect(width: 56.0pt, height: 18.0pt")
+            .count(),
+        1
+    );
+    assert_eq!(
+        typst_str
+            .matches("dy: 79.0pt, rect(width: 56.0pt, height: 18.0pt")
+            .count(),
+        1
+    );
+    assert_eq!(
+        typst_str
+            .matches("dy: 47.0pt, rect(width: 28.0pt, height: 18.0pt")
+            .count(),
+        1
+    );
+    assert!(!typst_str.contains("height: 82.0pt"));
+    let link = typst_str
+        .find("line(start: (64.0pt, 24.0pt), end: (64.0pt, 88.0pt)")
+        .unwrap();
+    let hadamard = typst_str.find("dy: 47.0pt, rect(").unwrap();
+    assert!(link < hadamard);
+}
+
+#[test]
+fn test_timeline_dark_background() {
+    let options = RenderOptions::new().background(Background::Fill(r##"rgb("#1e1e1e")"##.into()));
+    let (typst_str, _) =
+        circuit_into_timeline_typst_str_with_warnings(&timed_circuit(), &timed_device(), &options);
+    assert!(typst_str.contains("#set text(fill: white)"));
+    // The gates keep a dark text on their light fill, the markers are drawn in white.
+    assert_eq!(
+        typst_str.matches("fill: rgb(220, 232, 250)").count(),
+        typst_str.matches("text(size: 7pt, fill: black)").count()
+    );
+    assert!(typst_str.contains("rect(width: 2pt, height: 18.0pt, fill: white)"));
+    assert!(!typst_str.contains("fill: black))"));
+
+    let (typst_str, _) = circuit_into_timeline_typst_str_with_warnings(
+        &timed_circuit(),
+        &timed_device(),
+        &RenderOptions::new(),
+    );
+    assert!(typst_str.contains("rect(width: 2pt, height: 18.0pt, fill: black)"));
+}
+
+#[test]
+#[serial]
+fn test_timeline_image() {
+    circuit_to_timeline_image(&timed_circuit(), &timed_device(), &RenderOptions::new()).unwrap();
+}